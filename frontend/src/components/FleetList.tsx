@@ -278,7 +278,8 @@ export const FleetList: React.FC<FleetListProps> = ({ onFleetSelected }) => {
                 defenderNumber = parseInt(defender.name.split('Fleet_Mercenary_')[1]);
                 defenderOwnerId = "Mercenary";
             } else {
-                defenderNumber = parseInt(defender.name.split('Fleet_')[1].split('_')[1]);
+                // Owner ids may contain underscores (e.g. Merchant_2), so strip the whole prefix
+                defenderNumber = parseInt(defender.name.slice(`Fleet_${defender.owner_id}_`.length));
                 defenderOwnerId = defender.owner_id;
             }
             console.log('Parsed defender info:', { defenderNumber, defenderOwnerId });
@@ -13,6 +13,8 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
     const [showNewGame, setShowNewGame] = useState(false);
     const [showAdvancedSettings, setShowAdvancedSettings] = useState(false);
     const [isMenuHidden, setIsMenuHidden] = useState(false);
+    const [pilotName, setPilotName] = useState('');
+    const [password, setPassword] = useState('');
     const [settings, setSettings] = useState<GameSettings>({
         game_id: Date.now().toString(),
         display_name: 'New Game',
@@ -63,9 +65,9 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
 
     const handleNewGame = async () => {
         try {
-            // Clear caches before creating new game
-            await api.clearCaches();
             await api.createNewGame(settings);
+            // The game's first account claims its player and becomes its admin
+            await api.register(settings.game_id, settings.player_name, password);
             onStartGame();
         } catch (error) {
             console.error('Failed to create new game:', error);
@@ -75,12 +77,23 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
     const handleLoadGame = async (gameId: string) => {
         try {
             await api.loadGame(gameId);
+            await api.login(gameId, pilotName, password);
             onStartGame();
         } catch (error) {
             console.error('Failed to load game:', error);
         }
     };
 
+    const handleJoinGame = async (gameId: string) => {
+        try {
+            await api.loadGame(gameId);
+            await api.register(gameId, pilotName, password);
+            onStartGame();
+        } catch (error) {
+            console.error('Failed to join game:', error);
+        }
+    };
+
     const handleDeleteGame = async (gameId: string) => {
         try {
             await api.deleteGame(gameId);
@@ -90,6 +103,35 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
         }
     };
 
+    const handleExportGame = async (gameId: string, displayName: string) => {
+        try {
+            const archive = await api.exportGame(gameId);
+            const blob = new Blob([JSON.stringify(archive)], { type: 'application/json' });
+            const url = URL.createObjectURL(blob);
+            const link = document.createElement('a');
+            link.href = url;
+            link.download = `${displayName.replace(/[^a-z0-9_-]+/gi, '_')}.startrader.json`;
+            link.click();
+            URL.revokeObjectURL(url);
+        } catch (error) {
+            console.error('Failed to export game:', error);
+        }
+    };
+
+    const handleImportGame = async (event: React.ChangeEvent<HTMLInputElement>) => {
+        const file = event.target.files?.[0];
+        event.target.value = '';
+        if (!file) {
+            return;
+        }
+        try {
+            await api.importGame(JSON.parse(await file.text()));
+            loadSavedGames();
+        } catch (error) {
+            console.error('Failed to import game:', error);
+        }
+    };
+
     const addFaction = () => {
         setSettings({
             ...settings,
@@ -124,6 +166,10 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
                 
                 <div className="menu-buttons">
                     <button onClick={() => setShowNewGame(true)}>New Game</button>
+                    <label className="import-game-button">
+                        Import Game
+                        <input type="file" accept=".json,application/json" onChange={handleImportGame} hidden />
+                    </label>
                 </div>
 
                 {showNewGame && (
@@ -189,6 +235,20 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
                                 />
                             </div>
 
+                            <div className="setting-group">
+                                <label>Seed (optional):</label>
+                                <input
+                                    type="number"
+                                    value={settings.seed ?? ''}
+                                    placeholder="Random"
+                                    onChange={(e) => setSettings({
+                                        ...settings,
+                                        seed: e.target.value === '' ? undefined : parseInt(e.target.value)
+                                    })}
+                                    min="0"
+                                />
+                            </div>
+
                             <div className="setting-group">
                                 <label>Player Name:</label>
                                 <input
@@ -201,6 +261,16 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
                                 />
                             </div>
 
+                            <div className="setting-group">
+                                <label>Password:</label>
+                                <input
+                                    type="password"
+                                    value={password}
+                                    onChange={(e) => setPassword(e.target.value)}
+                                    minLength={8}
+                                />
+                            </div>
+
                             <div className="setting-group">
                                 <button onClick={() => setShowAdvancedSettings(!showAdvancedSettings)}>
                                     {showAdvancedSettings ? 'Hide Advanced Settings' : 'Show Advanced Settings'}
@@ -262,6 +332,24 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
 
                 <div className="saved-games">
                     <h2>Saved Games</h2>
+                    <div className="settings-form">
+                        <div className="setting-group">
+                            <label>Pilot Name:</label>
+                            <input
+                                type="text"
+                                value={pilotName}
+                                onChange={(e) => setPilotName(e.target.value)}
+                            />
+                        </div>
+                        <div className="setting-group">
+                            <label>Password:</label>
+                            <input
+                                type="password"
+                                value={password}
+                                onChange={(e) => setPassword(e.target.value)}
+                            />
+                        </div>
+                    </div>
                     {savedGames.map((game) => (
                         <div key={game.game_id} className="saved-game-item">
                             <div className="game-info">
@@ -270,6 +358,8 @@ export const MainMenu: React.FC<MainMenuProps> = ({ onStartGame }) => {
                             </div>
                             <div className="game-actions">
                                 <button onClick={() => handleLoadGame(game.game_id)}>Load</button>
+                                <button onClick={() => handleJoinGame(game.game_id)}>Join</button>
+                                <button onClick={() => handleExportGame(game.game_id, game.settings.display_name)}>Export</button>
                                 <button onClick={() => handleDeleteGame(game.game_id)}>Delete</button>
                             </div>
                         </div>
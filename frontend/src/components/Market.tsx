@@ -1,6 +1,6 @@
 import React, { useEffect, useMemo, useState } from 'react';
 import { api } from '../services/api';
-import { Fleet, Position, ResourceType, StarSystem, Player, Resource } from '../types/game';
+import { Fleet, Position, ResourceType, StarSystem, Player, Resource, MarketQuote } from '../types/game';
 import { MarketModal } from './MarketModal';
 import { ShipMarketModal } from './ShipMarketModal';
 
@@ -28,6 +28,7 @@ export const MarketView: React.FC<MarketViewProps> = ({ selectedFleet }) => {
     const [distMode, setDistMode] = useState<'first' | 'even' | 'selected'>('first');
     const [player, setPlayer] = useState<Player | null>(null);
     const [selectedResource, setSelectedResource] = useState<Resource | null>(null);
+    const [quote, setQuote] = useState<MarketQuote | null>(null);
     // Ship selection for viewing stock
     const [viewShipIndex, setViewShipIndex] = useState<number>(-1); // -1 => All ships
     // Local snapshot of the fleet so we can refresh cargo after trades
@@ -39,6 +40,19 @@ export const MarketView: React.FC<MarketViewProps> = ({ selectedFleet }) => {
     const [mapHalf, setMapHalf] = useState<number>(0);
     const [compareSearch, setCompareSearch] = useState<string>("");
 
+    // Totals include slippage, so ask the backend instead of multiplying unit prices
+    useEffect(() => {
+        if (!system || planetIndex == null || !selectedResource || qty <= 0) {
+            setQuote(null);
+            return;
+        }
+        let cancelled = false;
+        api.getMarketQuote(system.id, planetIndex, selectedResource.resource_type, qty)
+            .then(q => { if (!cancelled) setQuote(q); })
+            .catch(() => { if (!cancelled) setQuote(null); });
+        return () => { cancelled = true; };
+    }, [system, planetIndex, selectedResource, qty, market]);
+
     const planetKey = (sysId: number, planetIdx: number) => `${sysId}-${planetIdx}`;
     const isSelectedForCompare = (sysId: number, planetIdx: number) => compareSelected.includes(planetKey(sysId, planetIdx));
     const toggleCompareSelect = async (sysId: number, planetIdx: number) => {
@@ -294,8 +308,8 @@ export const MarketView: React.FC<MarketViewProps> = ({ selectedFleet }) => {
         if (!market) return <div>Loading market...</div>;
         const credits = player ? player.credits.toLocaleString() : '—';
         const selected = selectedResource;
-        const totalBuy = selected?.buy ? (selected.buy * qty).toFixed(3) : 'N/A';
-        const totalSell = selected?.sell ? (selected.sell * qty).toFixed(3) : 'N/A';
+        const totalBuy = quote?.buy_total != null ? quote.buy_total.toFixed(2) : 'N/A';
+        const totalSell = quote?.sell_total != null ? quote.sell_total.toFixed(2) : 'N/A';
         return (
             <div>
                 <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center', marginBottom: 8 }}>
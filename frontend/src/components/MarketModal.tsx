@@ -1,5 +1,5 @@
 import React, { useEffect, useRef, useState } from 'react';
-import { Planet, Resource, Player, Market, Fleet } from '../types/game';
+import { Planet, Resource, Player, Market, KnownMarket, Fleet } from '../types/game';
 import { api } from '../services/api';
 import './MarketModal.css';
 import { ApiResponse } from '../types/api';
@@ -18,7 +18,7 @@ interface MarketModalProps {
 
 export const MarketModal: React.FC<MarketModalProps> = ({ isOpen, onClose, systemId, planetId, planet, selectedFleet, embedded = false, onPlayerRefresh, showHeaderCredits = true }) => {
     const modalRef = useRef<HTMLDivElement>(null);
-    const [market, setMarket] = useState<Market | null>(null);
+    const [market, setMarket] = useState<Market | KnownMarket | null>(null);
     const [player, setPlayer] = useState<Player | null>(null);
     const [fleets, setFleets] = useState<Fleet[]>([]);
     const [selectedFleetName, setSelectedFleetName] = useState<string | null>(selectedFleet?.name || null);
@@ -271,7 +271,7 @@ export const MarketModal: React.FC<MarketModalProps> = ({ isOpen, onClose, syste
             );
             console.log('[MarketModal] BUY response:', response);
             await refreshAfterTrade();
-            setTradeMessage(response);
+            setTradeMessage(`Bought ${response.moved} ${response.resource_type}`);
             // Keep the selection and only reset the trade amount
             setTradeAmount(1);
         } catch (err) {
@@ -323,7 +323,7 @@ export const MarketModal: React.FC<MarketModalProps> = ({ isOpen, onClose, syste
             );
             console.log('[MarketModal] SELL response:', response);
             await refreshAfterTrade();
-            setTradeMessage(response);
+            setTradeMessage(`Sold ${response.moved} ${response.resource_type}`);
             // Keep the selection and only reset the trade amount
             setTradeAmount(1);
         } catch (err) {
@@ -372,6 +372,11 @@ export const MarketModal: React.FC<MarketModalProps> = ({ isOpen, onClose, syste
                                 {(displayPlanet?.position?.x ?? planet.position.x)}, {(displayPlanet?.position?.y ?? planet.position.y)}, {(displayPlanet?.position?.z ?? planet.position.z)}
                             )
                         </span>
+                        {market && 'age' in market && market.age > 0 && (
+                            <span className={`market-age ${market.stale ? 'stale' : ''}`}>
+                                <strong>Prices seen:</strong> {market.age} ticks ago{market.stale ? ' (stale)' : ''}
+                            </span>
+                        )}
                         {showHeaderCredits && player && (
                             <span className="player-credits">
                                 <strong>Credits:</strong> {player.credits.toLocaleString()} cr
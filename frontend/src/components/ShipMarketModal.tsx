@@ -4,6 +4,29 @@ import { api } from '../services/api';
 import './ShipMarketModal.css';
 import { ApiResponse } from '../types/api';
 
+// Mirrors max_hull in the server's ship model
+const maxHull = (ship: Ship): number => {
+    const baseHull = {
+        Fighter: 50,
+        Battleship: 200,
+        Freighter: 100,
+        Explorer: 150,
+        Shuttle: 30,
+        Capital: 300
+    }[ship.specialization] || 100;
+
+    const sizeMultiplier = {
+        Tiny: 0.5,
+        Small: 0.75,
+        Medium: 1.0,
+        Large: 1.5,
+        Huge: 2.0,
+        Planetary: 3.0
+    }[ship.size] || 1.0;
+
+    return Math.floor(baseHull * sizeMultiplier);
+};
+
 const calculateShipPrice = (ship: Ship): number => {
     const basePrice = {
         Tiny: 1000,
@@ -29,7 +52,7 @@ const calculateShipPrice = (ship: Ship): number => {
         Experimental: 1.5
     }[ship.engine] || 1.0;
 
-    const conditionMultiplier = Math.max(ship.hp / 100, 0.5);
+    const conditionMultiplier = Math.max(Math.min(ship.hp / maxHull(ship), 1), 0.5);
 
     return Math.floor(basePrice * specializationMultiplier * engineMultiplier * conditionMultiplier);
 };
@@ -250,6 +273,35 @@ export const ShipMarketModal: React.FC<ShipMarketModalProps> = ({ isOpen, onClos
         }
     };
 
+    const handleRepairFleet = async () => {
+        if (!selectedFleet) {
+            setTradeMessage('Please select a fleet first');
+            return;
+        }
+
+        try {
+            const quote = await api.getRepairQuote(systemId, planetId, selectedFleet);
+            if (!quote.success || !quote.data) {
+                setTradeMessage(quote.message || 'Failed to quote repairs');
+                return;
+            }
+            if (quote.data.ships.length === 0) {
+                setTradeMessage('No repairs needed');
+                return;
+            }
+            if (!window.confirm(`Repair ${quote.data.ships.length} ships for ${quote.data.total_cost.toLocaleString()} cr?`)) {
+                return;
+            }
+
+            const result = await api.repairFleet(systemId, planetId, selectedFleet);
+            await updateAllData();
+            setTradeMessage(result.success ? `Successfully ${result.message.toLowerCase()}` : result.message);
+        } catch (err) {
+            console.error('Repair error:', err);
+            setTradeMessage('Failed to repair fleet');
+        }
+    };
+
     // Helper function to update all data
     const updateAllData = async () => {
         try {
@@ -525,6 +577,9 @@ export const ShipMarketModal: React.FC<ShipMarketModalProps> = ({ isOpen, onClos
                         {selectedFleet && (
                             <div className="fleet-ships">
                                 <h3>Your Fleet Ships</h3>
+                                <button className="repair-button" onClick={handleRepairFleet}>
+                                    Repair Fleet
+                                </button>
                                 {selectedFleetData && selectedFleetData.ships.length > 0 ? (
                                     selectedFleetData.ships.map((ship, index) => (
                                         <div 
@@ -549,7 +604,7 @@ export const ShipMarketModal: React.FC<ShipMarketModalProps> = ({ isOpen, onClos
                                                     </div>
                                                     <div className="stat-item">
                                                         <span className="stat-label">HP:</span>
-                                                        <span className="stat-value">{ship.hp}</span>
+                                                        <span className="stat-value">{ship.hp} / {maxHull(ship)}</span>
                                                     </div>
                                                 </div>
                                             </div>
@@ -54,7 +54,8 @@ export const TraderEncounterModal: React.FC<TraderEncounterModalProps> = ({
                 fleetNumber,
                 selectedResource,
                 quantity,
-                isBuying ? 'buy' : 'sell'
+                isBuying ? 'buy' : 'sell',
+                encounteredFleet.name
             );
 
             setSuccess(result);
@@ -1,4 +1,4 @@
-import { StarSystem, Player, GameSettings, SavedGame, Fleet, Resource, ResourceType, Market, ShipMarket } from '../types/game';
+import { StarSystem, Player, GameSettings, SavedGame, GameArchive, MigrationReport, StorageBackend, StorageMigrationReport, Fleet, Resource, ResourceType, KnownMarket, MarketQuote, ScanReport, NearbySystem, NearbyFleet, SegmentHit, Position, CargoReceipt, CargoTransfer, Route, RouteObjective, RepairEstimate, EquipmentMarket, EquipmentSlot, OutfitReceipt, ShipMarket, GameTime, AdvanceTimeReport, Faction, FactionStanding, Mission, Session, GameEvent, GameEventType, Encounter, EncounterAction, EncounterReport, CombatState, SalvageField } from '../types/game';
 import { Ship } from '../types';
 import type { ApiResponse } from '../types/api.js';
 
@@ -13,6 +13,22 @@ class ApiError extends Error {
     }
 }
 
+// Game the requests are for, so one server can serve several games at once
+let currentGameId: string | null = null;
+// Token of the logged in player; the server only lets players act for themselves
+let sessionToken: string | null = null;
+
+function gameFetch(input: string, init: RequestInit = {}): Promise<Response> {
+    const headers = new Headers(init.headers);
+    if (currentGameId) {
+        headers.set('X-Game-Id', currentGameId);
+    }
+    if (sessionToken) {
+        headers.set('Authorization', `Bearer ${sessionToken}`);
+    }
+    return fetch(input, { ...init, headers });
+}
+
 async function handleApiResponse<T>(response: Response): Promise<T> {
     if (!response.ok) {
         throw new Error(`HTTP error! status: ${response.status}`);
@@ -30,7 +46,7 @@ async function handleApiResponse<T>(response: Response): Promise<T> {
 export const api = {
     // Player endpoints
     getPlayer: async (name: string): Promise<Player> => {
-        const response = await fetch(`${API_BASE_URL}/player/${name}`);
+        const response = await gameFetch(`${API_BASE_URL}/player/${name}`);
         if (!response.ok) {
             throw new Error(`Failed to load player: ${response.status}`);
         }
@@ -43,23 +59,23 @@ export const api = {
 
     // Galaxy endpoints
     getGalaxyMap: async (): Promise<StarSystem[]> => {
-        const response = await fetch(`${API_BASE_URL}/galaxy_map`);
+        const response = await gameFetch(`${API_BASE_URL}/galaxy_map`);
         return handleApiResponse<StarSystem[]>(response);
     },
 
     getStarSystem: async (id: number): Promise<StarSystem> => {
-        const response = await fetch(`${API_BASE_URL}/star_system/${id}`);
+        const response = await gameFetch(`${API_BASE_URL}/star_system/${id}`);
         return handleApiResponse<StarSystem>(response);
     },
 
     // Fleet endpoints
     getFleets: async (ownerId: string): Promise<Fleet[]> => {
-        const response = await fetch(`${API_BASE_URL}/fleet/${ownerId}`);
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}`);
         return handleApiResponse<Fleet[]>(response);
     },
 
     getFleet: async (ownerId: string, fleetNumber: number): Promise<Fleet | null> => {
-        const response = await fetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}`);
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}`);
         return handleApiResponse<Fleet | null>(response);
     },
 
@@ -70,7 +86,7 @@ export const api = {
     ): Promise<string> => {
         const { x, y, z, space, system_id, planet_id } = payload;
         console.log(`Moving fleet ${ownerId}_${fleetNumber} with intent`, payload);
-        const response = await fetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/move`, {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/move`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -99,8 +115,8 @@ export const api = {
     },
 
     // Market endpoints
-    getPlanetMarket: async (systemId: number, planetId: number): Promise<Market> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/market`);
+    getPlanetMarket: async (systemId: number, planetId: number): Promise<KnownMarket> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/market`);
         if (!response.ok) {
             throw new Error(`Failed to load market: ${response.status}`);
         }
@@ -111,6 +127,11 @@ export const api = {
         return data.data;
     },
 
+    getMarketQuote: async (systemId: number, planetId: number, resourceType: ResourceType, quantity: number): Promise<MarketQuote> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/market/quote/${resourceType}/${quantity}`);
+        return handleApiResponse<MarketQuote>(response);
+    },
+
     buyResource: async (
         systemId: number,
         planetId: number,
@@ -119,8 +140,9 @@ export const api = {
         fleetName?: string,
         distributionMode?: 'first' | 'even' | 'specific',
         allocations?: { ship_index: number; quantity: number }[],
-    ): Promise<string> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/buy`, {
+        allowPartial?: boolean,
+    ): Promise<CargoReceipt> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/buy`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -131,10 +153,11 @@ export const api = {
                 fleet_name: fleetName,
                 distribution_mode: distributionMode,
                 allocations: allocations,
+                allow_partial: allowPartial,
             }),
         });
         
-        return handleApiResponse<string>(response);
+        return handleApiResponse<CargoReceipt>(response);
     },
 
     sellResource: async (
@@ -145,8 +168,9 @@ export const api = {
         fleetName?: string,
         distributionMode?: 'first' | 'even' | 'specific',
         allocations?: { ship_index: number; quantity: number }[],
-    ): Promise<string> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/sell`, {
+        allowPartial?: boolean,
+    ): Promise<CargoReceipt> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/sell`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -157,45 +181,272 @@ export const api = {
                 fleet_name: fleetName,
                 distribution_mode: distributionMode,
                 allocations: allocations,
+                allow_partial: allowPartial,
             }),
         });
         
-        return handleApiResponse<string>(response);
+        return handleApiResponse<CargoReceipt>(response);
+    },
+
+    transferCargo: async (
+        ownerId: string,
+        fleetNumber: number,
+        resourceType: ResourceType,
+        quantity: number,
+        fromShip: number,
+        toShip: number,
+        allowPartial?: boolean,
+    ): Promise<CargoTransfer> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/cargo/transfer`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({
+                resource_type: resourceType,
+                quantity: quantity,
+                from_ship: fromShip,
+                to_ship: toShip,
+                allow_partial: allowPartial,
+            }),
+        });
+
+        return handleApiResponse<CargoTransfer>(response);
+    },
+
+    splitFleet: async (ownerId: string, fleetNumber: number, shipIndices: number[], displayName?: string): Promise<Fleet[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/split`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ ship_indices: shipIndices, display_name: displayName }),
+        });
+
+        return handleApiResponse<Fleet[]>(response);
+    },
+
+    mergeFleet: async (ownerId: string, fleetNumber: number, fleetName: string): Promise<Fleet> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/merge`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ fleet_name: fleetName }),
+        });
+
+        return handleApiResponse<Fleet>(response);
+    },
+
+    renameFleet: async (ownerId: string, fleetNumber: number, displayName: string): Promise<Fleet> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/rename`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ display_name: displayName }),
+        });
+
+        return handleApiResponse<Fleet>(response);
+    },
+
+    setFleetStance: async (ownerId: string, fleetNumber: number, stance: Exclude<CombatState, 'NotInCombat'>): Promise<Fleet> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/stance`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ stance }),
+        });
+
+        return handleApiResponse<Fleet>(response);
+    },
+
+    transferShips: async (ownerId: string, fleetNumber: number, targetFleet: string, shipIndices: number[]): Promise<Fleet[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/transfer_ships`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ target_fleet: targetFleet, ship_indices: shipIndices }),
+        });
+
+        return handleApiResponse<Fleet[]>(response);
+    },
+
+    planRoute: async (ownerId: string, fleetNumber: number, systemId: number, planetId?: number, objective: RouteObjective = 'distance'): Promise<Route> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/route/plan`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ system_id: systemId, planet_id: planetId, objective }),
+        });
+
+        return handleApiResponse<Route>(response);
+    },
+
+    followRoute: async (ownerId: string, fleetNumber: number, systemId: number, planetId?: number, objective: RouteObjective = 'distance'): Promise<Fleet> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/route`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ system_id: systemId, planet_id: planetId, objective }),
+        });
+
+        return handleApiResponse<Fleet>(response);
+    },
+
+    resumeRoute: async (ownerId: string, fleetNumber: number): Promise<Fleet> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/route/resume`, {
+            method: 'POST',
+        });
+
+        return handleApiResponse<Fleet>(response);
+    },
+
+    cancelRoute: async (ownerId: string, fleetNumber: number): Promise<Fleet> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/route`, {
+            method: 'DELETE',
+        });
+
+        return handleApiResponse<Fleet>(response);
+    },
+
+    scanWithFleet: async (ownerId: string, fleetNumber: number): Promise<ScanReport> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/scan`, {
+            method: 'POST',
+        });
+
+        return handleApiResponse<ScanReport>(response);
+    },
+
+    getNearestSystem: async (at: Position): Promise<NearbySystem> => {
+        const response = await gameFetch(`${API_BASE_URL}/galaxy/nearest_system?x=${at.x}&y=${at.y}&z=${at.z}`);
+        return handleApiResponse<NearbySystem>(response);
+    },
+
+    getFleetsNear: async (at: Position, radius: number): Promise<NearbyFleet[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/galaxy/fleets_near?x=${at.x}&y=${at.y}&z=${at.z}&radius=${radius}`);
+        return handleApiResponse<NearbyFleet[]>(response);
+    },
+
+    getSystemsAlong: async (from: Position, to: Position, radius: number): Promise<SegmentHit[]> => {
+        const response = await gameFetch(
+            `${API_BASE_URL}/galaxy/systems_along?x1=${from.x}&y1=${from.y}&z1=${from.z}&x2=${to.x}&y2=${to.y}&z2=${to.z}&radius=${radius}`
+        );
+        return handleApiResponse<SegmentHit[]>(response);
     },
 
     // Fleet owner endpoints
     //Returns a list of all fleet owners
     getFleetOwners: async (): Promise<string[]> => {
-        const response = await fetch(`${API_BASE_URL}/fleet/owners`);
+        const response = await gameFetch(`${API_BASE_URL}/fleet/owners`);
         return handleApiResponse<string[]>(response);
     },
 
     //Returns a list of all fleets owned by a specific owner
     getOwnerFleets: async (ownerId: string): Promise<Fleet[]> => {
-        const response = await fetch(`${API_BASE_URL}/fleet/${ownerId}`);
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}`);
         return handleApiResponse<Fleet[]>(response);
     },
 
     // Combat endpoints
-    initiateCombat: async (attackerId: string, attackerNumber: number, defenderId: string, defenderNumber: number): Promise<string> => {
-        const response = await fetch(`${API_BASE_URL}/fleet/${attackerId}/${attackerNumber}/attack/${defenderId}/${defenderNumber}`);
+    initiateCombat: async (attackerId: string, attackerNumber: number, defenderId: string, defenderNumber: number, retreatAfter?: number): Promise<string> => {
+        const query = retreatAfter === undefined ? '' : `?retreat_after=${retreatAfter}`;
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${attackerId}/${attackerNumber}/attack/${defenderId}/${defenderNumber}${query}`, {
+            method: 'POST',
+        });
         return handleApiResponse<string>(response);
     },
 
     // Encounter endpoints
-    checkForEncounter: async (ownerId: string, fleetNumber: number): Promise<Fleet[]> => {
-        const response = await fetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/encounter`);
-        return handleApiResponse<Fleet[]>(response);
+    checkForEncounter: async (ownerId: string, fleetNumber: number): Promise<Encounter[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${ownerId}/${fleetNumber}/encounter`);
+        return handleApiResponse<Encounter[]>(response);
+    },
+
+    getEncounters: async (pendingOnly = false): Promise<Encounter[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/encounters?pending=${pendingOnly}`);
+        return handleApiResponse<Encounter[]>(response);
+    },
+
+    resolveEncounter: async (
+        encounterId: string,
+        action: EncounterAction,
+        trade?: { resourceType: ResourceType; quantity: number; tradeType: 'buy' | 'sell' },
+        retreatAfter?: number
+    ): Promise<EncounterReport> => {
+        const response = await gameFetch(`${API_BASE_URL}/encounters/${encounterId}/resolve`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({
+                action,
+                resource_type: trade?.resourceType,
+                quantity: trade?.quantity,
+                trade_type: trade?.tradeType,
+                retreat_after: retreatAfter,
+            }),
+        });
+        return handleApiResponse<EncounterReport>(response);
+    },
+
+    // Salvage endpoints
+    getSalvage: async (): Promise<SalvageField[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/salvage`);
+        return handleApiResponse<SalvageField[]>(response);
+    },
+
+    lootSalvage: async (fieldId: string, fleetNumber: number, resourceType: ResourceType, quantity?: number): Promise<CargoReceipt> => {
+        const response = await gameFetch(`${API_BASE_URL}/salvage/${fieldId}/loot`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ fleet_number: fleetNumber, resource_type: resourceType, quantity }),
+        });
+        return handleApiResponse<CargoReceipt>(response);
+    },
+
+    captureShip: async (fieldId: string, fleetNumber: number, shipIndex: number): Promise<Fleet> => {
+        const response = await gameFetch(`${API_BASE_URL}/salvage/${fieldId}/capture`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ fleet_number: fleetNumber, ship_index: shipIndex }),
+        });
+        return handleApiResponse<Fleet>(response);
+    },
+
+    scrapShip: async (fieldId: string, fleetNumber: number, shipIndex: number, derelict = false): Promise<number> => {
+        const response = await gameFetch(`${API_BASE_URL}/salvage/${fieldId}/scrap`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ fleet_number: fleetNumber, ship_index: shipIndex, derelict }),
+        });
+        return handleApiResponse<number>(response);
     },
 
     // Trade endpoints
-    tradeWithTrader: async (fleetId: string, fleetNumber: number, resourceType: string, quantity: number, tradeType: 'buy' | 'sell'): Promise<string> => {
-        const response = await fetch(`${API_BASE_URL}/fleet/${fleetId}/${fleetNumber}/trade/${resourceType}/${quantity}/${tradeType}`);
+    tradeWithTrader: async (fleetId: string, fleetNumber: number, resourceType: string, quantity: number, tradeType: 'buy' | 'sell', traderFleet?: string): Promise<string> => {
+        const query = traderFleet ? `?trader=${encodeURIComponent(traderFleet)}` : '';
+        const response = await gameFetch(`${API_BASE_URL}/fleet/${fleetId}/${fleetNumber}/trade/${resourceType}/${quantity}/${tradeType}${query}`);
         return handleApiResponse<string>(response);
     },
 
+    getNpcTraders: async (): Promise<Fleet[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/traders`);
+        return handleApiResponse<Fleet[]>(response);
+    },
+
     getPlanetShipMarket: async (systemId: number, planetId: number): Promise<ApiResponse<ShipMarket>> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/ships`);
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/ships`);
         if (!response.ok) {
             throw new Error(`Failed to load ship market: ${response.status}`);
         }
@@ -207,7 +458,7 @@ export const api = {
     },
 
     buyShip: async (systemId: number, planetId: number, shipIndex: number, fleetName?: string): Promise<ApiResponse<string>> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/buy_ship`, {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/buy_ship`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -217,8 +468,51 @@ export const api = {
         return response.json();
     },
 
+    getRepairQuote: async (systemId: number, planetId: number, fleetName: string): Promise<ApiResponse<RepairEstimate>> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/repair?fleet_name=${encodeURIComponent(fleetName)}`);
+        return response.json();
+    },
+
+    repairFleet: async (systemId: number, planetId: number, fleetName: string, shipIndex?: number): Promise<ApiResponse<RepairEstimate>> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/repair`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ fleet_name: fleetName, ship_index: shipIndex }),
+        });
+        return response.json();
+    },
+
+    getEquipmentMarket: async (systemId: number, planetId: number): Promise<ApiResponse<EquipmentMarket>> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/equipment`);
+        return response.json();
+    },
+
+    installEquipment: async (systemId: number, planetId: number, fleetName: string, shipIndex: number, offerIndex: number): Promise<ApiResponse<OutfitReceipt>> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/equipment/install`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ fleet_name: fleetName, ship_index: shipIndex, offer_index: offerIndex }),
+        });
+        return response.json();
+    },
+
+    uninstallEquipment: async (systemId: number, planetId: number, fleetName: string, shipIndex: number, slot: EquipmentSlot, index: number): Promise<ApiResponse<OutfitReceipt>> => {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/equipment/uninstall`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ fleet_name: fleetName, ship_index: shipIndex, slot, index }),
+        });
+        return response.json();
+    },
+
     sellShip: async (systemId: number, planetId: number, shipIndex: number, fleetName: string): Promise<ApiResponse<string>> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/sell_ship`, {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/sell_ship`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -233,7 +527,7 @@ export const api = {
     },
 
     tradeInShip: async (systemId: number, planetId: number, shipIndex: number, fleetName: string, tradeInShipIndex: number): Promise<ApiResponse<string>> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/trade_in_ship`, {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/trade_in_ship`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -249,7 +543,7 @@ export const api = {
 
     // Resource trading
     buyFromPlanet: async (systemId: number, planetId: number, resourceType: ResourceType, quantity: number): Promise<string> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/buy`, {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/buy`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -268,7 +562,7 @@ export const api = {
     },
 
     sellToPlanet: async (systemId: number, planetId: number, resourceType: ResourceType, quantity: number): Promise<string> => {
-        const response = await fetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/sell`, {
+        const response = await gameFetch(`${API_BASE_URL}/planet/${systemId}/${planetId}/sell`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -288,28 +582,88 @@ export const api = {
 
     // Game Settings and Save Management
     listSavedGames: async (): Promise<SavedGame[]> => {
-        const response = await fetch(`${API_BASE_URL}/games`);
+        const response = await gameFetch(`${API_BASE_URL}/games`);
         return handleApiResponse<SavedGame[]>(response);
     },
 
     loadGame: async (gameId: string): Promise<void> => {
-        const response = await fetch(`${API_BASE_URL}/games/${gameId}/load`);
-        return handleApiResponse<void>(response);
+        const response = await gameFetch(`${API_BASE_URL}/games/${gameId}/load`);
+        await handleApiResponse<void>(response);
+        currentGameId = gameId;
     },
 
     createNewGame: async (settings: GameSettings): Promise<void> => {
-        const response = await fetch(`${API_BASE_URL}/games/new`, {
+        const response = await gameFetch(`${API_BASE_URL}/games/new`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
             },
             body: JSON.stringify(settings),
         });
-        return handleApiResponse<void>(response);
+        await handleApiResponse<void>(response);
+        currentGameId = settings.game_id;
+    },
+
+    // Accounts and sessions
+    register: async (gameId: string, name: string, password: string): Promise<Session> => {
+        const response = await fetch(`${API_BASE_URL}/games/${gameId}/register`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ name, password }),
+        });
+        const session = await handleApiResponse<Session>(response);
+        sessionToken = session.token;
+        currentGameId = session.game_id;
+        return session;
+    },
+
+    login: async (gameId: string, name: string, password: string): Promise<Session> => {
+        const response = await fetch(`${API_BASE_URL}/games/${gameId}/login`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ name, password }),
+        });
+        const session = await handleApiResponse<Session>(response);
+        sessionToken = session.token;
+        currentGameId = session.game_id;
+        return session;
+    },
+
+    logout: async (): Promise<void> => {
+        await gameFetch(`${API_BASE_URL}/logout`, { method: 'POST' });
+        sessionToken = null;
+    },
+
+    getSession: async (): Promise<Session> => {
+        const response = await gameFetch(`${API_BASE_URL}/session`);
+        return handleApiResponse<Session>(response);
+    },
+
+    // Pushes the game's events to `onEvent` until the returned function is
+    // called; `onLagged` fires when events were missed and views should reload
+    subscribeToEvents: (onEvent: (event: GameEvent) => void, onLagged?: () => void): (() => void) => {
+        if (!sessionToken) {
+            throw new Error('Log in to a game before subscribing to its events');
+        }
+        // EventSource cannot send headers, so the token goes in the query
+        const source = new EventSource(`${API_BASE_URL}/events?token=${encodeURIComponent(sessionToken)}`);
+        const types: GameEventType[] = [
+            'fleet_moved', 'fleet_arrived', 'combat_started', 'combat_resolved',
+            'market_price_changed', 'encounter_triggered', 'encounter_resolved', 'mission_updated', 'salvage_left',
+        ];
+        types.forEach(type => source.addEventListener(type, message => {
+            onEvent(JSON.parse((message as MessageEvent).data) as GameEvent);
+        }));
+        source.addEventListener('lagged', () => onLagged?.());
+        return () => source.close();
     },
 
     getGameSettings: async (): Promise<GameSettings> => {
-        const response = await fetch(`${API_BASE_URL}/settings`);
+        const response = await gameFetch(`${API_BASE_URL}/settings`);
         if (!response.ok) {
             throw new Error(`Failed to load game settings: ${response.status}`);
         }
@@ -321,7 +675,7 @@ export const api = {
     },
 
     updateGameSettings: async (settings: GameSettings): Promise<void> => {
-        const response = await fetch(`${API_BASE_URL}/settings`, {
+        const response = await gameFetch(`${API_BASE_URL}/settings`, {
             method: 'POST',
             headers: {
                 'Content-Type': 'application/json',
@@ -332,10 +686,46 @@ export const api = {
     },
 
     deleteGame: async (gameId: string): Promise<void> => {
-        const response = await fetch(`${API_BASE_URL}/games/${gameId}`, {
+        const response = await gameFetch(`${API_BASE_URL}/games/${gameId}`, {
             method: 'DELETE',
         });
-        return handleApiResponse<void>(response);
+        await handleApiResponse<void>(response);
+        if (currentGameId === gameId) {
+            currentGameId = null;
+            sessionToken = null;
+        }
+    },
+
+    previewGameMigration: async (gameId: string): Promise<MigrationReport> => {
+        const response = await gameFetch(`${API_BASE_URL}/games/${gameId}/migration`);
+        return handleApiResponse<MigrationReport>(response);
+    },
+
+    exportGame: async (gameId: string): Promise<GameArchive> => {
+        const response = await gameFetch(`${API_BASE_URL}/games/${gameId}/export`);
+        return handleApiResponse<GameArchive>(response);
+    },
+
+    migrateGameStorage: async (gameId: string, backend: StorageBackend): Promise<StorageMigrationReport> => {
+        const response = await gameFetch(`${API_BASE_URL}/games/${gameId}/storage`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ backend }),
+        });
+        return handleApiResponse<StorageMigrationReport>(response);
+    },
+
+    importGame: async (archive: GameArchive): Promise<SavedGame> => {
+        const response = await gameFetch(`${API_BASE_URL}/games/import`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify(archive),
+        });
+        return handleApiResponse<SavedGame>(response);
     },
 
     getPlayerFleets: async (ownerId?: string): Promise<ApiResponse<Fleet[]>> => {
@@ -364,6 +754,59 @@ export const api = {
         }
     },
     async clearCaches(): Promise<void> {
-        await fetch(`${API_BASE_URL}/clear-caches`, { method: 'POST' });
+        await gameFetch(`${API_BASE_URL}/clear-caches`, { method: 'POST' });
+    },
+
+    // Faction endpoints
+    getFactions: async (): Promise<Faction[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/factions`);
+        return handleApiResponse<Faction[]>(response);
+    },
+
+    getFaction: async (name: string): Promise<Faction> => {
+        const response = await gameFetch(`${API_BASE_URL}/factions/${name}`);
+        return handleApiResponse<Faction>(response);
+    },
+
+    getFactionTerritory: async (name: string): Promise<StarSystem[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/factions/${name}/territory`);
+        return handleApiResponse<StarSystem[]>(response);
+    },
+
+    getFactionStanding: async (playerName: string): Promise<FactionStanding[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/factions/standing/${playerName}`);
+        return handleApiResponse<FactionStanding[]>(response);
+    },
+
+    // Mission endpoints
+    getPlanetMissions: async (systemId: number, planetId: number): Promise<Mission[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/missions/planet/${systemId}/${planetId}`);
+        return handleApiResponse<Mission[]>(response);
+    },
+
+    getPlayerMissions: async (playerName: string): Promise<Mission[]> => {
+        const response = await gameFetch(`${API_BASE_URL}/missions/player/${playerName}`);
+        return handleApiResponse<Mission[]>(response);
+    },
+
+    acceptMission: async (missionId: string): Promise<Mission> => {
+        const response = await gameFetch(`${API_BASE_URL}/missions/${missionId}/accept`, { method: 'POST' });
+        return handleApiResponse<Mission>(response);
+    },
+
+    abandonMission: async (missionId: string): Promise<Mission> => {
+        const response = await gameFetch(`${API_BASE_URL}/missions/${missionId}/abandon`, { method: 'POST' });
+        return handleApiResponse<Mission>(response);
+    },
+
+    // Game clock endpoints
+    getGameTime: async (): Promise<GameTime> => {
+        const response = await gameFetch(`${API_BASE_URL}/time`);
+        return handleApiResponse<GameTime>(response);
+    },
+
+    advanceTime: async (ticks: number): Promise<AdvanceTimeReport> => {
+        const response = await gameFetch(`${API_BASE_URL}/time/advance/${ticks}`, { method: 'POST' });
+        return handleApiResponse<AdvanceTimeReport>(response);
     },
 };
\ No newline at end of file
@@ -33,13 +33,15 @@ export interface Weapon {
     MagneticResonanceDisruptor?: { damage: number };
 }
 
+export type CombatState = 'NotInCombat' | 'Aggressive' | 'Default' | 'Evasive' | 'Passive';
+
 export interface Ship {
     name: string;
     owner: string;
     position: Position;
     status: 'OnPlanetRough' | 'Docked' | 'Launching' | 'Landing' | 'OrbitingPlanet' | 'SubLightTravel' | 'Warp' | 'Stationary';
     hp: number;
-    combat_state: 'NotInCombat' | 'Aggressive' | 'Default' | 'Evasive' | 'Passive';
+    combat_state: CombatState;
     specialization: 'Fighter' | 'Battleship' | 'Freighter' | 'Explorer' | 'Shuttle' | 'Capital';
     size: 'Tiny' | 'Small' | 'Medium' | 'Large' | 'Huge' | 'Planetary';
     engine: 'Basic' | 'Advanced' | 'Experimental';
@@ -47,11 +49,48 @@ export interface Ship {
     cargo: Resource[];
     shields: Shield;
     armor: Armor;
+    modules?: Equipment[];
+    refit_until?: number;
     price?: number;
 }
 
+export type EquipmentSlot = 'Hardpoint' | 'Utility' | 'Engine';
+
+export interface Equipment {
+    Weapon?: Weapon;
+    ShieldGenerator?: { capacity: number };
+    ArmorPlating?: { capacity: number };
+    Engine?: 'Basic' | 'Advanced' | 'Experimental';
+}
+
+export interface EquipmentOffer {
+    equipment: Equipment;
+    price: number;
+    stock: number;
+}
+
+export interface EquipmentMarket {
+    offers: EquipmentOffer[];
+}
+
+export interface SlotUsage {
+    hardpoints: number;
+    hardpoints_used: number;
+    utility_slots: number;
+    utility_used: number;
+}
+
+export interface OutfitReceipt {
+    ship_name: string;
+    equipment: string;
+    credits_change: number;
+    ready_tick: number;
+    slots: SlotUsage;
+}
+
 export interface Fleet {
     name: string;
+    display_name?: string;
     owner_id: string;
     ships: Ship[];
     position: Position;
@@ -59,6 +98,77 @@ export interface Fleet {
     current_system_id: number | null;
     last_move_distance?: number;
     transition_message?: string;
+    transit?: FleetTransit | null;
+    trader?: TraderProfile;
+    route?: Route;
+}
+
+export type RouteObjective = 'distance' | 'time' | 'fuel' | 'danger';
+
+export interface Waypoint {
+    system_id: number;
+    star_name: string;
+    planet_id?: number | null;
+    position: Position;
+    local_position: Position;
+    distance: number;
+    ticks: number;
+    fuel: number;
+    danger: number;
+}
+
+export interface RouteEncounter {
+    leg: number;
+    tick: number;
+    system_id: number;
+    fleet_name: string;
+    owner_id: string;
+    ship_count: number;
+}
+
+export interface Route {
+    objective: RouteObjective;
+    jump_range: number;
+    waypoints: Waypoint[];
+    total_distance: number;
+    total_ticks: number;
+    total_fuel: number;
+    total_danger: number;
+    next_leg: number;
+    halted?: string;
+    encounters: RouteEncounter[];
+}
+
+export interface FleetTransit {
+    origin: Position;
+    destination: Position;
+    destination_local?: Position | null;
+    destination_system_id?: number | null;
+    departure_tick: number;
+    arrival_tick: number;
+    distance: number;
+    fuel_used: number;
+    status: 'SubLightTravel' | 'Warp';
+}
+
+export type TraderPersonality = 'Friendly' | 'Neutral' | 'Aggressive';
+
+export interface TradeRoute {
+    resource_type: string;
+    quantity: number;
+    buy_system_id: number;
+    buy_planet_id: number;
+    sell_system_id: number;
+    sell_planet_id: number;
+    purchase_cost: number;
+    expected_revenue: number;
+}
+
+export interface TraderProfile {
+    personality: TraderPersonality;
+    credits: number;
+    route?: TradeRoute | null;
+    total_profit: number;
 }
 
 export interface Planet {
@@ -89,6 +199,27 @@ export interface Player {
     credits: number;
     resources: Resource[];
     fleets: Fleet[];
+    reputation?: Record<string, number>;
+}
+
+export interface Faction {
+    name: string;
+    description: string;
+    reputation: number;
+    credits: number;
+    fleets: string[];
+    relations: Record<string, number>;
+    influence: number;
+    territory: number[];
+    capital_system_id?: number | null;
+}
+
+export type Standing = 'Hostile' | 'Unfriendly' | 'Neutral' | 'Friendly' | 'Allied';
+
+export interface FactionStanding {
+    faction: string;
+    reputation: number;
+    standing: Standing;
 }
 
 export enum ResourceType {
@@ -123,8 +254,83 @@ export interface GameSettings {
     factions: Faction[];
     created_at: string;
     last_played: string;
+    seed?: number;
+    schema_version?: number;
+    storage?: StorageBackend;
+}
+
+export type StorageBackend = 'json' | 'sqlite';
+
+export interface StorageMigrationReport {
+    game_id: string;
+    from: StorageBackend;
+    to: StorageBackend;
+    documents: number;
+}
+
+export type Role = 'player' | 'admin';
+
+export interface Session {
+    token: string;
+    game_id: string;
+    player_name: string;
+    role: Role;
+    expires_at: string;
+}
+
+export type EncounterKind = 'Pirate' | 'Trader' | 'Military' | 'Mercenary';
+
+export type EncounterAction = 'fight' | 'flee' | 'hail' | 'pay_tribute' | 'trade';
+
+export type EncounterStatus = 'Pending' | 'Won' | 'Lost' | 'Escaped' | 'Negotiated' | 'TributePaid' | 'Traded';
+
+export interface Encounter {
+    id: string;
+    kind: EncounterKind;
+    fleet_name: string;
+    owner_id: string;
+    hostile_fleet: string;
+    faction: string | null;
+    system_id: number | null;
+    position: Position;
+    tick: number;
+    status: EncounterStatus;
+    options: EncounterAction[];
+    tribute: number;
+    outcome?: string;
+}
+
+export interface EncounterReport {
+    encounter: Encounter;
+    log: string[];
+    salvage: SalvageField | null;
 }
 
+export interface SalvageField {
+    id: string;
+    claimant: string;
+    position: Position;
+    system_id: number | null;
+    tick: number;
+    cargo: Resource[];
+    wrecks: Ship[];
+    derelicts: Ship[];
+}
+
+export type GameEvent = { tick: number } & (
+    | { type: 'fleet_moved'; fleet: string; owner_id: string; from: Position; from_system_id: number | null; to: Position; to_system_id: number | null; arrival_tick: number | null }
+    | { type: 'fleet_arrived'; fleet: string; owner_id: string; position: Position; system_id: number | null }
+    | { type: 'combat_started'; attacker: string; attacker_owner: string; defender: string; defender_owner: string; position: Position; system_id: number | null }
+    | { type: 'combat_resolved'; attacker: string; attacker_owner: string; defender: string; defender_owner: string; position: Position; system_id: number | null; attacker_victory: boolean; attacker_losses: number; defender_losses: number }
+    | { type: 'market_price_changed'; system_id: number; planet_id: number; resource_type: ResourceType; buy: number | null; sell: number | null }
+    | { type: 'encounter_triggered'; fleet: string; owner_id: string; encounters: string[] }
+    | { type: 'encounter_resolved'; encounter_id: string; fleet: string; owner_id: string; hostile_fleet: string; status: EncounterStatus }
+    | { type: 'mission_updated'; mission: Mission }
+    | { type: 'salvage_left'; field_id: string; claimant: string; position: Position; system_id: number | null }
+);
+
+export type GameEventType = GameEvent['type'];
+
 export interface SavedGame {
     game_id: string;
     created_at: string;
@@ -132,8 +338,129 @@ export interface SavedGame {
     settings: GameSettings;
 }
 
+export interface MigrationStep {
+    from_version: number;
+    to_version: number;
+    description: string;
+    changes: string[];
+}
+
+export interface MigrationReport {
+    game_id: string;
+    from_version: number;
+    to_version: number;
+    dry_run: boolean;
+    steps: MigrationStep[];
+    files_changed: string[];
+}
+
+export interface ArchiveManifest {
+    format: string;
+    format_version: number;
+    game_id: string;
+    display_name: string;
+    exported_at: string;
+    files: string[];
+}
+
+export interface GameArchive {
+    manifest: ArchiveManifest;
+    saved_game: SavedGame;
+    files: Record<string, unknown>;
+}
+
+export interface ResourceProfile {
+    resource_type: ResourceType;
+    base_buy?: number;
+    base_sell?: number;
+    target_stock: number;
+    production: number;
+}
+
 export interface Market {
     resources: Resource[];
+    profiles?: ResourceProfile[];
+}
+
+/** A market as the player last saw it */
+export interface KnownMarket extends Market {
+    observed_tick: number;
+    age: number;
+    stale: boolean;
+}
+
+export interface ScanReport {
+    fleet_name: string;
+    tick: number;
+    range: number;
+    surveyed: number[];
+    discovered: number[];
+}
+
+export interface NearbySystem {
+    system_id: number;
+    position: Position;
+    distance: number;
+}
+
+export interface NearbyFleet {
+    fleet_name: string;
+    position: Position;
+    distance: number;
+}
+
+export interface SegmentHit {
+    key: number;
+    t: number;
+    distance: number;
+}
+
+export interface ShipCargoChange {
+    ship_index: number;
+    ship_name: string;
+    requested: number;
+    moved: number;
+    overflow: number;
+}
+
+export interface CargoReceipt {
+    resource_type: ResourceType;
+    requested: number;
+    moved: number;
+    overflow: number;
+    ships: ShipCargoChange[];
+}
+
+export interface CargoTransfer {
+    resource_type: ResourceType;
+    from_ship: string;
+    to_ship: string;
+    requested: number;
+    moved: number;
+    overflow: number;
+}
+
+export interface ShipRepairQuote {
+    ship_index: number;
+    ship_name: string;
+    hull_points: number;
+    armor_points: number;
+    shield_points: number;
+    cost: number;
+}
+
+export interface RepairEstimate {
+    fleet_name: string;
+    price_factor: number;
+    ships: ShipRepairQuote[];
+    total_cost: number;
+}
+
+export interface MarketQuote {
+    resource_type: ResourceType;
+    quantity: number;
+    buy_total?: number;
+    sell_total?: number;
 }
 
 export interface ShipMarket {
@@ -150,4 +477,39 @@ export interface MoveFleetResponse {
     current_system_id: number | null;
     local_current_position?: Position;
     local_target_position?: Position;
-}
\ No newline at end of file
+    transit?: FleetTransit;
+}
+
+export interface GameTime {
+    tick: number;
+    stardate: number;
+}
+
+export interface AdvanceTimeReport {
+    start_tick: number;
+    end_tick: number;
+    stardate: number;
+    log: string[];
+}
+
+export type MissionKind =
+    | { Transport: { resource_type: ResourceType; quantity: number; destination_system_id: number; destination_planet_id: number; delivered: number } }
+    | { Bounty: { target_fleet: string } }
+    | { Exploration: { system_id: number } };
+
+export type MissionStatus = 'Available' | 'Active' | 'Completed' | 'Failed' | 'Abandoned';
+
+export interface Mission {
+    id: string;
+    title: string;
+    description: string;
+    kind: MissionKind;
+    origin_system_id: number;
+    origin_planet_id: number;
+    reward_credits: number;
+    faction?: string | null;
+    posted_tick: number;
+    deadline_tick: number;
+    status: MissionStatus;
+    accepted_by?: string | null;
+}
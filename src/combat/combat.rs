@@ -1,14 +1,99 @@
 use crate::models::fleet::Fleet;
-use crate::models::ship::ship::{Ship, ShipType, ShipSize, ShipEngine};
+use crate::models::ship::ship::{CombatState, DAMAGED_HULL_THRESHOLD, Ship, ShipType, ShipSize, ShipEngine};
 use crate::models::position::Position;
+use crate::models::resource::Resource;
+use crate::models::rng::next_game_rng;
+use crate::models::travel::ship_speed;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on rounds fought before an engagement is called off.
+pub const MAX_COMBAT_ROUNDS: u32 = 10;
+
+/// Combat power added per point of weapon damage fitted to a ship.
+pub const WEAPON_POWER: f64 = 0.2;
+/// Combat power added per point of shield and armor capacity.
+pub const DEFENSE_POWER: f64 = 0.02;
+
+/// Share of its weapon damage a badly damaged ship still deals.
+pub const DAMAGED_WEAPON_FACTOR: f64 = 0.75;
+
+/// Chance to break off from an enemy exactly as fast as the fleet.
+pub const BASE_ESCAPE_CHANCE: f64 = 0.5;
+/// Change in escape chance per unit of speed the fleet has on its pursuers.
+pub const ESCAPE_CHANCE_PER_SPEED: f64 = 0.004;
+/// Bonus to escape when the whole fleet flies evasively; aggressive
+/// pursuers take the same amount off.
+pub const STANCE_ESCAPE_MODIFIER: f64 = 0.15;
+/// Share of every cargo hold thrown overboard to outrun pursuers.
+pub const RETREAT_JETTISON_SHARE: f64 = 0.25;
+
+/// Multiplier on a ship's firepower from the state of its hull.
+pub fn damage_factor(ship: &Ship) -> f64 {
+    if ship.is_badly_damaged() { DAMAGED_WEAPON_FACTOR } else { 1.0 }
+}
+
+/// Multiplier on the damage a ship deals in the given stance.
+pub fn stance_firepower(stance: CombatState) -> f64 {
+    match stance {
+        CombatState::Aggressive => 1.25,
+        CombatState::Evasive => 0.75,
+        CombatState::Passive => 0.5,
+        CombatState::Default | CombatState::NotInCombat => 1.0,
+    }
+}
+
+/// Multiplier on the damage a ship takes in the given stance.
+pub fn stance_exposure(stance: CombatState) -> f64 {
+    match stance {
+        CombatState::Aggressive => 1.2,
+        CombatState::Evasive => 0.7,
+        CombatState::Default | CombatState::Passive | CombatState::NotInCombat => 1.0,
+    }
+}
+
+/// Standing orders for a fight: the round after which each side tries to
+/// break off. `Some(0)` tries from the very first round.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct CombatOrders {
+    pub attacker_retreat_after: Option<u32>,
+    pub defender_retreat_after: Option<u32>,
+}
 
 #[derive(Debug, Clone)]
 pub struct CombatResult {
     pub attacker_losses: Vec<Ship>,
     pub defender_losses: Vec<Ship>,
     pub attacker_victory: bool,
+    /// The attacker broke off and got away
+    pub attacker_retreated: bool,
+    /// The defender broke off and got away
+    pub defender_retreated: bool,
+    /// Cargo the retreating fleet threw overboard
+    pub jettisoned: Vec<Resource>,
     pub combat_log: Vec<String>,
+    pub rounds: Vec<CombatRound>,
+}
+
+/// Every shot fired during a single round of combat.
+#[derive(Serialize, Debug, Clone)]
+pub struct CombatRound {
+    pub round: u32,
+    pub events: Vec<CombatEvent>,
+}
+
+/// A single weapon hit and how the damage was split between shields,
+/// armor and hull.
+#[derive(Serialize, Debug, Clone)]
+pub struct CombatEvent {
+    pub attacker_ship: String,
+    pub target_ship: String,
+    pub weapon: String,
+    pub damage: i32,
+    pub shield_absorbed: i32,
+    pub armor_absorbed: i32,
+    pub hull_damage: i32,
+    pub destroyed: bool,
 }
 
 pub fn calculate_combat_power(ship: &Ship) -> f64 {
@@ -36,7 +121,12 @@ pub fn calculate_combat_power(ship: &Ship) -> f64 {
         ShipEngine::Experimental => 1.5,
     };
 
-    base_power * size_multiplier * engine_multiplier
+    // Installed equipment counts on top of what the hull itself brings
+    let firepower: i32 = ship.weapons.iter().map(|weapon| weapon.damage()).sum();
+    let defenses = ship.shields.capacity + ship.armor.capacity;
+    let equipment_power = firepower as f64 * WEAPON_POWER + defenses as f64 * DEFENSE_POWER;
+
+    (base_power * size_multiplier * engine_multiplier + equipment_power) * damage_factor(ship)
 }
 
 pub fn calculate_fleet_power(fleet: &Fleet) -> f64 {
@@ -45,77 +135,341 @@ pub fn calculate_fleet_power(fleet: &Fleet) -> f64 {
         .sum()
 }
 
-pub fn auto_resolve_ship_combat(attacker: &mut Fleet, defender: &mut Fleet) -> CombatResult {
-    let mut combat_log = Vec::new();
-    let mut attacker_losses = Vec::new();
-    let mut defender_losses = Vec::new();
+/// Share of a fleet's surviving ships flying in the given stance.
+pub fn stance_share(fleet: &Fleet, stance: CombatState) -> f64 {
+    let living = fleet.ships.iter().filter(|ship| ship.hp > 0).count();
+    if living == 0 {
+        return 0.0;
+    }
+    fleet.ships.iter().filter(|ship| ship.hp > 0 && ship.combat_state == stance).count() as f64 / living as f64
+}
 
-    // Calculate initial fleet powers
-    let attacker_power = calculate_fleet_power(attacker);
-    let defender_power = calculate_fleet_power(defender);
-    
-    combat_log.push(format!("Combat initiated between {} and {}", attacker.name, defender.name));
-    combat_log.push(format!("Initial fleet powers - Attacker: {:.1}, Defender: {:.1}", attacker_power, defender_power));
+/// Puts every ship of a fleet in the given combat stance.
+pub fn set_fleet_stance(fleet: &mut Fleet, stance: CombatState) -> Result<(), String> {
+    if stance == CombatState::NotInCombat {
+        return Err("Choose Aggressive, Default, Evasive or Passive".to_string());
+    }
+    if fleet.ships.is_empty() {
+        return Err(format!("{} has no ships", fleet.name));
+    }
+    for ship in &mut fleet.ships {
+        ship.combat_state = stance;
+    }
+    Ok(())
+}
 
-    // Determine victory based on fleet power and some randomness
-    let mut rng = rand::thread_rng();
-    let random_factor = rng.gen_range(0.8..1.2); // 20% random variation
-    let attacker_victory = attacker_power * random_factor > defender_power;
+/// Chance that `fleet` gets away from `pursuers`.
+///
+/// A fleet flees at the speed of its slowest surviving ship and is chased at
+/// the speed of the fastest pursuer, so engines decide most escapes. Evasive
+/// ships slip away more easily, aggressive pursuers press harder, and passive
+/// ones do not give chase at all.
+pub fn escape_chance(fleet: &Fleet, pursuers: &Fleet) -> f64 {
+    if !has_living_ships(pursuers) || stance_share(pursuers, CombatState::Passive) == 1.0 {
+        return 1.0;
+    }
+    let speed = living_speed(fleet);
+    let pursuit = pursuers.ships.iter().filter(|ship| ship.hp > 0).map(ship_speed).fold(0.0, f64::max);
+    let chance = BASE_ESCAPE_CHANCE
+        + (speed - pursuit) * ESCAPE_CHANCE_PER_SPEED
+        + stance_share(fleet, CombatState::Evasive) * STANCE_ESCAPE_MODIFIER
+        - stance_share(pursuers, CombatState::Aggressive) * STANCE_ESCAPE_MODIFIER;
+    chance.clamp(0.05, 0.95)
+}
 
-    if attacker_victory {
-        // Attacker wins, defender takes more losses
-        let defender_loss_ratio = rng.gen_range(0.6..0.9);
-        let attacker_loss_ratio = rng.gen_range(0.2..0.4);
+/// Resolves a fight between two fleets using the active game's "combat" RNG
+/// stream, so replaying a game with the same seed replays its battles.
+///
+/// See [`auto_resolve_ship_combat_with_rng`] for the rules.
+pub fn auto_resolve_ship_combat(attacker: &mut Fleet, defender: &mut Fleet) -> Result<CombatResult, String> {
+    Ok(auto_resolve_ship_combat_with_rng(attacker, defender, &mut next_game_rng("combat")?))
+}
 
-        // Apply losses
-        let defender_loss_count = (defender.ships.len() as f64 * defender_loss_ratio) as usize;
-        let attacker_loss_count = (attacker.ships.len() as f64 * attacker_loss_ratio) as usize;
+/// Resolves a fight between two fleets with no retreat orders; fleets only
+/// break off when their stance tells them to.
+///
+/// See [`auto_resolve_ship_combat_with_orders`] for the rules.
+pub fn auto_resolve_ship_combat_with_rng<R: Rng + ?Sized>(attacker: &mut Fleet, defender: &mut Fleet, rng: &mut R) -> CombatResult {
+    auto_resolve_ship_combat_with_orders(attacker, defender, CombatOrders::default(), rng)
+}
 
-        for _ in 0..defender_loss_count {
-            if let Some(ship) = defender.ships.pop() {
-                defender_losses.push(ship);
-            }
+/// Resolves a fight between two fleets in rounds.
+///
+/// Each round every surviving ship fires each of its weapons at a surviving
+/// enemy: aggressive ships focus on the most battered target, everyone else
+/// picks one at random. Damage is scaled by the shooter's stance, the
+/// target's stance and the shooter's hull (badly damaged ships hit for
+/// `DAMAGED_WEAPON_FACTOR`), then soaked by shields first, then armor, and
+/// only what gets past both reduces hull points; a ship is destroyed when its
+/// hull reaches zero. Shields regenerate between rounds. The fight ends when
+/// one side is wiped out or gets away, nobody can land a hit, or
+/// `MAX_COMBAT_ROUNDS` is reached.
+///
+/// At the start of a round a fleet tries to break off when its orders say
+/// so, when it is mostly passive, or when it is mostly evasive and badly
+/// battered. The attempt succeeds with [`escape_chance`]; a fleet that fails
+/// spends the round turning to run and does not fire. A fleet that gets away
+/// takes a parting volley from aggressive enemies fast enough to keep up and
+/// jettisons `RETREAT_JETTISON_SHARE` of its cargo, and loses the fight.
+///
+/// Destroyed ships are moved out of the fleets into the loss lists; survivors
+/// stay in their fleets with whatever shields, armor and hull they have left.
+///
+/// # Arguments
+/// * `attacker` - The fleet initiating combat
+/// * `defender` - The fleet being attacked
+/// * `orders` - When each side tries to retreat
+/// * `rng` - Random source used for targeting, damage and escape rolls
+///
+/// # Returns
+/// A `CombatResult` with losses, the outcome and a per-round event record
+pub fn auto_resolve_ship_combat_with_orders<R: Rng + ?Sized>(attacker: &mut Fleet, defender: &mut Fleet, orders: CombatOrders, rng: &mut R) -> CombatResult {
+    let mut combat_log = Vec::new();
+    let mut rounds = Vec::new();
+    let mut attacker_damage_dealt = 0;
+    let mut defender_damage_dealt = 0;
+    let mut attacker_retreated = false;
+    let mut defender_retreated = false;
+    let mut jettisoned = Vec::new();
+
+    combat_log.push(format!("Combat initiated between {} and {}", attacker.name, defender.name));
+    combat_log.push(format!("Initial fleet powers - Attacker: {:.1}, Defender: {:.1}",
+        calculate_fleet_power(attacker), calculate_fleet_power(defender)));
+
+    for round in 1..=MAX_COMBAT_ROUNDS {
+        if !has_living_ships(attacker) || !has_living_ships(defender) {
+            break;
         }
 
-        for _ in 0..attacker_loss_count {
-            if let Some(ship) = attacker.ships.pop() {
-                attacker_losses.push(ship);
+        // Fleets breaking off try to get away before the shooting starts
+        let attacker_running = wants_to_retreat(attacker, orders.attacker_retreat_after, round);
+        let defender_running = wants_to_retreat(defender, orders.defender_retreat_after, round);
+        let mut escape = None;
+        if attacker_running {
+            escape = attempt_retreat(attacker, defender, rng).map(|getaway| (true, getaway));
+        }
+        if escape.is_none() && defender_running {
+            escape = attempt_retreat(defender, attacker, rng).map(|getaway| (false, getaway));
+        }
+        if let Some((attacker_fled, (pursuit, dropped))) = escape {
+            let fleeing = if attacker_fled { &*attacker } else { &*defender };
+            if has_living_ships(fleeing) {
+                combat_log.push(format!("Round {}: {} broke off and got away", round, fleeing.name));
+                attacker_retreated = attacker_fled;
+                defender_retreated = !attacker_fled;
+            } else {
+                combat_log.push(format!("Round {}: {} was run down as it tried to get away", round, fleeing.name));
+            }
+            log_events(&mut combat_log, &pursuit);
+            for resource in &dropped {
+                combat_log.push(format!("  {} jettisoned {} {}", fleeing.name, resource.quantity.unwrap_or(0), resource.resource_type));
             }
+            rounds.push(CombatRound { round, events: pursuit });
+            jettisoned = dropped;
+            break;
         }
 
-        combat_log.push(format!("Attacker victorious! Defender lost {} ships, Attacker lost {} ships", 
-            defender_loss_count, attacker_loss_count));
-    } else {
-        // Defender wins, attacker takes more losses
-        let attacker_loss_ratio = rng.gen_range(0.6..0.9);
-        let defender_loss_ratio = rng.gen_range(0.2..0.4);
+        // Attackers fire first; defenders destroyed by that volley do not
+        // return fire, and neither does a side that failed to get away
+        let mut events = if attacker_running { Vec::new() } else { fire_volley(&attacker.ships, &mut defender.ships, rng) };
+        attacker_damage_dealt += events.iter().map(|e| e.damage).sum::<i32>();
+        let defender_events = if defender_running { Vec::new() } else { fire_volley(&defender.ships, &mut attacker.ships, rng) };
+        defender_damage_dealt += defender_events.iter().map(|e| e.damage).sum::<i32>();
+        events.extend(defender_events);
 
-        // Apply losses
-        let attacker_loss_count = (attacker.ships.len() as f64 * attacker_loss_ratio) as usize;
-        let defender_loss_count = (defender.ships.len() as f64 * defender_loss_ratio) as usize;
+        if attacker_running || defender_running {
+            let running = if attacker_running { &attacker.name } else { &defender.name };
+            combat_log.push(format!("Round {}: {} failed to break off", round, running));
+        }
 
-        for _ in 0..attacker_loss_count {
-            if let Some(ship) = attacker.ships.pop() {
-                attacker_losses.push(ship);
-            }
+        if events.is_empty() {
+            combat_log.push(format!("Round {}: neither side could land a hit, combat ends", round));
+            break;
         }
 
-        for _ in 0..defender_loss_count {
-            if let Some(ship) = defender.ships.pop() {
-                defender_losses.push(ship);
+        combat_log.push(format!("Round {}: {} hits, {} ships destroyed",
+            round, events.len(), events.iter().filter(|e| e.destroyed).count()));
+        log_events(&mut combat_log, &events);
+
+        rounds.push(CombatRound { round, events });
+
+        // Shields recharge between rounds; armor and hull do not
+        for ship in attacker.ships.iter_mut().chain(defender.ships.iter_mut()) {
+            if ship.hp > 0 {
+                ship.shields.regenerate();
             }
         }
-
-        combat_log.push(format!("Defender victorious! Attacker lost {} ships, Defender lost {} ships", 
-            attacker_loss_count, defender_loss_count));
     }
 
+    let attacker_losses = remove_destroyed(attacker);
+    let defender_losses = remove_destroyed(defender);
+
+    let attacker_victory = match (attacker.ships.is_empty(), defender.ships.is_empty()) {
+        _ if attacker_retreated => false,
+        _ if defender_retreated => true,
+        (false, true) => true,
+        (true, false) => false,
+        _ => attacker_damage_dealt > defender_damage_dealt,
+    };
+
+    combat_log.push(format!("{} victorious! Attacker lost {} ships, Defender lost {} ships",
+        if attacker_victory { "Attacker" } else { "Defender" },
+        attacker_losses.len(), defender_losses.len()));
+
     CombatResult {
         attacker_losses,
         defender_losses,
         attacker_victory,
+        attacker_retreated,
+        defender_retreated,
+        jettisoned,
         combat_log,
+        rounds,
+    }
+}
+
+/// Applies a single hit to a ship, routing it through shields, armor and hull.
+///
+/// # Returns
+/// The `(shield_absorbed, armor_absorbed, hull_damage)` split of the hit
+pub fn apply_damage(ship: &mut Ship, damage: i32) -> (i32, i32, i32) {
+    let after_shields = ship.shields.calculate_damage(damage);
+    let after_armor = ship.armor.calculate_damage(after_shields);
+    let hull_damage = after_armor.min(ship.hp.max(0));
+    ship.hp -= hull_damage;
+
+    (damage - after_shields, after_shields - after_armor, hull_damage)
+}
+
+fn has_living_ships(fleet: &Fleet) -> bool {
+    fleet.ships.iter().any(|ship| ship.hp > 0)
+}
+
+/// Speed of a fleet's slowest surviving ship.
+fn living_speed(fleet: &Fleet) -> f64 {
+    fleet.ships.iter()
+        .filter(|ship| ship.hp > 0)
+        .map(ship_speed)
+        .min_by(|a, b| a.total_cmp(b))
+        .unwrap_or(0.0)
+}
+
+/// Surviving hull of a fleet as a share of what it had undamaged, counting
+/// destroyed ships as empty.
+fn fleet_hull_fraction(fleet: &Fleet) -> f64 {
+    let max_hull: i32 = fleet.ships.iter().map(|ship| ship.max_hp()).sum();
+    if max_hull <= 0 {
+        return 0.0;
+    }
+    fleet.ships.iter().map(|ship| ship.hp.max(0)).sum::<i32>() as f64 / max_hull as f64
+}
+
+/// Whether a fleet tries to break off at the start of `round`: when ordered
+/// to, when mostly passive, or when mostly evasive and badly battered.
+fn wants_to_retreat(fleet: &Fleet, retreat_after: Option<u32>, round: u32) -> bool {
+    retreat_after.is_some_and(|after| round > after)
+        || stance_share(fleet, CombatState::Passive) > 0.5
+        || (stance_share(fleet, CombatState::Evasive) > 0.5 && fleet_hull_fraction(fleet) < DAMAGED_HULL_THRESHOLD)
+}
+
+/// Rolls for `fleet` getting away from `enemy`. On success aggressive
+/// enemies at least as fast as the fleet fire a parting volley, and the
+/// survivors throw part of their cargo overboard to outrun them.
+///
+/// # Returns
+/// The pursuit volley and the jettisoned cargo, or `None` if the fleet
+/// could not get away
+fn attempt_retreat<R: Rng + ?Sized>(fleet: &mut Fleet, enemy: &Fleet, rng: &mut R) -> Option<(Vec<CombatEvent>, Vec<Resource>)> {
+    if !rng.gen_bool(escape_chance(fleet, enemy)) {
+        return None;
     }
+    let speed = living_speed(fleet);
+    let pursuers: Vec<Ship> = enemy.ships.iter()
+        .filter(|ship| ship.hp > 0 && ship.combat_state == CombatState::Aggressive && ship_speed(ship) >= speed)
+        .cloned()
+        .collect();
+    let pursuit = fire_volley(&pursuers, &mut fleet.ships, rng);
+    Some((pursuit, jettison_cargo(fleet, RETREAT_JETTISON_SHARE)))
+}
+
+/// Throws `share` of every cargo stack on the fleet's surviving ships
+/// overboard, rounding up so that fleeing always costs something.
+///
+/// # Returns
+/// What was thrown overboard, one entry per resource type
+pub fn jettison_cargo(fleet: &mut Fleet, share: f64) -> Vec<Resource> {
+    let mut dropped: Vec<Resource> = Vec::new();
+    for ship in fleet.ships.iter_mut().filter(|ship| ship.hp > 0) {
+        for stack in &mut ship.cargo {
+            let quantity = stack.quantity.unwrap_or(0);
+            let amount = ((quantity as f64 * share).ceil() as u32).min(quantity);
+            if amount == 0 {
+                continue;
+            }
+            stack.quantity = Some(quantity - amount);
+            match dropped.iter_mut().find(|resource| resource.resource_type == stack.resource_type) {
+                Some(resource) => resource.quantity = Some(resource.quantity.unwrap_or(0) + amount),
+                None => dropped.push(Resource::new(stack.resource_type, amount)),
+            }
+        }
+        ship.cargo.retain(|stack| stack.quantity.unwrap_or(0) > 0);
+    }
+    dropped
+}
+
+fn log_events(combat_log: &mut Vec<String>, events: &[CombatEvent]) {
+    for event in events {
+        combat_log.push(format!("  {} hit {} with {} for {} (shields {}, armor {}, hull {}){}",
+            event.attacker_ship, event.target_ship, event.weapon, event.damage,
+            event.shield_absorbed, event.armor_absorbed, event.hull_damage,
+            if event.destroyed { " - destroyed" } else { "" }));
+    }
+}
+
+/// Every living ship in `shooters` fires each of its weapons at a living
+/// ship in `targets`: the most battered one for aggressive shooters, a
+/// random one otherwise.
+fn fire_volley<R: Rng + ?Sized>(shooters: &[Ship], targets: &mut [Ship], rng: &mut R) -> Vec<CombatEvent> {
+    let mut events = Vec::new();
+
+    for shooter in shooters.iter().filter(|ship| ship.hp > 0) {
+        for weapon in &shooter.weapons {
+            let living: Vec<usize> = (0..targets.len()).filter(|&i| targets[i].hp > 0).collect();
+            if living.is_empty() {
+                return events;
+            }
+
+            let index = if shooter.combat_state == CombatState::Aggressive {
+                living.iter().copied().min_by_key(|&i| targets[i].hp).unwrap_or(living[0])
+            } else {
+                living[rng.gen_range(0..living.len())]
+            };
+            let target = &mut targets[index];
+            let firepower = weapon.damage() as f64 * damage_factor(shooter) * stance_firepower(shooter.combat_state);
+            let damage = (firepower * stance_exposure(target.combat_state) * rng.gen_range(0.8..=1.2)).round() as i32;
+            let (shield_absorbed, armor_absorbed, hull_damage) = apply_damage(target, damage);
+
+            events.push(CombatEvent {
+                attacker_ship: shooter.name.clone(),
+                target_ship: target.name.clone(),
+                weapon: weapon.name().to_string(),
+                damage,
+                shield_absorbed,
+                armor_absorbed,
+                hull_damage,
+                destroyed: target.hp <= 0,
+            });
+        }
+    }
+
+    events
+}
+
+/// Moves destroyed ships out of the fleet and returns them.
+fn remove_destroyed(fleet: &mut Fleet) -> Vec<Ship> {
+    let (destroyed, survivors): (Vec<Ship>, Vec<Ship>) = fleet.ships.drain(..).partition(|ship| ship.hp <= 0);
+    fleet.ships = survivors;
+    destroyed
 }
 
 pub fn can_engage_combat(attacker: &Fleet, defender: &Fleet) -> bool {
@@ -1,8 +1,3 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Mutex;
-use crate::models::star_system::StarSystem;
-use lazy_static::lazy_static;
-
 // Core game constants
 pub const PRINT_DEBUG: bool = true;
 
@@ -14,7 +9,3 @@ pub const PRINT_DEBUG: bool = true;
 //pub(crate) const HOST_PLAYER_ID: u32 = 0;\
 //pub const HOST_PLAYER_NAME: &str = "Player";
 
-lazy_static! {
-    pub static ref GLOBAL_GAME_WORLD: Mutex<Vec<StarSystem>> = Mutex::new(Vec::new());
-    pub static ref GAME_GENERATED: AtomicBool = AtomicBool::new(false);
-}
\ No newline at end of file
@@ -21,42 +21,54 @@ pub struct EncounterFleet {
 /// 
 /// # Arguments
 /// * `position` - The position where the encounter fleet should be generated
+/// * `rng` - The random number generator to draw from
 /// 
 /// # Returns
 /// An `EncounterFleet` containing randomly generated ships based on the fleet type
-pub fn generate_encounter_fleet(position: Position) -> EncounterFleet {
-    let mut rng = rand::thread_rng();
-    
+pub fn generate_encounter_fleet<R: Rng + ?Sized>(position: Position, rng: &mut R) -> EncounterFleet {
+
     // Generate a random fleet type with adjusted probabilities for combat encounters
     let fleet_types = vec![
-        ("Pirate", 1, 3, 0.4),    // 40% chance, 1-3 ships
-        ("Trader", 2, 4, 0.2),    // 20% chance, 2-4 ships
-        ("Military", 3, 5, 0.3),  // 30% chance, 3-5 ships
-        ("Mercenary", 2, 4, 0.1), // 10% chance, 2-4 ships
+        ("Pirate", 0.4),    // 40% chance
+        ("Trader", 0.2),    // 20% chance
+        ("Military", 0.3),  // 30% chance
+        ("Mercenary", 0.1), // 10% chance
     ];
     
     // Select fleet type based on probability
-    let roll = rand::random::<f64>();
+    let roll = rng.gen_range(0.0..1.0);
     let mut cumulative = 0.0;
-    let (fleet_type, min_ships, max_ships, _) = fleet_types.iter()
-        .find(|(_, _, _, prob)| {
+    let (fleet_type, _) = fleet_types.iter()
+        .find(|(_, prob)| {
             cumulative += prob;
             roll <= cumulative
         })
         .unwrap_or(&fleet_types[0]);
     
-    let ship_count = rng.gen_range(*min_ships..=*max_ships);
+    generate_fleet_of_type(fleet_type, position, rng)
+}
+
+/// Generates an encounter fleet of a given type: "Pirate" (1-3 ships),
+/// "Trader" (2-4), "Military" (3-5) or "Mercenary" (2-4). Unknown types
+/// get trader ships.
+pub fn generate_fleet_of_type<R: Rng + ?Sized>(fleet_type: &str, position: Position, rng: &mut R) -> EncounterFleet {
+    let (min_ships, max_ships) = match fleet_type {
+        "Pirate" => (1, 3),
+        "Military" => (3, 5),
+        _ => (2, 4),
+    };
+    let ship_count = rng.gen_range(min_ships..=max_ships);
     
     let mut ships = Vec::new();
     
     // Generate ships based on fleet type
     for _ in 0..ship_count {
-        let ship = match *fleet_type {
-            "Pirate" => generate_pirate_ship(),
-            "Trader" => generate_trader_ship(),
-            "Military" => generate_military_ship(),
-            "Mercenary" => generate_mercenary_ship(),
-            _ => generate_trader_ship(),
+        let ship = match fleet_type {
+            "Pirate" => generate_pirate_ship(rng),
+            "Trader" => generate_trader_ship(rng),
+            "Military" => generate_military_ship(rng),
+            "Mercenary" => generate_mercenary_ship(rng),
+            _ => generate_trader_ship(rng),
         };
         ships.push(ship);
     }
@@ -102,8 +114,7 @@ fn calculate_ship_price(ship: &Ship) -> f64 {
 /// 
 /// # Returns
 /// A `Ship` configured for combat with high damage weapons and balanced defenses
-fn generate_pirate_ship() -> Ship {
-    let mut rng = rand::thread_rng();
+fn generate_pirate_ship<R: Rng + ?Sized>(rng: &mut R) -> Ship {
     let mut ship = Ship {
         name: format!("Pirate_Ship_{}", rng.gen_range(1000..9999)),
         owner: format!("Pirate_{}", rng.gen_range(1000..9999)),
@@ -122,6 +133,8 @@ fn generate_pirate_ship() -> Ship {
         ],
         armor: Armor::new(75),
         price: None, // Pirate ships are not for sale
+        modules: Vec::new(),
+        refit_until: None,
     };
     ship.price = Some(calculate_ship_price(&ship));
     ship
@@ -131,8 +144,7 @@ fn generate_pirate_ship() -> Ship {
 /// 
 /// # Returns
 /// A `Ship` configured for trading with cargo space and minimal combat capabilities
-fn generate_trader_ship() -> Ship {
-    let mut rng = rand::thread_rng();
+fn generate_trader_ship<R: Rng + ?Sized>(rng: &mut R) -> Ship {
     let mut cargo = Vec::new();
     
     // Generate 2-4 random resources for each trader ship
@@ -185,6 +197,8 @@ fn generate_trader_ship() -> Ship {
         ],
         armor: Armor::new(50),
         price: None, // Will be set below
+        modules: Vec::new(),
+        refit_until: None,
     };
     ship.price = Some(calculate_ship_price(&ship));
     ship
@@ -194,8 +208,7 @@ fn generate_trader_ship() -> Ship {
 /// 
 /// # Returns
 /// A `Ship` configured for military operations with powerful weapons and heavy defenses
-fn generate_military_ship() -> Ship {
-    let mut rng = rand::thread_rng();
+fn generate_military_ship<R: Rng + ?Sized>(rng: &mut R) -> Ship {
     let mut ship = Ship {
         name: format!("Military_Ship_{}", rng.gen_range(1000..9999)),
         owner: format!("Military_{}", rng.gen_range(1000..9999)),
@@ -215,6 +228,8 @@ fn generate_military_ship() -> Ship {
         ],
         armor: Armor::new(150),
         price: None, // Will be set below
+        modules: Vec::new(),
+        refit_until: None,
     };
     ship.price = Some(calculate_ship_price(&ship));
     ship
@@ -224,8 +239,7 @@ fn generate_military_ship() -> Ship {
 /// 
 /// # Returns
 /// A `Ship` configured for mercenary operations with versatile weapons and defenses
-fn generate_mercenary_ship() -> Ship {
-    let mut rng = rand::thread_rng();
+fn generate_mercenary_ship<R: Rng + ?Sized>(rng: &mut R) -> Ship {
     let mut ship = Ship {
         name: format!("Mercenary_Ship_{}", rng.gen_range(1000..9999)),
         owner: format!("Mercenary_{}", rng.gen_range(1000..9999)),
@@ -244,6 +258,8 @@ fn generate_mercenary_ship() -> Ship {
         ],
         armor: Armor::new(100),
         price: None, // Mercenary ships are not for sale
+        modules: Vec::new(),
+        refit_until: None,
     };
     ship.price = Some(calculate_ship_price(&ship));
     ship
@@ -3,12 +3,38 @@ pub mod constants;
 pub mod combat;
 pub mod encounters;
 pub mod routes;
-pub mod game_state;
+pub mod simulation;
 
 pub use constants::*;
 
 #[cfg(test)]
 mod tests {
+    mod fixtures;
+    mod test_archive;
+    mod test_auth;
+    mod test_cargo;
+    mod test_clock;
+    mod test_combat;
+    mod test_context;
+    mod test_encounter;
+    mod test_events;
+    mod test_exploration;
+    mod test_faction;
+    mod test_fleet_management;
     mod test_fleet_movement;
+    mod test_market;
+    mod test_migration;
+    mod test_mission;
+    mod test_npc_trader;
+    mod test_outfitting;
+    mod test_persistence;
     mod test_position;
+    mod test_repair;
+    mod test_rng;
+    mod test_route;
+    mod test_salvage;
+    mod test_shipyard;
+    mod test_spatial;
+    mod test_storage;
+    mod test_travel;
 } 
\ No newline at end of file
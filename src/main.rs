@@ -3,6 +3,7 @@ pub mod models;
 pub mod routes;
 pub mod combat;
 pub mod encounters;
+pub mod simulation;
 
 use rocket::routes;
 use rocket::catchers;
@@ -10,21 +11,12 @@ use rocket::catchers;
 use std::env;
 
 use crate::routes::*;
-use crate::models::star_system::StarSystem;
+use crate::models::context::GameRegistry;
+use crate::models::auth::SessionStore;
 
 use rocket_cors::{AllowedOrigins, CorsOptions, AllowedHeaders};
 use rocket::fs::FileServer;
 
-use crate::constants::GLOBAL_GAME_WORLD;
-
-pub(crate) fn get_global_game_world() -> Vec<StarSystem> {
-    if let Ok(guard) = GLOBAL_GAME_WORLD.lock() {
-        guard.clone()
-    } else {
-        Vec::new()
-    }
-}
-
 /// The main entry point for the Rocket application.
 ///
 /// This function is responsible for launching the Rocket server and mounting
@@ -60,8 +52,15 @@ async fn main() {
             routes::get_fleet_owners,
             routes::initiate_combat,
             routes::check_for_encounter,
+            routes::get_encounters,
+            routes::resolve_encounter_action,
+            routes::get_salvage,
+            routes::loot_salvage,
+            routes::capture_salvage,
+            routes::scrap_salvage,
             routes::trade_with_trader,
             routes::get_planet_market,
+            routes::get_market_quote,
             routes::get_planet_ship_market,
             routes::buy_from_planet,
             routes::sell_to_planet,
@@ -76,12 +75,56 @@ async fn main() {
             routes::trade_in_ship,
             routes::get_player_fleets,
             routes::clear_caches,
+            routes::get_game_time,
+            routes::advance_time,
+            routes::get_factions,
+            routes::get_faction,
+            routes::get_faction_territory,
+            routes::get_faction_standing,
+            routes::get_planet_missions,
+            routes::get_player_missions,
+            routes::accept_mission,
+            routes::abandon_mission,
+            routes::get_npc_traders,
+            routes::export_game,
+            routes::import_game,
+            routes::preview_game_migration,
+            routes::get_repair_quote,
+            routes::repair_at_planet,
+            routes::get_equipment_market,
+            routes::install_equipment,
+            routes::uninstall_equipment,
+            routes::transfer_fleet_cargo,
+            routes::split_fleet,
+            routes::merge_fleet,
+            routes::rename_fleet,
+            routes::set_fleet_stance,
+            routes::transfer_fleet_ships,
+            routes::plan_fleet_route,
+            routes::follow_fleet_route,
+            routes::resume_fleet_route,
+            routes::cancel_fleet_route,
+            routes::scan_with_fleet,
+            routes::get_nearest_system,
+            routes::get_fleets_near,
+            routes::get_systems_along,
+            routes::migrate_game_storage,
+            routes::register_player,
+            routes::issue_player_claim,
+            routes::login_player,
+            routes::logout_player,
+            routes::get_session,
+            routes::stream_events,
         ])
+        .manage(GameRegistry::default())
+        .manage(SessionStore::default())
         .attach(cors)
         .register("/", catchers![internal_error])
         .configure(rocket::Config::figment()
             .merge(("address", "0.0.0.0"))
-            .merge(("port", 8000)))
+            .merge(("port", 8000))
+            // Imported game archives are far larger than any other request
+            .merge(("limits.json", "64 MiB")))
         .launch()
         .await;
 }
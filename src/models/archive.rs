@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::fs;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::models::faction::Faction;
+use crate::models::fleet::Fleet;
+use crate::models::game_state::game_data_path;
+use crate::models::migration::{migrate_game, schema_version};
+use crate::models::persistence::{Transaction, recover_game};
+use crate::models::storage::{init_storage, read_document, storage_for};
+use crate::models::player::Player;
+use crate::models::settings::{GameSettings, SavedGame};
+use crate::models::star_system::StarSystem;
+
+/// Identifies a file as a Star Trader game archive.
+pub const ARCHIVE_FORMAT: &str = "star_trader_game_archive";
+
+/// Version of the archive layout written by this server. Archives with any
+/// other version are refused.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Directories of a game that never leave the server, such as the players'
+/// logins and password hashes and the codes for claiming players.
+pub const PRIVATE_DIRECTORIES: &[&str] = &["accounts", "claims"];
+
+fn is_private(path: &str) -> bool {
+    PRIVATE_DIRECTORIES.iter().any(|directory| path.starts_with(&format!("{}/", directory)))
+}
+
+/// Describes what an archive contains.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub format_version: u32,
+    /// The id the game had on the server it was exported from
+    pub game_id: String,
+    pub display_name: String,
+    pub exported_at: String,
+    /// Paths of every file in the archive, relative to the game directory
+    pub files: Vec<String>,
+}
+
+/// A whole game in one self-contained JSON document.
+///
+/// Every save file under `data/game/<game_id>/` is stored by its relative
+/// path, using `/` as separator, next to the game's entry in the save list.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameArchive {
+    pub manifest: ArchiveManifest,
+    pub saved_game: SavedGame,
+    pub files: BTreeMap<String, serde_json::Value>,
+}
+
+/// Packs a saved game into an archive.
+///
+/// Interrupted saves are recovered first, so the archive only ever holds
+/// committed data. Torn save files make the export fail rather than ship a
+/// broken game. Player accounts are left out; players register again on
+/// the server the game is imported into.
+///
+/// # Arguments
+/// * `game_id` - The ID of the game to export
+///
+/// # Returns
+/// The archive, or an error if the game does not exist or cannot be read
+pub fn export_game(game_id: &str) -> Result<GameArchive, String> {
+    let saved_game = SavedGame::load_game(game_id)
+        .map_err(|e| format!("Failed to load saved game: {}", e))?
+        .ok_or_else(|| format!("Game {} not found", game_id))?;
+
+    let game_dir = game_data_path(game_id, &[]);
+    let recovery = recover_game(&game_dir).map_err(|e| format!("Failed to recover game data: {}", e))?;
+    if !recovery.torn_files.is_empty() {
+        return Err(format!("Cannot export a game with torn save files: {}", recovery.torn_files.join(", ")));
+    }
+
+    let storage = storage_for(game_id)?;
+    let mut files = BTreeMap::new();
+    for name in storage.keys()?.into_iter().filter(|name| !is_private(name)) {
+        let value: serde_json::Value = read_document(storage.as_ref(), &name)?
+            .ok_or_else(|| format!("{} vanished during export", name))?;
+        files.insert(name, value);
+    }
+
+    Ok(GameArchive {
+        manifest: ArchiveManifest {
+            format: ARCHIVE_FORMAT.to_string(),
+            format_version: ARCHIVE_FORMAT_VERSION,
+            game_id: game_id.to_string(),
+            display_name: saved_game.display_name.clone(),
+            exported_at: Utc::now().to_rfc3339(),
+            files: files.keys().cloned().collect(),
+        },
+        saved_game,
+        files,
+    })
+}
+
+/// A path is safe to restore if it stays inside the game directory and
+/// names a JSON file.
+fn is_safe_path(path: &str) -> bool {
+    path.ends_with(".json")
+        && !path.contains('\\')
+        && !path.contains(':')
+        && path.split('/').all(|component| !component.is_empty() && component != "." && component != "..")
+}
+
+fn parse_file<T: DeserializeOwned>(archive: &GameArchive, path: &str, problems: &mut Vec<String>) -> Option<T> {
+    let value = archive.files.get(path)?;
+    match serde_json::from_value(value.clone()) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            problems.push(format!("{} is invalid: {}", path, e));
+            None
+        }
+    }
+}
+
+fn check_directory<T: DeserializeOwned>(archive: &GameArchive, directory: &str, problems: &mut Vec<String>) {
+    let prefix = format!("{}/", directory);
+    for path in archive.files.keys().filter(|path| path.starts_with(&prefix)) {
+        parse_file::<T>(archive, path, problems);
+    }
+}
+
+impl GameArchive {
+    /// Checks that the archive is complete and that its files hold what the
+    /// game expects before anything is written to disk.
+    ///
+    /// # Returns
+    /// The settings of the archived game, or every problem found
+    pub fn validate(&self) -> Result<GameSettings, String> {
+        let manifest = &self.manifest;
+        if manifest.format != ARCHIVE_FORMAT {
+            return Err(format!("Not a game archive (format {:?})", manifest.format));
+        }
+        if manifest.format_version != ARCHIVE_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported archive format version {} (this server reads version {})",
+                manifest.format_version, ARCHIVE_FORMAT_VERSION
+            ));
+        }
+
+        let mut problems = Vec::new();
+        let listed: Vec<&String> = manifest.files.iter().collect();
+        let contained: Vec<&String> = self.files.keys().collect();
+        if listed != contained {
+            problems.push("Manifest file list does not match the archive contents".to_string());
+        }
+        for path in self.files.keys().filter(|path| !is_safe_path(path)) {
+            problems.push(format!("Invalid file path {:?}", path));
+        }
+        for path in self.files.keys().filter(|path| is_private(path)) {
+            problems.push(format!("Archives may not contain {}", path));
+        }
+        if self.saved_game.game_id != manifest.game_id {
+            problems.push("Saved game entry belongs to a different game".to_string());
+        }
+
+        let settings = parse_file::<GameSettings>(self, "settings.json", &mut problems);
+        match &settings {
+            Some(settings) => {
+                if settings.game_id != manifest.game_id {
+                    problems.push("settings.json belongs to a different game".to_string());
+                }
+                if let Err(e) = schema_version(&self.files["settings.json"]) {
+                    problems.push(e);
+                }
+                let player_path = format!("players/{}.json", settings.player_name);
+                if !self.files.contains_key(&player_path) {
+                    problems.push(format!("Missing {}", player_path));
+                }
+            }
+            None if !self.files.contains_key("settings.json") => problems.push("Missing settings.json".to_string()),
+            None => {}
+        }
+        check_directory::<Player>(self, "players", &mut problems);
+        check_directory::<Fleet>(self, "fleets", &mut problems);
+        check_directory::<StarSystem>(self, "star_systems", &mut problems);
+        check_directory::<Faction>(self, "factions", &mut problems);
+
+        match settings {
+            Some(settings) if problems.is_empty() => Ok(settings),
+            _ => Err(format!("Invalid game archive: {}", problems.join("; "))),
+        }
+    }
+}
+
+/// Restores an archive as a new game.
+///
+/// The game gets a fresh id so an archive can be imported next to the game
+/// it came from, or several times over, and is migrated to the current save
+/// format. All files are written in a single
+/// transaction, so a failed import leaves no partial game behind.
+///
+/// # Arguments
+/// * `archive` - The archive to import
+///
+/// # Returns
+/// The save list entry of the imported game
+pub fn import_game(archive: GameArchive) -> Result<SavedGame, String> {
+    let mut settings = archive.validate()?;
+    let game_id = uuid::Uuid::new_v4().to_string();
+    settings.game_id = game_id.clone();
+
+    let mut files = archive.files;
+    files.insert(
+        "settings.json".to_string(),
+        serde_json::to_value(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?,
+    );
+    // The game keeps the storage backend it had where it was exported
+    init_storage(&game_id, settings.storage)?;
+    let mut transaction = Transaction::new(&game_id);
+    for (path, value) in &files {
+        let components: Vec<&str> = path.split('/').collect();
+        transaction.stage(&components, value)?;
+    }
+    transaction.commit()?;
+
+    let saved_game = SavedGame {
+        game_id: game_id.clone(),
+        settings,
+        ..archive.saved_game
+    };
+    if let Err(e) = saved_game.save_game() {
+        let _ = fs::remove_dir_all(game_data_path(&game_id, &[]));
+        return Err(format!("Failed to save game entry: {}", e));
+    }
+    // Archives from older versions are brought up to date right away
+    migrate_game(&game_id, false)?;
+    Ok(SavedGame::load_game(&game_id).ok().flatten().unwrap_or(saved_game))
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use chrono::{DateTime, Duration, Utc};
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use serde::{Deserialize, Serialize};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::password_hash::SaltString;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use crate::models::persistence::Transaction;
+use crate::models::player::Player;
+use crate::models::rng::GameRng;
+use crate::models::settings::load_settings_for_game;
+use crate::models::storage::{load_document, save_document, storage_for};
+
+/// How long a session stays valid after logging in.
+pub const SESSION_HOURS: i64 = 24;
+/// Shortest password an account may have.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Player,
+    /// May adjust credits and manage the game and the server's caches; the account of the game's creator
+    Admin,
+}
+
+/// A login for one of a game's players, saved under `accounts/` in the game.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Account {
+    pub name: String,
+    pub role: Role,
+    /// Argon2 hash of the password in PHC string format, salt included
+    pub password_hash: String,
+    pub created_at: String,
+}
+
+impl Account {
+    fn new(name: &str, password: &str, role: Role) -> Result<Self, String> {
+        Ok(Account {
+            name: name.to_string(),
+            role,
+            password_hash: hash_secret(password)?,
+            created_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        verify_secret(&self.password_hash, password)
+    }
+}
+
+/// Hashes a password or other secret with Argon2 under a fresh random salt.
+fn hash_secret(secret: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default().hash_password(secret.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+/// Whether a secret matches a hash from `hash_secret`; the comparison takes
+/// the same time however much of the hash matches.
+fn verify_secret(hash: &str, secret: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| Argon2::default().verify_password(secret.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+/// Name and password sent to register or log in.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Credentials {
+    pub name: String,
+    pub password: String,
+    /// Code from `issue_claim`, needed to register a player that already exists
+    #[serde(default)]
+    pub claim_code: Option<String>,
+}
+
+/// Permission to register an account for a player that exists but has none,
+/// saved under `claims/` in the game. Only a hash of the code is kept.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerClaim {
+    pub name: String,
+    pub code_hash: String,
+    pub issued_at: String,
+}
+
+fn claim_path(name: &str) -> [String; 2] {
+    ["claims".to_string(), format!("{}.json", name)]
+}
+
+/// Issues the one-time code that lets an account claim `name`, a player of
+/// the game without an account, such as the one a game is created for.
+/// A new code replaces any issued before.
+pub fn issue_claim(game_id: &str, name: &str) -> Result<String, String> {
+    let name = validate_player_name(name)?;
+    if storage_for(game_id)?.load_player(name)?.is_none() {
+        return Err(format!("Player not found: {}", name));
+    }
+    if load_account(game_id, name)?.is_some() {
+        return Err(format!("Player {} already has an account", name));
+    }
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    let code: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let claim = PlayerClaim { name: name.to_string(), code_hash: hash_secret(&code)?, issued_at: Utc::now().to_rfc3339() };
+    let [directory, file] = claim_path(name);
+    save_document(game_id, &[&directory, &file], &claim)?;
+    Ok(code)
+}
+
+pub fn load_account(game_id: &str, name: &str) -> Result<Option<Account>, String> {
+    load_document(game_id, &["accounts", &format!("{}.json", name)])
+}
+
+/// Player names end up in save file names and fleet names, so they are
+/// kept to letters, digits, `-` and `_`.
+pub fn validate_player_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > 32 {
+        return Err("Player names must be 1 to 32 characters long".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Player names may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(name)
+}
+
+/// Creates an account for a player of a game and saves it with the player.
+///
+/// A new name joins the game as a new player with the game's starting
+/// credits and fleet. The name of a player that already exists, like the one
+/// a game was created for, is only taken with the claim code issued for it.
+/// The account of the game's creator is the game's admin.
+pub fn register(game_id: &str, credentials: &Credentials, rng: &mut GameRng) -> Result<Account, String> {
+    let name = validate_player_name(&credentials.name)?;
+    if credentials.password.len() < MIN_PASSWORD_LENGTH {
+        return Err(format!("Passwords must be at least {} characters long", MIN_PASSWORD_LENGTH));
+    }
+    if load_account(game_id, name)?.is_some() {
+        return Err(format!("Player {} already has an account", name));
+    }
+
+    let settings = load_settings_for_game(game_id).map_err(|e| e.to_string())?;
+    let role = if name == settings.player_name { Role::Admin } else { Role::Player };
+    let account = Account::new(name, &credentials.password, role)?;
+    let mut transaction = Transaction::new(game_id);
+    transaction.stage(&["accounts", &format!("{}.json", name)], &account)?;
+    if storage_for(game_id)?.load_player(name)?.is_some() {
+        let [directory, file] = claim_path(name);
+        let claim: Option<PlayerClaim> = load_document(game_id, &[&directory, &file])?;
+        let code = credentials.claim_code.as_deref().unwrap_or_default();
+        if !claim.is_some_and(|claim| verify_secret(&claim.code_hash, code)) {
+            return Err(format!("Player {} already exists; registering it takes the claim code the game's creator issued for it", name));
+        }
+        transaction.stage_removal(&[&directory, &file]);
+    } else {
+        let (player, fleet) = Player::start(name, &settings, rng);
+        println!("New player {} joins game {} with {}", name, game_id, fleet.name);
+        transaction.stage_player(&player)?;
+        transaction.stage_fleet(&fleet)?;
+    }
+    transaction.commit()?;
+    Ok(account)
+}
+
+/// The account of a game's player, if the password matches.
+pub fn login(game_id: &str, credentials: &Credentials) -> Result<Account, String> {
+    let name = validate_player_name(&credentials.name)?;
+    load_account(game_id, name)?
+        .filter(|account| account.verify(&credentials.password))
+        .ok_or_else(|| "Invalid player name or password".to_string())
+}
+
+/// A logged in player of one game, identified by a bearer token.
+#[derive(Serialize, Debug, Clone)]
+pub struct Session {
+    pub token: String,
+    pub game_id: String,
+    pub player_name: String,
+    pub role: Role,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    /// Fails unless the session's player is `owner_id`.
+    pub fn authorize(&self, owner_id: &str) -> Result<(), String> {
+        if self.player_name == owner_id {
+            Ok(())
+        } else {
+            Err(format!("{} may not act for {}", self.player_name, owner_id))
+        }
+    }
+
+    /// Fails unless the session is for `game_id`.
+    pub fn authorize_game(&self, game_id: &str) -> Result<(), String> {
+        if self.game_id == game_id {
+            Ok(())
+        } else {
+            Err(format!("{} is not logged in to game {}", self.player_name, game_id))
+        }
+    }
+
+    pub fn require_admin(&self) -> Result<(), String> {
+        match self.role {
+            Role::Admin => Ok(()),
+            Role::Player => Err("Only an admin may do this".to_string()),
+        }
+    }
+
+    /// Fails unless the session is an admin's of `game_id`.
+    pub fn require_admin_of(&self, game_id: &str) -> Result<(), String> {
+        self.require_admin().and_then(|_| self.authorize_game(game_id))
+    }
+}
+
+/// The open sessions of every game, kept as Rocket managed state.
+#[derive(Default)]
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    pub fn start(&self, game_id: &str, account: &Account) -> Result<Session, String> {
+        let session = Session {
+            token: uuid::Uuid::new_v4().simple().to_string(),
+            game_id: game_id.to_string(),
+            player_name: account.name.clone(),
+            role: account.role,
+            expires_at: Utc::now() + Duration::hours(SESSION_HOURS),
+        };
+        let mut sessions = self.sessions.lock().map_err(|_| "Failed to lock sessions".to_string())?;
+        sessions.retain(|_, session| session.expires_at > Utc::now());
+        sessions.insert(session.token.clone(), session.clone());
+        Ok(session)
+    }
+
+    /// The session of a token, unless it has expired.
+    pub fn get(&self, token: &str) -> Option<Session> {
+        let sessions = self.sessions.lock().ok()?;
+        sessions.get(token).filter(|session| session.expires_at > Utc::now()).cloned()
+    }
+
+    pub fn end(&self, token: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(token);
+        }
+    }
+
+    /// Logs everyone out of a game, e.g. when it is deleted or replaced.
+    pub fn end_game(&self, game_id: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.retain(|_, session| session.game_id != game_id);
+        }
+    }
+
+    /// The session named by a request's `Authorization: Bearer` header, or
+    /// by a `token` query parameter for clients that cannot set headers,
+    /// like a browser's `EventSource`.
+    pub fn for_request(&self, request: &Request<'_>) -> Option<Session> {
+        let token = match request.headers().get_one("Authorization") {
+            Some(header) => header.strip_prefix("Bearer ")?.to_string(),
+            None => request.query_value::<String>("token")?.ok()?,
+        };
+        self.get(token.trim())
+    }
+}
+
+/// Request guard for the player making a request.
+///
+/// Like `CurrentGame`, a request without a valid session still gets
+/// through; routes that act for a player fail with "Not logged in" when
+/// they ask for it.
+#[derive(Clone, Default)]
+pub struct PlayerSession(Option<Session>);
+
+impl PlayerSession {
+    pub fn new(session: Session) -> Self {
+        PlayerSession(Some(session))
+    }
+
+    pub fn session(&self) -> Result<&Session, String> {
+        self.0.as_ref().ok_or_else(|| "Not logged in. Please log in to this game first.".to_string())
+    }
+
+    /// Name of the logged in player.
+    pub fn player_name(&self) -> Result<&str, String> {
+        Ok(&self.session()?.player_name)
+    }
+
+    pub fn authorize(&self, owner_id: &str) -> Result<(), String> {
+        self.session()?.authorize(owner_id)
+    }
+
+    pub fn authorize_game(&self, game_id: &str) -> Result<(), String> {
+        self.session()?.authorize_game(game_id)
+    }
+
+    pub fn require_admin(&self) -> Result<(), String> {
+        self.session()?.require_admin()
+    }
+
+    pub fn require_admin_of(&self, game_id: &str) -> Result<(), String> {
+        self.session()?.require_admin_of(game_id)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PlayerSession {
+    type Error = String;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let session = request.rocket().state::<SessionStore>()
+            .and_then(|sessions| sessions.for_request(request));
+        Outcome::Success(PlayerSession(session))
+    }
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use crate::models::fleet::Fleet;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::ship::ship::Ship;
+use crate::models::trade::ShipAllocation;
+
+/// How units of a cargo order are spread over the ships of a fleet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CargoDistribution {
+    /// Ships are filled (or emptied) in fleet order
+    First,
+    /// Units are spread as evenly as the ships allow
+    Even,
+    /// Each ship takes the quantity allocated to it
+    Specific(Vec<ShipAllocation>),
+}
+
+impl CargoDistribution {
+    /// Parses the `distribution_mode` and `allocations` of a trade request.
+    /// A missing mode means `first`.
+    pub fn from_request(mode: Option<&str>, allocations: Option<&[ShipAllocation]>) -> Result<CargoDistribution, String> {
+        match mode.unwrap_or("first") {
+            "first" => Ok(CargoDistribution::First),
+            "even" => Ok(CargoDistribution::Even),
+            "specific" => allocations
+                .filter(|allocations| !allocations.is_empty())
+                .map(|allocations| CargoDistribution::Specific(allocations.to_vec()))
+                .ok_or_else(|| "Specific distribution needs at least one ship allocation".to_string()),
+            other => Err(format!("Unknown distribution mode: {}", other)),
+        }
+    }
+}
+
+/// What to do with an order that does not fit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillPolicy {
+    /// Refuse the whole order
+    AllOrNothing,
+    /// Move what fits and report the rest as overflow
+    Partial,
+}
+
+/// Whether units go into or out of the holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CargoMove {
+    Load,
+    Unload,
+}
+
+/// The part of a cargo order one ship handled.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ShipCargoChange {
+    pub ship_index: usize,
+    pub ship_name: String,
+    pub requested: u32,
+    pub moved: u32,
+    /// Units that did not fit in the hold, or were not there to unload
+    pub overflow: u32,
+}
+
+/// The outcome of a cargo order against a fleet's holds.
+#[derive(Serialize, Debug, Clone)]
+pub struct CargoReceipt {
+    pub resource_type: ResourceType,
+    pub requested: u32,
+    pub moved: u32,
+    pub overflow: u32,
+    pub ships: Vec<ShipCargoChange>,
+}
+
+impl CargoReceipt {
+    pub fn is_complete(&self) -> bool {
+        self.overflow == 0
+    }
+
+    /// Lists the ships that could not take their share, for error messages.
+    fn overflow_detail(&self) -> String {
+        let ships: Vec<String> = self.ships.iter()
+            .filter(|ship| ship.overflow > 0)
+            .map(|ship| format!("{} short by {}", ship.ship_name, ship.overflow))
+            .collect();
+        if ships.is_empty() {
+            format!("{} units could not be placed", self.overflow)
+        } else {
+            ships.join(", ")
+        }
+    }
+}
+
+/// Units moved by a transfer between two ships of the same fleet.
+#[derive(Serialize, Debug, Clone)]
+pub struct CargoTransfer {
+    pub resource_type: ResourceType,
+    pub from_ship: String,
+    pub to_ship: String,
+    pub requested: u32,
+    pub moved: u32,
+    pub overflow: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CargoTransferData {
+    pub resource_type: ResourceType,
+    pub quantity: u32,
+    pub from_ship: usize,
+    pub to_ship: usize,
+    #[serde(default)]
+    pub allow_partial: bool,
+}
+
+impl Ship {
+    /// Cargo space left in the ship's hold.
+    pub fn free_cargo(&self) -> u32 {
+        self.get_cargo_capacity().saturating_sub(self.get_current_cargo())
+    }
+
+    /// Units of a resource in the ship's hold.
+    pub fn cargo_quantity(&self, resource_type: ResourceType) -> u32 {
+        self.cargo.iter()
+            .filter(|cargo| cargo.resource_type == resource_type)
+            .map(|cargo| cargo.quantity.unwrap_or(0))
+            .sum()
+    }
+
+    fn stow(&mut self, resource_type: ResourceType, quantity: u32) {
+        match self.cargo.iter_mut().find(|cargo| cargo.resource_type == resource_type) {
+            Some(cargo) => cargo.quantity = Some(cargo.quantity.unwrap_or(0) + quantity),
+            None => self.cargo.push(Resource { resource_type, quantity: Some(quantity), buy: None, sell: None }),
+        }
+    }
+
+    fn unstow(&mut self, resource_type: ResourceType, mut quantity: u32) {
+        for cargo in self.cargo.iter_mut().filter(|cargo| cargo.resource_type == resource_type) {
+            let taken = cargo.quantity.unwrap_or(0).min(quantity);
+            cargo.quantity = Some(cargo.quantity.unwrap_or(0) - taken);
+            quantity -= taken;
+        }
+        self.cargo.retain(|cargo| cargo.resource_type != resource_type || cargo.quantity.unwrap_or(0) > 0);
+    }
+}
+
+/// Cargo ledger: the one place fleets' holds are filled and emptied, so
+/// every trade path enforces the same capacity rules.
+impl Fleet {
+    /// Cargo space left across all ships of the fleet.
+    pub fn free_cargo_capacity(&self) -> u32 {
+        self.ships.iter().map(Ship::free_cargo).sum()
+    }
+
+    /// Units of a resource carried by the fleet.
+    pub fn cargo_quantity(&self, resource_type: ResourceType) -> u32 {
+        self.ships.iter().map(|ship| ship.cargo_quantity(resource_type)).sum()
+    }
+
+    /// Works out how an order would be spread over the ships without moving anything.
+    fn plan_cargo(&self, direction: CargoMove, resource_type: ResourceType, quantity: u32, distribution: &CargoDistribution) -> Result<CargoReceipt, String> {
+        if quantity == 0 {
+            return Err("Quantity must be greater than zero".to_string());
+        }
+        if self.ships.is_empty() {
+            return Err(format!("Fleet {} has no ships", self.name));
+        }
+        let mut available: Vec<u32> = self.ships.iter()
+            .map(|ship| match direction {
+                CargoMove::Load => ship.free_cargo(),
+                CargoMove::Unload => ship.cargo_quantity(resource_type),
+            })
+            .collect();
+        let mut requested = vec![0u32; self.ships.len()];
+        let mut moved = vec![0u32; self.ships.len()];
+
+        match distribution {
+            CargoDistribution::First => {
+                let mut remaining = quantity;
+                for (index, space) in available.iter().enumerate() {
+                    let share = remaining.min(*space);
+                    requested[index] = share;
+                    moved[index] = share;
+                    remaining -= share;
+                }
+            }
+            CargoDistribution::Even => {
+                let mut remaining = quantity;
+                loop {
+                    let open: Vec<usize> = (0..available.len()).filter(|&index| available[index] > moved[index]).collect();
+                    if remaining == 0 || open.is_empty() {
+                        break;
+                    }
+                    let share = remaining / open.len() as u32;
+                    let extra = remaining as usize % open.len();
+                    for (position, index) in open.into_iter().enumerate() {
+                        let wanted = share + u32::from(position < extra);
+                        let taken = wanted.min(available[index] - moved[index]);
+                        moved[index] += taken;
+                        requested[index] += taken;
+                        remaining -= taken;
+                    }
+                }
+            }
+            CargoDistribution::Specific(allocations) => {
+                let allocated: u32 = allocations.iter().map(|allocation| allocation.quantity).sum();
+                if allocated != quantity {
+                    return Err(format!("Allocations total {} but the order is for {}", allocated, quantity));
+                }
+                for allocation in allocations {
+                    let space = available.get_mut(allocation.ship_index)
+                        .ok_or_else(|| format!("Invalid ship index: {} (fleet has {} ships)", allocation.ship_index, self.ships.len()))?;
+                    let taken = allocation.quantity.min(*space);
+                    *space -= taken;
+                    requested[allocation.ship_index] += allocation.quantity;
+                    moved[allocation.ship_index] += taken;
+                }
+            }
+        }
+
+        let ships: Vec<ShipCargoChange> = self.ships.iter().enumerate()
+            .filter(|(index, _)| requested[*index] > 0)
+            .map(|(index, ship)| ShipCargoChange {
+                ship_index: index,
+                ship_name: ship.name.clone(),
+                requested: requested[index],
+                moved: moved[index],
+                overflow: requested[index] - moved[index],
+            })
+            .collect();
+        let total: u32 = moved.iter().sum();
+        Ok(CargoReceipt { resource_type, requested: quantity, moved: total, overflow: quantity - total, ships })
+    }
+
+    fn apply_cargo(&mut self, direction: CargoMove, resource_type: ResourceType, quantity: u32, distribution: &CargoDistribution, policy: FillPolicy) -> Result<CargoReceipt, String> {
+        let receipt = self.plan_cargo(direction, resource_type, quantity, distribution)?;
+        if !receipt.is_complete() && (policy == FillPolicy::AllOrNothing || receipt.moved == 0) {
+            return Err(match direction {
+                CargoMove::Load => format!("Not enough cargo space for {} {}: {}", quantity, resource_type, receipt.overflow_detail()),
+                CargoMove::Unload => format!("Not enough {} in cargo to move {}: {}", resource_type, quantity, receipt.overflow_detail()),
+            });
+        }
+        for change in &receipt.ships {
+            let ship = &mut self.ships[change.ship_index];
+            match direction {
+                CargoMove::Load => ship.stow(resource_type, change.moved),
+                CargoMove::Unload => ship.unstow(resource_type, change.moved),
+            }
+        }
+        Ok(receipt)
+    }
+
+    /// Stows units in the fleet's holds.
+    ///
+    /// # Arguments
+    /// * `resource_type` - The resource to stow
+    /// * `quantity` - Units to stow
+    /// * `distribution` - How to spread the units over the ships
+    /// * `policy` - Whether an order that does not fit is refused or partially filled
+    ///
+    /// # Returns
+    /// What each ship took, or an error if nothing (or, for all-or-nothing
+    /// orders, not everything) fits
+    pub fn load_cargo(&mut self, resource_type: ResourceType, quantity: u32, distribution: &CargoDistribution, policy: FillPolicy) -> Result<CargoReceipt, String> {
+        self.apply_cargo(CargoMove::Load, resource_type, quantity, distribution, policy)
+    }
+
+    /// Removes units from the fleet's holds. Mirrors `load_cargo`, with
+    /// overflow counting units the ships did not have.
+    pub fn unload_cargo(&mut self, resource_type: ResourceType, quantity: u32, distribution: &CargoDistribution, policy: FillPolicy) -> Result<CargoReceipt, String> {
+        self.apply_cargo(CargoMove::Unload, resource_type, quantity, distribution, policy)
+    }
+
+    /// Moves units between two ships of the fleet.
+    pub fn transfer_cargo(&mut self, from_ship: usize, to_ship: usize, resource_type: ResourceType, quantity: u32, policy: FillPolicy) -> Result<CargoTransfer, String> {
+        if from_ship == to_ship {
+            return Err("Cannot transfer cargo from a ship to itself".to_string());
+        }
+        let (Some(source), Some(target)) = (self.ships.get(from_ship), self.ships.get(to_ship)) else {
+            return Err(format!("Invalid ship index (fleet has {} ships)", self.ships.len()));
+        };
+        if quantity == 0 {
+            return Err("Quantity must be greater than zero".to_string());
+        }
+        let moved = quantity.min(source.cargo_quantity(resource_type)).min(target.free_cargo());
+        if moved < quantity && (policy == FillPolicy::AllOrNothing || moved == 0) {
+            return Err(format!(
+                "Cannot move {} {}: {} holds {} and {} has room for {}",
+                quantity, resource_type, source.name, source.cargo_quantity(resource_type), target.name, target.free_cargo()
+            ));
+        }
+        let transfer = CargoTransfer {
+            resource_type,
+            from_ship: source.name.clone(),
+            to_ship: target.name.clone(),
+            requested: quantity,
+            moved,
+            overflow: quantity - moved,
+        };
+        self.ships[from_ship].unstow(resource_type, moved);
+        self.ships[to_ship].stow(resource_type, moved);
+        Ok(transfer)
+    }
+}
@@ -0,0 +1,104 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::models::game_state::current_game_id;
+use crate::models::storage::{load_document, save_document};
+use crate::models::resource::ResourceType;
+use crate::models::rng::game_uuid;
+
+/// Stardate of tick 0 in every game.
+pub const STARDATE_EPOCH: f64 = 3000.0;
+
+/// Ticks that make up one whole stardate.
+pub const TICKS_PER_STARDATE: u64 = 10;
+
+/// The persisted game clock. One tick is the smallest unit of simulated time;
+/// nothing in the world changes between ticks.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GameClock {
+    pub tick: u64,
+}
+
+/// Snapshot of the clock as returned to clients.
+#[derive(Serialize, Debug, Clone)]
+pub struct GameTime {
+    pub tick: u64,
+    pub stardate: f64,
+}
+
+impl GameClock {
+    /// Converts the current tick into a human readable stardate.
+    pub fn stardate(&self) -> f64 {
+        stardate_at(self.tick)
+    }
+
+    /// Loads the clock for a game, starting at tick 0 when none has been saved yet.
+    pub fn load(game_id: &str) -> Result<GameClock, String> {
+        Ok(load_document(game_id, &["clock.json"])?.unwrap_or_default())
+    }
+
+    pub fn save(&self, game_id: &str) -> Result<(), String> {
+        save_document(game_id, &["clock.json"], self)
+    }
+}
+
+impl From<&GameClock> for GameTime {
+    fn from(clock: &GameClock) -> Self {
+        GameTime {
+            tick: clock.tick,
+            stardate: clock.stardate(),
+        }
+    }
+}
+
+/// Converts a tick into a stardate.
+pub fn stardate_at(tick: u64) -> f64 {
+    STARDATE_EPOCH + tick as f64 / TICKS_PER_STARDATE as f64
+}
+
+/// Returns the current tick of the active game, or 0 when no game is loaded.
+pub fn current_tick() -> u64 {
+    current_game_id().ok()
+        .and_then(|game_id| GameClock::load(&game_id).ok())
+        .map(|clock| clock.tick)
+        .unwrap_or(0)
+}
+
+/// Something that will happen in the world once the clock reaches `due_tick`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduledEvent {
+    pub id: String,
+    pub due_tick: u64,
+    pub kind: ScheduledEventKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ScheduledEventKind {
+    /// Adds (or removes, when negative) stock from a planet's market
+    MarketShock {
+        system_id: usize,
+        planet_id: usize,
+        resource_type: ResourceType,
+        stock_change: i32,
+    },
+    /// A message recorded in the time advance log
+    Announcement { message: String },
+}
+
+impl ScheduledEvent {
+    pub fn new<R: Rng + ?Sized>(due_tick: u64, kind: ScheduledEventKind, rng: &mut R) -> Self {
+        ScheduledEvent {
+            id: game_uuid(rng),
+            due_tick,
+            kind,
+        }
+    }
+}
+
+/// Loads the pending scheduled events of a game.
+pub fn load_scheduled_events(game_id: &str) -> Result<Vec<ScheduledEvent>, String> {
+    Ok(load_document(game_id, &["scheduled_events.json"])?.unwrap_or_default())
+}
+
+pub fn save_scheduled_events(game_id: &str, events: &[ScheduledEvent]) -> Result<(), String> {
+    save_document(game_id, &["scheduled_events.json"], events)
+}
@@ -0,0 +1,248 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use rocket::Request;
+use rocket::request::{FromRequest, Outcome};
+use rocket::tokio::sync::broadcast;
+use crate::models::auth::SessionStore;
+use crate::models::events::{GameEvent, event_channel};
+use crate::models::fleet::Fleet;
+use crate::models::game_state::{Cache, game_data_path};
+use crate::models::market::Market;
+use crate::models::player::Player;
+use crate::models::spatial::{GalaxyIndex, invalidate_galaxy_index};
+use crate::models::star_system::StarSystem;
+use crate::models::storage::close_storage;
+
+/// Header a client sends to say which game a request is for.
+pub const GAME_ID_HEADER: &str = "X-Game-Id";
+
+/// Everything the server keeps in memory for one game: its caches, the
+/// galaxy as loaded, the spatial index over it, the channel its events
+/// are pushed to subscribers on and the lock its writers queue on.
+pub struct GameContext {
+    pub game_id: String,
+    pub players: Cache<Player>,
+    pub systems: Cache<StarSystem>,
+    pub fleets: Cache<Fleet>,
+    pub markets: Cache<Market>,
+    world: RwLock<Vec<StarSystem>>,
+    galaxy_index: RwLock<Option<GalaxyIndex>>,
+    events: broadcast::Sender<GameEvent>,
+    writing: Mutex<bool>,
+    write_done: Condvar,
+}
+
+thread_local! {
+    /// The game the code running on this thread works on, set by `GameContext::enter`.
+    static CURRENT: RefCell<Option<Arc<GameContext>>> = const { RefCell::new(None) };
+}
+
+/// Keeps a game entered on the current thread until dropped, then restores
+/// whichever game was entered before. Not `Send`, so it cannot be held
+/// across an `.await` and leak into another request.
+pub struct EnteredGame {
+    previous: Option<Arc<GameContext>>,
+    _writes: Option<WriteLock>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for EnteredGame {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Sole right to change a game until dropped. Anything that loads a game's
+/// state, changes it and saves it back holds one, so two requests (or a
+/// request and a tick) cannot each save over the other's changes.
+pub struct WriteLock {
+    context: Arc<GameContext>,
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        if let Ok(mut writing) = self.context.writing.lock() {
+            *writing = false;
+        }
+        self.context.write_done.notify_one();
+    }
+}
+
+impl GameContext {
+    pub fn new(game_id: &str) -> Arc<Self> {
+        Arc::new(GameContext {
+            game_id: game_id.to_string(),
+            players: Cache::new(30),
+            systems: Cache::new(60),
+            fleets: Cache::new(30),
+            markets: Cache::new(30),
+            world: RwLock::new(Vec::new()),
+            galaxy_index: RwLock::new(None),
+            events: event_channel(),
+            writing: Mutex::new(false),
+            write_done: Condvar::new(),
+        })
+    }
+
+    /// Makes this the game that model functions on this thread work on.
+    pub fn enter(self: &Arc<Self>) -> EnteredGame {
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(self.clone()));
+        EnteredGame { previous, _writes: None, _not_send: PhantomData }
+    }
+
+    /// Enters the game like `enter`, first waiting until no one else is
+    /// changing it; held until the returned guard is dropped.
+    pub fn enter_for_writes(self: &Arc<Self>) -> EnteredGame {
+        let writes = self.lock_writes();
+        let mut entered = self.enter();
+        entered._writes = Some(writes);
+        entered
+    }
+
+    /// Waits for the game's other writers to finish and keeps them out until
+    /// the lock is dropped. Not reentrant: taking it twice on one thread
+    /// deadlocks.
+    pub fn lock_writes(self: &Arc<Self>) -> WriteLock {
+        let mut writing = self.writing.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        while *writing {
+            writing = self.write_done.wait(writing).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        *writing = true;
+        WriteLock { context: self.clone() }
+    }
+
+    /// The game entered on this thread.
+    pub fn current() -> Result<Arc<GameContext>, String> {
+        CURRENT.with(|current| current.borrow().clone())
+            .ok_or_else(|| "No active game. Please create or load a game first.".to_string())
+    }
+
+    /// The game entered on this thread, if it is `game_id`.
+    pub fn current_for(game_id: &str) -> Option<Arc<GameContext>> {
+        Self::current().ok().filter(|context| context.game_id == game_id)
+    }
+
+    /// The galaxy as loaded into memory; empty until a route loads it.
+    pub fn world(&self) -> Vec<StarSystem> {
+        self.world.read().map(|world| world.clone()).unwrap_or_default()
+    }
+
+    pub fn set_world(&self, systems: Vec<StarSystem>) {
+        if let Ok(mut world) = self.world.write() {
+            *world = systems;
+        }
+    }
+
+    pub fn galaxy_index(&self) -> &RwLock<Option<GalaxyIndex>> {
+        &self.galaxy_index
+    }
+
+    /// Sends an event to everyone subscribed to this game; without
+    /// subscribers the event is dropped.
+    pub fn publish(&self, event: GameEvent) {
+        let _ = self.events.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<GameEvent> {
+        self.events.subscribe()
+    }
+
+    /// Forgets everything cached, so the next access reads the saves again.
+    pub fn clear_caches(&self) {
+        self.players.remove_all();
+        self.systems.remove_all();
+        self.fleets.remove_all();
+        self.markets.remove_all();
+        invalidate_galaxy_index(self);
+    }
+}
+
+/// The games a server is serving, kept as Rocket managed state.
+#[derive(Default)]
+pub struct GameRegistry {
+    games: Mutex<HashMap<String, Arc<GameContext>>>,
+}
+
+/// Checks that a game id is fit to name a game; ids are also path
+/// components, so anything that could step outside `data/game` is refused.
+pub fn validate_game_id(game_id: &str) -> Result<(), String> {
+    if game_id.is_empty() || !game_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Game ids may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(())
+}
+
+/// Whether a game id names a saved game.
+pub fn game_exists(game_id: &str) -> bool {
+    validate_game_id(game_id).is_ok() && game_data_path(game_id, &[]).is_dir()
+}
+
+impl GameRegistry {
+    /// The context of a saved game, created the first time it is asked for.
+    pub fn get(&self, game_id: &str) -> Result<Arc<GameContext>, String> {
+        if !game_exists(game_id) {
+            return Err(format!("Game not found: {}", game_id));
+        }
+        let mut games = self.games.lock().map_err(|_| "Failed to lock game registry".to_string())?;
+        Ok(games.entry(game_id.to_string()).or_insert_with(|| GameContext::new(game_id)).clone())
+    }
+
+    /// Drops a game's context, for games that were deleted or replaced on disk.
+    pub fn evict(&self, game_id: &str) {
+        if let Ok(mut games) = self.games.lock() {
+            games.remove(game_id);
+        }
+        close_storage(game_id);
+    }
+}
+
+/// Request guard resolving the game a request is for: the game of the
+/// request's session, else the `X-Game-Id` header, else a `game_id` query
+/// parameter. A request naming none of them is for no game at all.
+///
+/// A request for no known game still gets through; routes then fail with
+/// the usual "No active game" error from the first model call.
+#[derive(Clone, Default)]
+pub struct CurrentGame(Option<Arc<GameContext>>);
+
+impl CurrentGame {
+    pub fn new(context: Arc<GameContext>) -> Self {
+        CurrentGame(Some(context))
+    }
+
+    /// Enters the game on this thread for the rest of a route.
+    pub fn enter(&self) -> Option<EnteredGame> {
+        self.0.as_ref().map(|context| context.enter())
+    }
+
+    /// Enters the game for a route that changes it, waiting for any other
+    /// writer to the same game first.
+    pub fn enter_for_writes(&self) -> Option<EnteredGame> {
+        self.0.as_ref().map(|context| context.enter_for_writes())
+    }
+
+    pub fn context(&self) -> Result<&Arc<GameContext>, String> {
+        self.0.as_ref().ok_or_else(|| "No active game. Please create or load a game first.".to_string())
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CurrentGame {
+    type Error = String;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(registry) = request.rocket().state::<GameRegistry>() else {
+            return Outcome::Success(CurrentGame(None));
+        };
+        let session = request.rocket().state::<SessionStore>()
+            .and_then(|sessions| sessions.for_request(request));
+        let named = session.map(|session| session.game_id)
+            .or_else(|| request.headers().get_one(GAME_ID_HEADER).map(str::to_string))
+            .or_else(|| request.query_value::<String>("game_id").and_then(Result::ok));
+        let context = named.and_then(|game_id| registry.get(&game_id).ok());
+        Outcome::Success(CurrentGame(context))
+    }
+}
@@ -0,0 +1,479 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::combat::combat::{CombatOrders, CombatResult, auto_resolve_ship_combat_with_orders, calculate_fleet_power, stance_share};
+use crate::encounters::generate_fleet_of_type;
+use crate::models::faction::{ATTACK_REPUTATION_PENALTY, Faction, Standing, apply_reputation_change, record_piracy};
+use crate::models::fleet::Fleet;
+use crate::models::game_state::current_game_id;
+use crate::models::npc_trader::{is_npc_trader, trade_with_npc_trader};
+use crate::models::player::Player;
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::rng::game_uuid;
+use crate::models::route::system_danger;
+use crate::models::salvage::SalvageField;
+use crate::models::ship::ship::CombatState;
+use crate::models::star_system::StarSystem;
+use crate::models::storage::{load_document, save_document};
+use crate::models::trade::trade_with_fleet;
+
+/// Chance of an encounter per roll somewhere without any danger at all.
+pub const BASE_ENCOUNTER_CHANCE: f64 = 0.05;
+/// Extra chance per roll for each level of danger of the surroundings.
+pub const ENCOUNTER_CHANCE_PER_DANGER: f64 = 0.04;
+/// Danger level of deep space, between the systems.
+pub const DEEP_SPACE_DANGER: f64 = 4.0;
+/// Factions patrol their territory, keeping encounters down.
+pub const PATROLLED_ENCOUNTER_FACTOR: f64 = 0.6;
+/// Nobody keeps order in unclaimed systems and deep space.
+pub const UNCLAIMED_ENCOUNTER_FACTOR: f64 = 1.25;
+/// Upper bound on the chance of a single roll.
+pub const MAX_ENCOUNTER_CHANCE: f64 = 0.6;
+/// How close a fleet must come to a planet inside a system to meet its defenses.
+pub const PLANET_ENCOUNTER_RANGE: f64 = 3.2;
+
+/// Credits demanded in tribute per point of the hostile fleet's combat power.
+pub const TRIBUTE_PER_POWER: f64 = 10.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncounterKind {
+    Pirate,
+    Trader,
+    Military,
+    Mercenary,
+}
+
+impl EncounterKind {
+    /// Owner id of the fleets of this kind, e.g. `Fleet_Pirate_1234`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EncounterKind::Pirate => "Pirate",
+            EncounterKind::Trader => "Trader",
+            EncounterKind::Military => "Military",
+            EncounterKind::Mercenary => "Mercenary",
+        }
+    }
+
+    /// What a player may do about a fleet of this kind.
+    pub fn options(&self) -> Vec<EncounterAction> {
+        let mut options = vec![EncounterAction::Fight, EncounterAction::Flee, EncounterAction::Hail];
+        match self {
+            EncounterKind::Pirate | EncounterKind::Mercenary => options.push(EncounterAction::PayTribute),
+            EncounterKind::Trader => options.push(EncounterAction::Trade),
+            EncounterKind::Military => {}
+        }
+        options
+    }
+
+    /// Relative odds of meeting each kind, with or without a faction
+    /// patrolling the area.
+    fn weights(patrolled: bool) -> [(EncounterKind, f64); 4] {
+        if patrolled {
+            [(EncounterKind::Pirate, 0.2), (EncounterKind::Trader, 0.35), (EncounterKind::Military, 0.35), (EncounterKind::Mercenary, 0.1)]
+        } else {
+            [(EncounterKind::Pirate, 0.5), (EncounterKind::Trader, 0.2), (EncounterKind::Military, 0.1), (EncounterKind::Mercenary, 0.2)]
+        }
+    }
+}
+
+/// Whether a fleet belongs to nobody playing: the fleets met in
+/// encounters, the pirates preying on traders, and the NPC traders.
+pub fn is_npc_fleet(fleet: &Fleet) -> bool {
+    is_npc_trader(fleet)
+        || fleet.owner_id.starts_with("Pirate_")
+        || [EncounterKind::Pirate, EncounterKind::Trader, EncounterKind::Military, EncounterKind::Mercenary]
+            .iter()
+            .any(|kind| fleet.owner_id == kind.name())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EncounterAction {
+    Fight,
+    Flee,
+    /// Open a channel and try to talk the other fleet into moving on
+    Hail,
+    PayTribute,
+    Trade,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncounterStatus {
+    /// Waiting for the player to choose what to do
+    Pending,
+    /// The player's fleet won the fight
+    Won,
+    /// The player's fleet lost the fight, whether it chose it or failed to flee
+    Lost,
+    /// The player's fleet broke off and got away
+    Escaped,
+    Negotiated,
+    TributePaid,
+    Traded,
+}
+
+/// A meeting between a player's fleet and another fleet, saved until the
+/// player decides what to do about it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Encounter {
+    pub id: String,
+    pub kind: EncounterKind,
+    /// The player's fleet that was met
+    pub fleet_name: String,
+    pub owner_id: String,
+    /// The other fleet, saved with the game's fleets
+    pub hostile_fleet: String,
+    /// Faction whose patrol this is, for military fleets in its territory
+    pub faction: Option<String>,
+    pub system_id: Option<usize>,
+    pub position: Position,
+    pub tick: u64,
+    pub status: EncounterStatus,
+    /// Actions still open to the player
+    pub options: Vec<EncounterAction>,
+    /// Credits asked to be left alone, for fleets that take tribute
+    pub tribute: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<String>,
+}
+
+impl Encounter {
+    pub fn is_pending(&self) -> bool {
+        self.status == EncounterStatus::Pending
+    }
+
+    /// Whether the other fleet leaves the world once the encounter is over.
+    /// Persistent NPC traders go on with their business, and a fleet that
+    /// beat the player holds its ground.
+    pub fn hostile_departs(&self, hostile: &Fleet) -> bool {
+        if is_npc_trader(hostile) || self.is_pending() {
+            return false;
+        }
+        hostile.ships.is_empty() || self.status != EncounterStatus::Lost
+    }
+}
+
+/// The outcome of an encounter action, as returned to clients.
+#[derive(Serialize, Debug, Clone)]
+pub struct EncounterReport {
+    pub encounter: Encounter,
+    pub log: Vec<String>,
+    /// What the fight left behind, if there was one
+    pub salvage: Option<SalvageField>,
+}
+
+/// Every encounter of a game, stored as `encounters.json` in the game directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EncounterLog {
+    pub encounters: Vec<Encounter>,
+}
+
+impl EncounterLog {
+    pub fn load() -> Result<EncounterLog, String> {
+        Ok(load_document(&current_game_id()?, &["encounters.json"])?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_document(&current_game_id()?, &["encounters.json"], self)
+    }
+
+    /// Encounters of a player's fleets, in any state.
+    pub fn for_player(&self, player_name: &str) -> Vec<&Encounter> {
+        self.encounters.iter().filter(|e| e.owner_id == player_name).collect()
+    }
+
+    /// Encounters of a fleet still waiting for a decision.
+    pub fn pending_for_fleet(&self, fleet_name: &str) -> Vec<&Encounter> {
+        self.encounters.iter().filter(|e| e.fleet_name == fleet_name && e.is_pending()).collect()
+    }
+
+    pub fn get(&self, encounter_id: &str) -> Result<&Encounter, String> {
+        self.encounters.iter()
+            .find(|e| e.id == encounter_id)
+            .ok_or_else(|| format!("Encounter {} not found", encounter_id))
+    }
+
+    pub fn get_mut(&mut self, encounter_id: &str) -> Result<&mut Encounter, String> {
+        self.encounters.iter_mut()
+            .find(|e| e.id == encounter_id)
+            .ok_or_else(|| format!("Encounter {} not found", encounter_id))
+    }
+}
+
+/// Danger of a fleet's surroundings: the worst planet within encounter
+/// range, else the average of its system, else that of deep space.
+pub fn encounter_danger(fleet: &Fleet, system: Option<&StarSystem>) -> f64 {
+    let Some(system) = system.filter(|system| fleet.current_system_id == Some(system.id)) else {
+        return DEEP_SPACE_DANGER;
+    };
+    let worst_nearby = fleet.local_position.and_then(|local| {
+        system.planets.iter()
+            .filter(|planet| planet.position.distance(&local) <= PLANET_ENCOUNTER_RANGE)
+            .map(|planet| planet.danger.level() as f64)
+            .reduce(f64::max)
+    });
+    worst_nearby.unwrap_or_else(|| system_danger(system))
+}
+
+/// Chance of each encounter roll in surroundings of the given danger level,
+/// inside a faction's territory or not.
+pub fn encounter_chance(danger: f64, faction: Option<&Faction>) -> f64 {
+    let territory = if faction.is_some() { PATROLLED_ENCOUNTER_FACTOR } else { UNCLAIMED_ENCOUNTER_FACTOR };
+    ((BASE_ENCOUNTER_CHANCE + danger * ENCOUNTER_CHANCE_PER_DANGER) * territory).min(MAX_ENCOUNTER_CHANCE)
+}
+
+/// Picks what kind of fleet is met; factions' warships patrol their own
+/// territory while pirates prefer space nobody controls.
+pub fn roll_encounter_kind<R: Rng + ?Sized>(faction: Option<&Faction>, rng: &mut R) -> EncounterKind {
+    let weights = EncounterKind::weights(faction.is_some());
+    let mut roll = rng.gen_range(0.0..weights.iter().map(|(_, weight)| weight).sum::<f64>());
+    for (kind, weight) in weights {
+        if roll < weight {
+            return kind;
+        }
+        roll -= weight;
+    }
+    weights[0].0
+}
+
+/// Creates the fleet and the encounter for a player's fleet meeting a fleet
+/// of `kind`. The new fleet waits where the player's fleet is and still has
+/// to be saved, under a name no other fleet uses (see `name_taken`).
+pub fn create_encounter<R: Rng + ?Sized>(
+    kind: EncounterKind,
+    fleet: &Fleet,
+    faction: Option<&Faction>,
+    now: u64,
+    name_taken: impl Fn(&str) -> bool,
+    rng: &mut R,
+) -> (Encounter, Fleet) {
+    let generated = generate_fleet_of_type(kind.name(), fleet.position, rng);
+    let mut name = generated.name;
+    while name_taken(&name) {
+        name = format!("Fleet_{}_{}", kind.name(), rng.gen_range(1000..9999));
+    }
+    let mut hostile = Fleet::new(generated.owner_id, fleet.position, 0);
+    hostile.name = name;
+    hostile.ships = generated.ships;
+    hostile.local_position = fleet.local_position;
+    hostile.current_system_id = fleet.current_system_id;
+
+    let encounter = Encounter::new(kind, fleet, &hostile, faction.filter(|_| kind == EncounterKind::Military), now, rng);
+    (encounter, hostile)
+}
+
+/// Rolls for what a player's fleet meets where it stopped after a move of
+/// `distance`: the NPC traders working the same system, and a fleet for
+/// every roll that hits. Longer moves roll more often, and each roll is
+/// likelier in dangerous space nobody patrols.
+///
+/// # Returns
+/// The encounters, and the fleets met that still have to be saved
+#[allow(clippy::too_many_arguments)]
+pub fn roll_encounters<'a, R: Rng + ?Sized>(
+    fleet: &Fleet,
+    distance: f64,
+    danger: f64,
+    faction: Option<&Faction>,
+    nearby: impl IntoIterator<Item = &'a Fleet>,
+    now: u64,
+    name_taken: impl Fn(&str) -> bool,
+    rng: &mut R,
+) -> (Vec<Encounter>, Vec<Fleet>) {
+    let mut encounters: Vec<Encounter> = nearby.into_iter()
+        .filter(|other| {
+            is_npc_trader(other)
+                && !other.ships.is_empty()
+                && other.transit.is_none()
+                && other.current_system_id.is_some()
+                && other.current_system_id == fleet.current_system_id
+        })
+        .map(|trader| Encounter::with_trader(fleet, trader, now, rng))
+        .collect();
+
+    let rolls = (distance / 10.0).min(3.0) as i32;
+    let chance = encounter_chance(danger, faction);
+    let mut hostiles: Vec<Fleet> = Vec::new();
+    for _ in 0..rolls {
+        if rng.gen_bool(chance) {
+            let kind = roll_encounter_kind(faction, rng);
+            let taken = |name: &str| hostiles.iter().any(|hostile| hostile.name == name) || name_taken(name);
+            let (encounter, hostile) = create_encounter(kind, fleet, faction, now, taken, rng);
+            hostiles.push(hostile);
+            encounters.push(encounter);
+        }
+    }
+    (encounters, hostiles)
+}
+
+impl Encounter {
+    fn new<R: Rng + ?Sized>(kind: EncounterKind, fleet: &Fleet, hostile: &Fleet, faction: Option<&Faction>, now: u64, rng: &mut R) -> Self {
+        let options = kind.options();
+        let tribute = if options.contains(&EncounterAction::PayTribute) {
+            (calculate_fleet_power(hostile) * TRIBUTE_PER_POWER).round()
+        } else {
+            0.0
+        };
+        Encounter {
+            id: game_uuid(rng),
+            kind,
+            fleet_name: fleet.name.clone(),
+            owner_id: fleet.owner_id.clone(),
+            hostile_fleet: hostile.name.clone(),
+            faction: faction.map(|faction| faction.name.clone()),
+            system_id: fleet.current_system_id,
+            position: fleet.position,
+            tick: now,
+            status: EncounterStatus::Pending,
+            options,
+            tribute,
+            outcome: None,
+        }
+    }
+
+    /// An encounter with a persistent NPC trader met in the same system.
+    pub fn with_trader<R: Rng + ?Sized>(fleet: &Fleet, trader: &Fleet, now: u64, rng: &mut R) -> Self {
+        Encounter::new(EncounterKind::Trader, fleet, trader, None, now, rng)
+    }
+}
+
+/// Chance that hailing the other fleet of an encounter talks it into moving on.
+pub fn hail_chance(encounter: &Encounter, hostile: &Fleet, player: &Player) -> f64 {
+    let base = match encounter.kind {
+        EncounterKind::Trader => 0.9,
+        EncounterKind::Mercenary => 0.4,
+        EncounterKind::Pirate => 0.15,
+        EncounterKind::Military => match encounter.faction.as_deref().map(|f| Standing::from_reputation(player.reputation_with(f))) {
+            Some(Standing::Allied) | Some(Standing::Friendly) => 0.95,
+            Some(Standing::Neutral) | None => 0.6,
+            Some(Standing::Unfriendly) => 0.3,
+            Some(Standing::Hostile) => 0.05,
+        },
+    };
+    let temper = stance_share(hostile, CombatState::Passive) - stance_share(hostile, CombatState::Aggressive);
+    (base + temper * 0.2).clamp(0.0, 1.0)
+}
+
+/// What a player chose to do about an encounter, with the goods to swap
+/// when trading.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EncounterActionData {
+    pub action: EncounterAction,
+    pub resource_type: Option<ResourceType>,
+    pub quantity: Option<u32>,
+    /// "buy" or "sell", as for other trades
+    pub trade_type: Option<String>,
+    /// Round of a fight after which the player's fleet tries to break off
+    pub retreat_after: Option<u32>,
+}
+
+/// Carries out a player's choice on a pending encounter.
+///
+/// Fighting and fleeing resolve a combat between the two fleets: a fleeing
+/// fleet holds its fire and keeps trying to break off under the other's
+/// guns, and a fighting one can be ordered to break off after a number of
+/// rounds. A failed hail only takes hailing off the table. Fighting a
+/// faction's patrol costs reputation with that faction, and attacking a
+/// trader costs reputation with whoever polices the system.
+///
+/// # Returns
+/// A log of what happened, and the combat if there was one
+pub fn resolve_encounter<R: Rng + ?Sized>(
+    encounter: &mut Encounter,
+    data: &EncounterActionData,
+    player: &mut Player,
+    fleet: &mut Fleet,
+    hostile: &mut Fleet,
+    factions: &[Faction],
+    rng: &mut R,
+) -> Result<(Vec<String>, Option<CombatResult>), String> {
+    if !encounter.is_pending() {
+        return Err(format!("Encounter {} is already over", encounter.id));
+    }
+    if !encounter.options.contains(&data.action) {
+        return Err(format!("{:?} is not an option in this encounter", data.action));
+    }
+    if fleet.ships.is_empty() {
+        return Err(format!("{} has no ships left", fleet.name));
+    }
+
+    let mut log = Vec::new();
+    let mut combat = None;
+    match data.action {
+        EncounterAction::Fight => {
+            let orders = CombatOrders { attacker_retreat_after: data.retreat_after, ..CombatOrders::default() };
+            let result = auto_resolve_ship_combat_with_orders(fleet, hostile, orders, rng);
+            encounter.status = if result.attacker_retreated {
+                EncounterStatus::Escaped
+            } else if result.attacker_victory {
+                EncounterStatus::Won
+            } else {
+                EncounterStatus::Lost
+            };
+            log.extend(result.combat_log.iter().cloned());
+            let mut changes = match &encounter.faction {
+                Some(faction) => apply_reputation_change(player, factions, faction, ATTACK_REPUTATION_PENALTY),
+                None => Vec::new(),
+            };
+            // Preying on traders is piracy, whoever owns the trader
+            if encounter.kind == EncounterKind::Trader {
+                changes.extend(record_piracy(player, factions, encounter.system_id));
+            }
+            for change in changes {
+                log.push(format!("Reputation with {} changed by {} (now {})", change.faction, change.change, change.reputation));
+            }
+            combat = Some(result);
+        }
+        EncounterAction::Flee => {
+            // The other fleet opens fire while the player's fleet breaks off
+            let orders = CombatOrders { defender_retreat_after: Some(0), ..CombatOrders::default() };
+            let result = auto_resolve_ship_combat_with_orders(hostile, fleet, orders, rng);
+            encounter.status = if result.defender_retreated {
+                EncounterStatus::Escaped
+            } else if result.attacker_victory {
+                EncounterStatus::Lost
+            } else {
+                EncounterStatus::Won
+            };
+            log.extend(result.combat_log.iter().cloned());
+            combat = Some(result);
+        }
+        EncounterAction::Hail => {
+            if rng.gen_bool(hail_chance(encounter, hostile, player)) {
+                encounter.status = EncounterStatus::Negotiated;
+                log.push(format!("{} agreed to move on", hostile.name));
+            } else {
+                encounter.options.retain(|option| *option != EncounterAction::Hail);
+                log.push(format!("{} refuses to talk", hostile.name));
+            }
+        }
+        EncounterAction::PayTribute => {
+            if player.credits < encounter.tribute {
+                return Err(format!("Tribute of {:.0} credits is more than you have", encounter.tribute));
+            }
+            player.credits -= encounter.tribute;
+            encounter.status = EncounterStatus::TributePaid;
+            log.push(format!("Paid {:.0} credits to {}, who lets {} pass", encounter.tribute, hostile.name, fleet.name));
+        }
+        EncounterAction::Trade => {
+            let resource_type = data.resource_type.ok_or_else(|| "Choose a resource to trade".to_string())?;
+            let quantity = data.quantity.filter(|quantity| *quantity > 0).ok_or_else(|| "Choose a quantity to trade".to_string())?;
+            let trade_type = data.trade_type.as_deref().ok_or_else(|| "Choose whether to buy or sell".to_string())?;
+            if hostile.trader.is_some() {
+                trade_with_npc_trader(fleet, hostile, resource_type, quantity, trade_type, player)?;
+            } else {
+                trade_with_fleet(fleet, hostile, resource_type, quantity, trade_type, player)?;
+            }
+            encounter.status = EncounterStatus::Traded;
+            log.push(format!("Traded {} {} with {}", quantity, resource_type, hostile.name));
+        }
+    }
+
+    if !encounter.is_pending() {
+        encounter.options.clear();
+    }
+    encounter.outcome = combat.as_ref()
+        .and_then(|result| result.combat_log.last().cloned())
+        .or_else(|| log.last().cloned());
+    Ok((log, combat))
+}
@@ -0,0 +1,272 @@
+use std::collections::{BTreeMap, BTreeSet};
+use rocket::tokio::sync::broadcast;
+use serde::Serialize;
+use crate::models::clock::current_tick;
+use crate::models::context::GameContext;
+use crate::models::encounter::EncounterStatus;
+use crate::models::exploration::{FleetSight, STAR_SIGHT_RANGE};
+use crate::models::fleet::Fleet;
+use crate::models::market::Market;
+use crate::models::mission::Mission;
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::salvage::SalvageField;
+use crate::models::spatial::with_galaxy_index;
+use crate::models::storage::storage_for;
+
+/// Events a game keeps for subscribers that fall behind before dropping the oldest.
+pub const EVENT_BUFFER: usize = 256;
+
+/// A change in a game's world, as pushed to subscribed clients.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEventKind {
+    /// A fleet set off, or jumped, from one place to another
+    FleetMoved {
+        fleet: String,
+        owner_id: String,
+        from: Position,
+        from_system_id: Option<usize>,
+        to: Position,
+        to_system_id: Option<usize>,
+        /// Tick the fleet arrives on, while it is in transit
+        arrival_tick: Option<u64>,
+    },
+    FleetArrived {
+        fleet: String,
+        owner_id: String,
+        position: Position,
+        system_id: Option<usize>,
+    },
+    CombatStarted {
+        attacker: String,
+        attacker_owner: String,
+        defender: String,
+        defender_owner: String,
+        position: Position,
+        system_id: Option<usize>,
+    },
+    CombatResolved {
+        attacker: String,
+        attacker_owner: String,
+        defender: String,
+        defender_owner: String,
+        position: Position,
+        system_id: Option<usize>,
+        attacker_victory: bool,
+        attacker_losses: usize,
+        defender_losses: usize,
+    },
+    MarketPriceChanged {
+        system_id: usize,
+        planet_id: usize,
+        resource_type: ResourceType,
+        buy: Option<f64>,
+        sell: Option<f64>,
+    },
+    EncounterTriggered {
+        fleet: String,
+        owner_id: String,
+        encounters: Vec<String>,
+    },
+    EncounterResolved {
+        encounter_id: String,
+        fleet: String,
+        owner_id: String,
+        hostile_fleet: String,
+        status: EncounterStatus,
+    },
+    MissionUpdated {
+        mission: Mission,
+    },
+    /// A fight left wreckage, cargo or disabled ships for its winner
+    SalvageLeft {
+        field_id: String,
+        claimant: String,
+        position: Position,
+        system_id: Option<usize>,
+    },
+}
+
+impl GameEventKind {
+    /// Name the event is sent under, matching its `type` field.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEventKind::FleetMoved { .. } => "fleet_moved",
+            GameEventKind::FleetArrived { .. } => "fleet_arrived",
+            GameEventKind::CombatStarted { .. } => "combat_started",
+            GameEventKind::CombatResolved { .. } => "combat_resolved",
+            GameEventKind::MarketPriceChanged { .. } => "market_price_changed",
+            GameEventKind::EncounterTriggered { .. } => "encounter_triggered",
+            GameEventKind::EncounterResolved { .. } => "encounter_resolved",
+            GameEventKind::MissionUpdated { .. } => "mission_updated",
+            GameEventKind::SalvageLeft { .. } => "salvage_left",
+        }
+    }
+
+    /// The players allowed to see this event: whoever it happened to, and
+    /// anyone with a fleet where it happened.
+    pub fn audience(&self, game_id: &str) -> Audience {
+        let mut players = BTreeSet::new();
+        match self {
+            GameEventKind::FleetMoved { owner_id, from, from_system_id, to, to_system_id, arrival_tick, .. } => {
+                players.insert(owner_id.clone());
+                players.extend(observers(game_id, *from_system_id, from));
+                // Fleets in transit are out of sight until they arrive
+                if arrival_tick.is_none() {
+                    players.extend(observers(game_id, *to_system_id, to));
+                }
+            }
+            GameEventKind::FleetArrived { owner_id, position, system_id, .. } => {
+                players.insert(owner_id.clone());
+                players.extend(observers(game_id, *system_id, position));
+            }
+            GameEventKind::CombatStarted { attacker_owner, defender_owner, position, system_id, .. }
+            | GameEventKind::CombatResolved { attacker_owner, defender_owner, position, system_id, .. } => {
+                players.insert(attacker_owner.clone());
+                players.insert(defender_owner.clone());
+                players.extend(observers(game_id, *system_id, position));
+            }
+            GameEventKind::MarketPriceChanged { system_id, .. } => {
+                players.extend(observers(game_id, Some(*system_id), &Position { x: 0, y: 0, z: 0 }));
+            }
+            GameEventKind::EncounterTriggered { owner_id, .. }
+            | GameEventKind::EncounterResolved { owner_id, .. } => {
+                players.insert(owner_id.clone());
+            }
+            GameEventKind::SalvageLeft { claimant, position, system_id, .. } => {
+                players.insert(claimant.clone());
+                players.extend(observers(game_id, *system_id, position));
+            }
+            GameEventKind::MissionUpdated { mission } => match &mission.accepted_by {
+                Some(player_name) => {
+                    players.insert(player_name.clone());
+                }
+                // Anyone may take a mission still on the board
+                None => return Audience::Everyone,
+            },
+        }
+        Audience::Players(players)
+    }
+}
+
+/// Who may receive an event.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Audience {
+    #[default]
+    Everyone,
+    Players(BTreeSet<String>),
+}
+
+impl Audience {
+    pub fn includes(&self, player_name: &str) -> bool {
+        match self {
+            Audience::Everyone => true,
+            Audience::Players(players) => players.contains(player_name),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GameEvent {
+    pub tick: u64,
+    #[serde(flatten)]
+    pub kind: GameEventKind,
+    #[serde(skip)]
+    pub audience: Audience,
+}
+
+/// Players who see a place the way [`FleetSight`] lets them see fleets:
+/// with a fleet resting in the system, or at that point of deep space.
+fn observers(game_id: &str, system_id: Option<usize>, position: &Position) -> BTreeSet<String> {
+    let names = with_galaxy_index(game_id, |index| match system_id {
+        Some(system_id) => index.fleets_in_system(system_id),
+        None => index.fleets_within(position, STAR_SIGHT_RANGE).into_iter().map(|(name, _)| name).collect(),
+    }).unwrap_or_default();
+    let Ok(storage) = storage_for(game_id) else {
+        return BTreeSet::new();
+    };
+    let mut fleets_by_owner: BTreeMap<String, Vec<Fleet>> = BTreeMap::new();
+    for fleet in names.iter().filter_map(|name| storage.load_fleet(name).ok().flatten()) {
+        fleets_by_owner.entry(fleet.owner_id.clone()).or_default().push(fleet);
+    }
+    fleets_by_owner.into_iter()
+        .filter(|(owner, fleets)| FleetSight::new(owner, fleets.clone()).watches(system_id, position))
+        .map(|(owner, _)| owner)
+        .collect()
+}
+
+/// Sends an event to the subscribers of the game entered on this thread.
+///
+/// Events are only published once the change they describe is saved; with
+/// no game entered, or nobody listening, the event is dropped.
+pub fn publish(kind: GameEventKind) {
+    let Ok(context) = GameContext::current() else {
+        return;
+    };
+    let audience = kind.audience(&context.game_id);
+    context.publish(GameEvent { tick: current_tick(), kind, audience });
+}
+
+/// Publishes how a fleet moved: arrived straight away, or set off in transit.
+pub fn publish_fleet_moved(before: &Fleet, after: &Fleet) {
+    if before.position == after.position && before.current_system_id == after.current_system_id && after.transit.is_none() {
+        return;
+    }
+    let (to, to_system_id, arrival_tick) = match &after.transit {
+        Some(transit) => (transit.destination, transit.destination_system_id, Some(transit.arrival_tick)),
+        None => (after.position, after.current_system_id, None),
+    };
+    publish(GameEventKind::FleetMoved {
+        fleet: after.name.clone(),
+        owner_id: after.owner_id.clone(),
+        from: before.position,
+        from_system_id: before.current_system_id,
+        to,
+        to_system_id,
+        arrival_tick,
+    });
+    if after.transit.is_none() {
+        publish(GameEventKind::FleetArrived {
+            fleet: after.name.clone(),
+            owner_id: after.owner_id.clone(),
+            position: after.position,
+            system_id: after.current_system_id,
+        });
+    }
+}
+
+/// A fresh channel for a game's events.
+pub fn event_channel() -> broadcast::Sender<GameEvent> {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+/// Publishes missions whose state changed, e.g. ones just completed.
+pub fn publish_missions(missions: &[Mission]) {
+    for mission in missions {
+        publish(GameEventKind::MissionUpdated { mission: mission.clone() });
+    }
+}
+
+/// Publishes a salvage field a fight just left behind.
+pub fn publish_salvage(field: &SalvageField) {
+    publish(GameEventKind::SalvageLeft {
+        field_id: field.id.clone(),
+        claimant: field.claimant.clone(),
+        position: field.position,
+        system_id: field.system_id,
+    });
+}
+
+/// Publishes the prices a trade left a planet's market at.
+pub fn publish_market_prices(system_id: usize, planet_id: usize, market: &Market, resource_type: ResourceType) {
+    if let Some(resource) = market.resources.iter().find(|r| r.resource_type == resource_type) {
+        publish(GameEventKind::MarketPriceChanged {
+            system_id,
+            planet_id,
+            resource_type,
+            buy: resource.buy,
+            sell: resource.sell,
+        });
+    }
+}
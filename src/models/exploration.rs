@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::models::fleet::{Fleet, list_owner_fleets};
+use crate::models::storage::{load_document, save_document};
+use crate::models::market::Market;
+use crate::models::npc_trader::is_npc_trader;
+use crate::models::position::Position;
+use crate::models::ship::ship::ShipType;
+use crate::models::star_system::StarSystem;
+
+/// Distance at which a fleet can make out a star, but not its planets.
+pub const STAR_SIGHT_RANGE: f64 = 800.0;
+/// Distance at which an Explorer can survey a system without visiting it.
+pub const EXPLORER_SCAN_RANGE: f64 = 1200.0;
+/// Age in ticks after which a market observation is reported as stale.
+pub const MARKET_INTEL_STALE_TICKS: u64 = 50;
+
+/// How much a player knows about a star system.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Visibility {
+    /// Not on the player's charts at all
+    #[default]
+    Unknown,
+    /// The star has been sighted from afar; its planets are a mystery
+    StarVisible,
+    /// Visited or scanned: planets are known and markets were observed
+    Surveyed,
+}
+
+/// Prices and stock of a market as last seen by a player.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MarketIntel {
+    pub observed_tick: u64,
+    pub market: Market,
+}
+
+/// A player's knowledge of one star system.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SystemIntel {
+    pub visibility: Visibility,
+    /// Markets observed in the system, by planet id
+    #[serde(default)]
+    pub markets: BTreeMap<usize, MarketIntel>,
+}
+
+/// A market as a player knows it, with how old the information is.
+#[derive(Serialize, Debug, Clone)]
+pub struct KnownMarket {
+    #[serde(flatten)]
+    pub market: Market,
+    pub observed_tick: u64,
+    pub age: u64,
+    pub stale: bool,
+}
+
+/// Systems surveyed by an Explorer scan.
+#[derive(Serialize, Debug, Clone)]
+pub struct ScanReport {
+    pub fleet_name: String,
+    pub tick: u64,
+    pub range: f64,
+    pub surveyed: Vec<usize>,
+    /// Systems in range that were not surveyed before this scan
+    pub discovered: Vec<usize>,
+}
+
+/// Everything a player has learned about the galaxy.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerKnowledge {
+    pub player_name: String,
+    #[serde(default)]
+    pub systems: BTreeMap<usize, SystemIntel>,
+}
+
+/// Whether any ship of the fleet can run an exploration scan.
+pub fn has_explorer(fleet: &Fleet) -> bool {
+    fleet.ships.iter().any(|ship| matches!(ship.specialization, ShipType::Explorer) && ship.hp > 0)
+}
+
+/// Which fleets a player can see right now: their own, and other fleets
+/// stopped in a system or at a point of deep space where one of the
+/// player's fleets is stopped too.
+#[derive(Debug, Clone)]
+pub struct FleetSight {
+    pub player_name: String,
+    /// The player's fleets
+    pub observers: Vec<Fleet>,
+}
+
+impl FleetSight {
+    pub fn new(player_name: &str, observers: Vec<Fleet>) -> Self {
+        FleetSight { player_name: player_name.to_string(), observers }
+    }
+
+    /// Loads the fleets a player looks out from.
+    pub fn for_player(player_name: &str) -> Result<Self, String> {
+        let observers = list_owner_fleets(player_name).map_err(|e| format!("Failed to load fleets: {}", e))?;
+        Ok(FleetSight::new(player_name, observers))
+    }
+
+    pub fn can_see(&self, fleet: &Fleet) -> bool {
+        if fleet.owner_id == self.player_name {
+            return true;
+        }
+        fleet.transit.is_none() && self.watches(fleet.current_system_id, &fleet.position)
+    }
+
+    /// Whether one of the player's fleets is watching a place: resting in
+    /// the system, or at that very point of deep space.
+    pub fn watches(&self, system_id: Option<usize>, position: &Position) -> bool {
+        self.observers.iter()
+            .filter(|observer| observer.transit.is_none() && !observer.ships.is_empty())
+            .any(|observer| match (observer.current_system_id, system_id) {
+                (Some(watched), Some(system_id)) => watched == system_id,
+                (None, None) => observer.position == *position,
+                _ => false,
+            })
+    }
+}
+
+/// Loads the current market of every planet of a system, generating missing ones.
+pub fn load_system_markets(system: &StarSystem) -> BTreeMap<(usize, usize), Market> {
+    (0..system.planets.len())
+        .filter_map(|planet_id| Market::load(system.id, planet_id).ok().map(|market| ((system.id, planet_id), market)))
+        .collect()
+}
+
+impl PlayerKnowledge {
+    pub fn new(player_name: &str) -> Self {
+        PlayerKnowledge { player_name: player_name.to_string(), systems: BTreeMap::new() }
+    }
+
+    pub fn file_name(player_name: &str) -> String {
+        format!("{}.json", player_name)
+    }
+
+    /// Loads a player's knowledge; players who have seen nothing yet start empty.
+    pub fn load(game_id: &str, player_name: &str) -> Result<Self, String> {
+        Ok(load_document(game_id, &["knowledge", &Self::file_name(player_name)])?
+            .unwrap_or_else(|| PlayerKnowledge::new(player_name)))
+    }
+
+    pub fn save(&self, game_id: &str) -> Result<(), String> {
+        save_document(game_id, &["knowledge", &Self::file_name(&self.player_name)], self)
+    }
+
+    pub fn visibility(&self, system_id: usize) -> Visibility {
+        self.systems.get(&system_id).map_or(Visibility::Unknown, |intel| intel.visibility)
+    }
+
+    /// Raises what is known about a system; knowledge is never lost.
+    fn raise(&mut self, system_id: usize, visibility: Visibility) -> bool {
+        let intel = self.systems.entry(system_id).or_default();
+        if intel.visibility >= visibility {
+            return false;
+        }
+        intel.visibility = visibility;
+        true
+    }
+
+    /// Sights every star within `STAR_SIGHT_RANGE` of a position.
+    ///
+    /// # Returns
+    /// Whether anything new was learned
+    pub fn sight_stars(&mut self, from: &Position, systems: &[StarSystem]) -> bool {
+        let mut learned = false;
+        for system in systems.iter().filter(|system| from.distance(&system.position) <= STAR_SIGHT_RANGE) {
+            learned |= self.raise(system.id, Visibility::StarVisible);
+        }
+        learned
+    }
+
+    /// Surveys a system, recording the markets of its planets found in `markets`.
+    ///
+    /// # Returns
+    /// Whether the system was not surveyed before
+    pub fn survey(&mut self, system: &StarSystem, markets: &BTreeMap<(usize, usize), Market>, tick: u64) -> bool {
+        let discovered = self.raise(system.id, Visibility::Surveyed);
+        let intel = self.systems.entry(system.id).or_default();
+        for planet_id in 0..system.planets.len() {
+            if let Some(market) = markets.get(&(system.id, planet_id)) {
+                let mut seen = market.clone();
+                // Long-run supply and demand is not something a visitor can read off the price boards
+                seen.profiles.clear();
+                intel.markets.insert(planet_id, MarketIntel { observed_tick: tick, market: seen });
+            }
+        }
+        discovered
+    }
+
+    /// Updates knowledge from the player's fleets: each fleet sights the
+    /// stars around it and surveys the system it is resting in.
+    ///
+    /// # Arguments
+    /// * `fleets` - Fleets to observe from; fleets of other owners are ignored
+    /// * `systems` - Every star system of the galaxy
+    /// * `markets` - Current markets, for the systems the fleets rest in
+    /// * `tick` - The current game tick
+    pub fn observe(&mut self, fleets: &[Fleet], systems: &[StarSystem], markets: &BTreeMap<(usize, usize), Market>, tick: u64) {
+        for fleet in fleets {
+            if fleet.owner_id != self.player_name {
+                continue;
+            }
+            self.sight_stars(&fleet.position, systems);
+            if fleet.transit.is_none()
+                && let Some(system) = fleet.current_system_id.and_then(|id| systems.iter().find(|system| system.id == id))
+            {
+                self.survey(system, markets, tick);
+            }
+        }
+    }
+
+    /// Surveys every system within `EXPLORER_SCAN_RANGE` of a fleet with an Explorer.
+    ///
+    /// # Returns
+    /// The systems surveyed, or an error if the fleet cannot scan
+    pub fn scan(&mut self, fleet: &Fleet, systems: &[StarSystem], markets: &BTreeMap<(usize, usize), Market>, tick: u64) -> Result<ScanReport, String> {
+        if fleet.owner_id != self.player_name {
+            return Err(format!("Fleet {} does not belong to {}", fleet.name, self.player_name));
+        }
+        if !has_explorer(fleet) {
+            return Err(format!("Fleet {} has no working Explorer to scan with", fleet.name));
+        }
+        if fleet.transit.is_some() {
+            return Err(format!("Fleet {} cannot scan while travelling", fleet.name));
+        }
+        let mut report = ScanReport { fleet_name: fleet.name.clone(), tick, range: EXPLORER_SCAN_RANGE, surveyed: Vec::new(), discovered: Vec::new() };
+        for system in systems.iter().filter(|system| fleet.position.distance(&system.position) <= EXPLORER_SCAN_RANGE) {
+            if self.survey(system, markets, tick) {
+                report.discovered.push(system.id);
+            }
+            report.surveyed.push(system.id);
+        }
+        Ok(report)
+    }
+
+    /// A system as the player knows it, or `None` if it is not on their charts.
+    /// Planets are only listed for surveyed systems, each carrying the market
+    /// the player last saw there.
+    pub fn filter_system(&self, system: &StarSystem) -> Option<StarSystem> {
+        let intel = self.systems.get(&system.id)?;
+        let mut known = system.clone();
+        match intel.visibility {
+            Visibility::Unknown => return None,
+            Visibility::StarVisible => known.planets.clear(),
+            Visibility::Surveyed => {
+                for (planet_id, planet) in known.planets.iter_mut().enumerate() {
+                    planet.market = intel.markets.get(&planet_id)
+                        .map_or_else(|| Market { resources: Vec::new(), profiles: Vec::new() }, |seen| seen.market.clone());
+                }
+            }
+        }
+        Some(known)
+    }
+
+    /// The systems of the galaxy the player knows about, filtered as in `filter_system`.
+    pub fn filter_galaxy(&self, systems: &[StarSystem]) -> Vec<StarSystem> {
+        systems.iter().filter_map(|system| self.filter_system(system)).collect()
+    }
+
+    /// Systems at least sighted by the player, unfiltered, for planning routes through them.
+    pub fn charted_systems(&self, systems: &[StarSystem]) -> Vec<StarSystem> {
+        systems.iter()
+            .filter(|system| self.visibility(system.id) >= Visibility::StarVisible)
+            .cloned()
+            .collect()
+    }
+
+    /// Fails unless the player has surveyed a system and knows what its planets offer.
+    pub fn check_surveyed(&self, system_id: usize) -> Result<(), String> {
+        if self.visibility(system_id) != Visibility::Surveyed {
+            return Err(format!("System {} has not been surveyed", system_id));
+        }
+        Ok(())
+    }
+
+    /// The last observation of a planet's market.
+    ///
+    /// # Returns
+    /// The market with its age, or an error if the player never saw it
+    pub fn known_market(&self, system_id: usize, planet_id: usize, now: u64) -> Result<KnownMarket, String> {
+        self.check_surveyed(system_id)?;
+        let seen = self.systems.get(&system_id)
+            .and_then(|intel| intel.markets.get(&planet_id))
+            .ok_or_else(|| format!("No market information for planet {} in system {}", planet_id, system_id))?;
+        let age = now.saturating_sub(seen.observed_tick);
+        Ok(KnownMarket {
+            market: seen.market.clone(),
+            observed_tick: seen.observed_tick,
+            age,
+            stale: age > MARKET_INTEL_STALE_TICKS,
+        })
+    }
+}
+
+/// Loads a player's knowledge and brings it up to date with where their
+/// fleets are now, saving it if anything changed.
+///
+/// # Arguments
+/// * `game_id` - The game the player is in
+/// * `player_name` - Whose knowledge to refresh
+/// * `systems` - Every star system of the galaxy
+/// * `tick` - The current game tick
+pub fn refresh_knowledge(game_id: &str, player_name: &str, systems: &[StarSystem], tick: u64) -> Result<PlayerKnowledge, String> {
+    let mut knowledge = PlayerKnowledge::load(game_id, player_name)?;
+    let fleets: Vec<Fleet> = list_owner_fleets(player_name)
+        .map_err(|e| format!("Failed to load fleets: {}", e))?
+        .into_iter()
+        .filter(|fleet| !is_npc_trader(fleet))
+        .collect();
+    let markets: BTreeMap<(usize, usize), Market> = fleets.iter()
+        .filter(|fleet| fleet.transit.is_none())
+        .filter_map(|fleet| fleet.current_system_id)
+        .filter_map(|id| systems.iter().find(|system| system.id == id))
+        .flat_map(load_system_markets)
+        .collect();
+
+    let before = serde_json::to_string(&knowledge).map_err(|e| e.to_string())?;
+    knowledge.observe(&fleets, systems, &markets, tick);
+    if serde_json::to_string(&knowledge).map_err(|e| e.to_string())? != before {
+        knowledge.save(game_id)?;
+    }
+    Ok(knowledge)
+}
@@ -2,11 +2,27 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::io::Read;
 use std::path::Path;
-use std::fs;
-use std::fs::File;
 use std::collections::HashMap;
+use rand::Rng;
+use rand::seq::SliceRandom;
 use crate::models::settings::load_settings;
-use crate::models::game_state::game_path;
+use crate::models::game_state::current_game_id;
+use crate::models::storage::storage_for;
+use crate::models::player::Player;
+use crate::models::star_system::StarSystem;
+
+/// Player reputation with a faction is kept within `-MAX_REPUTATION..=MAX_REPUTATION`.
+pub const MAX_REPUTATION: f32 = 100.0;
+
+/// Reputation lost with a faction when the player attacks one of its fleets.
+pub const ATTACK_REPUTATION_PENALTY: f32 = -15.0;
+
+/// Reputation gained with a faction for each trade with one of its fleets.
+pub const TRADE_REPUTATION_GAIN: f32 = 2.0;
+
+/// Reputation lost with the faction policing a system when the player
+/// attacks a trader there.
+pub const PIRACY_REPUTATION_PENALTY: f32 = -10.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Faction {
@@ -16,6 +32,51 @@ pub struct Faction {
     pub credits: f32,
     pub fleets: Vec<String>,
     pub relations: HashMap<String, f32>,
+    #[serde(default)]
+    pub influence: u32,
+    /// Ids of the star systems this faction controls
+    #[serde(default)]
+    pub territory: Vec<usize>,
+    #[serde(default)]
+    pub capital_system_id: Option<usize>,
+}
+
+/// How a faction regards the player, derived from reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Standing {
+    Hostile,
+    Unfriendly,
+    Neutral,
+    Friendly,
+    Allied,
+}
+
+impl Standing {
+    pub fn from_reputation(reputation: f32) -> Self {
+        match reputation {
+            r if r <= -50.0 => Standing::Hostile,
+            r if r <= -10.0 => Standing::Unfriendly,
+            r if r < 10.0 => Standing::Neutral,
+            r if r < 50.0 => Standing::Friendly,
+            _ => Standing::Allied,
+        }
+    }
+}
+
+/// The player's standing with a single faction, as returned to clients.
+#[derive(Debug, Clone, Serialize)]
+pub struct FactionStanding {
+    pub faction: String,
+    pub reputation: f32,
+    pub standing: Standing,
+}
+
+/// A reputation change applied to the player.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReputationChange {
+    pub faction: String,
+    pub change: f32,
+    pub reputation: f32,
 }
 
 impl fmt::Display for Faction {
@@ -33,6 +94,9 @@ impl Faction {
             credits: 1000.0,
             fleets: Vec::new(),
             relations: HashMap::new(),
+            influence: 0,
+            territory: Vec::new(),
+            capital_system_id: None,
         }
     }
 
@@ -46,26 +110,148 @@ impl Faction {
 }
 
 pub fn save_faction(faction: &Faction) -> std::io::Result<()> {
-    let faction_path = game_path(&["factions", &format!("{}.json", faction.name)]);
-    let file = File::create(faction_path)?;
-    serde_json::to_writer(file, faction)?;
-    Ok(())
+    current_game_id()
+        .and_then(|game_id| storage_for(&game_id))
+        .and_then(|storage| storage.save_faction(faction))
+        .map_err(std::io::Error::other)
 }
 
 pub fn load_faction(faction_name: &str) -> std::io::Result<Option<Faction>> {
     let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-    let faction_path = game_path(&["factions", &format!("{}.json", faction_name)]);
-
-    if !faction_path.exists() {
-        return Ok(None);
-    }
-
-    let file = File::open(faction_path)?;
-    let faction: Faction = serde_json::from_reader(file)?;
-    Ok(Some(faction))
+    storage_for(&settings.game_id)
+        .and_then(|storage| storage.load_faction(faction_name))
+        .map_err(std::io::Error::other)
 }
 
 pub fn update_relations(faction1: &mut Faction, faction2: &mut Faction, change: f32) {
     faction1.reputation += change;
     faction2.reputation += change;
-} 
\ No newline at end of file
+}
+
+/// Loads every faction of the active game, sorted by name.
+pub fn list_factions() -> std::io::Result<Vec<Faction>> {
+    let mut factions = current_game_id()
+        .and_then(|game_id| storage_for(&game_id))
+        .and_then(|storage| storage.list_factions())
+        .map_err(std::io::Error::other)?;
+    factions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(factions)
+}
+
+/// Returns the faction that controls a star system, if any.
+pub fn controlling_faction(factions: &[Faction], system_id: usize) -> Option<&Faction> {
+    factions.iter().find(|faction| faction.territory.contains(&system_id))
+}
+
+/// Records an attack on a trader: whoever keeps order in the system takes
+/// it badly. Piracy in unclaimed systems and deep space goes unpunished.
+pub fn record_piracy(player: &mut Player, factions: &[Faction], system_id: Option<usize>) -> Vec<ReputationChange> {
+    match system_id.and_then(|system_id| controlling_faction(factions, system_id)) {
+        Some(faction) => apply_reputation_change(player, factions, &faction.name, PIRACY_REPUTATION_PENALTY),
+        None => Vec::new(),
+    }
+}
+
+/// Rolls the starting relations between every pair of factions.
+///
+/// Relations are symmetric and range from -50 (rivals) to 50 (allies).
+pub fn generate_relations<R: Rng + ?Sized>(factions: &mut [Faction], rng: &mut R) {
+    for i in 0..factions.len() {
+        for j in (i + 1)..factions.len() {
+            let relation = rng.gen_range(-50.0..=50.0_f32).round();
+            let (left, right) = factions.split_at_mut(j);
+            left[i].add_relation(right[0].name.clone(), relation);
+            right[0].add_relation(left[i].name.clone(), relation);
+        }
+    }
+}
+
+/// Divides the galaxy between factions.
+///
+/// Every faction gets a distinct capital system. Other systems go to the
+/// faction whose influence, weighted by distance to its capital, is the
+/// strongest, provided the system lies within that faction's reach of
+/// `influence`% of the map width. Systems out of everyone's reach stay
+/// unclaimed.
+///
+/// # Arguments
+/// * `factions` - The factions to assign territory to; `influence` must be set
+/// * `systems` - Every star system in the galaxy
+/// * `map_width` - Width of the galaxy map
+/// * `rng` - The random number generator used to pick capitals
+pub fn assign_territories<R: Rng + ?Sized>(factions: &mut [Faction], systems: &[StarSystem], map_width: u32, rng: &mut R) {
+    let mut capitals: Vec<&StarSystem> = systems.iter().collect();
+    capitals.shuffle(rng);
+
+    for (faction, capital) in factions.iter_mut().zip(capitals) {
+        faction.capital_system_id = Some(capital.id);
+        faction.territory = vec![capital.id];
+    }
+
+    for system in systems {
+        if factions.iter().any(|faction| faction.capital_system_id == Some(system.id)) {
+            continue;
+        }
+
+        let mut best: Option<(usize, f64)> = None;
+        for (index, faction) in factions.iter().enumerate() {
+            let Some(capital) = faction.capital_system_id.and_then(|id| systems.iter().find(|s| s.id == id)) else {
+                continue;
+            };
+            let distance = system.position.distance(&capital.position);
+            let reach = map_width as f64 * faction.influence as f64 / 100.0;
+            if distance > reach {
+                continue;
+            }
+            let strength = faction.influence as f64 / (1.0 + distance);
+            if best.is_none_or(|(_, best_strength)| strength > best_strength) {
+                best = Some((index, strength));
+            }
+        }
+
+        if let Some((index, _)) = best {
+            factions[index].territory.push(system.id);
+        }
+    }
+
+    for faction in factions.iter_mut() {
+        faction.territory.sort_unstable();
+    }
+}
+
+/// Changes the player's reputation with a faction.
+///
+/// Factions friendly to the affected faction follow along in proportion to
+/// their relation, while its rivals react in the opposite direction. Owners
+/// that are not factions (pirates, merchants, ...) leave reputation untouched.
+///
+/// # Arguments
+/// * `player` - The player whose reputation changes
+/// * `factions` - Every faction of the game
+/// * `faction_name` - The faction the player acted against or with
+/// * `change` - The reputation change with that faction
+///
+/// # Returns
+/// The changes that were applied, one per affected faction
+pub fn apply_reputation_change(player: &mut Player, factions: &[Faction], faction_name: &str, change: f32) -> Vec<ReputationChange> {
+    let Some(faction) = factions.iter().find(|f| f.name == faction_name) else {
+        return Vec::new();
+    };
+
+    let mut changes = vec![(faction.name.clone(), change)];
+    let mut relations: Vec<(&String, &f32)> = faction.relations.iter().collect();
+    relations.sort_by(|a, b| a.0.cmp(b.0));
+    for (other, relation) in relations {
+        let ripple = change * relation / 100.0;
+        if ripple.abs() >= 0.5 {
+            changes.push((other.clone(), ripple.round()));
+        }
+    }
+
+    changes.into_iter()
+        .map(|(faction, change)| {
+            let reputation = player.adjust_reputation(&faction, change);
+            ReputationChange { faction, change, reputation }
+        })
+        .collect()
+}
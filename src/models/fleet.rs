@@ -3,19 +3,25 @@ use crate::models::star_system::StarSystem;
 use crate::models::position::Position;
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::fs;
-use std::path::Path;
-use std::fs::File;
 use std::io::Read;
-use rand::random;
+use rand::distributions::Standard;
+use rand::Rng;
 use crate::models::settings::load_settings;
 use crate::encounters::EncounterFleet;
 use crate::models::settings::GameSettings;
-use crate::models::game_state::game_data_path;
+use crate::models::travel::FleetTransit;
+use crate::models::npc_trader::TraderProfile;
+use crate::models::storage::{document_key, list_documents, storage_for, write_document};
+use crate::models::route::Route;
+use crate::models::context::GameContext;
+use crate::models::spatial::track_fleet;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fleet {
     pub name: String,
+    /// Name chosen by the owner; `name` stays the fleet's id and file name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
     pub owner_id: String,
     pub ships: Vec<Ship>,
     pub position: Position,
@@ -23,6 +29,15 @@ pub struct Fleet {
     pub local_position: Option<Position>,
     pub current_system_id: Option<usize>, // Optional because fleet might be between systems
     pub last_move_distance: Option<f64>,
+    /// Set while the fleet is travelling; cleared when it arrives
+    #[serde(default)]
+    pub transit: Option<FleetTransit>,
+    /// Trading state of NPC trader fleets; `None` for everyone else
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trader: Option<TraderProfile>,
+    /// Multi-leg route the fleet is following, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<Route>,
 }
 
 #[derive(Serialize, Debug)]
@@ -40,6 +55,8 @@ pub struct MoveFleetResponse {
     pub local_current_position: Option<Position>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub local_target_position: Option<Position>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transit: Option<FleetTransit>,
 }
 
 #[derive(Serialize, Debug, Default, Clone)]
@@ -83,12 +100,16 @@ impl Fleet {
     pub fn new(owner_id: String, position: Position, fleet_number: usize) -> Self {
         Fleet {
             name: format!("Fleet_{}_{}", owner_id, fleet_number),
+            display_name: None,
+            route: None,
             owner_id,
             ships: Vec::new(),
             position,
             local_position: None,
             current_system_id: None,
             last_move_distance: None,
+            transit: None,
+            trader: None,
         }
     }
 
@@ -129,21 +150,20 @@ impl Fleet {
         }
     }
 
-    /// Loads a fleet from a JSON file at the specified path.
+    /// Loads a fleet of the active game by its name.
     ///
     /// The function returns an `std::io::Result` containing the loaded fleet.
-    /// If the file does not exist or if there is an error while reading the file,
+    /// If the fleet does not exist or if there is an error while reading it,
     /// the function returns an `std::io::Error`.
-    pub fn load(path: &str) -> std::io::Result<Fleet> {
+    pub fn load(fleet_name: &str) -> std::io::Result<Fleet> {
         let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        let fleet_path = game_data_path(&settings.game_id, &["fleets", &format!("{}.json", path)]);
-
-        let file = File::open(fleet_path)?;
-        let fleet: Fleet = serde_json::from_reader(file)?;
-        Ok(fleet)
+        storage_for(&settings.game_id)
+            .and_then(|storage| storage.load_fleet(fleet_name))
+            .map_err(std::io::Error::other)?
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("Fleet {} not found", fleet_name)))
     }
 
-    /// Saves the fleet to a JSON file at the specified path.
+    /// Saves the fleet in the active game's storage under the given name.
     ///
     /// The function creates the necessary directories and writes the fleet
     /// data in JSON format to the file. If the operation is successful,
@@ -151,24 +171,17 @@ impl Fleet {
     ///
     /// # Arguments
     ///
-    /// * `path` - A string slice that specifies the path where the fleet
-    ///   should be saved. This path is relative to the game's data directory.
+    /// * `fleet_name` - The name the fleet is stored under, usually its own
     ///
     /// # Errors
     ///
     /// This function will return an error if there is an issue creating the
     /// directories, creating the file, or writing the fleet data to the file.
-    pub fn save(&self, path: &str) -> std::io::Result<()> {
+    pub fn save(&self, fleet_name: &str) -> std::io::Result<()> {
         let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        let fleet_path = game_data_path(&settings.game_id, &["fleets", &format!("{}.json", path)]);
-
-        if let Some(parent) = fleet_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        let file = File::create(fleet_path)?;
-        serde_json::to_writer(file, self)?;
-        Ok(())
+        storage_for(&settings.game_id)
+            .and_then(|storage| write_document(storage.as_ref(), &document_key(&["fleets", &format!("{}.json", fleet_name)]), self))
+            .map_err(std::io::Error::other)
     }
 
     
@@ -224,27 +237,14 @@ impl Fleet {
 /// If there is an IO error while reading the directory, an error will be returned.
 pub fn get_next_fleet_number(owner_id: &str) -> std::io::Result<usize> {
     let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-    let fleets_dir = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("fleets");
-
-    if !fleets_dir.exists() {
-        return Ok(1);
-    }
+    let file_names = list_documents(&settings.game_id, &["fleets"]).map_err(std::io::Error::other)?;
 
     let mut max_number = 0;
-    for entry in fs::read_dir(fleets_dir)? {
-        if let Ok(entry) = entry {
-            if let Some(file_name) = entry.file_name().to_str() {
-                let prefix = format!("Fleet_{}", owner_id);
-                if file_name.starts_with(&prefix) {
-                    if let Some(number) = file_name.split('_').last().and_then(|n| n.split('.').next()) {
-                        if let Ok(num) = number.parse::<usize>() {
-                            max_number = max_number.max(num);
-                        }
-                    }
-                }
+    let prefix = format!("Fleet_{}", owner_id);
+    for file_name in file_names.iter().filter(|name| name.starts_with(&prefix)) {
+        if let Some(number) = file_name.split('_').last().and_then(|n| n.split('.').next()) {
+            if let Ok(num) = number.parse::<usize>() {
+                max_number = max_number.max(num);
             }
         }
     }
@@ -261,34 +261,35 @@ pub fn get_next_fleet_number(owner_id: &str) -> std::io::Result<usize> {
 /// in the returned vector.
 pub fn list_owner_fleets(owner_id: &str) -> std::io::Result<Vec<Fleet>> {
     let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-    let fleets_dir = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("fleets");
-
-    if !fleets_dir.exists() {
-        return Ok(Vec::new());
-    }
+    let file_names = list_documents(&settings.game_id, &["fleets"]).map_err(std::io::Error::other)?;
 
     let mut fleets = Vec::new();
     let prefix = format!("Fleet_{}_", owner_id);
     
-    for entry in fs::read_dir(fleets_dir)? {
-        if let Ok(entry) = entry {
-            if let Some(file_name) = entry.file_name().to_str() {
-                if file_name.starts_with(&prefix) {
-                    let fleet_name = file_name.trim_end_matches(".json");
-                    if let Ok(Some(fleet)) = load_fleet(fleet_name) {
-                        fleets.push(fleet);
-                    }
-                }
-            }
+    for file_name in file_names.iter().filter(|name| name.starts_with(&prefix)) {
+        let fleet_name = file_name.trim_end_matches(".json");
+        if let Ok(Some(fleet)) = load_fleet(fleet_name) {
+            fleets.push(fleet);
         }
     }
     println!("Fleets for owner {}: {:?}", owner_id, fleets);
     Ok(fleets)
 }
 
+/// Lists every fleet saved for the given game, regardless of owner.
+///
+/// # Errors
+///
+/// If there is an IO error while reading the directory, an error will be returned.
+/// Fleet files that are not valid JSON are skipped.
+pub fn list_all_fleets(game_id: &str) -> std::io::Result<Vec<Fleet>> {
+    let mut fleets = storage_for(game_id)
+        .and_then(|storage| storage.list_fleets())
+        .map_err(std::io::Error::other)?;
+    fleets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fleets)
+}
+
 /// Generates a new random fleet for the given `owner_id` and `ship_count`, saves it to a file, and returns the generated `Fleet` object.
 ///
 /// The file name is in the format "Fleet_<owner_id>_<fleet_number>.json", where `<fleet_number>` is a number starting from 1 and incrementing for each new fleet for the given `owner_id`.
@@ -296,27 +297,18 @@ pub fn list_owner_fleets(owner_id: &str) -> std::io::Result<Vec<Fleet>> {
 /// # Errors
 ///
 /// If there is an IO error while creating the file or writing to it, an error will be returned. If there is an error while generating the fleet, an error will be returned.
-pub fn generate_and_save_fleet(owner_id: String, position: Position, ship_count: usize) -> std::io::Result<Fleet> {
+pub fn generate_and_save_fleet<R: Rng + ?Sized>(owner_id: String, position: Position, ship_count: usize, rng: &mut R) -> std::io::Result<Fleet> {
     let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
     let fleet_number = get_next_fleet_number(&owner_id)?;
     let fleet_name = format!("Fleet_{}_{}", owner_id, fleet_number);
     
     // Generate new fleet
-    let fleet = generate_random_fleet(owner_id, position, ship_count, fleet_number);
+    let fleet = generate_random_fleet(owner_id, position, ship_count, fleet_number, rng);
     
     // Save the fleet
-    let fleet_path = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("fleets")
-        .join(format!("{}.json", fleet_name));
-
-    if let Some(parent) = fleet_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let file = std::fs::File::create(fleet_path)?;
-    serde_json::to_writer(file, &fleet)?;
+    storage_for(&settings.game_id)
+        .and_then(|storage| write_document(storage.as_ref(), &document_key(&["fleets", &format!("{}.json", fleet_name)]), &fleet))
+        .map_err(std::io::Error::other)?;
     
     Ok(fleet)
 }
@@ -330,11 +322,11 @@ pub fn generate_and_save_fleet(owner_id: String, position: Position, ship_count:
 /// # Returns
 ///
 /// The generated `Fleet` object.
-pub fn generate_random_fleet(owner_id: String, position: Position, ship_count: usize, fleet_number: usize) -> Fleet {
+pub fn generate_random_fleet<R: Rng + ?Sized>(owner_id: String, position: Position, ship_count: usize, fleet_number: usize, rng: &mut R) -> Fleet {
     let mut fleet = Fleet::new(owner_id, position.clone(), fleet_number);
     
     for _ in 0..ship_count {
-        let mut ship = random::<Ship>();
+        let mut ship: Ship = rng.sample(Standard);
         ship.owner = fleet.owner_id.clone();
         ship.position = position.clone();
         fleet.add_ship(ship);
@@ -363,19 +355,7 @@ pub fn generate_random_fleet(owner_id: String, position: Position, ship_count: u
 /// * `Err(String)` if there is an error accessing the file or parsing the JSON.
 pub fn load_fleet(fleet_name: &str) -> Result<Option<Fleet>, String> {
     let settings = load_settings().map_err(|e| e.to_string())?;
-    let fleet_path = game_data_path(&settings.game_id, &["fleets", &format!("{}.json", fleet_name)]);
-
-    if !fleet_path.exists() {
-        return Ok(None);
-    }
-
-    let file = std::fs::File::open(fleet_path)
-        .map_err(|e| format!("Failed to open fleet file: {}", e))?;
-    
-    let fleet: Fleet = serde_json::from_reader(file)
-        .map_err(|e| format!("Failed to parse fleet data: {}", e))?;
-    
-    Ok(Some(fleet))
+    storage_for(&settings.game_id)?.load_fleet(fleet_name)
 }
 
 /// Saves a `Fleet` object to a JSON file at the specified path.
@@ -387,19 +367,11 @@ pub fn load_fleet(fleet_name: &str) -> Result<Option<Fleet>, String> {
 /// If there is an IO error while creating the file or writing to it, an error will be returned. If there is an error while generating the fleet, an error will be returned.
 pub fn save_fleet(fleet: &Fleet) -> Result<(), String> {
     let settings = load_settings().map_err(|e| e.to_string())?;
-    let fleet_path = game_data_path(&settings.game_id, &["fleets", &format!("{}.json", fleet.name)]);
-
-    if let Some(parent) = fleet_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create fleet directory: {}", e))?;
-    }
-
-    let file = std::fs::File::create(fleet_path)
-        .map_err(|e| format!("Failed to create fleet file: {}", e))?;
-    
-    serde_json::to_writer(file, fleet)
+    storage_for(&settings.game_id)?.save_fleet(fleet)
         .map_err(|e| format!("Failed to write fleet data: {}", e))?;
-    
+    if let Some(context) = GameContext::current_for(&settings.game_id) {
+        track_fleet(&context, fleet);
+    }
     Ok(())
 }
 
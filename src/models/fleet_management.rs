@@ -0,0 +1,147 @@
+use serde::Deserialize;
+use crate::models::fleet::Fleet;
+use crate::models::npc_trader::is_npc_trader;
+use crate::models::ship::ship::CombatState;
+
+/// Longest name a player can give a fleet.
+pub const MAX_FLEET_NAME_LENGTH: usize = 32;
+
+#[derive(Deserialize, Debug)]
+pub struct SplitFleetData {
+    /// Ships that leave for the new fleet
+    pub ship_indices: Vec<usize>,
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MergeFleetData {
+    /// The fleet absorbed into the one named in the path; its file is removed
+    pub fleet_name: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RenameFleetData {
+    pub display_name: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FleetStanceData {
+    pub stance: CombatState,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransferShipsData {
+    pub target_fleet: String,
+    pub ship_indices: Vec<usize>,
+}
+
+/// Whether two fleets are at the same spot and free to exchange ships.
+pub fn co_located(a: &Fleet, b: &Fleet) -> bool {
+    a.transit.is_none()
+        && b.transit.is_none()
+        && a.position == b.position
+        && a.current_system_id == b.current_system_id
+        && a.local_position == b.local_position
+}
+
+/// Checks that ships can move from `from` to `to`.
+fn check_exchange(from: &Fleet, to: &Fleet) -> Result<(), String> {
+    if from.name == to.name {
+        return Err(format!("{} cannot exchange ships with itself", from.name));
+    }
+    if from.owner_id != to.owner_id {
+        return Err(format!("{} and {} have different owners", from.name, to.name));
+    }
+    if is_npc_trader(from) || is_npc_trader(to) {
+        return Err("Trader fleets cannot be reorganized".to_string());
+    }
+    if !co_located(from, to) {
+        return Err(format!("{} and {} must be at the same position and not travelling", from.name, to.name));
+    }
+    Ok(())
+}
+
+/// Checks a list of ship indices against a fleet and returns them sorted.
+fn selected_ships(fleet: &Fleet, ship_indices: &[usize]) -> Result<Vec<usize>, String> {
+    let mut indices = ship_indices.to_vec();
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.is_empty() {
+        return Err("Select at least one ship".to_string());
+    }
+    if let Some(index) = indices.iter().find(|&&index| index >= fleet.ships.len()) {
+        return Err(format!("Invalid ship index: {} (fleet has {} ships)", index, fleet.ships.len()));
+    }
+    if indices.len() == fleet.ships.len() {
+        return Err(format!("{} cannot give away all of its ships; merge the fleets instead", fleet.name));
+    }
+    Ok(indices)
+}
+
+/// Moves the selected ships from one fleet to another, keeping their order.
+fn move_ships(from: &mut Fleet, to: &mut Fleet, indices: &[usize]) {
+    for &index in indices.iter().rev() {
+        let mut ship = from.ships.remove(index);
+        ship.position = to.position;
+        to.ships.push(ship);
+    }
+    let moved = to.ships.len() - indices.len();
+    to.ships[moved..].reverse();
+}
+
+/// Checks a name a player wants to give a fleet and returns it trimmed.
+pub fn validate_fleet_name(display_name: &str) -> Result<String, String> {
+    let trimmed = display_name.trim();
+    if trimmed.is_empty() {
+        return Err("Fleet name cannot be empty".to_string());
+    }
+    if trimmed.chars().count() > MAX_FLEET_NAME_LENGTH {
+        return Err(format!("Fleet name cannot be longer than {} characters", MAX_FLEET_NAME_LENGTH));
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Detaches ships from a fleet into a new fleet at the same position.
+///
+/// # Arguments
+/// * `source` - The fleet the ships leave
+/// * `ship_indices` - The ships to detach; at least one ship must stay behind
+/// * `fleet_number` - Number of the new fleet, which names it `Fleet_<owner>_<number>`
+///
+/// # Returns
+/// The new fleet, or an error if the split is not possible
+pub fn split_fleet(source: &mut Fleet, ship_indices: &[usize], fleet_number: usize) -> Result<Fleet, String> {
+    if source.transit.is_some() {
+        return Err(format!("{} cannot split while travelling", source.name));
+    }
+    if is_npc_trader(source) {
+        return Err("Trader fleets cannot be reorganized".to_string());
+    }
+    let indices = selected_ships(source, ship_indices)?;
+
+    let mut detached = Fleet::new(source.owner_id.clone(), source.position, fleet_number);
+    detached.current_system_id = source.current_system_id;
+    detached.local_position = source.local_position;
+    if detached.name == source.name {
+        return Err(format!("Fleet {} already exists", detached.name));
+    }
+    move_ships(source, &mut detached, &indices);
+    Ok(detached)
+}
+
+/// Moves every ship of `source` into `target`. The caller deletes `source`.
+pub fn merge_fleets(target: &mut Fleet, mut source: Fleet) -> Result<(), String> {
+    check_exchange(&source, target)?;
+    let indices: Vec<usize> = (0..source.ships.len()).collect();
+    move_ships(&mut source, target, &indices);
+    Ok(())
+}
+
+/// Moves some ships between two fleets at the same position.
+pub fn transfer_ships(from: &mut Fleet, to: &mut Fleet, ship_indices: &[usize]) -> Result<(), String> {
+    check_exchange(from, to)?;
+    let indices = selected_ships(from, ship_indices)?;
+    move_ships(from, to, &indices);
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
@@ -21,14 +22,16 @@ pub struct Galaxy{
 /// * `map_height` - Height of the galaxy map
 /// * `map_length` - Length of the galaxy map
 /// * `star_count` - Number of star systems to generate
+/// * `rng` - The random number generator to draw from
 /// 
 /// # Returns
 /// A vector of star systems positioned within the specified dimensions
-pub fn generate_galaxy(
+pub fn generate_galaxy<R: Rng + ?Sized>(
     map_width: i32,
     map_height: i32,
     map_length: i32,
     star_count: i32,
+    rng: &mut R,
 ) -> Result<Vec<StarSystem>, String> {
     println!("Starting generate_galaxy with dimensions: {}x{}x{}, stars: {}", 
         map_width, map_height, map_length, star_count);
@@ -41,12 +44,12 @@ pub fn generate_galaxy(
 
     while star_systems.len() < star_count as usize && attempts < max_attempts {
         println!("Generating star system {}/{}", star_systems.len() + 1, star_count);
-        let position = random_nonzero_position(map_width, map_height, map_length);
+        let position = random_nonzero_position(map_width, map_height, map_length, rng);
         
         if !unique_positions.contains(&position) {
             println!("Found unique position for star system: {:?}", position);
             unique_positions.insert(position.clone());
-            let system = generate_star_system(map_width, map_height, map_length, &mut existing_names);
+            let system = generate_star_system(map_width, map_height, map_length, &mut existing_names, rng);
             println!("Successfully generated star system at position {:?}", position);
             star_systems.push(system);
         } else {
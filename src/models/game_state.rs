@@ -1,50 +1,17 @@
 use std::path::{Path, PathBuf};
 use std::fs::{self, File};
-use std::sync::{Arc, RwLock, Mutex};
+use std::sync::{Arc, RwLock};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
-use std::io::Write;
-use lazy_static::lazy_static;
-use crate::models::settings::load_settings;
+use crate::models::context::GameContext;
 use crate::models::player::Player;
 use crate::models::star_system::StarSystem;
 use crate::models::fleet::Fleet;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use crate::models::market::Market;
-
-#[derive(Clone)]
-pub struct GameState {
-    pub current_game_id: Option<String>,
-    pub credits: f64,
-}
-
-lazy_static! {
-    pub static ref GAME_STATE: Mutex<GameState> = {
-        println!("Initializing game state");
-        Mutex::new(GameState {
-            current_game_id: None,
-            credits: 0.0,
-        })
-    };
-}
-
-pub fn get_game_state() -> Result<GameState, String> {
-    if let Ok(guard) = GAME_STATE.lock() {
-        Ok((*guard).clone())
-    } else {
-        Err("Failed to lock game state".to_string())
-    }
-}
-
-pub fn save_game_state(state: GameState) -> Result<(), String> {
-    if let Ok(mut guard) = GAME_STATE.lock() {
-        *guard = state;
-        Ok(())
-    } else {
-        Err("Failed to lock game state".to_string())
-    }
-}
+use crate::models::persistence::{Transaction, write_json_atomic};
+use crate::models::spatial::track_fleet;
+use crate::models::storage::{close_storage, storage_for};
 
 // Cache structure
 #[derive(Clone)]
@@ -91,40 +58,29 @@ impl<T: Clone> Cache<T> {
     }
 }
 
-// Global caches
-lazy_static! {
-    pub static ref PLAYER_CACHE: Cache<Player> = Cache::new(30); // 30 seconds TTL
-    pub static ref SYSTEM_CACHE: Cache<StarSystem> = Cache::new(60); // 60 seconds TTL
-    pub static ref FLEET_CACHE: Cache<Fleet> = Cache::new(30); // 30 seconds TTL
-    pub static ref MARKET_CACHE: Cache<Market> = Cache::new(30); // 30 seconds TTL
+/// Root of everything the server writes. Unit tests write under a
+/// per-process temp directory so they never touch the checked-out `data`.
+pub fn data_root() -> PathBuf {
+    if cfg!(test) {
+        std::env::temp_dir().join(format!("star_trader_test_{}", std::process::id()))
+    } else {
+        PathBuf::from("data")
+    }
 }
 
-pub fn game_path(components: &[&str]) -> PathBuf {
-    let mut path = PathBuf::from("data").join("game");
-    
-    // Try to get game_id from game state, but don't fail if we can't
-    if let Ok(state) = get_game_state() {
-        if let Some(game_id) = state.current_game_id {
-            path = path.join(game_id);
-        }
-    }
-    
-    for component in components {
-        path = path.join(component);
-    }
-    path
+/// Path of a file in the game entered on this thread. Errors when no game
+/// is entered rather than writing outside every game.
+pub fn game_path(components: &[&str]) -> Result<PathBuf, String> {
+    let context = GameContext::current()?;
+    Ok(game_data_path(&context.game_id, components))
 }
 
 pub fn game_path_with_id(game_id: &str, components: &[&str]) -> PathBuf {
-    let mut path = PathBuf::from("data").join("game").join(game_id);
-    for component in components {
-        path = path.join(component);
-    }
-    path
+    game_data_path(game_id, components)
 }
 
 pub fn game_data_path(game_id: &str, path: &[&str]) -> std::path::PathBuf {
-    let mut full_path = std::path::PathBuf::from("data").join("game").join(game_id);
+    let mut full_path = data_root().join("game").join(game_id);
     for component in path {
         full_path = full_path.join(component);
     }
@@ -138,17 +94,11 @@ pub fn ensure_parent_dirs(path: &Path) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Saves `data` as JSON, replacing the file atomically so a crash never
+/// leaves it half written.
 pub fn save_json<T: Serialize>(path: &Path, data: &T) -> Result<(), String> {
-    println!("Starting to save JSON to {}", path.display());
-    ensure_parent_dirs(path)
-        .map_err(|e| format!("Failed to create directories: {}", e))?;
-    
-    println!("Creating file at {}", path.display());
-    let file = File::create(path)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
-    
-    println!("Serializing data to JSON");
-    serde_json::to_writer(file, data)
+    println!("Saving JSON to {}", path.display());
+    write_json_atomic(path, data)
         .map_err(|e| format!("Failed to write JSON: {}", e))
 }
 
@@ -160,127 +110,100 @@ pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
         .map_err(|e| format!("Failed to parse JSON: {}", e))
 }
 
+/// The id of the game entered on this thread.
+pub fn current_game_id() -> Result<String, String> {
+    Ok(GameContext::current()?.game_id.clone())
+}
+
 pub fn save_player(player: &Player) -> Result<(), String> {
-    let path = game_path(&["players", &format!("{}.json", player.name)]);
-    save_json(&path, player)?;
-    PLAYER_CACHE.set(player.name.clone(), player.clone());
+    let context = GameContext::current()?;
+    storage_for(&context.game_id)?.save_player(player)?;
+    context.players.set(player.name.clone(), player.clone());
     Ok(())
 }
 
 pub fn load_player(name: &str) -> Result<Player, String> {
-    if let Some(player) = PLAYER_CACHE.get(name) {
+    let context = GameContext::current()?;
+    if let Some(player) = context.players.get(name) {
         return Ok(player);
     }
     
-    let path = game_path(&["players", &format!("{}.json", name)]);
-    if !path.exists() {
-        return Err(format!("Player file not found: {}", path.display()));
-    }
-    
-    let player: Player = load_json(&path)?;
-    PLAYER_CACHE.set(name.to_string(), player.clone());
+    let player = storage_for(&context.game_id)?.load_player(name)?
+        .ok_or_else(|| format!("Player not found: {}", name))?;
+    context.players.set(name.to_string(), player.clone());
     Ok(player)
 }
 
 pub fn save_star_system(system_id: usize, system: &StarSystem) -> Result<(), String> {
-    let path = game_path(&["star_systems", &format!("system_{}.json", system_id)]);
-    save_json(&path, system)?;
-    SYSTEM_CACHE.set(system_id.to_string(), system.clone());
+    let context = GameContext::current()?;
+    storage_for(&context.game_id)?.save_system(system_id, system)?;
+    context.systems.set(system_id.to_string(), system.clone());
     Ok(())
 }
 
 pub fn load_star_system(system_id: usize) -> Result<StarSystem, String> {
-    if let Some(system) = SYSTEM_CACHE.get(&system_id.to_string()) {
+    let context = GameContext::current()?;
+    if let Some(system) = context.systems.get(&system_id.to_string()) {
         return Ok(system);
     }
     
-    let path = game_path(&["star_systems", &format!("system_{}.json", system_id)]);
-    let system: StarSystem = load_json(&path)?;
-    SYSTEM_CACHE.set(system_id.to_string(), system.clone());
+    let system = storage_for(&context.game_id)?.load_system(system_id)?
+        .ok_or_else(|| format!("Star system not found: {}", system_id))?;
+    context.systems.set(system_id.to_string(), system.clone());
     Ok(system)
 }
 
 pub fn save_fleet(fleet: &Fleet) -> Result<(), String> {
-    let path = game_path(&["fleets", &format!("{}.json", fleet.name)]);
-    save_json(&path, fleet)?;
-    FLEET_CACHE.set(fleet.name.clone(), fleet.clone());
+    let context = GameContext::current()?;
+    storage_for(&context.game_id)?.save_fleet(fleet)?;
+    context.fleets.set(fleet.name.clone(), fleet.clone());
+    track_fleet(&context, fleet);
     Ok(())
 }
 
 pub fn load_fleet(name: &str) -> Result<Fleet, String> {
-    if let Some(fleet) = FLEET_CACHE.get(name) {
+    let context = GameContext::current()?;
+    if let Some(fleet) = context.fleets.get(name) {
         return Ok(fleet);
     }
     
-    let path = game_path(&["fleets", &format!("{}.json", name)]);
-    let fleet: Fleet = load_json(&path)?;
-    FLEET_CACHE.set(name.to_string(), fleet.clone());
+    let fleet = storage_for(&context.game_id)?.load_fleet(name)?
+        .ok_or_else(|| format!("Fleet not found: {}", name))?;
+    context.fleets.set(name.to_string(), fleet.clone());
     Ok(fleet)
 }
 
-// Helper for trade operations
+// Helper for trade operations: player and system are saved together or not at all
 pub fn save_trade_state(player: &Player, system: &StarSystem, system_id: usize) -> Result<(), String> {
-    // Save player state first
-    let player_path = game_path(&["players", &format!("{}.json", player.name)]);
-    save_json(&player_path, player).map_err(|e| format!("Failed to save player state: {}", e))?;
-    
-    // Save star system state
-    let system_path = game_path(&["star_systems", &format!("system_{}.json", system_id)]);
-    save_json(&system_path, system).map_err(|e| format!("Failed to save system state: {}", e))?;
-    
+    let mut transaction = Transaction::begin()?;
+    transaction.stage_player(player)?;
+    transaction.stage(&["star_systems", &format!("system_{}.json", system_id)], system)?;
+    transaction.commit().map_err(|e| format!("Failed to save trade state: {}", e))?;
+    GameContext::current()?.systems.set(system_id.to_string(), system.clone());
     Ok(())
 }
 
-// Helper to save all star systems to individual files
+// Helper to save all star systems to their individual documents in one batch
 pub fn save_star_systems(systems: &[StarSystem]) -> Result<(), String> {
     println!("Starting to save {} star systems", systems.len());
-    let settings = load_settings().map_err(|e| format!("Failed to load settings: {}", e))?;
-    println!("Loaded settings");
-    let systems_dir = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("star_systems");
-    println!("Creating systems directory at {}", systems_dir.display());
-    if let Err(e) = fs::create_dir_all(&systems_dir) {
-        return Err(format!("Failed to create systems directory: {}", e));
-    }
-    
-    // Process systems in smaller batches
-    const BATCH_SIZE: usize = 2; // Process 2 systems at a time
-    for chunk in systems.chunks(BATCH_SIZE) {
-        for (i, system) in chunk.iter().enumerate() {
-            let system_index = i + (chunk.as_ptr() as usize - systems.as_ptr() as usize) / std::mem::size_of::<StarSystem>();
-            println!("Processing system {}/{} with {} planets", 
-                system_index + 1, systems.len(), system.planets.len());
-            
-            let system_path = systems_dir.join(format!("system_{}.json", system_index));
-            
-            // Use a buffered writer for better performance
-            let file = File::create(&system_path)
-                .map_err(|e| format!("Failed to create file for system {}: {}", system_index, e))?;
-            let mut writer = std::io::BufWriter::new(file);
-            
-            serde_json::to_writer(&mut writer, system)
-                .map_err(|e| format!("Failed to serialize system {}: {}", system_index, e))?;
-            
-            // Ensure the buffer is flushed
-            writer.flush()
-                .map_err(|e| format!("Failed to flush system {}: {}", system_index, e))?;
-            
-            println!("Successfully saved system {}", system_index);
-            
-            // Clear the cache after each system to free memory
-            SYSTEM_CACHE.remove(&system_index.to_string());
-        }
-    }
+    let context = GameContext::current()?;
+    let mut transaction = Transaction::new(&context.game_id);
+    for (system_index, system) in systems.iter().enumerate() {
+        println!("Staging system {}/{} with {} planets", system_index + 1, systems.len(), system.planets.len());
+        transaction.stage(&["star_systems", &format!("system_{}.json", system_index)], system)?;
+        context.systems.remove(&system_index.to_string());
+    }
+    transaction.commit().map_err(|e| format!("Failed to save star systems: {}", e))?;
     
     println!("Completed saving all star systems");
     Ok(())
 }
 
+/// Clears the caches of the game entered on this thread and closes its
+/// storage, so everything is read back from the saves.
 pub fn clear_caches() {
-    PLAYER_CACHE.remove_all();
-    SYSTEM_CACHE.remove_all();
-    FLEET_CACHE.remove_all();
-    MARKET_CACHE.remove_all();
+    if let Ok(context) = GameContext::current() {
+        context.clear_caches();
+        close_storage(&context.game_id);
+    }
 } 
\ No newline at end of file
@@ -1,17 +1,13 @@
 use crate::models::planet::Planet;
+use crate::models::persistence::Transaction;
+use crate::models::storage::{document_key, read_document, storage_for};
 use crate::models::ship::ship::Ship;
 use crate::models::resource::Resource;
 use crate::models::galaxy::generate_galaxy;
 use crate::models::settings::{GameSettings, load_settings};
-use std::fs;
-use std::fs::File;
-use serde_json;
 use std::io::Read;
-use serde_json::to_writer;
 use crate::models::star_system::StarSystem;
-use crate::models::game_state::game_path;
-use std::path::Path;
-use rand::thread_rng;
+use crate::models::rng::rng_for;
 use rand::seq::SliceRandom;
 use rand::Rng;
 use crate::models::planet::PlanetSpecialization;
@@ -22,7 +18,8 @@ use crate::models::position::random_position;
 use crate::models::star_system::generate_star_system;
 use serde::ser::{Serialize, Serializer, SerializeSeq};
 use serde::Deserialize;
-use crate::constants::GLOBAL_GAME_WORLD;
+use crate::models::context::GameContext;
+use crate::models::spatial::invalidate_galaxy_index;
 use std::error::Error;
 
 pub struct GameWorld {
@@ -61,28 +58,24 @@ impl GameWorld {
 /// A Result containing either the loaded star systems or an error.
 pub fn create_game_world_file(settings: &GameSettings, force_regenerate: bool) -> Result<Vec<StarSystem>, String> {
     println!("Starting game world creation");
-    let game_path = Path::new("data").join("game").join(&settings.game_id);
-    let game_world_path = game_path.join("GameWorld.json");
+    let storage = storage_for(&settings.game_id)?;
     
     // Check if we need to regenerate
-    if !force_regenerate && game_world_path.exists() {
-        println!("Game world file exists, loading from disk");
-        let file = File::open(&game_world_path)
-            .map_err(|e| format!("Failed to open game world file: {}", e))?;
-        let world: Vec<StarSystem> = serde_json::from_reader(file)
-            .map_err(|e| format!("Failed to deserialize game world: {}", e))?;
+    if !force_regenerate && let Some(world) = storage.load_galaxy()? {
         println!("Successfully loaded game world with {} systems", world.len());
         
-        // Update the global game world
-        if let Ok(mut guard) = GLOBAL_GAME_WORLD.lock() {
-            *guard = world.clone();
+        // Keep the game's in-memory galaxy in step
+        if let Some(context) = GameContext::current_for(&settings.game_id) {
+            context.set_world(world.clone());
+            invalidate_galaxy_index(&context);
         }
         
         return Ok(world);
     }
     
     println!("Generating new game world");
-    let mut rng = thread_rng();
+    // The whole galaxy comes from the game seed so it can be regenerated exactly
+    let mut rng = rng_for(settings.seed, "galaxy");
     let mut existing_names = std::collections::HashSet::new();
     let mut world = Vec::with_capacity(settings.star_count as usize);
     
@@ -93,7 +86,8 @@ pub fn create_game_world_file(settings: &GameSettings, force_regenerate: bool) -
             settings.map_width as i32,
             settings.map_height as i32,
             settings.map_length as i32,
-            &mut existing_names
+            &mut existing_names,
+            &mut rng
         );
         system.id = i as usize;
         println!("Successfully generated star system at position {:?}", system.position);
@@ -103,26 +97,19 @@ pub fn create_game_world_file(settings: &GameSettings, force_regenerate: bool) -
     println!("Successfully generated galaxy with {} star systems", world.len());
     
     // Save the game world
-    if let Some(parent) = game_world_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create game directory: {}", e))?;
-    }
-    
-    let file = File::create(&game_world_path)
-        .map_err(|e| format!("Failed to create game world file: {}", e))?;
+    storage.save_galaxy(&world)
+        .map_err(|e| format!("Failed to save game world: {}", e))?;
     
-    serde_json::to_writer(file, &world)
-        .map_err(|e| format!("Failed to serialize game world: {}", e))?;
-    
-    // Update the global game world
-    if let Ok(mut guard) = GLOBAL_GAME_WORLD.lock() {
-        *guard = world.clone();
+    // Keep the game's in-memory galaxy in step
+    if let Some(context) = GameContext::current_for(&settings.game_id) {
+        context.set_world(world.clone());
+        invalidate_galaxy_index(&context);
     }
     
     Ok(world)
 }
 
-/// Loads a game world from the specified game directory.
+/// Loads a game world from the specified game's storage.
 ///
 /// # Arguments
 /// - `game_id` - A string slice that holds the identifier for the game instance.
@@ -130,24 +117,13 @@ pub fn create_game_world_file(settings: &GameSettings, force_regenerate: bool) -
 /// # Returns
 /// A Result containing either the loaded star systems or an error.
 pub fn load_game_world(game_id: &str) -> std::io::Result<Vec<StarSystem>> {
-    let world_file = Path::new("data")
-        .join("game")
-        .join(game_id)
-        .join("GameWorld.json");
-        
-    if !world_file.exists() {
-        return Err(std::io::Error::new(
+    storage_for(game_id)
+        .and_then(|storage| storage.load_galaxy())
+        .map_err(std::io::Error::other)?
+        .ok_or_else(|| std::io::Error::new(
             std::io::ErrorKind::NotFound,
             "Game world file not found",
-        ));
-    }
-
-    // Open the file for reading
-    let file = File::open(&world_file)?;
-    
-    let mut systems = Vec::new();
-    systems = serde_json::from_reader(file)?;
-    Ok(systems)
+        ))
 }
 
 impl PlayerState {
@@ -161,78 +137,59 @@ impl PlayerState {
     }
 }
 
-/// Saves a single star system to its individual file
-pub fn save_star_system(_game_id: &str, system_id: usize, system: &StarSystem) -> std::io::Result<()> {
-    let system_path = game_path(&["star_systems", &format!("system_{}.json", system_id)]);
-
-    let file = File::create(system_path)?;
-    to_writer(file, system)?;
-    Ok(())
+/// Saves a single star system to its own document
+pub fn save_star_system(game_id: &str, system_id: usize, system: &StarSystem) -> std::io::Result<()> {
+    storage_for(game_id)
+        .and_then(|storage| storage.save_system(system_id, system))
+        .map_err(std::io::Error::other)
 }
 
-/// Loads a single star system from its individual file
-pub fn load_star_system(_game_id: &str, system_id: usize) -> std::io::Result<Option<StarSystem>> {
-    let system_path = game_path(&["star_systems", &format!("system_{}.json", system_id)]);
-
-    if !system_path.exists() {
-        return Ok(None);
-    }
-
-    let file = File::open(system_path)?;
-    let system: StarSystem = serde_json::from_reader(file)?;
-    Ok(Some(system))
+/// Loads a single star system from its own document
+pub fn load_star_system(game_id: &str, system_id: usize) -> std::io::Result<Option<StarSystem>> {
+    storage_for(game_id)
+        .and_then(|storage| storage.load_system(system_id))
+        .map_err(std::io::Error::other)
 }
 
-/// Saves a game world to the specified game directory.
+/// Saves a game world to the specified game's storage.
 ///
 /// # Arguments
-/// - `_game_id` - A string slice that holds the identifier for the game instance.
+/// - `game_id` - A string slice that holds the identifier for the game instance.
 /// - `star_systems` - A vector of star systems to save.
 ///
 /// # Returns
 /// A Result indicating success or failure.
-pub fn save_game_world(_game_id: &str, star_systems: &[StarSystem]) -> std::io::Result<()> {
-    let world_file = game_path(&["GameWorld.json"]);
-    
-    // Create the game directory if it doesn't exist
-    if let Some(parent) = world_file.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    let file = File::create(&world_file)?;
-    to_writer(file, star_systems)?;
-    Ok(())
+pub fn save_game_world(game_id: &str, star_systems: &[StarSystem]) -> std::io::Result<()> {
+    storage_for(game_id)
+        .and_then(|storage| storage.save_galaxy(star_systems))
+        .map_err(std::io::Error::other)
 }
 
-/// Retrieves the global game world initialized at crate root.
-pub fn get_global_game_world() -> Vec<StarSystem> {
-    if let Ok(guard) = crate::GLOBAL_GAME_WORLD.lock() {
-        guard.clone()
-    } else {
-        Vec::new()
+/// Loads every star system saved to its own document, in no particular order.
+pub fn load_saved_star_systems(game_id: &str) -> Result<Vec<StarSystem>, String> {
+    let storage = storage_for(game_id)?;
+    let mut systems = Vec::new();
+    for name in storage.list("star_systems")? {
+        if let Ok(Some(system)) = read_document(storage.as_ref(), &document_key(&["star_systems", &name])) {
+            systems.push(system);
+        }
     }
+    Ok(systems)
+}
+
+/// The galaxy loaded into memory for the game entered on this thread.
+pub fn current_game_world() -> Vec<StarSystem> {
+    GameContext::current().map(|context| context.world()).unwrap_or_default()
 }
 
 pub fn split_game_world_into_systems() -> Result<(), Box<dyn Error>> {
     let settings = load_settings()?;
-    let game_path = Path::new("data").join("game").join(&settings.game_id);
-    let star_systems_path = game_path.join("star_systems");
-
-    // Create star_systems directory if it doesn't exist
-    if !star_systems_path.exists() {
-        fs::create_dir_all(&star_systems_path)?;
-    }
-
-    // Load the game world
-    let game_world = get_global_game_world();
+    let mut transaction = Transaction::new(&settings.game_id);
 
-    // Save each system to its own file
-    for (system_id, system) in game_world.iter().enumerate() {
-        let system_path = star_systems_path.join(format!("Star_System_{}.json", system_id));
-        let file = File::create(system_path)?;
-        serde_json::to_writer(file, system)?;
+    // Save each system to its own document
+    for (system_id, system) in current_game_world().iter().enumerate() {
+        transaction.stage(&["star_systems", &format!("Star_System_{}.json", system_id)], system)?;
     }
 
-    Ok(())
+    Ok(transaction.commit()?)
 }
-
@@ -1,6 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::path::Path;
+use crate::models::storage::{save_document, storage_for};
 use crate::models::settings::load_settings;
 use crate::models::planet::PlanetSpecialization;
 use crate::models::economy::Economy;
@@ -8,14 +7,58 @@ use crate::models::resource::{Resource, ResourceType};
 use strum::IntoEnumIterator;
 use rand::Rng;
 use crate::models::ship::ship::{Ship, ShipSize, ShipType, ShipEngine};
-use crate::models::game_state::{load_json, save_json, game_path};
-use crate::models::game_world::get_global_game_world;
+use crate::models::game_world::current_game_world;
 use std::error::Error;
-use rand::thread_rng;
+use rand::distributions::Standard;
+use crate::models::rng::next_game_rng;
+use crate::models::outfitting::RESALE_SHARE;
+
+/// Stock level at which a resource trades at its base price. Markets holding
+/// more than this sell cheaper, markets holding less charge more.
+pub const REFERENCE_STOCK: f64 = 60.0;
+
+/// How strongly prices react to stock: price scales with (REFERENCE_STOCK / stock)^elasticity.
+pub const PRICE_ELASTICITY: f64 = 0.5;
+
+/// Bounds on how far prices can drift from their base price.
+pub const MIN_PRICE_FACTOR: f64 = 0.25;
+pub const MAX_PRICE_FACTOR: f64 = 4.0;
+
+/// Fraction of the gap between current and target stock closed every tick.
+pub const MEAN_REVERSION_RATE: f64 = 0.05;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Market {
-    pub resources: Vec<Resource>
+    pub resources: Vec<Resource>,
+    /// Long-run supply and demand of each traded resource. `resources` holds
+    /// the current stock and the marginal prices derived from it.
+    #[serde(default)]
+    pub profiles: Vec<ResourceProfile>,
+}
+
+/// Baseline behaviour of one resource on one planet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResourceProfile {
+    pub resource_type: ResourceType,
+    /// Price per unit the planet charges at `REFERENCE_STOCK`
+    pub base_buy: Option<f64>,
+    /// Price per unit the planet pays at `REFERENCE_STOCK`
+    pub base_sell: Option<f64>,
+    /// Stock level the planet's industry and population settle at
+    pub target_stock: u32,
+    /// Units produced per tick (negative when the planet consumes the resource)
+    pub production: i32,
+}
+
+/// Total price for trading a quantity of a resource, including slippage.
+#[derive(Serialize, Debug, Clone)]
+pub struct MarketQuote {
+    pub resource_type: ResourceType,
+    pub quantity: u32,
+    /// Cost of buying `quantity` from the planet, if it has enough stock
+    pub buy_total: Option<f64>,
+    /// Credits received for selling `quantity` to the planet
+    pub sell_total: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -26,7 +69,8 @@ pub struct ShipMarket {
 impl Default for Market {
     fn default() -> Self {
         Market {
-            resources: Vec::new()
+            resources: Vec::new(),
+            profiles: Vec::new(),
         }
     }
 }
@@ -40,30 +84,38 @@ impl Default for ShipMarket {
 }
 
 impl Market {
-    pub fn new(specialization: &PlanetSpecialization, economy: &Economy) -> Market {
-        let resources = generate_market_resources(specialization, economy);
-        Market {
-            resources
-        }
+    pub fn new<R: Rng + ?Sized>(specialization: &PlanetSpecialization, economy: &Economy, rng: &mut R) -> Market {
+        let profiles = generate_resource_profiles(specialization, economy);
+        let resources = profiles.iter().map(|profile| {
+            // Start somewhere around the planet's natural stock level
+            let quantity = if profile.base_buy.is_some() || profile.base_sell.is_some() {
+                let target = profile.target_stock as f64;
+                Some(rng.gen_range((target * 0.5)..=(target * 1.5)).round() as u32)
+            } else {
+                None
+            };
+            Resource {
+                resource_type: profile.resource_type,
+                buy: None,
+                sell: None,
+                quantity,
+            }
+        }).collect();
+
+        let mut market = Market { resources, profiles };
+        market.refresh_prices();
+        market
     }
 
     pub fn load(system_id: usize, planet_id: usize) -> std::io::Result<Market> {
         let settings = load_settings()?;
-        let market_dir = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets");
-
-        // Create market directory if it doesn't exist
-        if !market_dir.exists() {
-            fs::create_dir_all(&market_dir)?;
-        }
-
-        let market_path = market_dir.join(format!("market_{}_{}.json", system_id, planet_id));
+        let saved = storage_for(&settings.game_id)
+            .and_then(|storage| storage.load_market(system_id, planet_id))
+            .map_err(std::io::Error::other)?;
 
-        if market_path.exists() {
-            let file = File::open(market_path)?;
-            Ok(serde_json::from_reader(file)?)
+        if let Some(mut market) = saved {
+            market.ensure_profiles();
+            Ok(market)
         } else {
             // If market doesn't exist, create a new one
             let planet = crate::models::planet::load_planet(system_id, planet_id)?
@@ -72,7 +124,7 @@ impl Market {
                     format!("Planet not found for system {} planet {}", system_id, planet_id),
                 ))?;
             
-            let market = Market::new(&planet.specialization, &planet.economy);
+            let market = Market::new(&planet.specialization, &planet.economy, &mut next_game_rng("market").map_err(std::io::Error::other)?);
             
             // Save the new market
             market.save(system_id, planet_id)?;
@@ -82,64 +134,240 @@ impl Market {
 
     pub fn save(&self, system_id: usize, planet_id: usize) -> std::io::Result<()> {
         let settings = load_settings()?;
-        let market_path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets")
-            .join(format!("market_{}_{}.json", system_id, planet_id));
-
-        if let Some(parent) = market_path.parent() {
-            fs::create_dir_all(parent)?;
+        storage_for(&settings.game_id)
+            .and_then(|storage| storage.save_market(system_id, planet_id, self))
+            .map_err(std::io::Error::other)
+    }
+
+    /// Markets saved before stock dynamics existed have no profiles. Treat
+    /// their current prices and stock as the baseline so they keep trading
+    /// the way they did.
+    fn ensure_profiles(&mut self) {
+        for resource in &self.resources {
+            if self.profiles.iter().any(|p| p.resource_type == resource.resource_type) {
+                continue;
+            }
+            self.profiles.push(ResourceProfile {
+                resource_type: resource.resource_type,
+                base_buy: resource.buy,
+                base_sell: resource.sell,
+                target_stock: resource.quantity.unwrap_or(REFERENCE_STOCK as u32),
+                production: 0,
+            });
+        }
+    }
+
+    fn profile(&self, resource_type: ResourceType) -> Option<&ResourceProfile> {
+        self.profiles.iter().find(|p| p.resource_type == resource_type)
+    }
+
+    fn stock(&self, resource_type: ResourceType) -> Option<u32> {
+        self.resources.iter()
+            .find(|r| r.resource_type == resource_type)
+            .and_then(|r| r.quantity)
+    }
+
+    /// Recomputes the marginal buy/sell prices shown for every resource from
+    /// its current stock.
+    pub fn refresh_prices(&mut self) {
+        for resource in &mut self.resources {
+            let Some(profile) = self.profiles.iter().find(|p| p.resource_type == resource.resource_type) else {
+                continue;
+            };
+            let stock = resource.quantity.unwrap_or(0);
+            resource.buy = profile.base_buy.map(|base| round_price(base * price_factor(stock)));
+            resource.sell = profile.base_sell.map(|base| round_price(base * price_factor(stock)));
         }
+    }
 
-        let market_json = serde_json::to_string_pretty(self)?;
-        fs::write(market_path, market_json)
+    /// Quotes the total cost of buying `quantity` units from this market.
+    ///
+    /// The price rises as the purchase drains stock. Every unit is priced on
+    /// its own, so splitting an order never changes what it costs.
+    pub fn quote_buy(&self, resource_type: ResourceType, quantity: u32) -> Result<f64, &'static str> {
+        let profile = self.profile(resource_type).ok_or("Resource not available in market")?;
+        let base = profile.base_buy.ok_or("Resource cannot be bought")?;
+        let stock = self.stock(resource_type).ok_or("Resource not available in market")?;
+        if stock < quantity {
+            return Err("Not enough resources available");
+        }
+        Ok(slippage_total(base, stock - quantity, stock))
     }
 
-    pub fn needs_update(&self, specialization: &PlanetSpecialization, economy: &Economy) -> bool {
-        // Since we don't store specialization and economy anymore,
-        // we'll need to regenerate the market each time
-        true
+    /// Quotes the credits received for selling `quantity` units to this market.
+    ///
+    /// The price falls as the sale floods the market with stock.
+    pub fn quote_sell(&self, resource_type: ResourceType, quantity: u32) -> Result<f64, &'static str> {
+        let profile = self.profile(resource_type).ok_or("Resource not available in market")?;
+        let base = profile.base_sell.ok_or("Resource cannot be sold")?;
+        let stock = self.stock(resource_type).unwrap_or(0);
+        Ok(slippage_total(base, stock, stock.saturating_add(quantity)))
     }
 
-    pub fn update(&mut self, specialization: &PlanetSpecialization, economy: &Economy) -> std::io::Result<()> {
-        self.resources = generate_market_resources(specialization, economy);
-        Ok(())
+    /// Quotes both directions for a resource. Directions the market cannot
+    /// trade are left empty.
+    pub fn quote(&self, resource_type: ResourceType, quantity: u32) -> MarketQuote {
+        MarketQuote {
+            resource_type,
+            quantity,
+            buy_total: self.quote_buy(resource_type, quantity).ok(),
+            sell_total: self.quote_sell(resource_type, quantity).ok(),
+        }
     }
 
     pub fn buy_resource(&mut self, resource_type: ResourceType, quantity: u32, _system_id: usize, _planet_id: usize) -> Result<f64, &'static str> {
-        let resource = self.resources.iter_mut().find(|r| r.resource_type == resource_type)
-            .ok_or("Resource not available in market")?;
-        
-        let available_quantity = resource.quantity.unwrap_or(0);
-        if available_quantity < quantity {
-            return Err("Not enough resources available");
+        let total_cost = self.quote_buy(resource_type, quantity)?;
+        if let Some(resource) = self.resources.iter_mut().find(|r| r.resource_type == resource_type) {
+            resource.quantity = Some(resource.quantity.unwrap_or(0) - quantity);
         }
-        
-        let buy_price = resource.buy.ok_or("Resource cannot be bought")?;
-        let total_cost = ((buy_price * quantity as f64) * 100.0).round() / 100.0;
-        resource.quantity = Some(available_quantity - quantity);
-        
+        self.refresh_prices();
+
         Ok(total_cost)
     }
 
     pub fn sell_resource(&mut self, resource_type: ResourceType, quantity: u32, _system_id: usize, _planet_id: usize) -> Result<f64, &'static str> {
-        let resource = self.resources.iter_mut().find(|r| r.resource_type == resource_type)
-            .ok_or("Resource not available in market")?;
-        
-        let current_quantity = resource.quantity.unwrap_or(0);
-        let sell_price = resource.sell.ok_or("Resource cannot be sold")?;
-        let total_value = ((sell_price * quantity as f64) * 100.0).round() / 100.0;
-        resource.quantity = Some(current_quantity + quantity);
-        
+        let total_value = self.quote_sell(resource_type, quantity)?;
+        if let Some(resource) = self.resources.iter_mut().find(|r| r.resource_type == resource_type) {
+            resource.quantity = Some(resource.quantity.unwrap_or(0).saturating_add(quantity));
+        }
+        self.refresh_prices();
+
         Ok(total_value)
     }
+
+    /// Lets the market recover from trading over `ticks` of game time.
+    ///
+    /// Each tick stock moves back toward the planet's target level, closing
+    /// `MEAN_REVERSION_RATE` of the gap but never less than the planet's own
+    /// production or consumption rate.
+    pub fn advance(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            for resource in &mut self.resources {
+                let (Some(profile), Some(stock)) = (
+                    self.profiles.iter().find(|p| p.resource_type == resource.resource_type),
+                    resource.quantity,
+                ) else {
+                    continue;
+                };
+
+                let gap = profile.target_stock as f64 - stock as f64;
+                let step = (gap.abs() * MEAN_REVERSION_RATE)
+                    .max(profile.production.unsigned_abs() as f64)
+                    .min(gap.abs())
+                    .ceil();
+                let next = stock as f64 + step.copysign(gap);
+                resource.quantity = Some(next.max(0.0) as u32);
+            }
+        }
+        self.refresh_prices();
+    }
+}
+
+/// Loads every resource market that has been saved for a game, keyed by
+/// `(system_id, planet_id)`. Markets that were never visited do not exist on
+/// disk yet and are generated lazily by `Market::load` instead.
+pub fn list_saved_markets(game_id: &str) -> std::io::Result<Vec<((usize, usize), Market)>> {
+    let storage = storage_for(game_id).map_err(std::io::Error::other)?;
+    let file_names = storage.list("markets").map_err(std::io::Error::other)?;
+
+    let mut markets = Vec::new();
+    for file_name in &file_names {
+        let Some(ids) = file_name.strip_prefix("market_")
+            .and_then(|name| name.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        let Some((system_id, planet_id)) = ids.split_once('_')
+            .and_then(|(s, p)| Some((s.parse::<usize>().ok()?, p.parse::<usize>().ok()?)))
+        else {
+            continue;
+        };
+        if let Ok(Some(mut market)) = storage.load_market(system_id, planet_id) {
+            market.ensure_profiles();
+            markets.push(((system_id, planet_id), market));
+        }
+    }
+    markets.sort_by_key(|(key, _)| *key);
+    Ok(markets)
+}
+
+pub fn price_factor(stock: u32) -> f64 {
+    (REFERENCE_STOCK / (stock.max(1) as f64))
+        .powf(PRICE_ELASTICITY)
+        .clamp(MIN_PRICE_FACTOR, MAX_PRICE_FACTOR)
+}
+
+fn round_price(price: f64) -> f64 {
+    (price * 100.0).round() / 100.0
+}
+
+/// Total for trading the units between stock levels `low` and `high`, in
+/// either direction.
+///
+/// Each unit costs the average of the price before and after it changes
+/// hands, so a trade is the sum of its one-unit trades and buying a lot
+/// back after selling it (or the other way round) retraces the same prices.
+fn slippage_total(base: f64, low: u32, high: u32) -> f64 {
+    let mut total = 0.0;
+    for stock in low..high {
+        if price_factor(stock) <= MIN_PRICE_FACTOR {
+            // Past this stock level every unit trades at the floor price
+            total += round_price(base * MIN_PRICE_FACTOR) * (high - stock) as f64;
+            break;
+        }
+        total += round_price(base * (price_factor(stock) + price_factor(stock + 1)) / 2.0);
+    }
+    round_price(total)
+}
+
+/// Units of a resource a planet produces (positive) or consumes (negative)
+/// per tick, before its economy is taken into account.
+fn base_production(specialization: &PlanetSpecialization, resource_type: ResourceType) -> i32 {
+    use PlanetSpecialization as S;
+    use ResourceType as R;
+    match (specialization, resource_type) {
+        (S::Agriculture, R::Food) => 6,
+        (S::Agriculture, R::Water) => 3,
+        (S::Agriculture, R::Electronics) => -1,
+        (S::Mining, R::Minerals) => 6,
+        (S::Mining, R::Metals) => 3,
+        (S::Mining, R::Food | R::Water) => -2,
+        (S::Manufacturing, R::Electronics) => 4,
+        (S::Manufacturing, R::Metals) => 2,
+        (S::Manufacturing, R::Minerals) => -4,
+        (S::Technology, R::Electronics) => 6,
+        (S::Technology, R::Metals) => -3,
+        (S::Research, R::Narcotics) => 1,
+        (S::Research, R::Electronics) => -3,
+        (S::Tourism, R::LuxuryGoods) => -4,
+        (S::Tourism, R::Food) => -3,
+        (S::Tourism, R::Water) => -2,
+        (S::Service, R::Food | R::Water | R::LuxuryGoods) => -2,
+        // Every spaceport burns some fuel and feeds its population
+        (_, R::Fuel) => -1,
+        (_, R::Food | R::Water) => -1,
+        _ => 0,
+    }
 }
 
-pub fn generate_market_resources(specialization: &PlanetSpecialization, economy: &Economy) -> Vec<Resource> {
-    let mut resources = Vec::new();
-    let mut rng = rand::thread_rng();
-    
+/// How much of its potential output an economy actually realises.
+fn economy_activity(economy: &Economy) -> f64 {
+    match economy {
+        Economy::Booming => 1.5,
+        Economy::Growing => 1.25,
+        Economy::Stable => 1.0,
+        Economy::Struggling => 0.75,
+        Economy::Declining => 0.5,
+        Economy::Crashing => 0.25,
+        Economy::Nonexistent => 0.0,
+    }
+}
+
+/// Builds the supply and demand baseline for every resource a planet trades.
+///
+/// Producers settle at large stockpiles (cheap), consumers at small ones
+/// (expensive), and the economy scales both prices and flows.
+pub fn generate_resource_profiles(specialization: &PlanetSpecialization, economy: &Economy) -> Vec<ResourceProfile> {
     // Base price multiplier based on economy
     let economy_multiplier: f64 = match economy {
         Economy::Booming => 1.5,
@@ -151,8 +379,7 @@ pub fn generate_market_resources(specialization: &PlanetSpecialization, economy:
         Economy::Nonexistent => 0.2,
     };
 
-    // Generate market resources based on specialization
-    for resource_type in ResourceType::iter() {
+    ResourceType::iter().map(|resource_type| {
         let (buy_price, sell_price): (Option<f64>, Option<f64>) = match resource_type {
             // Essential resources that all planets should trade
             ResourceType::Water | ResourceType::Food | ResourceType::Fuel => {
@@ -182,44 +409,33 @@ pub fn generate_market_resources(specialization: &PlanetSpecialization, economy:
             },
         };
 
-        // Apply economy multiplier to prices
-        let buy = buy_price.map(|p| p * economy_multiplier);
-        let sell = sell_price.map(|p| p * economy_multiplier);
+        let production = (base_production(specialization, resource_type) as f64 * economy_activity(economy)).round() as i32;
+        let target_stock = (REFERENCE_STOCK + production as f64 * 15.0).clamp(10.0, 300.0) as u32;
 
-        // Generate random quantity if the planet trades this resource
-        let quantity = if buy.is_some() || sell.is_some() {
-            Some(rng.gen_range(10..100))
-        } else {
-            None
-        };
-
-        resources.push(Resource {
+        ResourceProfile {
             resource_type,
-            buy,
-            sell,
-            quantity,
-        });
-    }
-
-    resources
+            // Apply economy multiplier to prices
+            base_buy: buy_price.map(|p| p * economy_multiplier),
+            base_sell: sell_price.map(|p| p * economy_multiplier),
+            target_stock,
+            production,
+        }
+    }).collect()
 }
 
-pub fn generate_market_for_planet(planet_name: &str, system_id: usize, planet_id: usize, specialization: &PlanetSpecialization, economy: &Economy) -> Market {
-    Market {
-        resources: generate_market_resources(specialization, economy)
-    }
+pub fn generate_market_for_planet<R: Rng + ?Sized>(planet_name: &str, system_id: usize, planet_id: usize, specialization: &PlanetSpecialization, economy: &Economy, rng: &mut R) -> Market {
+    Market::new(specialization, economy, rng)
 }
 
-pub fn generate_ship_market() -> ShipMarket {
+pub fn generate_ship_market<R: Rng + ?Sized>(rng: &mut R) -> ShipMarket {
     println!("Starting ship market generation");
-    let mut rng = rand::thread_rng();
     let ship_count = rng.gen_range(3..=8); // Generate 3-8 ships
     println!("Generating {} ships", ship_count);
     let mut ships = Vec::with_capacity(ship_count);
 
     for i in 0..ship_count {
         println!("Generating ship {}/{}", i + 1, ship_count);
-        let mut ship: Ship = rand::random();
+        let mut ship: Ship = rng.sample(Standard);
         println!("Generated ship: {}", ship.name);
         ship.price = Some(calculate_ship_price(&ship));
         ships.push(ship);
@@ -254,24 +470,20 @@ pub fn calculate_ship_price(ship: &Ship) -> f64 {
         ShipEngine::Experimental => 1.5,
     };
 
-    let condition_multiplier = (ship.hp as f64 / 100.0).max(0.5);
+    let condition_multiplier = ship.hull_fraction().max(0.5);
 
-    base_price * specialization_multiplier * engine_multiplier * condition_multiplier
+    // Fitted weapons and modules add what they would fetch on their own
+    let equipment_value = ship.equipment_value() * RESALE_SHARE;
+
+    base_price * specialization_multiplier * engine_multiplier * condition_multiplier + equipment_value
 }
 
-pub fn regenerate_system_markets(system_id: usize) -> Result<(), Box<dyn Error>> {
+pub fn regenerate_system_markets<R: Rng + ?Sized>(system_id: usize, rng: &mut R) -> Result<(), Box<dyn Error>> {
     println!("Starting market regeneration for system {}", system_id);
     let settings = load_settings()?;
-    let game_path = Path::new("data").join("game").join(&settings.game_id);
-    let markets_path = game_path.join("markets");
-    
-    if !markets_path.exists() {
-        println!("Creating markets directory at {}", markets_path.display());
-        fs::create_dir_all(&markets_path)?;
-    }
 
     println!("Loading game world for system {}", system_id);
-    let game_world = get_global_game_world();
+    let game_world = current_game_world();
     if game_world.is_empty() {
         return Err(Box::new(std::io::Error::new(
             std::io::ErrorKind::NotFound,
@@ -297,10 +509,9 @@ pub fn regenerate_system_markets(system_id: usize) -> Result<(), Box<dyn Error>>
     for (planet_id, planet) in system.planets.iter().enumerate() {
         println!("Generating market for planet {}: {}", planet_id, planet.name);
         // Generate and save planet market
-        let market = generate_market_for_planet(&planet.name, system_id, planet_id, &planet.specialization, &planet.economy);
-        let market_path = markets_path.join(format!("market_{}_{}.json", system_id, planet_id));
-        println!("Saving planet market to {}", market_path.display());
-        if let Err(e) = save_json(&market_path, &market) {
+        let market = generate_market_for_planet(&planet.name, system_id, planet_id, &planet.specialization, &planet.economy, rng);
+        println!("Saving planet market {}_{}", system_id, planet_id);
+        if let Err(e) = save_document(&settings.game_id, &["markets", &format!("market_{}_{}.json", system_id, planet_id)], &market) {
             println!("Error saving planet market: {}", e);
             return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to save planet market: {}", e))));
         }
@@ -308,10 +519,9 @@ pub fn regenerate_system_markets(system_id: usize) -> Result<(), Box<dyn Error>>
 
         // Generate and save ship market
         println!("Generating ship market for planet {}", planet.name);
-        let ship_market = generate_ship_market();
-        let ship_market_path = markets_path.join(format!("ships_{}_{}.json", system_id, planet_id));
-        println!("Saving ship market to {}", ship_market_path.display());
-        if let Err(e) = save_json(&ship_market_path, &ship_market) {
+        let ship_market = generate_ship_market(rng);
+        println!("Saving ship market {}_{}", system_id, planet_id);
+        if let Err(e) = save_document(&settings.game_id, &["markets", &format!("ships_{}_{}.json", system_id, planet_id)], &ship_market) {
             println!("Error saving ship market: {}", e);
             return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to save ship market: {}", e))));
         }
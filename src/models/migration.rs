@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use crate::models::game_state::clear_caches;
+use crate::models::persistence::Transaction;
+use crate::models::storage::{read_document, storage_for};
+use crate::models::rng::random_seed;
+use crate::models::settings::{GameSettings, SavedGame};
+
+/// Version of the save layout written by this server.
+///
+/// Bump it together with a new entry in `MIGRATIONS` whenever a change to a
+/// saved struct needs older files rewritten.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// The save files migrations know how to upgrade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SaveKind {
+    Settings,
+    Player,
+    Fleet,
+    StarSystem,
+    GameWorld,
+}
+
+impl SaveKind {
+    /// Works out what a file holds from its path relative to the game directory.
+    pub fn of(path: &str) -> Option<SaveKind> {
+        match path.split_once('/') {
+            None if path == "settings.json" => Some(SaveKind::Settings),
+            None if path == "GameWorld.json" => Some(SaveKind::GameWorld),
+            Some(("players", _)) => Some(SaveKind::Player),
+            Some(("fleets", _)) => Some(SaveKind::Fleet),
+            Some(("star_systems", _)) => Some(SaveKind::StarSystem),
+            _ => None,
+        }
+    }
+}
+
+/// One step of the pipeline, upgrading saves from `from` to `from + 1`.
+struct Migration {
+    from: u32,
+    description: &'static str,
+    /// Rewrites one file in place and describes each change it made
+    apply: fn(SaveKind, &str, &mut Value) -> Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "Write out fields that saves from before versioning left implicit",
+        apply: fill_unversioned_fields,
+    },
+    Migration {
+        from: 1,
+        description: "Add faction reputation and fleet travel state",
+        apply: add_reputation_and_transit,
+    },
+];
+
+/// Inserts `field` with `value` if the object does not have it yet.
+fn add_field(object: &mut Value, field: &str, value: Value, changes: &mut Vec<String>) {
+    if let Some(object) = object.as_object_mut()
+        && !object.contains_key(field)
+    {
+        changes.push(format!("added {}", field));
+        object.insert(field.to_string(), value);
+    }
+}
+
+/// The system id encoded in a star system file name, e.g. `system_3.json`
+/// or `Star_System_3.json`.
+fn system_id_from_path(path: &str) -> Option<u64> {
+    path.strip_suffix(".json")?.rsplit('_').next()?.parse().ok()
+}
+
+fn fill_unversioned_fields(kind: SaveKind, path: &str, value: &mut Value) -> Vec<String> {
+    let mut changes = Vec::new();
+    match kind {
+        SaveKind::Settings => {
+            add_field(value, "display_name", json!("New Game"), &mut changes);
+            // Without a stored seed every load used to roll a new one
+            add_field(value, "seed", json!(random_seed()), &mut changes);
+        }
+        SaveKind::Fleet => {
+            for field in ["local_position", "current_system_id", "last_move_distance"] {
+                add_field(value, field, Value::Null, &mut changes);
+            }
+        }
+        SaveKind::StarSystem => {
+            // Systems without an id all read as system 0
+            if let Some(id) = system_id_from_path(path) {
+                add_field(value, "id", json!(id), &mut changes);
+            }
+        }
+        SaveKind::GameWorld => {
+            if let Some(systems) = value.as_array_mut() {
+                for (index, system) in systems.iter_mut().enumerate() {
+                    let mut added = Vec::new();
+                    add_field(system, "id", json!(index), &mut added);
+                    changes.extend(added.into_iter().map(|change| format!("{} to system {}", change, index)));
+                }
+            }
+        }
+        SaveKind::Player => {}
+    }
+    changes
+}
+
+fn add_reputation_and_transit(kind: SaveKind, _path: &str, value: &mut Value) -> Vec<String> {
+    let mut changes = Vec::new();
+    match kind {
+        SaveKind::Player => add_field(value, "reputation", Value::Object(Map::new()), &mut changes),
+        SaveKind::Fleet => add_field(value, "transit", Value::Null, &mut changes),
+        _ => {}
+    }
+    changes
+}
+
+/// What one migration step changed, or would change.
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationStep {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub description: String,
+    /// One entry per change, prefixed with the file it applies to
+    pub changes: Vec<String>,
+}
+
+/// The outcome of migrating a game.
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationReport {
+    pub game_id: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    /// True if nothing was written
+    pub dry_run: bool,
+    pub steps: Vec<MigrationStep>,
+    /// Files rewritten (or that would be rewritten), relative to the game directory
+    pub files_changed: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn is_up_to_date(&self) -> bool {
+        self.from_version == self.to_version
+    }
+
+    /// One line describing the migration.
+    pub fn summary(&self) -> String {
+        if self.is_up_to_date() {
+            return format!("Save format is up to date (version {})", self.to_version);
+        }
+        format!(
+            "{} save format from version {} to {} ({} files)",
+            if self.dry_run { "Would migrate" } else { "Migrated" },
+            self.from_version,
+            self.to_version,
+            self.files_changed.len()
+        )
+    }
+}
+
+/// Returns the schema version recorded in a settings file, refusing
+/// versions newer than this server understands.
+pub fn schema_version(settings: &Value) -> Result<u32, String> {
+    let version = settings.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+    if version > CURRENT_SCHEMA_VERSION as u64 {
+        return Err(format!(
+            "Game was saved in format version {}, but this server only supports versions up to {}. Update the server to load it.",
+            version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+    Ok(version as u32)
+}
+
+/// Upgrades every save file of a game to `CURRENT_SCHEMA_VERSION`, one
+/// migration step at a time.
+///
+/// All rewritten files are committed in a single transaction, with the new
+/// version recorded in `settings.json`, so an interrupted migration is
+/// either finished or never happened.
+///
+/// # Arguments
+/// * `game_id` - The ID of the game to migrate
+/// * `dry_run` - Report what would change without writing anything
+///
+/// # Returns
+/// A report of every change, or an error if the game is from a newer version
+/// or its files cannot be read
+pub fn migrate_game(game_id: &str, dry_run: bool) -> Result<MigrationReport, String> {
+    let storage = storage_for(game_id)?;
+    let settings: Value = read_document(storage.as_ref(), "settings.json")?
+        .ok_or_else(|| format!("No settings found for game {}", game_id))?;
+    let from_version = schema_version(&settings)?;
+    let mut report = MigrationReport {
+        game_id: game_id.to_string(),
+        from_version,
+        to_version: CURRENT_SCHEMA_VERSION,
+        dry_run,
+        steps: Vec::new(),
+        files_changed: Vec::new(),
+    };
+    if report.is_up_to_date() {
+        return Ok(report);
+    }
+
+    let mut files: BTreeMap<String, (SaveKind, Value)> = BTreeMap::new();
+    for name in storage.keys()? {
+        if let Some(kind) = SaveKind::of(&name)
+            && let Some(value) = read_document(storage.as_ref(), &name)?
+        {
+            files.insert(name, (kind, value));
+        }
+    }
+
+    let mut changed: Vec<String> = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|migration| migration.from >= from_version) {
+        let mut step = MigrationStep {
+            from_version: migration.from,
+            to_version: migration.from + 1,
+            description: migration.description.to_string(),
+            changes: Vec::new(),
+        };
+        for (name, (kind, value)) in files.iter_mut() {
+            let changes = (migration.apply)(*kind, name, value);
+            if !changes.is_empty() && !changed.contains(name) {
+                changed.push(name.clone());
+            }
+            step.changes.extend(changes.into_iter().map(|change| format!("{}: {}", name, change)));
+        }
+        report.steps.push(step);
+    }
+
+    let settings = &mut files.get_mut("settings.json").expect("settings.json was read above").1;
+    settings["schema_version"] = json!(CURRENT_SCHEMA_VERSION);
+    if !changed.iter().any(|name| name == "settings.json") {
+        changed.push("settings.json".to_string());
+    }
+    changed.sort();
+    report.files_changed = changed;
+    if dry_run {
+        return Ok(report);
+    }
+
+    let migrated_settings: GameSettings = serde_json::from_value(files["settings.json"].1.clone())
+        .map_err(|e| format!("Migrated settings.json is invalid: {}", e))?;
+    let mut transaction = Transaction::new(game_id);
+    for name in &report.files_changed {
+        let components: Vec<&str> = name.split('/').collect();
+        transaction.stage(&components, &files[name].1)?;
+    }
+    transaction.commit()?;
+
+    // The save list keeps its own copy of the settings
+    if let Some(mut saved_game) = SavedGame::load_game(game_id).map_err(|e| format!("Failed to load saved game: {}", e))? {
+        saved_game.settings = migrated_settings;
+        saved_game.save_game().map_err(|e| format!("Failed to update saved game: {}", e))?;
+    }
+    clear_caches();
+    Ok(report)
+}
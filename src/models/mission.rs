@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use crate::models::faction::{Faction, apply_reputation_change, controlling_faction};
+use crate::models::game_state::current_game_id;
+use crate::models::storage::{load_document, save_document};
+use crate::models::player::Player;
+use crate::models::resource::ResourceType;
+use crate::models::rng::game_uuid;
+use crate::models::star_system::StarSystem;
+
+/// Number of open missions each planet's board offers at a time.
+pub const MISSIONS_PER_PLANET: usize = 3;
+
+/// Number of missions a player may have accepted at once.
+pub const MAX_ACTIVE_MISSIONS: usize = 5;
+
+/// Reputation earned with the issuing faction on completion.
+pub const MISSION_REPUTATION_REWARD: f32 = 5.0;
+
+/// Reputation lost with the issuing faction when a mission is abandoned or failed.
+pub const MISSION_REPUTATION_PENALTY: f32 = -3.0;
+
+/// Ticks a posted mission stays on the board before it is withdrawn.
+pub const MISSION_POSTING_TICKS: u64 = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum MissionKind {
+    /// Sell `quantity` units of a resource to the destination planet's market
+    Transport {
+        resource_type: ResourceType,
+        quantity: u32,
+        destination_system_id: usize,
+        destination_planet_id: usize,
+        #[serde(default)]
+        delivered: u32,
+    },
+    /// Destroy every ship of the target fleet
+    Bounty { target_fleet: String },
+    /// Bring a fleet into the target star system
+    Exploration { system_id: usize },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum MissionStatus {
+    Available,
+    Active,
+    Completed,
+    Failed,
+    Abandoned,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Mission {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub kind: MissionKind,
+    /// The planet whose mission board offers this mission
+    pub origin_system_id: usize,
+    pub origin_planet_id: usize,
+    pub reward_credits: f64,
+    /// Faction that issued the mission and whose reputation is at stake
+    pub faction: Option<String>,
+    pub posted_tick: u64,
+    pub deadline_tick: u64,
+    pub status: MissionStatus,
+    pub accepted_by: Option<String>,
+}
+
+/// Every mission of a game, stored as `missions.json` in the game directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MissionBoard {
+    pub missions: Vec<Mission>,
+}
+
+impl MissionBoard {
+    pub fn load() -> Result<MissionBoard, String> {
+        Ok(load_document(&current_game_id()?, &["missions.json"])?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_document(&current_game_id()?, &["missions.json"], self)
+    }
+
+    /// Missions still open for acceptance at a planet.
+    pub fn available_at(&self, system_id: usize, planet_id: usize) -> Vec<&Mission> {
+        self.missions.iter()
+            .filter(|m| m.status == MissionStatus::Available)
+            .filter(|m| m.origin_system_id == system_id && m.origin_planet_id == planet_id)
+            .collect()
+    }
+
+    /// Missions accepted by a player, in any state.
+    pub fn for_player(&self, player_name: &str) -> Vec<&Mission> {
+        self.missions.iter()
+            .filter(|m| m.accepted_by.as_deref() == Some(player_name))
+            .collect()
+    }
+
+    fn get_mut(&mut self, mission_id: &str) -> Result<&mut Mission, String> {
+        self.missions.iter_mut()
+            .find(|m| m.id == mission_id)
+            .ok_or_else(|| format!("Mission {} not found", mission_id))
+    }
+
+    /// Accepts an available mission on behalf of a player.
+    pub fn accept(&mut self, mission_id: &str, player_name: &str, now: u64) -> Result<&Mission, String> {
+        let active = self.missions.iter()
+            .filter(|m| m.status == MissionStatus::Active && m.accepted_by.as_deref() == Some(player_name))
+            .count();
+        if active >= MAX_ACTIVE_MISSIONS {
+            return Err(format!("You cannot have more than {} active missions", MAX_ACTIVE_MISSIONS));
+        }
+
+        let mission = self.get_mut(mission_id)?;
+        if mission.status != MissionStatus::Available {
+            return Err("Mission is no longer available".to_string());
+        }
+        if now >= mission.deadline_tick {
+            return Err("Mission has expired".to_string());
+        }
+        mission.status = MissionStatus::Active;
+        mission.accepted_by = Some(player_name.to_string());
+        Ok(mission)
+    }
+
+    /// Gives up an active mission. The issuing faction takes it badly.
+    pub fn abandon(&mut self, mission_id: &str, player: &mut Player, factions: &[Faction]) -> Result<Mission, String> {
+        let mission = self.get_mut(mission_id)?;
+        if mission.status != MissionStatus::Active || mission.accepted_by.as_deref() != Some(player.name.as_str()) {
+            return Err("You have not accepted this mission".to_string());
+        }
+        mission.status = MissionStatus::Abandoned;
+        let mission = mission.clone();
+        settle_mission(player, &mission, factions);
+        Ok(mission)
+    }
+
+    /// Completes an active mission of `player` whose objective has been met
+    /// and pays out the reward.
+    fn complete(&mut self, index: usize, player: &mut Player, factions: &[Faction]) -> Mission {
+        let mission = &mut self.missions[index];
+        mission.status = MissionStatus::Completed;
+        let mission = mission.clone();
+        settle_mission(player, &mission, factions);
+        mission
+    }
+
+    fn active_indices(&self, player_name: &str) -> Vec<usize> {
+        self.missions.iter().enumerate()
+            .filter(|(_, m)| m.status == MissionStatus::Active && m.accepted_by.as_deref() == Some(player_name))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Counts a sale to a planet's market toward the player's transport missions.
+    ///
+    /// # Returns
+    /// The missions completed by this delivery
+    pub fn record_delivery(&mut self, player: &mut Player, factions: &[Faction], system_id: usize, planet_id: usize, resource: ResourceType, amount: u32) -> Vec<Mission> {
+        let mut completed = Vec::new();
+        let mut remaining = amount;
+        for index in self.active_indices(&player.name) {
+            if remaining == 0 {
+                break;
+            }
+            if let MissionKind::Transport { resource_type, quantity, destination_system_id, destination_planet_id, delivered } = &mut self.missions[index].kind {
+                if *resource_type != resource || *destination_system_id != system_id || *destination_planet_id != planet_id {
+                    continue;
+                }
+                let counted = remaining.min(*quantity - *delivered);
+                *delivered += counted;
+                remaining -= counted;
+                if *delivered >= *quantity {
+                    completed.push(self.complete(index, player, factions));
+                }
+            }
+        }
+        completed
+    }
+
+    /// Completes bounty missions on a fleet the player has just destroyed.
+    pub fn record_fleet_destroyed(&mut self, player: &mut Player, factions: &[Faction], fleet_name: &str) -> Vec<Mission> {
+        let mut completed = Vec::new();
+        for index in self.active_indices(&player.name) {
+            if matches!(&self.missions[index].kind, MissionKind::Bounty { target_fleet } if target_fleet == fleet_name) {
+                completed.push(self.complete(index, player, factions));
+            }
+        }
+        completed
+    }
+
+    /// Marks the player's exploration missions for a star system as completed
+    /// without paying out; the caller settles them with `settle_mission`.
+    pub fn complete_exploration(&mut self, player_name: &str, visited_system_id: usize) -> Vec<Mission> {
+        let mut completed = Vec::new();
+        for index in self.active_indices(player_name) {
+            let mission = &mut self.missions[index];
+            if matches!(mission.kind, MissionKind::Exploration { system_id } if system_id == visited_system_id) {
+                mission.status = MissionStatus::Completed;
+                completed.push(mission.clone());
+            }
+        }
+        completed
+    }
+
+    /// Completes exploration missions for a star system a player's fleet has reached.
+    pub fn record_system_visit(&mut self, player: &mut Player, factions: &[Faction], visited_system_id: usize) -> Vec<Mission> {
+        let completed = self.complete_exploration(&player.name, visited_system_id);
+        for mission in &completed {
+            settle_mission(player, mission, factions);
+        }
+        completed
+    }
+
+    /// Fails active missions whose deadline has passed and withdraws stale
+    /// postings nobody accepted.
+    ///
+    /// # Returns
+    /// The missions that failed, so their penalties can be applied
+    pub fn expire(&mut self, now: u64) -> Vec<Mission> {
+        let mut failed = Vec::new();
+        self.missions.retain_mut(|mission| match mission.status {
+            MissionStatus::Available => now < mission.deadline_tick,
+            MissionStatus::Active if now >= mission.deadline_tick => {
+                mission.status = MissionStatus::Failed;
+                failed.push(mission.clone());
+                true
+            }
+            _ => true,
+        });
+        failed
+    }
+
+    /// Posts new missions at every planet whose board has fewer than
+    /// `MISSIONS_PER_PLANET` open ones.
+    ///
+    /// # Returns
+    /// The number of missions posted
+    pub fn restock<R: Rng + ?Sized>(
+        &mut self,
+        systems: &[StarSystem],
+        factions: &[Faction],
+        pirate_fleets: &[String],
+        now: u64,
+        rng: &mut R,
+    ) -> usize {
+        let mut open: HashMap<(usize, usize), usize> = HashMap::new();
+        for mission in self.missions.iter().filter(|m| m.status == MissionStatus::Available) {
+            *open.entry((mission.origin_system_id, mission.origin_planet_id)).or_insert(0) += 1;
+        }
+        let mut posted = 0;
+        for system in systems {
+            for planet_id in 0..system.planets.len() {
+                let missing = MISSIONS_PER_PLANET.saturating_sub(open.get(&(system.id, planet_id)).copied().unwrap_or(0));
+                for _ in 0..missing {
+                    if let Some(mission) = generate_mission(system.id, planet_id, systems, factions, pirate_fleets, now, rng) {
+                        self.missions.push(mission);
+                        posted += 1;
+                    }
+                }
+            }
+        }
+        posted
+    }
+
+    /// Whether some planet's board has fewer than `MISSIONS_PER_PLANET` open missions.
+    pub fn needs_restock(&self, systems: &[StarSystem]) -> bool {
+        let open = self.missions.iter().filter(|m| m.status == MissionStatus::Available).count();
+        let planets: usize = systems.iter().map(|system| system.planets.len()).sum();
+        open < planets * MISSIONS_PER_PLANET
+    }
+}
+
+/// Applies the consequences of a finished mission to the player: the reward
+/// for completion, or a reputation penalty for failing or abandoning it.
+pub fn settle_mission(player: &mut Player, mission: &Mission, factions: &[Faction]) {
+    let reputation_change = match mission.status {
+        MissionStatus::Completed => {
+            player.credits += mission.reward_credits;
+            MISSION_REPUTATION_REWARD
+        }
+        MissionStatus::Failed | MissionStatus::Abandoned => MISSION_REPUTATION_PENALTY,
+        MissionStatus::Available | MissionStatus::Active => return,
+    };
+    if let Some(faction) = &mission.faction {
+        apply_reputation_change(player, factions, faction, reputation_change);
+    }
+}
+
+/// Generates a random mission offered by a planet.
+///
+/// # Arguments
+/// * `system_id` - System of the planet offering the mission
+/// * `planet_id` - Planet offering the mission
+/// * `systems` - Every star system in the galaxy
+/// * `factions` - Every faction, used to find the issuer
+/// * `pirate_fleets` - Names of fleets that bounties may target
+/// * `now` - The current game tick
+/// * `rng` - The random number generator to use
+///
+/// # Returns
+/// The generated mission, or `None` if the galaxy offers nothing to do
+pub fn generate_mission<R: Rng + ?Sized>(
+    system_id: usize,
+    planet_id: usize,
+    systems: &[StarSystem],
+    factions: &[Faction],
+    pirate_fleets: &[String],
+    now: u64,
+    rng: &mut R,
+) -> Option<Mission> {
+    let origin = systems.iter().find(|s| s.id == system_id)?;
+    let planet_name = origin.planets.get(planet_id).map(|p| p.name.clone())?;
+    let others: Vec<&StarSystem> = systems.iter().filter(|s| s.id != system_id).collect();
+
+    let (title, description, kind, reward_credits, travel) = match rng.gen_range(0..3) {
+        0 => {
+            let destination = *others.choose(rng)?;
+            if destination.planets.is_empty() {
+                return None;
+            }
+            let destination_planet_id = rng.gen_range(0..destination.planets.len());
+            let resource_types: Vec<ResourceType> = ResourceType::iter().collect();
+            let resource_type = *resource_types.choose(rng)?;
+            let quantity = rng.gen_range(10..=50);
+            let distance = origin.position.distance(&destination.position);
+            (
+                format!("Deliver {} to {}", resource_type, destination.planets[destination_planet_id].name),
+                format!(
+                    "{} needs {} units of {} delivered to {} in the {} system.",
+                    planet_name, quantity, resource_type, destination.planets[destination_planet_id].name, destination.star.name
+                ),
+                MissionKind::Transport { resource_type, quantity, destination_system_id: destination.id, destination_planet_id, delivered: 0 },
+                (quantity as f64 * 8.0 + distance * 0.5).round(),
+                distance,
+            )
+        }
+        1 => {
+            let target_fleet = pirate_fleets.choose(rng)?.clone();
+            (
+                format!("Bounty: {}", target_fleet),
+                format!("The authorities on {} have placed a bounty on the pirate fleet {}.", planet_name, target_fleet),
+                MissionKind::Bounty { target_fleet },
+                rng.gen_range(500..=1500) as f64,
+                1000.0,
+            )
+        }
+        _ => {
+            let destination = *others.choose(rng)?;
+            let distance = origin.position.distance(&destination.position);
+            (
+                format!("Survey {}", destination.star.name),
+                format!("Cartographers on {} want a fleet to chart the {} system.", planet_name, destination.star.name),
+                MissionKind::Exploration { system_id: destination.id },
+                (200.0 + distance * 0.8).round(),
+                distance,
+            )
+        }
+    };
+
+    // Deadlines leave room for a basic engine to make the trip several times over
+    let deadline_tick = now + MISSION_POSTING_TICKS + (travel / 10.0).ceil() as u64;
+
+    Some(Mission {
+        id: game_uuid(rng),
+        title,
+        description,
+        kind,
+        origin_system_id: system_id,
+        origin_planet_id: planet_id,
+        reward_credits,
+        faction: controlling_faction(factions, system_id).map(|f| f.name.clone()),
+        posted_tick: now,
+        deadline_tick,
+        status: MissionStatus::Available,
+        accepted_by: None,
+    })
+}
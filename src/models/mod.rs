@@ -15,4 +15,25 @@ pub mod settings;
 pub mod response;
 pub mod game_state;
 pub mod trader;
-pub mod economy;
\ No newline at end of file
+pub mod economy;
+pub mod rng;
+pub mod clock;
+pub mod travel;
+pub mod mission;
+pub mod npc_trader;
+pub mod persistence;
+pub mod archive;
+pub mod migration;
+pub mod repair;
+pub mod outfitting;
+pub mod cargo;
+pub mod fleet_management;
+pub mod route;
+pub mod exploration;
+pub mod spatial;
+pub mod storage;
+pub mod context;
+pub mod auth;
+pub mod events;
+pub mod encounter;
+pub mod salvage;
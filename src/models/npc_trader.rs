@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::models::cargo::{CargoDistribution, FillPolicy};
+use crate::models::fleet::{DistanceBreakdown, Fleet};
+use crate::models::market::Market;
+use crate::models::player::Player;
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::star_system::StarSystem;
+use crate::models::trade::trade_with_fleet;
+use crate::models::trader::TraderPersonality;
+use crate::models::travel::{fleet_fuel, fuel_required};
+
+/// Owner prefix of the persistent NPC trader fleets created with a new game.
+pub const NPC_TRADER_OWNER_PREFIX: &str = "Merchant_";
+
+/// Number of NPC trader fleets created with a new game.
+pub const NPC_TRADER_COUNT: usize = 5;
+
+/// Credits an NPC trader starts out with.
+pub const NPC_TRADER_STARTING_CREDITS: f64 = 5000.0;
+
+/// Trading state of an NPC trader fleet, persisted with the fleet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TraderProfile {
+    pub personality: TraderPersonality,
+    pub credits: f64,
+    /// The run the trader is currently making, if any
+    #[serde(default)]
+    pub route: Option<TradeRoute>,
+    /// Profit made over every completed route
+    #[serde(default)]
+    pub total_profit: f64,
+}
+
+/// A cargo bought at one planet to be sold at another.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TradeRoute {
+    pub resource_type: ResourceType,
+    pub quantity: u32,
+    pub buy_system_id: usize,
+    pub buy_planet_id: usize,
+    pub sell_system_id: usize,
+    pub sell_planet_id: usize,
+    /// Total credits paid for the cargo
+    pub purchase_cost: f64,
+    /// Credits the destination market quoted for the cargo when the route was planned
+    pub expected_revenue: f64,
+}
+
+/// How a personality trades.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradingStyle {
+    /// Smallest profit, as a share of the purchase cost, worth setting out for
+    pub min_margin: f64,
+    /// Share of its credits the trader puts into a single cargo
+    pub investment_share: f64,
+    /// Number of nearest systems considered as destinations
+    pub scan_systems: usize,
+    /// Markup on the purchase price when selling to a player
+    pub player_markup: f64,
+    /// Discount on the purchase price when buying from a player
+    pub player_discount: f64,
+}
+
+impl TraderProfile {
+    pub fn new(personality: TraderPersonality) -> Self {
+        TraderProfile {
+            personality,
+            credits: NPC_TRADER_STARTING_CREDITS,
+            route: None,
+            total_profit: 0.0,
+        }
+    }
+
+    pub fn style(&self) -> TradingStyle {
+        trading_style(&self.personality)
+    }
+}
+
+/// Friendly traders make short, cautious runs and deal fairly with players.
+/// Aggressive traders range further, stake more and drive hard bargains.
+pub fn trading_style(personality: &TraderPersonality) -> TradingStyle {
+    match personality {
+        TraderPersonality::Friendly => TradingStyle {
+            min_margin: 0.05,
+            investment_share: 0.5,
+            scan_systems: 3,
+            player_markup: 0.05,
+            player_discount: 0.05,
+        },
+        TraderPersonality::Neutral => TradingStyle {
+            min_margin: 0.1,
+            investment_share: 0.7,
+            scan_systems: 5,
+            player_markup: 0.15,
+            player_discount: 0.15,
+        },
+        TraderPersonality::Aggressive => TradingStyle {
+            min_margin: 0.2,
+            investment_share: 0.9,
+            scan_systems: 8,
+            player_markup: 0.3,
+            player_discount: 0.3,
+        },
+    }
+}
+
+/// Returns true for the persistent NPC trader fleets.
+pub fn is_npc_trader(fleet: &Fleet) -> bool {
+    fleet.trader.is_some() || fleet.owner_id.starts_with(NPC_TRADER_OWNER_PREFIX)
+}
+
+/// Stows `quantity` units in the fleet's holds, filling ships in order.
+/// Traders plan purchases within their free capacity, so anything that
+/// does not fit is simply not loaded.
+///
+/// `buy` and `sell` are the prices the fleet offers other traders for the
+/// resource and are set on every stack of it.
+///
+/// # Returns
+/// The number of units stowed
+pub fn load_cargo(fleet: &mut Fleet, resource_type: ResourceType, quantity: u32, buy: Option<f64>, sell: Option<f64>) -> u32 {
+    let stowed = fleet.load_cargo(resource_type, quantity, &CargoDistribution::First, FillPolicy::Partial)
+        .map_or(0, |receipt| receipt.moved);
+    for cargo in fleet.ships.iter_mut().flat_map(|ship| ship.cargo.iter_mut()) {
+        if cargo.resource_type == resource_type {
+            cargo.buy = buy;
+            cargo.sell = sell;
+        }
+    }
+    stowed
+}
+
+/// Removes every unit of a resource from the fleet's holds.
+///
+/// # Returns
+/// The number of units removed
+pub fn unload_cargo(fleet: &mut Fleet, resource_type: ResourceType) -> u32 {
+    let mut removed = 0;
+    for ship in &mut fleet.ships {
+        ship.cargo.retain(|cargo| {
+            if cargo.resource_type == resource_type {
+                removed += cargo.quantity.unwrap_or(0);
+                false
+            } else {
+                true
+            }
+        });
+    }
+    removed
+}
+
+/// A straight trip through deep space between two galaxy positions.
+pub fn deep_space_trip(from: &Position, to: &Position) -> DistanceBreakdown {
+    let distance = from.distance(to);
+    DistanceBreakdown {
+        deep_space: distance,
+        total_raw: distance,
+        scale_factor: 1.0,
+        total_scaled: distance,
+        ..Default::default()
+    }
+}
+
+/// The `count` systems closest to `origin`, nearest first, excluding `origin` itself.
+pub fn nearest_systems<'a>(origin: &StarSystem, systems: &'a [StarSystem], count: usize) -> Vec<&'a StarSystem> {
+    let mut others: Vec<&StarSystem> = systems.iter().filter(|system| system.id != origin.id).collect();
+    others.sort_by(|a, b| {
+        origin.position.distance(&a.position).total_cmp(&origin.position.distance(&b.position))
+    });
+    others.truncate(count);
+    others
+}
+
+/// Largest quantity, up to `max`, whose purchase fits in `budget`.
+fn affordable_quantity(market: &Market, resource_type: ResourceType, max: u32, budget: f64) -> u32 {
+    let fits = |quantity: u32| market.quote_buy(resource_type, quantity).is_ok_and(|cost| cost <= budget);
+    // The total cost only grows with quantity, so search for the boundary
+    let (mut low, mut high) = (0, max);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+/// Finds the most profitable cargo a trader can buy in `origin` and sell in one of `destinations`.
+///
+/// Only markets present in `markets` are considered. Fuel is never traded,
+/// and the cargo leaves room for any fuel the trip still needs.
+///
+/// # Arguments
+/// * `fleet` - The trader's fleet
+/// * `profile` - The trader's state
+/// * `origin` - The system the trader is in
+/// * `destinations` - Systems the trader is willing to sail to
+/// * `markets` - Known markets keyed by `(system_id, planet_id)`
+///
+/// # Returns
+/// The best route meeting the trader's minimum margin, if any
+pub fn plan_trade_route(
+    fleet: &Fleet,
+    profile: &TraderProfile,
+    origin: &StarSystem,
+    destinations: &[&StarSystem],
+    markets: &BTreeMap<(usize, usize), Market>,
+) -> Option<TradeRoute> {
+    let style = profile.style();
+    let budget = profile.credits * style.investment_share;
+    let capacity = fleet.free_cargo_capacity();
+    let fuel = fleet_fuel(fleet);
+    let mut best: Option<(f64, TradeRoute)> = None;
+
+    for buy_planet_id in 0..origin.planets.len() {
+        let Some(source) = markets.get(&(origin.id, buy_planet_id)) else {
+            continue;
+        };
+        for resource in source.resources.iter().filter(|r| r.resource_type != ResourceType::Fuel) {
+            let stock = resource.quantity.unwrap_or(0);
+            for destination in destinations {
+                let trip = deep_space_trip(&origin.position, &destination.position);
+                let room = capacity.saturating_sub(fuel_required(fleet, trip.total_scaled).saturating_sub(fuel));
+                let most = affordable_quantity(source, resource.resource_type, room.min(stock), budget);
+                // Slippage at both ends can make a smaller cargo the better deal
+                let quantities = std::iter::successors(Some(most), |quantity| Some(quantity / 2))
+                    .take_while(|quantity| *quantity > 0);
+                for quantity in quantities {
+                    let Ok(cost) = source.quote_buy(resource.resource_type, quantity) else {
+                        continue;
+                    };
+                    for sell_planet_id in 0..destination.planets.len() {
+                        let Some(target) = markets.get(&(destination.id, sell_planet_id)) else {
+                            continue;
+                        };
+                        let Ok(revenue) = target.quote_sell(resource.resource_type, quantity) else {
+                            continue;
+                        };
+                        let profit = revenue - cost;
+                        if cost <= 0.0 || profit / cost < style.min_margin {
+                            continue;
+                        }
+                        if best.as_ref().is_some_and(|(best_profit, _)| *best_profit >= profit) {
+                            continue;
+                        }
+                        best = Some((profit, TradeRoute {
+                            resource_type: resource.resource_type,
+                            quantity,
+                            buy_system_id: origin.id,
+                            buy_planet_id,
+                            sell_system_id: destination.id,
+                            sell_planet_id,
+                            purchase_cost: cost,
+                            expected_revenue: revenue,
+                        }));
+                    }
+                }
+            }
+        }
+    }
+    best.map(|(_, route)| route)
+}
+
+/// Prices a trader asks and bids for a cargo it bought at `unit_cost`.
+///
+/// # Returns
+/// `(buy, sell)`: what a player pays per unit, and what the trader pays a player per unit
+pub fn player_prices(personality: &TraderPersonality, unit_cost: f64) -> (f64, f64) {
+    let style = trading_style(personality);
+    let round = |price: f64| (price * 100.0).round() / 100.0;
+    (round(unit_cost * (1.0 + style.player_markup)), round(unit_cost * (1.0 - style.player_discount)))
+}
+
+/// Trades between a player's fleet and an NPC trader fleet.
+///
+/// Prices are the ones the trader set on its cargo. The trader's credits
+/// move with the player's, and it refuses to buy more than it can pay for.
+///
+/// # Returns
+/// The credits the player paid (negative) or received (positive)
+pub fn trade_with_npc_trader(
+    player_fleet: &mut Fleet,
+    trader_fleet: &mut Fleet,
+    resource_type: ResourceType,
+    quantity: u32,
+    trade_type: &str,
+    player: &mut Player,
+) -> Result<f64, String> {
+    if quantity == 0 {
+        return Err("Quantity must be greater than zero".to_string());
+    }
+    let Some(profile) = trader_fleet.trader.clone() else {
+        return Err(format!("{} is not an NPC trader", trader_fleet.name));
+    };
+    let cargo = trader_fleet.ships.iter()
+        .flat_map(|ship| ship.cargo.iter())
+        .find(|cargo| cargo.resource_type == resource_type);
+    let price = match trade_type {
+        "buy" => cargo.and_then(|cargo| cargo.buy),
+        _ => cargo.and_then(|cargo| cargo.sell),
+    };
+    // Supplies such as fuel carry no price: the trader keeps them for itself
+    let Some(price) = price else {
+        return Err(format!("{} is not dealing in {}", trader_fleet.name, resource_type));
+    };
+    if trade_type == "sell" && price * quantity as f64 > profile.credits {
+        return Err(format!("{} cannot afford {} {}", trader_fleet.name, quantity, resource_type));
+    }
+
+    let credits_before = player.credits;
+    trade_with_fleet(player_fleet, trader_fleet, resource_type, quantity, trade_type, player)?;
+    let change = player.credits - credits_before;
+    if let Some(profile) = trader_fleet.trader.as_mut() {
+        profile.credits -= change;
+    }
+    Ok(change)
+}
@@ -0,0 +1,301 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::models::game_state::current_game_id;
+use crate::models::storage::{load_document, save_document};
+use crate::models::planet::{Planet, PlanetSpecialization};
+use crate::models::repair::economy_cost_factor;
+use crate::models::rng::next_game_rng;
+use crate::models::ship::equipment::{Equipment, Slot, SlotUsage};
+use crate::models::ship::ship::{Ship, ShipEngine};
+use crate::models::ship::weapon::Weapon;
+
+/// Share of a component's price charged as labor to fit or remove it.
+pub const INSTALL_FEE_SHARE: f64 = 0.1;
+/// Share of a component's price a yard pays for it second hand.
+pub const RESALE_SHARE: f64 = 0.5;
+
+/// A component for sale at a planet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EquipmentOffer {
+    pub equipment: Equipment,
+    pub price: f64,
+    pub stock: u32,
+}
+
+/// The components a planet's yard has in stock, saved next to its
+/// resource and ship markets.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EquipmentMarket {
+    pub offers: Vec<EquipmentOffer>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InstallData {
+    pub fleet_name: String,
+    pub ship_index: usize,
+    pub offer_index: usize,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UninstallData {
+    pub fleet_name: String,
+    pub ship_index: usize,
+    pub slot: Slot,
+    /// Position of the component among the ship's weapons or modules
+    pub index: usize,
+}
+
+/// What an outfitting job did to a ship and the player's credits.
+#[derive(Serialize, Debug, Clone)]
+pub struct OutfitReceipt {
+    pub ship_name: String,
+    pub equipment: String,
+    /// Credits gained by the player; negative when the player paid
+    pub credits_change: f64,
+    pub ready_tick: u64,
+    pub slots: SlotUsage,
+}
+
+/// Every component yards can stock.
+fn catalogue() -> Vec<Equipment> {
+    let mut items = vec![
+        Equipment::Weapon(Weapon::PhotonSingularityBeam { damage: 10 }),
+        Equipment::Weapon(Weapon::QuantumEntanglementTorpedo { damage: 20 }),
+        Equipment::Weapon(Weapon::NeutronBeam { damage: 30 }),
+        Equipment::Weapon(Weapon::GravitonPulse { damage: 40 }),
+        Equipment::Weapon(Weapon::MagneticResonanceDisruptor { damage: 50 }),
+        Equipment::Engine(ShipEngine::Basic),
+        Equipment::Engine(ShipEngine::Advanced),
+        Equipment::Engine(ShipEngine::Experimental),
+    ];
+    for capacity in [50, 100, 200] {
+        items.push(Equipment::ShieldGenerator { capacity });
+        items.push(Equipment::ArmorPlating { capacity });
+    }
+    items
+}
+
+/// Whether a planet's industry specializes in a kind of component.
+fn specialty(specialization: &PlanetSpecialization, equipment: &Equipment) -> bool {
+    matches!(
+        (specialization, equipment),
+        (PlanetSpecialization::Technology, Equipment::Weapon(_) | Equipment::ShieldGenerator { .. })
+            | (PlanetSpecialization::Manufacturing, Equipment::ArmorPlating { .. } | Equipment::Engine(_))
+            | (PlanetSpecialization::Mining, Equipment::ArmorPlating { .. })
+            | (PlanetSpecialization::Research, Equipment::ShieldGenerator { .. } | Equipment::Engine(_))
+    )
+}
+
+/// The price factor of a component at a planet: specialists sell their
+/// own products cheaply, and the economy scales everything.
+///
+/// # Returns
+/// The factor, or `None` if the planet has no yard
+pub fn planet_price_factor(planet: &Planet, equipment: &Equipment) -> Option<f64> {
+    let specialization = if specialty(&planet.specialization, equipment) { 0.8 } else { 1.0 };
+    economy_cost_factor(&planet.economy).map(|economy| specialization * economy)
+}
+
+fn planet_price(planet: &Planet, equipment: &Equipment) -> Result<f64, String> {
+    planet_price_factor(planet, equipment)
+        .map(|factor| (equipment.base_price() * factor).round())
+        .ok_or_else(|| format!("{} has no shipyard", planet.name))
+}
+
+impl EquipmentMarket {
+    /// Stocks a yard. Planets carry a handful of components, favouring
+    /// the ones their industry makes.
+    pub fn generate<R: Rng + ?Sized>(planet: &Planet, rng: &mut R) -> EquipmentMarket {
+        let mut pool = catalogue();
+        pool.extend(catalogue().into_iter().filter(|item| specialty(&planet.specialization, item)));
+        pool.shuffle(rng);
+
+        let count = rng.gen_range(4..=7);
+        let mut offers: Vec<EquipmentOffer> = Vec::new();
+        for equipment in pool {
+            if offers.len() >= count {
+                break;
+            }
+            if offers.iter().any(|offer| offer.equipment.name() == equipment.name()) {
+                continue;
+            }
+            let Some(factor) = planet_price_factor(planet, &equipment) else { break };
+            offers.push(EquipmentOffer {
+                price: (equipment.base_price() * factor).round(),
+                stock: rng.gen_range(1..=3),
+                equipment,
+            });
+        }
+        EquipmentMarket { offers }
+    }
+
+    pub fn file_name(system_id: usize, planet_id: usize) -> String {
+        format!("equipment_{}_{}.json", system_id, planet_id)
+    }
+
+    /// Loads a planet's yard, stocking it the first time it is visited.
+    pub fn load_or_generate(system_id: usize, planet_id: usize, planet: &Planet) -> Result<EquipmentMarket, String> {
+        if let Some(market) = load_document(&current_game_id()?, &["markets", &Self::file_name(system_id, planet_id)])? {
+            return Ok(market);
+        }
+        let market = EquipmentMarket::generate(planet, &mut next_game_rng("equipment")?);
+        market.save(system_id, planet_id)?;
+        Ok(market)
+    }
+
+    pub fn save(&self, system_id: usize, planet_id: usize) -> Result<(), String> {
+        save_document(&current_game_id()?, &["markets", &Self::file_name(system_id, planet_id)], self)
+    }
+
+    /// Puts a component the yard bought back on sale.
+    fn restock(&mut self, equipment: Equipment, price: f64) {
+        match self.offers.iter_mut().find(|offer| offer.equipment.name() == equipment.name()) {
+            Some(offer) => offer.stock += 1,
+            None => self.offers.push(EquipmentOffer { equipment, price, stock: 1 }),
+        }
+    }
+}
+
+/// Fits a component to a ship, checking its slots.
+///
+/// # Returns
+/// The engine that was swapped out, if the component is an engine
+pub fn install(ship: &mut Ship, equipment: Equipment, now: u64) -> Result<Option<Equipment>, String> {
+    if let Some(until) = ship.refit_until.filter(|_| ship.is_refitting(now)) {
+        return Err(format!("{} is in the yard until tick {}", ship.name, until));
+    }
+    let slots = ship.slot_usage();
+    let removed = match &equipment {
+        Equipment::Weapon(weapon) => {
+            if slots.hardpoints_used >= slots.hardpoints {
+                return Err(format!("{} has no free hardpoint ({} of {} in use)", ship.name, slots.hardpoints_used, slots.hardpoints));
+            }
+            ship.weapons.push(weapon.clone());
+            None
+        }
+        Equipment::ShieldGenerator { .. } | Equipment::ArmorPlating { .. } => {
+            if slots.utility_used >= slots.utility_slots {
+                return Err(format!("{} has no free utility slot ({} of {} in use)", ship.name, slots.utility_used, slots.utility_slots));
+            }
+            apply_module(ship, &equipment, 1);
+            ship.modules.push(equipment.clone());
+            None
+        }
+        Equipment::Engine(engine) => Some(Equipment::Engine(std::mem::replace(&mut ship.engine, engine.clone()))),
+    };
+    ship.refit_until = Some(now + equipment.downtime_ticks());
+    Ok(removed)
+}
+
+/// Removes a weapon or module from a ship.
+///
+/// # Arguments
+/// * `slot` - Hardpoint to remove a weapon, Utility to remove a module
+/// * `index` - Position of the component in the ship's weapons or modules
+///
+/// # Returns
+/// The removed component
+pub fn uninstall(ship: &mut Ship, slot: Slot, index: usize, now: u64) -> Result<Equipment, String> {
+    if let Some(until) = ship.refit_until.filter(|_| ship.is_refitting(now)) {
+        return Err(format!("{} is in the yard until tick {}", ship.name, until));
+    }
+    let removed = match slot {
+        Slot::Hardpoint if index < ship.weapons.len() => Equipment::Weapon(ship.weapons.remove(index)),
+        Slot::Utility if index < ship.modules.len() => {
+            let module = ship.modules.remove(index);
+            apply_module(ship, &module, -1);
+            module
+        }
+        Slot::Engine => return Err("A ship cannot fly without an engine; install a replacement instead".to_string()),
+        _ => return Err(format!("{} has no {:?} component at index {}", ship.name, slot, index)),
+    };
+    ship.refit_until = Some(now + removed.downtime_ticks());
+    Ok(removed)
+}
+
+/// Adds (`sign` 1) or removes (`sign` -1) a module's capacity.
+fn apply_module(ship: &mut Ship, module: &Equipment, sign: i32) {
+    match module {
+        Equipment::ShieldGenerator { capacity } => ship.shields.resize(ship.shields.capacity + sign * capacity),
+        Equipment::ArmorPlating { capacity } => ship.armor.resize(ship.armor.capacity + sign * capacity),
+        _ => {}
+    }
+}
+
+/// Buys a component from a planet's yard and fits it.
+///
+/// The player pays the asking price plus the fitting fee. A replaced engine
+/// is sold to the yard.
+///
+/// # Arguments
+/// * `market` - The planet's yard
+/// * `planet` - The planet, for pricing what the yard buys back
+/// * `offer_index` - The offer to buy
+/// * `ship` - The ship to fit it to
+/// * `credits` - Credits the player has available
+/// * `now` - The current game tick
+///
+/// # Returns
+/// A receipt of the job, or an error if it cannot be done
+pub fn install_from_market(market: &mut EquipmentMarket, planet: &Planet, offer_index: usize, ship: &mut Ship, credits: f64, now: u64) -> Result<OutfitReceipt, String> {
+    let offer = market.offers.get(offer_index)
+        .filter(|offer| offer.stock > 0)
+        .ok_or_else(|| format!("Invalid equipment offer: {}", offer_index))?
+        .clone();
+    let mut cost = offer.price * (1.0 + INSTALL_FEE_SHARE);
+    if credits < cost {
+        return Err(format!("Insufficient credits: {} costs {:.0} fitted but you have {:.0}", offer.equipment.name(), cost, credits));
+    }
+
+    let removed = install(ship, offer.equipment.clone(), now)?;
+    market.offers[offer_index].stock -= 1;
+    if market.offers[offer_index].stock == 0 {
+        market.offers.remove(offer_index);
+    }
+    if let Some(old_engine) = removed {
+        let price = planet_price(planet, &old_engine)?;
+        cost -= price * RESALE_SHARE;
+        market.restock(old_engine, price);
+    }
+
+    Ok(OutfitReceipt {
+        ship_name: ship.name.clone(),
+        equipment: offer.equipment.name(),
+        credits_change: -cost.round(),
+        ready_tick: ship.refit_until.unwrap_or(now),
+        slots: ship.slot_usage(),
+    })
+}
+
+/// Removes a component from a ship and sells it to the planet's yard.
+///
+/// The player receives the yard's buy-back price less the labor fee, which
+/// can leave the player paying for removing cheap parts.
+pub fn uninstall_to_market(market: &mut EquipmentMarket, planet: &Planet, ship: &mut Ship, slot: Slot, index: usize, credits: f64, now: u64) -> Result<OutfitReceipt, String> {
+    let component = match slot {
+        Slot::Hardpoint => ship.weapons.get(index).cloned().map(Equipment::Weapon),
+        Slot::Utility => ship.modules.get(index).cloned(),
+        Slot::Engine => None,
+    };
+    // Price before touching the ship so a planet without a yard changes nothing
+    let price = match &component {
+        Some(component) => planet_price(planet, component)?,
+        None => 0.0,
+    };
+    let change = (price * (RESALE_SHARE - INSTALL_FEE_SHARE)).round();
+    if credits + change < 0.0 {
+        return Err("Insufficient credits for the removal".to_string());
+    }
+
+    let removed = uninstall(ship, slot, index, now)?;
+    let name = removed.name();
+    market.restock(removed, price);
+    Ok(OutfitReceipt {
+        ship_name: ship.name.clone(),
+        equipment: name,
+        credits_change: change,
+        ready_tick: ship.refit_until.unwrap_or(now),
+        slots: ship.slot_usage(),
+    })
+}
@@ -0,0 +1,363 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::models::fleet::Fleet;
+use crate::models::context::GameContext;
+use crate::models::spatial::{track_fleet, untrack_fleet};
+use crate::models::market::{Market, ShipMarket};
+use crate::models::encounter::EncounterLog;
+use crate::models::salvage::SalvageLog;
+use crate::models::mission::MissionBoard;
+use crate::models::outfitting::EquipmentMarket;
+use crate::models::player::Player;
+use crate::models::settings::load_settings;
+use crate::models::storage::{DocumentWrite, document_key, storage_for};
+
+/// Suffix of the temp file a save is written to before it replaces the real file.
+pub const TEMP_SUFFIX: &str = ".tmp";
+
+/// Suffix of the write-ahead journal a transaction leaves while it is being applied.
+pub const JOURNAL_SUFFIX: &str = ".journal";
+
+/// Returns a fresh temp path next to `path`. Every write gets its own temp
+/// file so concurrent saves of the same file never interleave.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("save");
+    path.with_file_name(format!("{}.{}{}", file_name, uuid::Uuid::new_v4().simple(), TEMP_SUFFIX))
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Replaces the file at `path` with `contents` without ever leaving a
+/// partially written file behind.
+///
+/// The contents go to a temp file first, which is flushed to disk and then
+/// renamed over the target. A crash leaves either the old file or the new
+/// one, plus at worst a stray temp file that `recover_game` cleans up.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp = temp_path(path);
+    if let Err(e) = write_synced(&temp, contents).and_then(|_| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Serializes `data` as JSON and writes it with `write_atomic`.
+pub fn write_json_atomic<T: Serialize + ?Sized>(path: &Path, data: &T) -> io::Result<()> {
+    let json = serde_json::to_vec(data)?;
+    write_atomic(path, &json)
+}
+
+/// One file replaced or removed by a transaction.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct JournalEntry {
+    target: PathBuf,
+    /// The new contents; `None` removes the target
+    #[serde(default)]
+    temp: Option<PathBuf>,
+}
+
+impl JournalEntry {
+    fn apply(&self) -> io::Result<()> {
+        match &self.temp {
+            Some(temp) => fs::rename(temp, &self.target),
+            None => match fs::remove_file(&self.target) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// Applies a batch of file writes and removals under `root` all together
+/// or not at all.
+///
+/// Every file is written to a temp file first, then the batch is recorded
+/// in a write-ahead journal: writing the journal is the commit point. Only
+/// then are the temp files renamed into place. If the process dies before
+/// the journal is written nothing has changed; if it dies after,
+/// `recover_game` finishes the renames.
+///
+/// # Returns
+/// A Result indicating success or failure. On failure before the commit
+/// point the files on disk are untouched.
+pub fn apply_journaled(root: &Path, files: Vec<(PathBuf, Option<Vec<u8>>)>) -> Result<(), String> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let mut entries: Vec<JournalEntry> = Vec::with_capacity(files.len());
+    let roll_back = |entries: &[JournalEntry]| {
+        for temp in entries.iter().filter_map(|entry| entry.temp.as_ref()) {
+            let _ = fs::remove_file(temp);
+        }
+    };
+    for (target, contents) in &files {
+        let Some(contents) = contents else {
+            entries.push(JournalEntry { target: target.clone(), temp: None });
+            continue;
+        };
+        let temp = temp_path(target);
+        if let Err(e) = write_synced(&temp, contents) {
+            let _ = fs::remove_file(&temp);
+            roll_back(&entries);
+            return Err(format!("Failed to write {}: {}", target.display(), e));
+        }
+        entries.push(JournalEntry { target: target.clone(), temp: Some(temp) });
+    }
+
+    // Commit point: once the journal is on disk the batch will be applied
+    let journal = root.join(format!("tx_{}{}", uuid::Uuid::new_v4().simple(), JOURNAL_SUFFIX));
+    if let Err(e) = write_json_atomic(&journal, &entries) {
+        roll_back(&entries);
+        return Err(format!("Failed to write transaction journal: {}", e));
+    }
+
+    for entry in &entries {
+        entry.apply().map_err(|e| {
+            format!("Failed to apply {} (it will be completed on the next load): {}", entry.target.display(), e)
+        })?;
+    }
+    fs::remove_file(&journal).map_err(|e| format!("Failed to remove transaction journal: {}", e))
+}
+
+/// A set of saves that lands in the game's storage all together or not at all.
+///
+/// Entities are serialized as they are staged and handed to the game's
+/// `Storage` as one batch on `commit`: the JSON backend applies it through
+/// `apply_journaled`, SQLite in a single database transaction.
+pub struct Transaction {
+    game_id: String,
+    /// Staged documents; `None` contents remove the document
+    writes: Vec<DocumentWrite>,
+    players: Vec<Player>,
+    fleets: Vec<Fleet>,
+    removed_fleets: Vec<String>,
+}
+
+impl Transaction {
+    pub fn new(game_id: &str) -> Self {
+        Transaction {
+            game_id: game_id.to_string(),
+            writes: Vec::new(),
+            players: Vec::new(),
+            fleets: Vec::new(),
+            removed_fleets: Vec::new(),
+        }
+    }
+
+    /// Starts a transaction on the game named in the current settings.
+    pub fn begin() -> Result<Self, String> {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        Ok(Transaction::new(&settings.game_id))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Stages `data` to be written to `path`, relative to the game directory.
+    /// Staging the same document twice keeps only the later version.
+    pub fn stage<T: Serialize + ?Sized>(&mut self, path: &[&str], data: &T) -> Result<(), String> {
+        let key = document_key(path);
+        let json = serde_json::to_vec(data)
+            .map_err(|e| format!("Failed to serialize {}: {}", key, e))?;
+        self.writes.retain(|(staged, _)| *staged != key);
+        self.writes.push((key, Some(json)));
+        Ok(())
+    }
+
+    /// Stages the removal of the document at `path`, relative to the game directory.
+    pub fn stage_removal(&mut self, path: &[&str]) {
+        let key = document_key(path);
+        self.writes.retain(|(staged, _)| *staged != key);
+        self.writes.push((key, None));
+    }
+
+    pub fn stage_player(&mut self, player: &Player) -> Result<(), String> {
+        self.stage(&["players", &format!("{}.json", player.name)], player)?;
+        self.players.push(player.clone());
+        Ok(())
+    }
+
+    pub fn stage_fleet(&mut self, fleet: &Fleet) -> Result<(), String> {
+        self.stage(&["fleets", &format!("{}.json", fleet.name)], fleet)?;
+        self.fleets.push(fleet.clone());
+        Ok(())
+    }
+
+    /// Stages deleting a fleet's save file, for fleets merged into another.
+    pub fn stage_fleet_removal(&mut self, fleet_name: &str) {
+        self.stage_removal(&["fleets", &format!("{}.json", fleet_name)]);
+        self.fleets.retain(|fleet| fleet.name != fleet_name);
+        self.removed_fleets.push(fleet_name.to_string());
+    }
+
+    pub fn stage_market(&mut self, system_id: usize, planet_id: usize, market: &Market) -> Result<(), String> {
+        self.stage(&["markets", &format!("market_{}_{}.json", system_id, planet_id)], market)
+    }
+
+    pub fn stage_ship_market(&mut self, system_id: usize, planet_id: usize, market: &ShipMarket) -> Result<(), String> {
+        self.stage(&["markets", &format!("ships_{}_{}.json", system_id, planet_id)], market)
+    }
+
+    pub fn stage_equipment_market(&mut self, system_id: usize, planet_id: usize, market: &EquipmentMarket) -> Result<(), String> {
+        self.stage(&["markets", &EquipmentMarket::file_name(system_id, planet_id)], market)
+    }
+
+    pub fn stage_missions(&mut self, board: &MissionBoard) -> Result<(), String> {
+        self.stage(&["missions.json"], board)
+    }
+
+    pub fn stage_encounters(&mut self, log: &EncounterLog) -> Result<(), String> {
+        self.stage(&["encounters.json"], log)
+    }
+
+    pub fn stage_salvage(&mut self, log: &SalvageLog) -> Result<(), String> {
+        self.stage(&["salvage.json"], log)
+    }
+
+    /// Applies every staged write, or none of them.
+    ///
+    /// # Returns
+    /// A Result indicating success or failure. On failure before the commit
+    /// point the stored documents are untouched.
+    pub fn commit(self) -> Result<(), String> {
+        if self.writes.is_empty() {
+            return Ok(());
+        }
+        storage_for(&self.game_id)?.apply(self.writes)?;
+
+        let Some(context) = GameContext::current_for(&self.game_id) else {
+            return Ok(());
+        };
+        for player in self.players {
+            context.players.set(player.name.clone(), player);
+        }
+        for fleet in self.fleets {
+            track_fleet(&context, &fleet);
+            context.fleets.set(fleet.name.clone(), fleet);
+        }
+        for fleet_name in self.removed_fleets {
+            untrack_fleet(&context, &fleet_name);
+            context.fleets.remove(&fleet_name);
+        }
+        Ok(())
+    }
+}
+
+/// What `recover_game` found in a game directory.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RecoveryReport {
+    /// Files written by a committed transaction that was interrupted before it finished
+    pub completed_writes: Vec<String>,
+    /// Temp files of saves that never committed and were thrown away
+    pub discarded_writes: Vec<String>,
+    /// Save files that cannot be read, for example because a crash truncated them
+    pub torn_files: Vec<String>,
+}
+
+impl RecoveryReport {
+    pub fn is_clean(&self) -> bool {
+        self.completed_writes.is_empty() && self.discarded_writes.is_empty() && self.torn_files.is_empty()
+    }
+
+    /// One line describing what was repaired and what could not be.
+    pub fn summary(&self) -> String {
+        if self.is_clean() {
+            return "No interrupted saves found".to_string();
+        }
+        let mut summary = format!(
+            "Completed {} interrupted writes, discarded {} uncommitted writes",
+            self.completed_writes.len(),
+            self.discarded_writes.len()
+        );
+        if !self.torn_files.is_empty() {
+            summary.push_str(&format!("; torn save files: {}", self.torn_files.join(", ")));
+        }
+        summary
+    }
+}
+
+pub(crate) fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_suffix(path: &Path, suffix: &str) -> bool {
+    path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.ends_with(suffix))
+}
+
+/// Brings a game directory back to a consistent state after a crash.
+///
+/// Transactions whose journal made it to disk are rolled forward, temp files
+/// of writes that never committed are deleted, and every remaining JSON file
+/// is parsed so torn saves can be reported.
+///
+/// # Arguments
+/// * `game_dir` - The directory of the game to check
+///
+/// # Returns
+/// A report of the repairs, or an error if the directory cannot be read
+pub fn recover_game(game_dir: &Path) -> io::Result<RecoveryReport> {
+    let mut report = RecoveryReport::default();
+    if !game_dir.exists() {
+        return Ok(report);
+    }
+
+    let mut files = Vec::new();
+    collect_files(game_dir, &mut files)?;
+    for journal in files.iter().filter(|path| has_suffix(path, JOURNAL_SUFFIX)) {
+        // A journal that cannot be read was never committed; its temp files
+        // are discarded below
+        let entries: Vec<JournalEntry> = File::open(journal)
+            .ok()
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default();
+        for entry in entries {
+            // Renamed temp files and removed targets were already applied
+            let pending = match &entry.temp {
+                Some(temp) => temp.exists(),
+                None => entry.target.exists(),
+            };
+            if pending {
+                entry.apply()?;
+                report.completed_writes.push(entry.target.display().to_string());
+            }
+        }
+        fs::remove_file(journal)?;
+    }
+
+    for temp in files.iter().filter(|path| has_suffix(path, TEMP_SUFFIX) && path.exists()) {
+        fs::remove_file(temp)?;
+        report.discarded_writes.push(temp.display().to_string());
+    }
+
+    for file in files.iter().filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json")) {
+        let readable = File::open(file)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, serde_json::Value>(io::BufReader::new(file)).ok())
+            .is_some();
+        if !readable {
+            report.torn_files.push(file.display().to_string());
+        }
+    }
+    report.torn_files.sort();
+    Ok(report)
+}
@@ -1,4 +1,5 @@
 use crate::constants::PRINT_DEBUG;
+use crate::models::storage::{load_document, save_document, storage_for};
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -6,12 +7,10 @@ use std::collections::HashSet;
 use std::fmt;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
-use std::fs::{self, File};
-use std::path::Path;
 use crate::models::settings::load_settings;
-use crate::models::game_state::game_path;
 use crate::models::economy::Economy;
 use crate::models::market::{Market, ShipMarket};
+use crate::models::rng::next_game_rng;
 
 use super::position::{random_nonzero_position, Position};
 use super::resource::{Resource, ResourceType};
@@ -52,6 +51,24 @@ pub enum PlanetDanger {
     Deadly,
     Insidious,
 }
+impl PlanetDanger {
+    /// Position on the danger scale, from 0 (VerySafe) to 9 (Insidious).
+    pub fn level(&self) -> u32 {
+        match self {
+            PlanetDanger::VerySafe => 0,
+            PlanetDanger::Safe => 1,
+            PlanetDanger::Harmless => 2,
+            PlanetDanger::Benign => 3,
+            PlanetDanger::Normal => 4,
+            PlanetDanger::Tainted => 5,
+            PlanetDanger::Hazardous => 6,
+            PlanetDanger::Corrosive => 7,
+            PlanetDanger::Deadly => 8,
+            PlanetDanger::Insidious => 9,
+        }
+    }
+}
+
 impl fmt::Display for PlanetDanger {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -158,11 +175,12 @@ impl Distribution<PlanetSpecialization> for Standard {
     }
 }
 
-pub fn generate_planets(
+pub fn generate_planets<R: Rng + ?Sized>(
     num_planets: u32,
     map_width: i32,
     map_height: i32,
     map_length: i32,
+    rng: &mut R,
 ) -> Vec<Planet> {
     // Initialize a vector to hold the planets
     let mut planets = Vec::with_capacity(num_planets as usize);
@@ -177,7 +195,7 @@ pub fn generate_planets(
         
         // Try to find a unique position
         loop {
-            position = random_nonzero_position(map_width, map_height, map_length);
+            position = random_nonzero_position(map_width, map_height, map_length, rng);
             if !unique_positions.contains(&position) || attempts >= 10 {
                 break;
             }
@@ -189,12 +207,12 @@ pub fn generate_planets(
         }
         
         unique_positions.insert(position);
-        let economy: Economy = rand::random();
-        let specialization: PlanetSpecialization = rand::random();
-        let biome: Biome = rand::random();
-        let danger: PlanetDanger = rand::random();
+        let economy: Economy = rng.sample(Standard);
+        let specialization: PlanetSpecialization = rng.sample(Standard);
+        let biome: Biome = rng.sample(Standard);
+        let danger: PlanetDanger = rng.sample(Standard);
         
-        let market = Market::new(&specialization, &economy);
+        let market = Market::new(&specialization, &economy, rng);
         let planet = Planet {
             name,
             description: format!("A {} planet with {} economy", biome, economy),
@@ -233,9 +251,8 @@ fn remove_colliding_planets(planets: &mut Vec<Planet>) {
     }
 }
 
-fn generate_planet_market(specialization: &PlanetSpecialization, economy: &Economy) -> Vec<Resource> {
+fn generate_planet_market<R: Rng + ?Sized>(specialization: &PlanetSpecialization, economy: &Economy, rng: &mut R) -> Vec<Resource> {
     let mut market = Vec::new();
-    let mut rng = rand::thread_rng();
     
     // Base price multiplier based on economy
     let economy_multiplier = match economy {
@@ -317,10 +334,10 @@ impl PlanetTrait for Planet {
 }
 
 impl Planet {
-    pub fn new(name: String, position: Position, specialization: PlanetSpecialization, economy: Economy) -> Self {
-        let biome: Biome = rand::random();
-        let danger: PlanetDanger = rand::random();
-        let market = Market::new(&specialization, &economy);
+    pub fn new<R: Rng + ?Sized>(name: String, position: Position, specialization: PlanetSpecialization, economy: Economy, rng: &mut R) -> Self {
+        let biome: Biome = rng.sample(Standard);
+        let danger: PlanetDanger = rng.sample(Standard);
+        let market = Market::new(&specialization, &economy, rng);
         
         Planet {
             name,
@@ -337,30 +354,20 @@ impl Planet {
 
     pub fn get_ship_market(&self, system_id: usize, planet_id: usize) -> std::io::Result<Vec<Ship>> {
         let settings = load_settings()?;
-        let market_path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets")
-            .join(format!("ships_{}_{}.json", system_id, planet_id));
-
-        if market_path.exists() {
-            let file = File::open(market_path)?;
-            Ok(serde_json::from_reader(file)?)
+        let market_path = ["markets", &format!("ships_{}_{}.json", system_id, planet_id)];
+
+        if let Some(ships) = load_document(&settings.game_id, &market_path).map_err(std::io::Error::other)? {
+            Ok(ships)
         } else {
             // Generate new ship market if none exists
-            let ships = self.generate_ship_market();
+            let ships = self.generate_ship_market(&mut next_game_rng("ship_market").map_err(std::io::Error::other)?);
             // Save the generated market
-            if let Some(parent) = market_path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            let market_json = serde_json::to_string(&ships)?;
-            fs::write(&market_path, market_json)?;
+            save_document(&settings.game_id, &market_path, &ships).map_err(std::io::Error::other)?;
             Ok(ships)
         }
     }
 
-    pub fn generate_ship_market(&self) -> Vec<Ship> {
-        let mut rng = rand::thread_rng();
+    pub fn generate_ship_market<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Ship> {
         let ship_count = rng.gen_range(3..=8); // Generate 3-8 ships
         let mut ships = Vec::new();
 
@@ -376,7 +383,7 @@ impl Planet {
                 }
             };
 
-            let mut ship: Ship = rand::random();
+            let mut ship: Ship = rng.sample(Standard);
             ship.specialization = ship_type;
             
             // Set price based on ship size and planet economy
@@ -403,22 +410,12 @@ impl Planet {
 
     fn save_ship_market(&self, market: &[Ship], system_id: usize, planet_id: usize) -> std::io::Result<()> {
         let settings = load_settings()?;
-        let market_path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets")
-            .join(format!("ships_{}_{}.json", system_id, planet_id));
-
-        if let Some(parent) = market_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let market_json = serde_json::to_string(market)?;
-        fs::write(market_path, market_json)
+        save_document(&settings.game_id, &["markets", &format!("ships_{}_{}.json", system_id, planet_id)], market)
+            .map_err(std::io::Error::other)
     }
 
     pub fn refresh_ship_market(&mut self, system_id: usize, planet_id: usize) -> std::io::Result<()> {
-        let ships = self.generate_ship_market();
+        let ships = self.generate_ship_market(&mut next_game_rng("ship_market").map_err(std::io::Error::other)?);
         self.save_ship_market(&ships, system_id, planet_id)
     }
 
@@ -436,10 +433,8 @@ impl Planet {
         println!("  Fleet: {}", fleet_name);
         println!("  Trade-in ship: {:?}", trade_in_ship);
 
-        let settings = load_settings().map_err(|e| e.to_string())?;
-        let fleet_path = format!("data/game/{}/fleets/{}.json", settings.game_id, fleet_name);
-        println!("Loading fleet from: {}", fleet_path);
-        let mut fleet = Fleet::load(&fleet_path).map_err(|e| format!("Failed to load fleet: {}", e))?;
+        println!("Loading fleet {}", fleet_name);
+        let mut fleet = Fleet::load(fleet_name).map_err(|e| format!("Failed to load fleet: {}", e))?;
         println!("Loaded fleet with {} ships", fleet.ships.len());
 
         // Load the ship market
@@ -546,7 +541,7 @@ impl Planet {
 
         // Save the updated fleet
         println!("Saving updated fleet");
-        fleet.save(&fleet_path).map_err(|e| format!("Failed to save fleet: {}", e))?;
+        fleet.save(fleet_name).map_err(|e| format!("Failed to save fleet: {}", e))?;
 
         // Save the updated market
         println!("Saving updated market");
@@ -558,25 +553,13 @@ impl Planet {
 
     pub fn save_market(&self, market: &[Resource]) -> std::io::Result<()> {
         let settings = load_settings()?;
-        let market_path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets")
-            .join(format!("{}_resources.json", self.name));
-
-        if let Some(parent) = market_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let market_json = serde_json::to_string(market)?;
-        fs::write(market_path, market_json)
+        save_document(&settings.game_id, &["markets", &format!("{}_resources.json", self.name)], market)
+            .map_err(std::io::Error::other)
     }
 
     pub fn sell_ship(&mut self, ship_name: &str, fleet_name: &str, player: &mut Player, system_id: usize, planet_id: usize) -> Result<(), String> {
-        let settings = load_settings().map_err(|e| e.to_string())?;
-        let fleet_path = format!("data/game/{}/fleets/{}.json", settings.game_id, fleet_name);
         // Load the player's fleet
-        let mut fleet = Fleet::load(&fleet_path).map_err(|e| format!("Failed to load fleet: {}", e))?;
+        let mut fleet = Fleet::load(fleet_name).map_err(|e| format!("Failed to load fleet: {}", e))?;
 
         // Find the ship in the fleet
         let ship_index = fleet.ships.iter()
@@ -619,7 +602,7 @@ impl Planet {
         player.credits += ship_value;
 
         // Save the updated fleet
-        fleet.save(&fleet_path).map_err(|e| format!("Failed to save fleet: {}", e))?;
+        fleet.save(fleet_name).map_err(|e| format!("Failed to save fleet: {}", e))?;
 
         // Add ship to market with calculated value
         let mut market_ships = self.get_ship_market(system_id, planet_id).map_err(|e| e.to_string())?;
@@ -642,69 +625,24 @@ impl Planet {
 
 pub fn load_planet(system_id: usize, planet_id: usize) -> std::io::Result<Option<Planet>> {
     let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-    let planet_path = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("systems")
-        .join(format!("System_{}", system_id))
-        .join(format!("Planet_{}.json", planet_id));
-
-    if !planet_path.exists() {
-        return Ok(None);
-    }
-
-    let file = File::open(planet_path)?;
-    let planet: Planet = serde_json::from_reader(file)?;
-    Ok(Some(planet))
+    load_document(&settings.game_id, &["systems", &format!("System_{}", system_id), &format!("Planet_{}.json", planet_id)])
+        .map_err(std::io::Error::other)
 }
 
 pub fn save_planet(system_id: usize, planet_id: usize, planet: &Planet) -> std::io::Result<()> {
-    let planet_path = game_path(&["systems", &format!("System_{}", system_id), &format!("Planet_{}.json", planet_id)]);
-
-    if let Some(parent) = planet_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    let file = File::create(planet_path)?;
-    serde_json::to_writer(file, planet)?;
-    Ok(())
-}
-
-pub fn get_fleet_path(fleet_name: &str) -> String {
-    let path = game_path(&["fleets", &format!("{}.json", fleet_name)]);
-    path.to_string_lossy().into_owned()
+    let settings = load_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    save_document(&settings.game_id, &["systems", &format!("System_{}", system_id), &format!("Planet_{}.json", planet_id)], planet)
+        .map_err(std::io::Error::other)
 }
 
 pub fn load_planet_market(system_id: usize, planet_id: usize) -> Result<Market, String> {
     let settings = load_settings().map_err(|e| e.to_string())?;
-    let market_path = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("markets")
-        .join(format!("market_{}_{}.json", system_id, planet_id));
-
-    if !market_path.exists() {
-        return Err("Market not found".to_string());
-    }
-
-    let file = File::open(market_path).map_err(|e| e.to_string())?;
-    let market: Market = serde_json::from_reader(file).map_err(|e| e.to_string())?;
-    Ok(market)
+    storage_for(&settings.game_id)?.load_market(system_id, planet_id)?
+        .ok_or_else(|| "Market not found".to_string())
 }
 
 pub fn load_planet_ship_market(system_id: usize, planet_id: usize) -> Result<ShipMarket, String> {
     let settings = load_settings().map_err(|e| e.to_string())?;
-    let market_path = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("markets")
-        .join(format!("ships_{}_{}.json", system_id, planet_id));
-
-    if !market_path.exists() {
-        return Err("Ship market not found".to_string());
-    }
-
-    let file = File::open(market_path).map_err(|e| e.to_string())?;
-    let market: ShipMarket = serde_json::from_reader(file).map_err(|e| e.to_string())?;
-    Ok(market)
+    load_document(&settings.game_id, &["markets", &format!("ships_{}_{}.json", system_id, planet_id)])?
+        .ok_or_else(|| "Ship market not found".to_string())
 }
\ No newline at end of file
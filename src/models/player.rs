@@ -1,11 +1,15 @@
+use rand::Rng;
+use crate::models::storage::storage_for;
 use serde::Serialize;
 use serde::Deserialize;
-use serde_json::to_writer;
 use crate::models::resource::{Resource, ResourceType, generate_resources_no_trade};
-use crate::models::game_state::PLAYER_CACHE;
-use crate::models::game_state::game_path;
-use std::path::Path;
-use crate::models::settings::load_settings;
+use crate::models::context::GameContext;
+use crate::models::settings::{GameSettings, load_settings};
+use crate::models::fleet::Fleet;
+use crate::models::position::random_position;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::faction::MAX_REPUTATION;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
@@ -13,6 +17,9 @@ pub struct Player {
     pub resources: Vec<Resource>,
     pub credits: f64,
     pub fleets: Vec<String>, // Store fleet names
+    /// Reputation with each faction, from -100 (hostile) to 100 (allied)
+    #[serde(default)]
+    pub reputation: HashMap<String, f32>,
 }
 
 impl Player {
@@ -21,18 +28,77 @@ impl Player {
     /// # Arguments
     /// * `player_name` - The name of the player
     /// * `starting_credits` - The amount of credits the player starts with
+    /// * `rng` - The random number generator used for the starting resources
     /// 
     /// # Returns
     /// A new Player instance with the specified name and starting credits
-    pub fn new(player_name: &str, starting_credits: f64) -> Self {
+    pub fn new<R: Rng + ?Sized>(player_name: &str, starting_credits: f64, rng: &mut R) -> Self {
         Player {
             name: player_name.to_string(),
-            resources: generate_resources_no_trade(),
+            resources: generate_resources_no_trade(rng),
             credits: starting_credits,
             fleets: vec![format!("Fleet_{}_{}", player_name, 1)], // Initialize with first fleet
+            reputation: HashMap::new(),
         }
     }
 
+    /// Creates a player joining a game, with the game's starting credits and
+    /// a first fleet of one small freighter somewhere in the galaxy.
+    ///
+    /// # Returns
+    /// The player and their fleet, both still to be saved
+    pub fn start<R: Rng + ?Sized>(player_name: &str, settings: &GameSettings, rng: &mut R) -> (Self, Fleet) {
+        let player = Player::new(player_name, settings.starting_credits, rng);
+        let mut fleet = Fleet::new(
+            player_name.to_string(),
+            random_position(
+                settings.map_width as i32,
+                settings.map_height as i32,
+                settings.map_length as i32,
+                rng
+            ),
+            1
+        );
+
+        let mut starting_ship = Ship::new_with_rng(
+            ShipType::Freighter,
+            ShipSize::Small,
+            ShipEngine::Basic,
+            rng
+        );
+        starting_ship.position = fleet.position.clone();
+        starting_ship.hp = 100; // Ensure full health
+        starting_ship.name = format!("{}'s First Ship", player_name);
+        fleet.ships.push(starting_ship);
+        (player, fleet)
+    }
+
+    /// Returns the player's reputation with a faction; factions the player
+    /// has never dealt with start at 0.
+    pub fn reputation_with(&self, faction_name: &str) -> f32 {
+        self.reputation.get(faction_name).copied().unwrap_or(0.0)
+    }
+
+    /// Adds `change` to the player's reputation with a faction, clamped to
+    /// `±MAX_REPUTATION`, and returns the new value.
+    pub fn adjust_reputation(&mut self, faction_name: &str, change: f32) -> f32 {
+        let reputation = (self.reputation_with(faction_name) + change).clamp(-MAX_REPUTATION, MAX_REPUTATION);
+        self.reputation.insert(faction_name.to_string(), reputation);
+        reputation
+    }
+
+    /// Records that the player owns a fleet.
+    pub fn add_fleet(&mut self, fleet_name: &str) {
+        if !self.fleets.iter().any(|name| name == fleet_name) {
+            self.fleets.push(fleet_name.to_string());
+        }
+    }
+
+    /// Forgets a fleet the player no longer has.
+    pub fn remove_fleet(&mut self, fleet_name: &str) {
+        self.fleets.retain(|name| name != fleet_name);
+    }
+
     pub fn add_resource(&mut self, resource_type: ResourceType, quantity: u32) {
         if let Some(existing_resource) = self.resources.iter_mut().find(|r| r.resource_type == resource_type) {
             // If the resource exists, add to its quantity
@@ -68,25 +134,12 @@ impl Player {
     * within the game directory. If necessary, creates the required directories.
     * Returns the newly created Player object.
     **/
-    pub fn create_player(game_id: &str, player_name: &str, starting_credits: f64) -> Player {
-        // Create the path to the player file
-        let data_path = Path::new("data")
-            .join("game")
-            .join(game_id)
-            .join("players")
-            .join(format!("{}.json", player_name));
-
+    pub fn create_player<R: Rng + ?Sized>(game_id: &str, player_name: &str, starting_credits: f64, rng: &mut R) -> Player {
         // Create a new player
-        let player = Player::new(player_name, starting_credits);
+        let player = Player::new(player_name, starting_credits, rng);
 
-        // Create the file and handle any errors
-        let file = match std::fs::File::create(&data_path) {
-            Ok(file) => file,
-            Err(e) => panic!("Failed to create player file: {}", e),
-        };
-
-        // Write the player data to the file
-        match to_writer(&file, &player) {
+        // Write the player data to the game's storage
+        match storage_for(game_id).and_then(|storage| storage.save_player(&player)) {
             Ok(_) => println!("Successfully wrote player data to file"),
             Err(e) => panic!("Failed to write player data to file: {}", e),
         }
@@ -100,14 +153,10 @@ impl Player {
     /// A Result indicating whether the save was successful
     pub fn save(&self) -> Result<(), String> {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("players")
-            .join(format!("{}.json", self.name));
-        let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
-        serde_json::to_writer(&file, self).map_err(|e| e.to_string())?;
-        PLAYER_CACHE.set(self.name.clone(), self.clone());
+        storage_for(&settings.game_id)?.save_player(self)?;
+        if let Some(context) = GameContext::current_for(&settings.game_id) {
+            context.players.set(self.name.clone(), self.clone());
+        }
         Ok(())
     }
 }
\ No newline at end of file
@@ -46,18 +46,17 @@ impl Position {
 /// * `x_range` - The range of the x coordinate
 /// * `y_range` - The range of the y coordinate
 /// * `z_range` - The range of the z coordinate
+/// * `rng` - The random number generator to draw from
 ///
 /// # Returns
 /// A new `Position` with a random, but non-zero, x, y and z coordinate within the specified range.
 /// # Usage:
 /// ```
 /// # use star_trader_game::models::position::random_nonzero_position;
-/// let pos = random_nonzero_position(10, 10, 10);
+/// let pos = random_nonzero_position(10, 10, 10, &mut rand::thread_rng());
 /// assert!(pos.x != 0 && pos.y != 0 && pos.z != 0);
 /// ```
-pub fn random_nonzero_position(x_range: i32, y_range: i32, z_range: i32) -> Position {
-    let mut rng = rand::thread_rng();
-
+pub fn random_nonzero_position<R: Rng + ?Sized>(x_range: i32, y_range: i32, z_range: i32, rng: &mut R) -> Position {
     let mut x = rng.gen_range(-x_range..=x_range);
     let mut y = rng.gen_range(-y_range..=y_range);
     let mut z = rng.gen_range(-z_range..=z_range);
@@ -78,17 +77,17 @@ pub fn random_nonzero_position(x_range: i32, y_range: i32, z_range: i32) -> Posi
 /// * `map_width` - The range for the x coordinate (-map_width to map_width)
 /// * `map_height` - The range for the y coordinate (-map_height to map_height)
 /// * `map_length` - The range for the z coordinate (-map_length to map_length)
+/// * `rng` - The random number generator to draw from
 ///
 /// # Returns
 /// A `Position` with random x, y, and z coordinates within the specified ranges.
 /// # Usage:
 /// ```
 /// # use star_trader_game::models::position::random_position;
-/// let pos = random_position(10, 10, 10);
+/// let pos = random_position(10, 10, 10, &mut rand::thread_rng());
 /// assert!(pos.x >= -10 && pos.x <= 10 && pos.y >= -10 && pos.y <= 10 && pos.z >= -10 && pos.z <= 10);
 /// ```
-pub fn random_position(map_width: i32, map_height: i32, map_length: i32) -> Position {
-    let mut rng = rand::thread_rng();
+pub fn random_position<R: Rng + ?Sized>(map_width: i32, map_height: i32, map_length: i32, rng: &mut R) -> Position {
     Position {
         x: rng.gen_range(-map_width..=map_width),
         y: rng.gen_range(-map_height..=map_height),
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use crate::models::economy::Economy;
+use crate::models::fleet::Fleet;
+use crate::models::planet::{Planet, PlanetSpecialization};
+use crate::models::ship::ship::{Ship, ShipSize};
+
+/// Credits per hull point restored at a planet with a price factor of 1.0.
+pub const HULL_REPAIR_COST: f64 = 5.0;
+/// Credits per armor point restored.
+pub const ARMOR_REPAIR_COST: f64 = 2.0;
+/// Credits per shield point recharged.
+pub const SHIELD_REPAIR_COST: f64 = 0.5;
+
+#[derive(Deserialize, Debug)]
+pub struct RepairData {
+    pub fleet_name: String,
+    /// Repairs a single ship of the fleet; every damaged ship if not set
+    #[serde(default)]
+    pub ship_index: Option<usize>,
+}
+
+/// The repairs one ship needs and what they cost.
+#[derive(Serialize, Debug, Clone)]
+pub struct ShipRepairQuote {
+    pub ship_index: usize,
+    pub ship_name: String,
+    pub hull_points: i32,
+    pub armor_points: i32,
+    pub shield_points: i32,
+    pub cost: f64,
+}
+
+/// The repairs a fleet needs at a planet.
+#[derive(Serialize, Debug, Clone)]
+pub struct RepairEstimate {
+    pub fleet_name: String,
+    /// Combined specialization and economy price factor of the planet
+    pub price_factor: f64,
+    pub ships: Vec<ShipRepairQuote>,
+    pub total_cost: f64,
+}
+
+/// Bigger hulls need bigger docks and cost more per point to work on.
+pub fn size_cost_factor(size: &ShipSize) -> f64 {
+    match size {
+        ShipSize::Tiny => 0.8,
+        ShipSize::Small => 0.9,
+        ShipSize::Medium => 1.0,
+        ShipSize::Large => 1.2,
+        ShipSize::Huge => 1.5,
+        ShipSize::Planetary => 2.0,
+    }
+}
+
+/// Industrial worlds have the yards and parts to repair ships cheaply.
+pub fn specialization_cost_factor(specialization: &PlanetSpecialization) -> f64 {
+    match specialization {
+        PlanetSpecialization::Manufacturing => 0.6,
+        PlanetSpecialization::Technology => 0.8,
+        PlanetSpecialization::Mining => 0.9,
+        PlanetSpecialization::Research | PlanetSpecialization::Service => 1.0,
+        PlanetSpecialization::Agriculture => 1.2,
+        PlanetSpecialization::Tourism => 1.4,
+        PlanetSpecialization::None => 1.5,
+    }
+}
+
+/// Busy yards charge a premium and failing economies lack parts. Planets
+/// without an economy have no repair facilities at all.
+pub fn economy_cost_factor(economy: &Economy) -> Option<f64> {
+    match economy {
+        Economy::Booming => Some(1.3),
+        Economy::Growing => Some(1.1),
+        Economy::Stable => Some(1.0),
+        Economy::Struggling => Some(1.1),
+        Economy::Declining => Some(1.25),
+        Economy::Crashing => Some(1.5),
+        Economy::Nonexistent => None,
+    }
+}
+
+/// The price factor of repairs at a planet.
+///
+/// # Returns
+/// The factor, or an error if the planet offers no repairs
+pub fn planet_repair_factor(planet: &Planet) -> Result<f64, String> {
+    let economy = economy_cost_factor(&planet.economy)
+        .ok_or_else(|| format!("{} has no repair facilities", planet.name))?;
+    Ok(specialization_cost_factor(&planet.specialization) * economy)
+}
+
+/// Prices restoring a ship's hull, armor and shields to full.
+pub fn quote_ship_repair(ship: &Ship, ship_index: usize, price_factor: f64) -> ShipRepairQuote {
+    let hull_points = (ship.max_hp() - ship.hp).max(0);
+    let armor_points = (ship.armor.capacity - ship.armor.current).max(0);
+    let shield_points = (ship.shields.capacity - ship.shields.current).max(0);
+    let points_cost = hull_points as f64 * HULL_REPAIR_COST
+        + armor_points as f64 * ARMOR_REPAIR_COST
+        + shield_points as f64 * SHIELD_REPAIR_COST;
+
+    ShipRepairQuote {
+        ship_index,
+        ship_name: ship.name.clone(),
+        hull_points,
+        armor_points,
+        shield_points,
+        cost: (points_cost * size_cost_factor(&ship.size) * price_factor).round(),
+    }
+}
+
+/// Prices the repairs of a fleet at a planet.
+///
+/// # Arguments
+/// * `fleet` - The fleet to repair
+/// * `planet` - The planet doing the work
+/// * `ship_index` - A single ship to repair, or `None` for every damaged ship
+///
+/// # Returns
+/// The estimate, or an error if the planet offers no repairs or the ship does not exist
+pub fn quote_fleet_repair(fleet: &Fleet, planet: &Planet, ship_index: Option<usize>) -> Result<RepairEstimate, String> {
+    let price_factor = planet_repair_factor(planet)?;
+    if let Some(index) = ship_index
+        && index >= fleet.ships.len()
+    {
+        return Err(format!("Invalid ship index: {} (fleet has {} ships)", index, fleet.ships.len()));
+    }
+
+    let ships: Vec<ShipRepairQuote> = fleet.ships.iter().enumerate()
+        .filter(|(index, ship)| ship_index.is_none_or(|selected| selected == *index) && ship.needs_repair())
+        .map(|(index, ship)| quote_ship_repair(ship, index, price_factor))
+        .collect();
+
+    Ok(RepairEstimate {
+        fleet_name: fleet.name.clone(),
+        price_factor,
+        total_cost: ships.iter().map(|quote| quote.cost).sum(),
+        ships,
+    })
+}
+
+/// Restores a ship's hull, armor and shields to full.
+pub fn repair_ship(ship: &mut Ship) {
+    ship.hp = ship.hp.max(ship.max_hp());
+    ship.armor.current = ship.armor.capacity;
+    ship.shields.current = ship.shields.capacity;
+}
+
+/// Carries out the repairs of an estimate on the fleet it was made for.
+pub fn apply_repairs(fleet: &mut Fleet, estimate: &RepairEstimate) {
+    for quote in &estimate.ships {
+        if let Some(ship) = fleet.ships.get_mut(quote.ship_index) {
+            repair_ship(ship);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
@@ -51,13 +51,11 @@ impl Resource {
 }
 
 // Generate a vector of random resources for the Traders
-pub fn generate_resources() -> Vec<Resource> {
+pub fn generate_resources<R: Rng + ?Sized>(rng: &mut R) -> Vec<Resource> {
     let mut inventory = vec![]; // Create an empty vector to hold the resources
 
     let resource_types: Vec<ResourceType> = ResourceType::iter().collect(); // Define a vector of all possible resource types
 
-    let mut rng = thread_rng(); // Create a new random number generator
-
     // Loop through each resource type and generate a random Resource object for it
     for resource_type in resource_types {
         let buy_price: Option<f64> = if rng.gen_bool(0.7) {
@@ -104,13 +102,11 @@ pub fn generate_resources() -> Vec<Resource> {
 * # Returns
 * A vector of Resource objects with random quantities, but without any buy or sell prices.
 **/
-pub fn generate_resources_no_trade() -> Vec<Resource> {
+pub fn generate_resources_no_trade<R: Rng + ?Sized>(rng: &mut R) -> Vec<Resource> {
     let mut inventory = vec![]; // Create an empty vector to hold the resources
 
     let resource_types: Vec<ResourceType> = ResourceType::iter().collect(); // Define a vector of all possible resource types
 
-    let mut rng = thread_rng(); // Create a new random number generator
-
     // Loop through each resource type and generate a random Resource object for it
     for resource_type in resource_types {
         let quantity: Option<u32> = if rng.gen_bool(0.7) {
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::models::game_state::current_game_id;
+use crate::models::storage::{load_document, save_document};
+use crate::models::settings::load_settings_for_game;
+
+/// The RNG used for everything random inside a game.
+///
+/// `StdRng` is a seedable ChaCha generator, so the same seed always yields the
+/// same sequence of values.
+pub type GameRng = StdRng;
+
+/// Per-stream draw counters, persisted as `rng_state.json` in the game directory.
+///
+/// Every simulation step (a combat, an encounter roll, ...) takes the next
+/// counter value of its stream, so replaying the same actions against the same
+/// seed reproduces the same outcomes even across server restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RngState {
+    pub counters: BTreeMap<String, u64>,
+}
+
+/// Generates a fresh seed for games that did not specify one.
+///
+/// Seeds are kept below 2^53 so they survive a round trip through the
+/// frontend's JSON numbers.
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen_range(0..(1u64 << 53))
+}
+
+/// Creates a deterministic RNG for a named stream of a game seed.
+///
+/// Different stream names produce independent sequences, so adding draws to
+/// one subsystem does not shift the results of another.
+///
+/// # Arguments
+/// * `seed` - The game seed from `GameSettings`
+/// * `stream` - Name of the subsystem drawing numbers (e.g. "galaxy", "combat:3")
+///
+/// # Returns
+/// A seeded `GameRng`
+pub fn rng_for(seed: u64, stream: &str) -> GameRng {
+    // FNV-1a keeps the stream hash stable across Rust versions, unlike DefaultHasher
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in stream.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    GameRng::seed_from_u64(seed ^ hash)
+}
+
+/// Draws an id for something created inside a game, such as a mission or a
+/// salvage field, so replays hand out the same ids.
+///
+/// # Returns
+/// A random (version 4) UUID built from the RNG's bytes
+pub fn game_uuid<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut bytes = [0u8; 16];
+    rng.fill(&mut bytes);
+    uuid::Builder::from_random_bytes(bytes).into_uuid().to_string()
+}
+
+/// Returns the RNG for the next step of `stream` in the active game and
+/// advances that stream's persisted counter.
+///
+/// # Arguments
+/// * `stream` - Name of the simulation stream (e.g. "combat", "encounter")
+///
+/// # Returns
+/// A `GameRng` unique to this step of the stream, or an error if no game is
+/// loaded or its RNG state cannot be read or saved; drawing from anything
+/// else would break the game's replays
+pub fn next_game_rng(stream: &str) -> Result<GameRng, String> {
+    let game_id = current_game_id()?;
+    let seed = load_settings_for_game(&game_id)
+        .map_err(|e| format!("Failed to load settings for the RNG: {}", e))?
+        .seed;
+
+    let state_path = ["rng_state.json"];
+    let mut state: RngState = load_document(&game_id, &state_path)?.unwrap_or_default();
+    let counter = state.counters.entry(stream.to_string()).or_insert(0);
+    let step = *counter;
+    *counter += 1;
+    save_document(&game_id, &state_path, &state)?;
+
+    Ok(rng_for(seed, &format!("{}:{}", stream, step)))
+}
@@ -0,0 +1,333 @@
+use serde::{Deserialize, Serialize};
+use crate::models::encounter::{Encounter, EncounterKind};
+use crate::models::fleet::Fleet;
+use crate::models::npc_trader::deep_space_trip;
+use crate::models::position::Position;
+use crate::models::ship::ship::ShipEngine;
+use crate::models::spatial::PointGrid;
+use crate::models::star_system::StarSystem;
+use crate::models::travel::{FUEL_PER_DISTANCE, begin_transit, fleet_speed, fuel_factor, travel_ticks};
+
+/// What a planned route keeps as small as possible.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteObjective {
+    #[default]
+    Distance,
+    /// Fewest ticks; every leg takes at least one tick, so fewer jumps win ties
+    Time,
+    Fuel,
+    /// Least total danger of the systems passed through
+    Danger,
+}
+
+/// Longest single jump between systems a ship with the engine can make.
+pub fn jump_range(engine: &ShipEngine) -> f64 {
+    match engine {
+        ShipEngine::Basic => 800.0,
+        ShipEngine::Advanced => 1500.0,
+        ShipEngine::Experimental => 3000.0,
+    }
+}
+
+/// A fleet jumps as far as its shortest-ranged ship.
+///
+/// # Returns
+/// The jump range, or `None` if the fleet has no ships
+pub fn fleet_jump_range(fleet: &Fleet) -> Option<f64> {
+    fleet.ships.iter()
+        .map(|ship| jump_range(&ship.engine))
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+/// Average danger level of a system's planets; empty systems are safe.
+pub fn system_danger(system: &StarSystem) -> f64 {
+    if system.planets.is_empty() {
+        return 0.0;
+    }
+    system.planets.iter().map(|planet| planet.danger.level() as f64).sum::<f64>() / system.planets.len() as f64
+}
+
+/// One leg of a route, ending at a system or one of its planets.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Waypoint {
+    pub system_id: usize,
+    pub star_name: String,
+    #[serde(default)]
+    pub planet_id: Option<usize>,
+    /// Galaxy position of the system
+    pub position: Position,
+    /// Position inside the system the leg ends at
+    pub local_position: Position,
+    pub distance: f64,
+    pub ticks: u64,
+    pub fuel: u32,
+    pub danger: f64,
+}
+
+/// An encounter that halted a fleet following a route at the end of a leg;
+/// the encounter itself waits in the game's encounter log.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RouteEncounter {
+    pub leg: usize,
+    pub tick: u64,
+    pub system_id: usize,
+    pub encounter_id: String,
+    pub kind: EncounterKind,
+    /// The fleet that was met
+    pub fleet_name: String,
+}
+
+/// An ordered list of waypoints and, once a fleet follows it, its progress.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Route {
+    pub objective: RouteObjective,
+    pub jump_range: f64,
+    pub waypoints: Vec<Waypoint>,
+    pub total_distance: f64,
+    pub total_ticks: u64,
+    pub total_fuel: u32,
+    pub total_danger: f64,
+    /// Index of the leg being flown, or the next one to fly while halted
+    #[serde(default)]
+    pub next_leg: usize,
+    /// Set when the fleet stopped following the route, for example after an encounter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub halted: Option<String>,
+    #[serde(default)]
+    pub encounters: Vec<RouteEncounter>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RoutePlanData {
+    pub system_id: usize,
+    #[serde(default)]
+    pub planet_id: Option<usize>,
+    #[serde(default)]
+    pub objective: RouteObjective,
+}
+
+impl Route {
+    pub fn is_finished(&self) -> bool {
+        self.next_leg >= self.waypoints.len()
+    }
+
+    pub fn current_leg(&self) -> Option<&Waypoint> {
+        self.waypoints.get(self.next_leg)
+    }
+}
+
+/// Cost of one leg under an objective. Tiny distance terms break ties
+/// between otherwise equal routes in favour of the shorter one.
+fn leg_cost(objective: RouteObjective, distance: f64, ticks: u64, fuel: f64, danger: f64) -> f64 {
+    match objective {
+        RouteObjective::Distance => distance,
+        RouteObjective::Time => ticks as f64 + distance * 1e-6,
+        RouteObjective::Fuel => fuel,
+        RouteObjective::Danger => danger + distance * 1e-6,
+    }
+}
+
+/// Plans a route for a fleet to a system, or to a planet in it.
+///
+/// Systems are linked when they lie within the fleet's jump range of each
+/// other, and the cheapest chain of jumps under the objective wins. The
+/// fleet may start in deep space.
+///
+/// # Arguments
+/// * `fleet` - The fleet to plan for; its ships set speed, fuel use and jump range
+/// * `systems` - Every star system of the galaxy
+/// * `destination_id` - The system to reach
+/// * `planet_id` - A planet of the destination to end at, if any
+/// * `objective` - What to minimize
+///
+/// # Returns
+/// The route, or an error if the destination is unknown or out of reach
+pub fn plan_route(fleet: &Fleet, systems: &[StarSystem], destination_id: usize, planet_id: Option<usize>, objective: RouteObjective) -> Result<Route, String> {
+    let range = fleet_jump_range(fleet).ok_or_else(|| format!("Fleet {} has no ships", fleet.name))?;
+    let speed = fleet_speed(fleet).unwrap_or(1.0);
+    let fuel_per_distance = FUEL_PER_DISTANCE * fleet.ships.iter().map(|ship| fuel_factor(&ship.size)).sum::<f64>();
+    let target = systems.iter().position(|system| system.id == destination_id)
+        .ok_or_else(|| format!("Star system {} not found", destination_id))?;
+    let destination_planet = match planet_id {
+        Some(planet_id) => Some(systems[target].planets.get(planet_id)
+            .ok_or_else(|| format!("Planet {} not found in system {}", planet_id, destination_id))?),
+        None => None,
+    };
+
+    // Node `systems.len()` is the fleet's starting point
+    let start = systems.len();
+    let position_of = |node: usize| if node == start { fleet.position } else { systems[node].position };
+    let mut cost = vec![f64::INFINITY; systems.len() + 1];
+    let mut previous: Vec<Option<usize>> = vec![None; systems.len() + 1];
+    let mut done = vec![false; systems.len() + 1];
+    cost[start] = 0.0;
+    // Cells as wide as a jump, so each node's reachable systems lie in the 27 cells around it
+    let mut grid = PointGrid::new(range);
+    for (node, system) in systems.iter().enumerate() {
+        grid.insert(node, system.position);
+    }
+
+    while let Some(node) = (0..=systems.len())
+        .filter(|&node| !done[node] && cost[node].is_finite())
+        .min_by(|&a, &b| cost[a].total_cmp(&cost[b]))
+    {
+        done[node] = true;
+        if node == target {
+            break;
+        }
+        for (next, distance) in grid.within(&position_of(node), range) {
+            if done[next] {
+                continue;
+            }
+            let leg = leg_cost(objective, distance, travel_ticks(distance, speed), distance * fuel_per_distance, system_danger(&systems[next]));
+            if cost[node] + leg < cost[next] {
+                cost[next] = cost[node] + leg;
+                previous[next] = Some(node);
+            }
+        }
+    }
+    if !cost[target].is_finite() {
+        return Err(format!(
+            "{} is out of reach: no chain of jumps of at most {:.0} leads there",
+            systems[target].star.name, range
+        ));
+    }
+
+    let mut chain = vec![target];
+    while let Some(node) = previous[*chain.last().unwrap_or(&start)] {
+        chain.push(node);
+    }
+    chain.reverse();
+
+    let mut waypoints = Vec::new();
+    let mut from = fleet.position;
+    for node in chain.into_iter().filter(|&node| node != start) {
+        let system = &systems[node];
+        let distance = from.distance(&system.position);
+        let is_destination = node == target;
+        waypoints.push(Waypoint {
+            system_id: system.id,
+            star_name: system.star.name.clone(),
+            planet_id: if is_destination { planet_id } else { None },
+            position: system.position,
+            local_position: destination_planet.filter(|_| is_destination)
+                .map_or(Position { x: 0, y: 0, z: 0 }, |planet| planet.position),
+            distance,
+            ticks: travel_ticks(distance, speed),
+            fuel: (distance * fuel_per_distance).round() as u32,
+            danger: system_danger(system),
+        });
+        from = system.position;
+    }
+
+    Ok(Route {
+        objective,
+        jump_range: range,
+        total_distance: waypoints.iter().map(|waypoint| waypoint.distance).sum(),
+        total_ticks: waypoints.iter().map(|waypoint| waypoint.ticks).sum(),
+        total_fuel: waypoints.iter().map(|waypoint| waypoint.fuel).sum(),
+        total_danger: waypoints.iter().map(|waypoint| waypoint.danger).sum(),
+        waypoints,
+        next_leg: 0,
+        halted: None,
+        encounters: Vec::new(),
+    })
+}
+
+/// Sends a fleet on the next leg of its route.
+///
+/// # Returns
+/// The fleet to persist, in transit (or already arrived for legs without
+/// distance), or an error if the fleet cannot make the jump
+pub fn depart_on_route(fleet: &Fleet, now: u64) -> Result<Fleet, String> {
+    let route = fleet.route.as_ref().ok_or_else(|| format!("Fleet {} is not following a route", fleet.name))?;
+    let waypoint = route.current_leg().ok_or_else(|| format!("Fleet {} has finished its route", fleet.name))?;
+
+    let mut arrived = fleet.clone();
+    arrived.update_position(waypoint.position);
+    arrived.current_system_id = Some(waypoint.system_id);
+    arrived.local_position = Some(waypoint.local_position);
+    let trip = deep_space_trip(&fleet.position, &waypoint.position);
+    begin_transit(fleet.clone(), arrived, &trip, now)
+}
+
+/// Whether a fleet following a route has reached the end of its current leg.
+pub fn reached_waypoint(fleet: &Fleet) -> bool {
+    fleet.transit.is_none()
+        && fleet.route.as_ref()
+            .and_then(Route::current_leg)
+            .is_some_and(|waypoint| fleet.current_system_id == Some(waypoint.system_id) && fleet.position == waypoint.position)
+}
+
+/// Moves a fleet along its route by one tick.
+///
+/// A fleet that finished a leg sets off on the next one unless it met
+/// anyone there: `pending` are the fleet's encounters still waiting for a
+/// decision, rolled when it arrived. An encounter or a leg the fleet cannot
+/// fly halts the route until its owner resumes it.
+///
+/// # Returns
+/// A line for the tick log if anything happened
+pub fn advance_route(fleet: &mut Fleet, tick: u64, pending: &[&Encounter]) -> Option<String> {
+    if fleet.transit.is_some() {
+        return None;
+    }
+    let route = fleet.route.as_ref()?;
+    if route.halted.is_some() {
+        return None;
+    }
+    if !reached_waypoint(fleet) {
+        return Some(depart_or_halt(fleet, tick, pending));
+    }
+
+    let route = fleet.route.as_mut()?;
+    let leg = route.next_leg;
+    let waypoint = route.waypoints[leg].clone();
+    route.next_leg += 1;
+    if !pending.is_empty() {
+        route.encounters.extend(pending.iter().map(|encounter| RouteEncounter {
+            leg,
+            tick,
+            system_id: waypoint.system_id,
+            encounter_id: encounter.id.clone(),
+            kind: encounter.kind,
+            fleet_name: encounter.hostile_fleet.clone(),
+        }));
+        let met: Vec<String> = pending.iter()
+            .map(|encounter| format!("{} ({})", encounter.hostile_fleet, encounter.kind.name()))
+            .collect();
+        let message = format!("Encountered {} at {}", met.join(", "), waypoint.star_name);
+        route.halted = Some(message.clone());
+        return Some(format!("Tick {}: {} halted its route. {}", tick, fleet.name, message));
+    }
+    if route.is_finished() {
+        fleet.route = None;
+        return Some(format!("Tick {}: {} reached the end of its route at {}", tick, fleet.name, waypoint.star_name));
+    }
+    Some(depart_or_halt(fleet, tick, pending))
+}
+
+/// Departs on the current leg, or halts the route with the reason it cannot.
+fn depart_or_halt(fleet: &mut Fleet, tick: u64, pending: &[&Encounter]) -> String {
+    let star_name = fleet.route.as_ref()
+        .and_then(Route::current_leg)
+        .map_or_else(String::new, |waypoint| waypoint.star_name.clone());
+    let departed = if pending.is_empty() {
+        depart_on_route(fleet, tick)
+    } else {
+        Err(format!("{} encounters are waiting for a decision", pending.len()))
+    };
+    match departed {
+        Ok(departed) => {
+            *fleet = departed;
+            format!("Tick {}: {} set off for {}", tick, fleet.name, star_name)
+        }
+        Err(e) => {
+            if let Some(route) = fleet.route.as_mut() {
+                route.halted = Some(e.clone());
+            }
+            format!("Tick {}: {} halted its route: {}", tick, fleet.name, e)
+        }
+    }
+}
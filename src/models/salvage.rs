@@ -0,0 +1,257 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::combat::combat::CombatResult;
+use crate::models::cargo::{CargoDistribution, CargoReceipt, FillPolicy};
+use crate::models::encounter::is_npc_fleet;
+use crate::models::fleet::Fleet;
+use crate::models::game_state::current_game_id;
+use crate::models::market::calculate_ship_price;
+use crate::models::position::Position;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::rng::game_uuid;
+use crate::models::ship::ship::{CombatState, Ship};
+use crate::models::storage::{load_document, save_document};
+
+/// Share of a ship's market price its hull fetches when scrapped.
+pub const SCRAP_VALUE_SHARE: f64 = 0.15;
+/// Ticks a salvage field drifts before the wreckage scatters.
+pub const SALVAGE_FIELD_LIFETIME: u64 = 48;
+
+/// What a fight left behind, for the fleet that held the field to pick over.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SalvageField {
+    pub id: String,
+    /// Owner of the winning fleet; only they may loot the field
+    pub claimant: String,
+    pub position: Position,
+    pub system_id: Option<usize>,
+    /// Tick of the fight
+    pub tick: u64,
+    /// Cargo spilled from destroyed ships or thrown overboard
+    pub cargo: Vec<Resource>,
+    /// Hulls of destroyed ships, only worth their scrap value
+    pub wrecks: Vec<Ship>,
+    /// Ships the loser left disabled, with their armor stripped and their
+    /// holds still full; they can be captured
+    pub derelicts: Vec<Ship>,
+}
+
+impl SalvageField {
+    pub fn is_empty(&self) -> bool {
+        self.cargo.is_empty() && self.wrecks.is_empty() && self.derelicts.is_empty()
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.tick + SALVAGE_FIELD_LIFETIME
+    }
+
+    /// Units of a resource floating in the field.
+    pub fn cargo_quantity(&self, resource_type: ResourceType) -> u32 {
+        self.cargo.iter()
+            .filter(|resource| resource.resource_type == resource_type)
+            .map(|resource| resource.quantity.unwrap_or(0))
+            .sum()
+    }
+
+    /// One line on what the field holds, for combat reports.
+    pub fn summary(&self) -> String {
+        let units: u32 = self.cargo.iter().map(|resource| resource.quantity.unwrap_or(0)).sum();
+        format!("Salvage left for {}: {} units of cargo, {} wrecks, {} disabled ships",
+            self.claimant, units, self.wrecks.len(), self.derelicts.len())
+    }
+
+    /// Checks that `fleet` may work the field: it belongs to the claimant,
+    /// has ships, and sits where the fight happened.
+    pub fn check_reach(&self, fleet: &Fleet, now: u64) -> Result<(), String> {
+        if self.is_expired(now) {
+            return Err("The wreckage has scattered".to_string());
+        }
+        if fleet.owner_id != self.claimant {
+            return Err(format!("The salvage belongs to {}", self.claimant));
+        }
+        if fleet.ships.is_empty() {
+            return Err(format!("{} has no ships", fleet.name));
+        }
+        if fleet.transit.is_some() || fleet.position != self.position || fleet.current_system_id != self.system_id {
+            return Err(format!("{} must be at the salvage field", fleet.name));
+        }
+        Ok(())
+    }
+}
+
+/// Every salvage field of a game, stored as `salvage.json` in the game directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SalvageLog {
+    pub fields: Vec<SalvageField>,
+}
+
+impl SalvageLog {
+    pub fn load() -> Result<SalvageLog, String> {
+        Ok(load_document(&current_game_id()?, &["salvage.json"])?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_document(&current_game_id()?, &["salvage.json"], self)
+    }
+
+    /// Adds a field, dropping those that have scattered or been picked clean.
+    pub fn record(&mut self, field: SalvageField, now: u64) {
+        self.fields.retain(|field| !field.is_expired(now) && !field.is_empty());
+        self.fields.push(field);
+    }
+
+    /// Fields a player may still loot.
+    pub fn for_claimant(&self, claimant: &str, now: u64) -> Vec<&SalvageField> {
+        self.fields.iter().filter(|field| field.claimant == claimant && !field.is_expired(now)).collect()
+    }
+
+    pub fn get_mut(&mut self, field_id: &str) -> Result<&mut SalvageField, String> {
+        self.fields.iter_mut()
+            .find(|field| field.id == field_id)
+            .ok_or_else(|| format!("Salvage field {} not found", field_id))
+    }
+}
+
+/// Credits a ship's hull and fittings fetch as scrap.
+pub fn scrap_value(ship: &Ship) -> f64 {
+    (calculate_ship_price(ship) * SCRAP_VALUE_SHARE).round()
+}
+
+/// Adds `quantity` units to a list of resources, one entry per type.
+fn add_resource(resources: &mut Vec<Resource>, resource_type: ResourceType, quantity: u32) {
+    if quantity == 0 {
+        return;
+    }
+    match resources.iter_mut().find(|resource| resource.resource_type == resource_type) {
+        Some(resource) => resource.quantity = Some(resource.quantity.unwrap_or(0) + quantity),
+        None => resources.push(Resource::new(resource_type, quantity)),
+    }
+}
+
+/// Gathers what a fight left behind into a salvage field claimed by the
+/// winner: the cargo of every destroyed ship and anything jettisoned, the
+/// wrecks themselves, and the loser's disabled ships. Only a player's
+/// fleet with ships left boards those, and only if the loser stayed to the
+/// end; a fleet that retreated tows its disabled ships away.
+///
+/// # Arguments
+/// * `result` - The fight, with its losses and jettisoned cargo
+/// * `attacker` - The attacking fleet as it came out of the fight
+/// * `defender` - The defending fleet as it came out of the fight
+/// * `now` - Current game tick
+/// * `rng` - Random number generator the field's id is drawn from
+///
+/// # Returns
+/// The field, or `None` if the fight left nothing worth having
+pub fn collect_salvage<R: Rng + ?Sized>(result: &CombatResult, attacker: &mut Fleet, defender: &mut Fleet, now: u64, rng: &mut R) -> Option<SalvageField> {
+    let (winner, loser) = if result.attacker_victory { (attacker, defender) } else { (defender, attacker) };
+    let loser_retreated = if result.attacker_victory { result.defender_retreated } else { result.attacker_retreated };
+
+    let mut cargo = Vec::new();
+    for resource in &result.jettisoned {
+        add_resource(&mut cargo, resource.resource_type, resource.quantity.unwrap_or(0));
+    }
+    let mut wrecks = Vec::new();
+    for ship in result.attacker_losses.iter().chain(&result.defender_losses) {
+        let mut wreck = ship.clone();
+        for resource in wreck.cargo.drain(..) {
+            add_resource(&mut cargo, resource.resource_type, resource.quantity.unwrap_or(0));
+        }
+        wrecks.push(wreck);
+    }
+
+    let mut derelicts = Vec::new();
+    if !winner.ships.is_empty() && !is_npc_fleet(winner) && !loser_retreated {
+        let (disabled, operational): (Vec<Ship>, Vec<Ship>) = loser.ships.drain(..).partition(Ship::is_disabled);
+        loser.ships = operational;
+        derelicts = disabled;
+    }
+
+    let field = SalvageField {
+        id: game_uuid(rng),
+        claimant: winner.owner_id.clone(),
+        position: winner.position,
+        system_id: winner.current_system_id,
+        tick: now,
+        cargo,
+        wrecks,
+        derelicts,
+    };
+    if field.is_empty() { None } else { Some(field) }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LootData {
+    pub fleet_number: usize,
+    pub resource_type: ResourceType,
+    /// Units to take; everything in the field when missing
+    #[serde(default)]
+    pub quantity: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CaptureData {
+    pub fleet_number: usize,
+    /// Index of the ship among the field's derelicts
+    pub ship_index: usize,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScrapData {
+    pub fleet_number: usize,
+    pub ship_index: usize,
+    /// Scrap one of the derelicts rather than one of the wrecks
+    #[serde(default)]
+    pub derelict: bool,
+}
+
+/// Hauls floating cargo into a fleet's holds, as much as fits.
+pub fn loot_cargo(field: &mut SalvageField, fleet: &mut Fleet, resource_type: ResourceType, quantity: Option<u32>) -> Result<CargoReceipt, String> {
+    let available = field.cargo_quantity(resource_type);
+    if available == 0 {
+        return Err(format!("There is no {} in the salvage field", resource_type));
+    }
+    let quantity = quantity.unwrap_or(available).min(available);
+    let receipt = fleet.load_cargo(resource_type, quantity, &CargoDistribution::First, FillPolicy::Partial)?;
+
+    if let Some(resource) = field.cargo.iter_mut().find(|resource| resource.resource_type == resource_type) {
+        resource.quantity = Some(available - receipt.moved);
+    }
+    field.cargo.retain(|resource| resource.quantity.unwrap_or(0) > 0);
+    Ok(receipt)
+}
+
+/// Boards a derelict and adds it, with whatever is in its hold, to the fleet.
+///
+/// # Returns
+/// The name of the captured ship
+pub fn capture_ship(field: &mut SalvageField, fleet: &mut Fleet, ship_index: usize) -> Result<String, String> {
+    if ship_index >= field.derelicts.len() {
+        return Err(format!("Invalid ship index: {} (field has {} derelicts)", ship_index, field.derelicts.len()));
+    }
+    let mut ship = field.derelicts.remove(ship_index);
+    ship.owner = fleet.owner_id.clone();
+    ship.position = fleet.position;
+    ship.combat_state = CombatState::NotInCombat;
+    ship.price = None;
+    let name = ship.name.clone();
+    fleet.ships.push(ship);
+    Ok(name)
+}
+
+/// Breaks up a wreck or a derelict for its scrap value. Cargo still aboard
+/// a derelict spills into the field.
+///
+/// # Returns
+/// The name of the scrapped ship and the credits it fetched
+pub fn scrap_ship(field: &mut SalvageField, ship_index: usize, derelict: bool) -> Result<(String, f64), String> {
+    let ships = if derelict { &mut field.derelicts } else { &mut field.wrecks };
+    if ship_index >= ships.len() {
+        return Err(format!("Invalid ship index: {} (field has {} {})", ship_index, ships.len(), if derelict { "derelicts" } else { "wrecks" }));
+    }
+    let mut ship = ships.remove(ship_index);
+    for resource in ship.cargo.drain(..) {
+        add_resource(&mut field.cargo, resource.resource_type, resource.quantity.unwrap_or(0));
+    }
+    Ok((ship.name.clone(), scrap_value(&ship)))
+}
@@ -1,13 +1,14 @@
 use serde::{Deserialize, Serialize};
+use crate::models::persistence::write_json_atomic;
 use std::fs;
 use std::io;
 use chrono::Utc;
 use rocket::form::FromForm;
-use crate::models::game_state::game_path;
-use std::path::Path;
-use std::fs::File;
-use std::io::Read;
+use crate::models::game_state::{current_game_id, data_root};
+use crate::models::storage::{StorageBackend, storage_for};
 use uuid;
+use crate::models::rng::random_seed;
+use crate::models::migration::CURRENT_SCHEMA_VERSION;
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromForm)]
 pub struct GameSettings {
@@ -23,6 +24,19 @@ pub struct GameSettings {
     pub created_at: String,
     pub last_played: String,
     pub factions: Vec<FactionSettings>,
+    /// Seed for all world generation and simulation randomness in this game
+    #[serde(default = "random_seed")]
+    #[field(default = random_seed())]
+    pub seed: u64,
+    /// Layout version of this game's save files; games saved before
+    /// versioning was introduced read as 0 and are migrated on load
+    #[serde(default)]
+    #[field(default = 0)]
+    pub schema_version: u32,
+    /// Backend the game's saves are kept in, chosen when the game is created
+    #[serde(default)]
+    #[field(default = StorageBackend::Json)]
+    pub storage: StorageBackend,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromForm)]
@@ -54,6 +68,9 @@ impl Default for GameSettings {
                 FactionSettings { name: "Republic".to_string(), influence: 50 },
                 FactionSettings { name: "Alliance".to_string(), influence: 50 },
             ],
+            seed: random_seed(),
+            schema_version: CURRENT_SCHEMA_VERSION,
+            storage: StorageBackend::Json,
         }
     }
 }
@@ -78,29 +95,25 @@ impl GameSettings {
                 FactionSettings { name: "Republic".to_string(), influence: 50 },
                 FactionSettings { name: "Alliance".to_string(), influence: 50 },
             ],
+            seed: random_seed(),
+            schema_version: CURRENT_SCHEMA_VERSION,
+            storage: StorageBackend::Json,
         }
     }
 
     pub fn save(&self) -> io::Result<()> {
-        let settings_path = game_path(&["settings.json"]);
-        let file = fs::File::create(settings_path)?;
-        serde_json::to_writer(file, self)?;
-        Ok(())
+        storage_for(&self.game_id)
+            .and_then(|storage| storage.save_settings(self))
+            .map_err(io::Error::other)
     }
 
     pub fn load() -> io::Result<Self> {
-        let settings_path = game_path(&["settings.json"]);
-        if !settings_path.exists() {
-            return Err(io::Error::new(io::ErrorKind::NotFound, "No settings file found"));
-        }
-
-        let file = fs::File::open(settings_path)?;
-        let settings: GameSettings = serde_json::from_reader(file)?;
-        Ok(settings)
+        let game_id = current_game_id().map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))?;
+        load_settings_for_game(&game_id)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavedGame {
     pub game_id: String,
     pub display_name: String,
@@ -111,17 +124,15 @@ pub struct SavedGame {
 
 impl SavedGame {
     pub fn save_game(&self) -> std::io::Result<()> {
-        let saves_dir = Path::new("data").join("saves");
+        let saves_dir = data_root().join("saves");
         fs::create_dir_all(&saves_dir)?;
         
         let save_file = saves_dir.join(format!("{}.json", self.game_id));
-        let file = fs::File::create(save_file)?;
-        serde_json::to_writer(file, self)?;
-        Ok(())
+        write_json_atomic(&save_file, self)
     }
 
     pub fn load_game(game_id: &str) -> std::io::Result<Option<Self>> {
-        let save_file = Path::new("data").join("saves").join(format!("{}.json", game_id));
+        let save_file = data_root().join("saves").join(format!("{}.json", game_id));
         if !save_file.exists() {
             return Ok(None);
         }
@@ -132,7 +143,7 @@ impl SavedGame {
     }
 
     pub fn list_saved_games() -> std::io::Result<Vec<SavedGame>> {
-        let saves_dir = Path::new("data").join("saves");
+        let saves_dir = data_root().join("saves");
         if !saves_dir.exists() {
             return Ok(Vec::new());
         }
@@ -151,7 +162,7 @@ impl SavedGame {
     }
 
     pub fn load_current_game() -> std::io::Result<Option<Self>> {
-        let saves_dir = Path::new("data").join("saves");
+        let saves_dir = data_root().join("saves");
         if !saves_dir.exists() {
             return Ok(None);
         }
@@ -175,36 +186,13 @@ impl SavedGame {
     }
 }
 
-/// Loads game settings from the settings.json file.
+/// Loads the settings of the active game from its storage.
 /// 
 /// # Returns
 /// A Result containing either the loaded settings or an error
 pub fn load_settings() -> Result<GameSettings, std::io::Error> {
-    let state = crate::models::game_state::get_game_state()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-    
-    let game_id = state.current_game_id.ok_or_else(|| {
-        std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No active game. Please create or load a game first."
-        )
-    })?;
-    
-    let settings_path = Path::new("data").join("game").join(&game_id).join("settings.json");
-    
-    if !settings_path.exists() {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("No settings found for game {}", game_id)
-        ));
-    }
-
-    let mut file = File::open(settings_path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    
-    let settings: GameSettings = serde_json::from_str(&contents)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let game_id = current_game_id().map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+    let settings = load_settings_for_game(&game_id)?;
     println!("Successfully loaded settings for game {}", game_id);
     Ok(settings)
 }
@@ -217,23 +205,13 @@ pub fn load_settings() -> Result<GameSettings, std::io::Error> {
 /// # Returns
 /// A Result containing either the loaded settings or an error
 pub fn load_settings_for_game(game_id: &str) -> Result<GameSettings, std::io::Error> {
-    let settings_path = Path::new("data").join("game").join(game_id).join("settings.json");
-    
-    if !settings_path.exists() {
-        return Err(std::io::Error::new(
+    storage_for(game_id)
+        .and_then(|storage| storage.load_settings())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        .ok_or_else(|| std::io::Error::new(
             std::io::ErrorKind::NotFound,
             format!("No settings found for game {}", game_id)
-        ));
-    }
-
-    let mut file = File::open(settings_path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    
-    let settings: GameSettings = serde_json::from_str(&contents)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-    println!("Successfully loaded settings for game {}", game_id);
-    Ok(settings)
+        ))
 }
 
 /// Saves the current game settings to the settings.json file.
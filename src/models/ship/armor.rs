@@ -19,6 +19,8 @@ impl Armor {
         }
     }
 
+    /// Soaks up to 75% of the incoming damage (limited by the remaining armor)
+    /// and returns the damage that reaches the hull.
     pub fn calculate_damage(&mut self, damage: i32) -> i32 {
         if PRINT_DEBUG {
             println!(
@@ -29,15 +31,24 @@ impl Armor {
         }
 
         let reduction_percentage = 0.75; // Armor reduces damage by 75%
-        let reduction = (reduction_percentage / 100.0) * self.current as f32;
-        let reduced_damage = (damage as f32 - reduction).max(0.0) as i32;
-        
-        // Reduce armor current
-        self.current = (self.current - damage).max(0);
-        
-        // Regenerate armor
+        let absorbed = ((damage.max(0) as f32 * reduction_percentage).round() as i32).min(self.current.max(0));
+        self.current -= absorbed;
+
+        damage - absorbed
+    }
+
+    /// Changes the capacity, keeping the regen rate in proportion. Plating
+    /// that is added arrives intact; plating that is removed takes its share
+    /// of the remaining armor with it.
+    pub fn resize(&mut self, capacity: i32) {
+        let capacity = capacity.max(0);
+        self.current = (self.current + capacity - self.capacity).clamp(0, capacity);
+        self.capacity = capacity;
+        self.regen = self.capacity / 20;
+    }
+
+    /// Restores `regen` points of armor, capped at capacity.
+    pub fn regenerate(&mut self) {
         self.current = (self.current + self.regen).min(self.capacity);
-        
-        reduced_damage
     }
 }
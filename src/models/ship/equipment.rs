@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+use super::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use super::weapon::Weapon;
+
+/// The kinds of mounting point a ship offers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Slot {
+    /// Weapon mounts
+    Hardpoint,
+    /// Internal slots for shield generators and armor plating
+    Utility,
+    /// The single engine bay
+    Engine,
+}
+
+/// A component that can be bought, installed on a ship and removed again.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Equipment {
+    Weapon(Weapon),
+    /// Adds `capacity` to the ship's shields
+    ShieldGenerator { capacity: i32 },
+    /// Adds `capacity` to the ship's armor
+    ArmorPlating { capacity: i32 },
+    Engine(ShipEngine),
+}
+
+impl Equipment {
+    pub fn slot(&self) -> Slot {
+        match self {
+            Equipment::Weapon(_) => Slot::Hardpoint,
+            Equipment::ShieldGenerator { .. } | Equipment::ArmorPlating { .. } => Slot::Utility,
+            Equipment::Engine(_) => Slot::Engine,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Equipment::Weapon(weapon) => weapon.name().to_string(),
+            Equipment::ShieldGenerator { capacity } => format!("Shield Generator ({})", capacity),
+            Equipment::ArmorPlating { capacity } => format!("Armor Plating ({})", capacity),
+            Equipment::Engine(engine) => format!("{:?} Engine", engine),
+        }
+    }
+
+    /// List price of the component before any planet markup.
+    pub fn base_price(&self) -> f64 {
+        match self {
+            Equipment::Weapon(weapon) => weapon.damage() as f64 * 100.0,
+            Equipment::ShieldGenerator { capacity } => *capacity as f64 * 20.0,
+            Equipment::ArmorPlating { capacity } => *capacity as f64 * 15.0,
+            Equipment::Engine(ShipEngine::Basic) => 2000.0,
+            Equipment::Engine(ShipEngine::Advanced) => 6000.0,
+            Equipment::Engine(ShipEngine::Experimental) => 15000.0,
+        }
+    }
+
+    /// Ticks a ship spends in the yard while this component is fitted or removed.
+    pub fn downtime_ticks(&self) -> u64 {
+        match self.slot() {
+            Slot::Hardpoint => 1,
+            Slot::Utility => 2,
+            Slot::Engine => 3,
+        }
+    }
+}
+
+/// Weapon mounts of a ship: warships carry more, and bigger hulls add room.
+pub fn hardpoints(specialization: &ShipType, size: &ShipSize) -> usize {
+    let base = match specialization {
+        ShipType::Fighter => 2,
+        ShipType::Battleship => 5,
+        ShipType::Freighter => 2,
+        ShipType::Explorer => 3,
+        ShipType::Shuttle => 2,
+        ShipType::Capital => 6,
+    };
+    let size_bonus = match size {
+        ShipSize::Tiny | ShipSize::Small => 0,
+        ShipSize::Medium => 1,
+        ShipSize::Large => 2,
+        ShipSize::Huge => 3,
+        ShipSize::Planetary => 4,
+    };
+    base + size_bonus
+}
+
+/// Utility slots of a ship, set by its hull size.
+pub fn utility_slots(size: &ShipSize) -> usize {
+    match size {
+        ShipSize::Tiny => 1,
+        ShipSize::Small => 2,
+        ShipSize::Medium => 3,
+        ShipSize::Large => 4,
+        ShipSize::Huge => 5,
+        ShipSize::Planetary => 6,
+    }
+}
+
+/// How many slots of each kind a ship has and how many are in use.
+#[derive(Serialize, Debug, Clone)]
+pub struct SlotUsage {
+    pub hardpoints: usize,
+    pub hardpoints_used: usize,
+    pub utility_slots: usize,
+    pub utility_used: usize,
+}
+
+impl Ship {
+    pub fn slot_usage(&self) -> SlotUsage {
+        SlotUsage {
+            hardpoints: hardpoints(&self.specialization, &self.size),
+            hardpoints_used: self.weapons.len(),
+            utility_slots: utility_slots(&self.size),
+            utility_used: self.modules.len(),
+        }
+    }
+
+    /// Whether the ship is still in the yard at `tick`.
+    pub fn is_refitting(&self, tick: u64) -> bool {
+        self.refit_until.is_some_and(|until| tick < until)
+    }
+
+    /// Combined list value of the weapons and modules fitted to the ship.
+    pub fn equipment_value(&self) -> f64 {
+        let weapons: f64 = self.weapons.iter().map(|weapon| Equipment::Weapon(weapon.clone()).base_price()).sum();
+        let modules: f64 = self.modules.iter().map(Equipment::base_price).sum();
+        weapons + modules
+    }
+}
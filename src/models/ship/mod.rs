@@ -3,9 +3,11 @@ pub mod armor;
 pub mod weapon;
 pub mod shield;
 pub mod status;
+pub mod equipment;
 
 pub use ship::Ship;
 pub use armor::Armor;
 pub use weapon::Weapon;
 pub use shield::Shield;
+pub use equipment::Equipment;
 pub use status::{ShipStatus, CombatState};
\ No newline at end of file
@@ -17,22 +17,30 @@ impl Shield {
         }
     }
 
+    /// Absorbs as much of the incoming damage as the shield can hold and
+    /// returns the damage that bleeds through to the armor.
     pub fn calculate_damage(&mut self, damage: i32) -> i32 {
         if PRINT_DEBUG {
             println!("Calculating damage with shield current: {}", self.current);
             println!("Incoming damage: {}", damage);
         }
-        
-        let reduction_percentage = 1.00;
-        let reduction = (reduction_percentage / 100.0) * self.current as f32;
-        let reduced_damage = (damage as f32 - reduction).max(0.0) as i32;
-        
-        // Reduce shield current
-        self.current = (self.current - damage).max(0);
-        
-        // Regenerate shields
+
+        let absorbed = damage.clamp(0, self.current.max(0));
+        self.current -= absorbed;
+
+        damage - absorbed
+    }
+
+    /// Changes the capacity, keeping the regen rate in proportion. Charge
+    /// above the new capacity is lost.
+    pub fn resize(&mut self, capacity: i32) {
+        self.capacity = capacity.max(0);
+        self.current = self.current.min(self.capacity);
+        self.regen = self.capacity / 10;
+    }
+
+    /// Restores `regen` points of shield, capped at capacity.
+    pub fn regenerate(&mut self) {
         self.current = (self.current + self.regen).min(self.capacity);
-        
-        reduced_damage
     }
 }
@@ -9,6 +9,7 @@ use rand::distributions::{Distribution, Standard};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use super::equipment::Equipment;
 use super::weapon::Weapon;
 
 /// Represents a ship in the game with various attributes and capabilities.
@@ -45,6 +46,40 @@ pub struct Ship {
     pub armor: Armor,
     /// The current market price of the ship (if available for sale)
     pub price: Option<f64>,
+    /// Shield generators and armor plating fitted to the ship's utility slots
+    #[serde(default)]
+    pub modules: Vec<Equipment>,
+    /// Tick at which outfitting work on the ship is finished; its fleet
+    /// cannot leave before then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refit_until: Option<u64>,
+}
+
+/// Fraction of its maximum hull below which a ship counts as badly damaged.
+/// Badly damaged ships fly and fight worse until they are repaired.
+pub const DAMAGED_HULL_THRESHOLD: f64 = 0.5;
+
+/// Hull points of an undamaged ship of the given type and size.
+pub fn max_hull(specialization: &ShipType, size: &ShipSize) -> i32 {
+    let base_hp = match specialization {
+        ShipType::Fighter => 50,
+        ShipType::Battleship => 200,
+        ShipType::Freighter => 100,
+        ShipType::Explorer => 150,
+        ShipType::Shuttle => 30,
+        ShipType::Capital => 300,
+    };
+
+    let size_multiplier = match size {
+        ShipSize::Tiny => 0.5,
+        ShipSize::Small => 0.75,
+        ShipSize::Medium => 1.0,
+        ShipSize::Large => 1.5,
+        ShipSize::Huge => 2.0,
+        ShipSize::Planetary => 3.0,
+    };
+
+    (base_hp as f32 * size_multiplier) as i32
 }
 
 /// Represents the current operational status of a ship
@@ -69,7 +104,7 @@ pub enum ShipStatus {
 }
 
 /// Represents the current combat stance of a ship
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum CombatState {
     /// Ship is not engaged in combat
     NotInCombat,
@@ -249,9 +284,8 @@ fn generate_ship_weapons(specialization: &ShipType) -> Vec<Weapon> {
     weapons
 }
 
-fn generate_ship_resources(specialization: &ShipType) -> Vec<Resource> {
+fn generate_ship_resources<R: Rng + ?Sized>(specialization: &ShipType, rng: &mut R) -> Vec<Resource> {
     let mut resources = Vec::new();
-    let mut rng = rand::thread_rng();
 
     // All ships get some fuel
     let fuel_amount = match specialization {
@@ -355,8 +389,7 @@ impl Distribution<ShipStatus> for Standard {
     }
 }
 
-fn generate_ship_name() -> String {
-    let mut rng = rand::thread_rng();
+fn generate_ship_name<R: Rng + ?Sized>(rng: &mut R) -> String {
     let prefixes = [
         "Alpha",
         "Beta",
@@ -468,7 +501,7 @@ fn generate_ship_name() -> String {
     name
 }
 
-pub fn generate_owner_name() -> String {
+pub fn generate_owner_name<R: Rng + ?Sized>(rng: &mut R) -> String {
     let prefixes = vec![
         "Star", "Nova", "Galactic", "Cosmic", "Interstellar", "Astro", "Space", "Stellar",
         "Celestial", "Lunar", "Solar", "Nebula", "Orion", "Andromeda", "Proxima", "Voyager",
@@ -519,10 +552,10 @@ pub fn generate_owner_name() -> String {
         "the Voidweaver", "the Starweaver", "the Voidweaver", "the Starweaver", "the Voidweaver"
     ];
 
-    let prefix = prefixes[rand::random::<usize>() % prefixes.len()];
-    let first_name = first_names[rand::random::<usize>() % first_names.len()];
-    let last_name = last_names[rand::random::<usize>() % last_names.len()];
-    let suffix = suffixes[rand::random::<usize>() % suffixes.len()];
+    let prefix = prefixes[rng.gen_range(0..prefixes.len())];
+    let first_name = first_names[rng.gen_range(0..first_names.len())];
+    let last_name = last_names[rng.gen_range(0..last_names.len())];
+    let suffix = suffixes[rng.gen_range(0..suffixes.len())];
 
     format!("{} {} {} {}", prefix, first_name, last_name, suffix)
 }
@@ -560,31 +593,13 @@ impl Distribution<Ship> for Standard {
         };
 
         // Calculate base stats based on ship type and size
-        let base_hp = match specialization {
-            ShipType::Fighter => 50,
-            ShipType::Battleship => 200,
-            ShipType::Freighter => 100,
-            ShipType::Explorer => 150,
-            ShipType::Shuttle => 30,
-            ShipType::Capital => 300,
-        };
-
-        let size_multiplier = match size {
-            ShipSize::Tiny => 0.5,
-            ShipSize::Small => 0.75,
-            ShipSize::Medium => 1.0,
-            ShipSize::Large => 1.5,
-            ShipSize::Huge => 2.0,
-            ShipSize::Planetary => 3.0,
-        };
-
-        let hp = (base_hp as f32 * size_multiplier) as i32;
+        let hp = max_hull(&specialization, &size);
         let shield_capacity = (hp as f32 * 1.5) as i32;
         let armor_capacity = (hp as f32 * 2.0) as i32;
 
         // Generate weapons and cargo based on ship type
         let weapons = generate_ship_weapons(&specialization);
-        let cargo = generate_ship_resources(&specialization);
+        let cargo = generate_ship_resources(&specialization, rng);
 
         Ship {
             name,
@@ -601,6 +616,8 @@ impl Distribution<Ship> for Standard {
             shields: Shield::new(shield_capacity),
             armor: Armor::new(armor_capacity),
             price: None,
+            modules: Vec::new(),
+            refit_until: None,
         }
     }
 }
@@ -616,35 +633,32 @@ impl Ship {
     /// # Returns
     /// A new `Ship` instance with default values for other fields
     pub fn new(specialization: ShipType, size: ShipSize, engine: ShipEngine) -> Self {
-        // Calculate base stats based on ship type and size
-        let base_hp = match specialization {
-            ShipType::Fighter => 50,
-            ShipType::Battleship => 200,
-            ShipType::Freighter => 100,
-            ShipType::Explorer => 150,
-            ShipType::Shuttle => 30,
-            ShipType::Capital => 300,
-        };
-
-        let size_multiplier = match size {
-            ShipSize::Tiny => 0.5,
-            ShipSize::Small => 0.75,
-            ShipSize::Medium => 1.0,
-            ShipSize::Large => 1.5,
-            ShipSize::Huge => 2.0,
-            ShipSize::Planetary => 3.0,
-        };
+        Self::new_with_rng(specialization, size, engine, &mut rand::thread_rng())
+    }
 
-        let hp = (base_hp as f32 * size_multiplier) as i32;
+    /// Creates a new ship like [`Ship::new`], drawing its name and starting
+    /// cargo from the given random number generator.
+    /// 
+    /// # Arguments
+    /// * `specialization` - The type/role of the ship
+    /// * `size` - The physical size of the ship
+    /// * `engine` - The type of engine installed
+    /// * `rng` - The random number generator to draw from
+    /// 
+    /// # Returns
+    /// A new `Ship` instance with default values for other fields
+    pub fn new_with_rng<R: Rng + ?Sized>(specialization: ShipType, size: ShipSize, engine: ShipEngine, rng: &mut R) -> Self {
+        // Calculate base stats based on ship type and size
+        let hp = max_hull(&specialization, &size);
         let shield_capacity = (hp as f32 * 1.5) as i32;
         let armor_capacity = (hp as f32 * 2.0) as i32;
 
         // Generate weapons and cargo based on ship type
         let weapons = generate_ship_weapons(&specialization);
-        let cargo = generate_ship_resources(&specialization);
+        let cargo = generate_ship_resources(&specialization, rng);
 
         Ship {
-            name: format!("Ship_{}", rand::random::<u32>()),
+            name: format!("Ship_{}", rng.gen_range(0..u32::MAX)),
             owner: String::new(), // Will be set by fleet
             position: Position { x: 0, y: 0, z: 0 }, // Will be set by fleet
             status: ShipStatus::Stationary,
@@ -658,6 +672,8 @@ impl Ship {
             shields: Shield::new(shield_capacity),
             armor: Armor::new(armor_capacity),
             price: None,
+            modules: Vec::new(),
+            refit_until: None,
         }
     }
 
@@ -676,6 +692,34 @@ impl Ship {
         }
     }
 
+    /// Returns the hull points of the ship when undamaged.
+    pub fn max_hp(&self) -> i32 {
+        max_hull(&self.specialization, &self.size)
+    }
+
+    /// Returns the remaining hull as a fraction of the maximum, from 0.0 to 1.0.
+    pub fn hull_fraction(&self) -> f64 {
+        (self.hp.max(0) as f64 / self.max_hp().max(1) as f64).min(1.0)
+    }
+
+    /// Whether the hull is below `DAMAGED_HULL_THRESHOLD`.
+    pub fn is_badly_damaged(&self) -> bool {
+        self.hull_fraction() < DAMAGED_HULL_THRESHOLD
+    }
+
+    /// Whether the ship is still in one piece but has had its armor stripped
+    /// away, leaving it dead in space for anyone to board.
+    pub fn is_disabled(&self) -> bool {
+        self.hp > 0 && self.armor.capacity > 0 && self.armor.current <= 0
+    }
+
+    /// Whether any of hull, armor or shields is below its maximum.
+    pub fn needs_repair(&self) -> bool {
+        self.hp < self.max_hp()
+            || self.armor.current < self.armor.capacity
+            || self.shields.current < self.shields.capacity
+    }
+
     /// Returns the current amount of cargo being carried by the ship.
     /// 
     /// # Returns
@@ -19,4 +19,14 @@ impl Weapon {
             Weapon::MagneticResonanceDisruptor { damage } => *damage,
         }
     }
-}
\ No newline at end of file
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Weapon::PhotonSingularityBeam { .. } => "Photon Singularity Beam",
+            Weapon::QuantumEntanglementTorpedo { .. } => "Quantum Entanglement Torpedo",
+            Weapon::NeutronBeam { .. } => "Neutron Beam",
+            Weapon::GravitonPulse { .. } => "Graviton Pulse",
+            Weapon::MagneticResonanceDisruptor { .. } => "Magnetic Resonance Disruptor",
+        }
+    }
+}
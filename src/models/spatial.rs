@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use serde::Serialize;
+use crate::models::context::GameContext;
+use crate::models::fleet::{Fleet, list_all_fleets};
+use crate::models::game_world::load_game_world;
+use crate::models::position::Position;
+use crate::models::star_system::StarSystem;
+
+/// Edge length of a grid cell for the galaxy index.
+pub const GALAXY_CELL_SIZE: f64 = 250.0;
+/// Edge length of a grid cell for the planets of a system.
+pub const PLANET_CELL_SIZE: f64 = 100.0;
+
+/// Nearest-point searches give up beyond this distance.
+const MAX_SEARCH_RADIUS: f64 = 1e9;
+
+type Cell = (i64, i64, i64);
+
+/// A uniform grid over `Position`s.
+///
+/// Points are bucketed into cubic cells, so radius and segment queries only
+/// look at the cells they overlap instead of every point.
+#[derive(Debug, Clone)]
+pub struct PointGrid<K> {
+    cell_size: f64,
+    cells: HashMap<Cell, Vec<(K, Position)>>,
+    positions: HashMap<K, Position>,
+}
+
+/// A point found near a segment.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SegmentHit<K> {
+    pub key: K,
+    /// How far along the segment the closest approach is, from 0.0 to 1.0
+    pub t: f64,
+    /// Distance from the segment at the closest approach
+    pub distance: f64,
+}
+
+fn to_f64(p: &Position) -> (f64, f64, f64) {
+    (p.x as f64, p.y as f64, p.z as f64)
+}
+
+/// Distance from `p` to the segment `a`-`b`, and where along it the closest point lies.
+pub fn segment_distance(a: &Position, b: &Position, p: &Position) -> (f64, f64) {
+    let (a, b, p) = (to_f64(a), to_f64(b), to_f64(p));
+    let d = (b.0 - a.0, b.1 - a.1, b.2 - a.2);
+    let length_squared = d.0 * d.0 + d.1 * d.1 + d.2 * d.2;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * d.0 + (p.1 - a.1) * d.1 + (p.2 - a.2) * d.2) / length_squared).clamp(0.0, 1.0)
+    };
+    let closest = (a.0 + d.0 * t, a.1 + d.1 * t, a.2 + d.2 * t);
+    let offset = (p.0 - closest.0, p.1 - closest.1, p.2 - closest.2);
+    (t, (offset.0 * offset.0 + offset.1 * offset.1 + offset.2 * offset.2).sqrt())
+}
+
+impl<K: Clone + Eq + Hash + Ord> PointGrid<K> {
+    pub fn new(cell_size: f64) -> Self {
+        PointGrid { cell_size: cell_size.max(1.0), cells: HashMap::new(), positions: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn position(&self, key: &K) -> Option<Position> {
+        self.positions.get(key).copied()
+    }
+
+    fn cell_of(&self, x: f64, y: f64, z: f64) -> Cell {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+            (z / self.cell_size).floor() as i64,
+        )
+    }
+
+    /// Adds a point, or moves it if the key is already in the grid.
+    pub fn insert(&mut self, key: K, position: Position) {
+        self.remove(&key);
+        let (x, y, z) = to_f64(&position);
+        let cell = self.cell_of(x, y, z);
+        self.cells.entry(cell).or_default().push((key.clone(), position));
+        self.positions.insert(key, position);
+    }
+
+    /// Removes a point.
+    ///
+    /// # Returns
+    /// Whether the key was in the grid
+    pub fn remove(&mut self, key: &K) -> bool {
+        let Some(position) = self.positions.remove(key) else {
+            return false;
+        };
+        let (x, y, z) = to_f64(&position);
+        let cell = self.cell_of(x, y, z);
+        if let Some(points) = self.cells.get_mut(&cell) {
+            points.retain(|(other, _)| other != key);
+            if points.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+        true
+    }
+
+    /// Points in the cells overlapping an axis-aligned box. Falls back to
+    /// every occupied cell when the box covers more cells than are occupied.
+    fn candidates(&self, min: (f64, f64, f64), max: (f64, f64, f64)) -> Vec<&(K, Position)> {
+        let low = self.cell_of(min.0, min.1, min.2);
+        let high = self.cell_of(max.0, max.1, max.2);
+        let span = |a: i64, b: i64| (b - a + 1).max(0) as u128;
+        let box_cells = span(low.0, high.0) * span(low.1, high.1) * span(low.2, high.2);
+        if box_cells > self.cells.len() as u128 {
+            return self.cells.iter()
+                .filter(|(cell, _)| (low.0..=high.0).contains(&cell.0) && (low.1..=high.1).contains(&cell.1) && (low.2..=high.2).contains(&cell.2))
+                .flat_map(|(_, points)| points.iter())
+                .collect();
+        }
+        let mut found = Vec::new();
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                for z in low.2..=high.2 {
+                    if let Some(points) = self.cells.get(&(x, y, z)) {
+                        found.extend(points.iter());
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Every point within `radius` of `center`, nearest first.
+    pub fn within(&self, center: &Position, radius: f64) -> Vec<(K, f64)> {
+        let (x, y, z) = to_f64(center);
+        let mut found: Vec<(K, f64)> = self.candidates((x - radius, y - radius, z - radius), (x + radius, y + radius, z + radius))
+            .into_iter()
+            .map(|(key, position)| (key.clone(), center.distance(position)))
+            .filter(|(_, distance)| *distance <= radius)
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        found
+    }
+
+    /// The point closest to `center`, searching outwards ring by ring.
+    pub fn nearest(&self, center: &Position) -> Option<(K, f64)> {
+        self.nearest_matching(center, |_| true)
+    }
+
+    /// The point closest to `center` among those accepted by `accept`.
+    pub fn nearest_matching(&self, center: &Position, accept: impl Fn(&K) -> bool) -> Option<(K, f64)> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut radius = self.cell_size;
+        loop {
+            // A hit within `radius` is final: anything closer would have been found too
+            if let Some(hit) = self.within(center, radius).into_iter().find(|(key, _)| accept(key)) {
+                return Some(hit);
+            }
+            if radius > MAX_SEARCH_RADIUS {
+                return None;
+            }
+            radius *= 2.0;
+        }
+    }
+
+    /// Every point within `radius` of the segment `from`-`to`, in the order
+    /// the segment passes them.
+    pub fn along_segment(&self, from: &Position, to: &Position, radius: f64) -> Vec<SegmentHit<K>> {
+        let (a, b) = (to_f64(from), to_f64(to));
+        let min = (a.0.min(b.0) - radius, a.1.min(b.1) - radius, a.2.min(b.2) - radius);
+        let max = (a.0.max(b.0) + radius, a.1.max(b.1) + radius, a.2.max(b.2) + radius);
+        let mut hits: Vec<SegmentHit<K>> = self.candidates(min, max)
+            .into_iter()
+            .filter_map(|(key, position)| {
+                let (t, distance) = segment_distance(from, to, position);
+                (distance <= radius).then(|| SegmentHit { key: key.clone(), t, distance })
+            })
+            .collect();
+        hits.sort_by(|a, b| a.t.total_cmp(&b.t).then_with(|| a.key.cmp(&b.key)));
+        hits
+    }
+}
+
+/// Where systems, their planets and fleets are, for proximity lookups
+/// without loading and scanning every save file.
+#[derive(Debug, Clone)]
+pub struct GalaxyIndex {
+    pub game_id: String,
+    systems: PointGrid<usize>,
+    /// Planets of each system, at their positions inside the system
+    planets: HashMap<usize, PointGrid<usize>>,
+    fleets: PointGrid<String>,
+    /// Fleets by system they are in
+    fleet_systems: HashMap<String, usize>,
+}
+
+impl GalaxyIndex {
+    pub fn build(game_id: &str, systems: &[StarSystem], fleets: &[Fleet]) -> Self {
+        let mut index = GalaxyIndex {
+            game_id: game_id.to_string(),
+            systems: PointGrid::new(GALAXY_CELL_SIZE),
+            planets: HashMap::new(),
+            fleets: PointGrid::new(GALAXY_CELL_SIZE),
+            fleet_systems: HashMap::new(),
+        };
+        for system in systems {
+            index.systems.insert(system.id, system.position);
+            let planets = index.planets.entry(system.id).or_insert_with(|| PointGrid::new(PLANET_CELL_SIZE));
+            for (planet_id, planet) in system.planets.iter().enumerate() {
+                planets.insert(planet_id, planet.position);
+            }
+        }
+        for fleet in fleets {
+            index.update_fleet(fleet);
+        }
+        index
+    }
+
+    pub fn system_count(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn fleet_count(&self) -> usize {
+        self.fleets.len()
+    }
+
+    /// The system centred exactly on `position`, if any.
+    pub fn system_at(&self, position: &Position) -> Option<usize> {
+        self.systems.within(position, 0.0).into_iter().next().map(|(id, _)| id)
+    }
+
+    pub fn nearest_system(&self, position: &Position) -> Option<(usize, f64)> {
+        self.systems.nearest(position)
+    }
+
+    /// The nearest system among those accepted by `accept`, such as the ones a player has charted.
+    pub fn nearest_system_matching(&self, position: &Position, accept: impl Fn(usize) -> bool) -> Option<(usize, f64)> {
+        self.systems.nearest_matching(position, |id| accept(*id))
+    }
+
+    pub fn system_position(&self, system_id: usize) -> Option<Position> {
+        self.systems.position(&system_id)
+    }
+
+    pub fn fleet_position(&self, fleet_name: &str) -> Option<Position> {
+        self.fleets.position(&fleet_name.to_string())
+    }
+
+    pub fn systems_within(&self, position: &Position, radius: f64) -> Vec<(usize, f64)> {
+        self.systems.within(position, radius)
+    }
+
+    /// Systems passed within `radius` of the straight line between two points.
+    pub fn systems_along_segment(&self, from: &Position, to: &Position, radius: f64) -> Vec<SegmentHit<usize>> {
+        self.systems.along_segment(from, to, radius)
+    }
+
+    /// Planets of a system within `radius` of a position inside it.
+    pub fn planets_within(&self, system_id: usize, local_position: &Position, radius: f64) -> Vec<(usize, f64)> {
+        self.planets.get(&system_id).map_or_else(Vec::new, |planets| planets.within(local_position, radius))
+    }
+
+    pub fn fleets_within(&self, position: &Position, radius: f64) -> Vec<(String, f64)> {
+        self.fleets.within(position, radius)
+    }
+
+    /// Fleets currently in a system, in name order.
+    pub fn fleets_in_system(&self, system_id: usize) -> Vec<String> {
+        let mut names: Vec<String> = self.fleet_systems.iter()
+            .filter(|(_, id)| **id == system_id)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Records where a fleet is now.
+    pub fn update_fleet(&mut self, fleet: &Fleet) {
+        self.fleets.insert(fleet.name.clone(), fleet.position);
+        match fleet.current_system_id {
+            Some(system_id) => self.fleet_systems.insert(fleet.name.clone(), system_id),
+            None => self.fleet_systems.remove(&fleet.name),
+        };
+    }
+
+    pub fn remove_fleet(&mut self, fleet_name: &str) {
+        self.fleets.remove(&fleet_name.to_string());
+        self.fleet_systems.remove(fleet_name);
+    }
+}
+
+/// Runs a query against the index of a game, building it from the saved
+/// world and fleets the first time it is needed. The index is kept in the
+/// game's context when the game is the one entered on this thread.
+pub fn with_galaxy_index<T>(game_id: &str, query: impl FnOnce(&GalaxyIndex) -> T) -> Result<T, String> {
+    let context = GameContext::current_for(game_id);
+    if let Some(context) = &context
+        && let Ok(guard) = context.galaxy_index().read()
+        && let Some(index) = guard.as_ref()
+    {
+        return Ok(query(index));
+    }
+
+    let systems = load_game_world(game_id).map_err(|e| format!("Failed to load game world: {}", e))?;
+    let fleets = list_all_fleets(game_id).map_err(|e| format!("Failed to load fleets: {}", e))?;
+    let index = GalaxyIndex::build(game_id, &systems, &fleets);
+    let result = query(&index);
+    if let Some(context) = context
+        && let Ok(mut guard) = context.galaxy_index().write()
+    {
+        *guard = Some(index);
+    }
+    Ok(result)
+}
+
+/// Keeps a game's index in step with a fleet that was just saved.
+pub fn track_fleet(context: &GameContext, fleet: &Fleet) {
+    if let Ok(mut guard) = context.galaxy_index().write()
+        && let Some(index) = guard.as_mut()
+    {
+        index.update_fleet(fleet);
+    }
+}
+
+/// Forgets a game's index so the next query rebuilds it from the saves.
+pub fn invalidate_galaxy_index(context: &GameContext) {
+    if let Ok(mut guard) = context.galaxy_index().write() {
+        *guard = None;
+    }
+}
+
+/// Drops a deleted fleet from a game's index.
+pub fn untrack_fleet(context: &GameContext, fleet_name: &str) {
+    if let Ok(mut guard) = context.galaxy_index().write()
+        && let Some(index) = guard.as_mut()
+    {
+        index.remove_fleet(fleet_name);
+    }
+}
@@ -50,9 +50,9 @@ impl Distribution<StarType> for Standard {
     }
 }
 
-pub fn generate_star(map_width: i32, map_height: i32, map_length: i32, existing_names: &std::collections::HashSet<String>) -> Star {
-    let name = generate_star_name(existing_names);
-    let star_type: StarType = rand::random();
+pub fn generate_star<R: Rng + ?Sized>(map_width: i32, map_height: i32, map_length: i32, existing_names: &std::collections::HashSet<String>, rng: &mut R) -> Star {
+    let name = generate_star_name(existing_names, rng);
+    let star_type: StarType = rng.sample(Standard);
     // For now, always place the star at the center (0,0,0)
     // In the future, we can add special cases for binary/trinary systems
     let position = Position { x: 0, y: 0, z: 0 };
@@ -63,8 +63,7 @@ pub fn generate_star(map_width: i32, map_height: i32, map_length: i32, existing_
     }
 }
 
-fn generate_star_name(existing_names: &std::collections::HashSet<String>) -> String {
-    let mut rng = rand::thread_rng();
+fn generate_star_name<R: Rng + ?Sized>(existing_names: &std::collections::HashSet<String>, rng: &mut R) -> String {
     let mut attempts = 0;
     const MAX_ATTEMPTS: u32 = 100;
 
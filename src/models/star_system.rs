@@ -21,27 +21,24 @@ impl StarSystem {
 
 impl Default for StarSystem {
     fn default() -> Self {
-        generate_star_system_default()
+        generate_star_system_default(&mut rand::thread_rng())
     }
 }
 
 /// Generates a star system with random properties at the specified position
 /// 
 /// # Arguments
-/// * `map_width` - Width of the galaxy map
-/// * `map_height` - Height of the galaxy map
-/// * `map_length` - Length of the galaxy map
+/// * `rng` - The random number generator to draw from
 /// 
 /// # Returns
 /// A new StarSystem with random properties
-pub fn generate_star_system_default() -> StarSystem {
-    // Create a random number generator
-    let mut rng = rand::thread_rng();
+pub fn generate_star_system_default<R: Rng + ?Sized>(rng: &mut R) -> StarSystem {
     let mut existing_names: std::collections::HashSet<String> = std::collections::HashSet::new();
-    let star = generate_star(1000, 1000, 1000, &existing_names); // Default to 1000x1000x1000 if no dimensions provided
+    let star = generate_star(1000, 1000, 1000, &existing_names, rng); // Default to 1000x1000x1000 if no dimensions provided
     existing_names.insert(star.name.clone());
-    let planets = generate_planets(rng.gen_range(3..10), 1000, 1000, 1000); // Default to 1000x1000x1000 if no dimensions provided
-    let position = random_position(1000, 1000, 1000); // Default to 1000x1000x1000 if no dimensions provided
+    let planet_count = rng.gen_range(3..10);
+    let planets = generate_planets(planet_count, 1000, 1000, 1000, rng); // Default to 1000x1000x1000 if no dimensions provided
+    let position = random_position(1000, 1000, 1000, rng); // Default to 1000x1000x1000 if no dimensions provided
 
     let star_system = StarSystem { 
         id: 0, // placeholder; caller should assign real id when saving
@@ -60,17 +57,16 @@ pub fn generate_star_system_default() -> StarSystem {
 /// * `map_height` - Height of the galaxy map
 /// * `map_length` - Length of the galaxy map
 /// * `existing_names` - A mutable reference to a set of existing names
+/// * `rng` - The random number generator to draw from
 /// 
 /// # Returns
 /// A new StarSystem with random properties
-pub fn generate_star_system(map_width: i32, map_height: i32, map_length: i32, existing_names: &mut std::collections::HashSet<String>) -> StarSystem {
-    // Create a random number generator
-    let mut rng = rand::thread_rng();
+pub fn generate_star_system<R: Rng + ?Sized>(map_width: i32, map_height: i32, map_length: i32, existing_names: &mut std::collections::HashSet<String>, rng: &mut R) -> StarSystem {
     let planet_count = rng.gen_range(3..10);
-    let star = generate_star(map_width, map_height, map_length, existing_names);
+    let star = generate_star(map_width, map_height, map_length, existing_names, rng);
     existing_names.insert(star.name.clone());
-    let planets = generate_planets(planet_count, map_width, map_height, map_length);
-    let position = random_position(map_width, map_height, map_length);
+    let planets = generate_planets(planet_count, map_width, map_height, map_length, rng);
+    let position = random_position(map_width, map_height, map_length, rng);
 
     let star_system = StarSystem { 
         id: 0,
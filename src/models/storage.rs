@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use lazy_static::lazy_static;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::models::faction::Faction;
+use crate::models::fleet::Fleet;
+use crate::models::game_state::game_data_path;
+use crate::models::market::Market;
+use crate::models::persistence::{apply_journaled, collect_files, write_json_atomic};
+use crate::models::player::Player;
+use crate::models::settings::{GameSettings, SavedGame};
+use crate::models::star_system::StarSystem;
+
+/// File in a game directory recording which backend holds the game's saves.
+pub const STORAGE_CONFIG_FILE: &str = "storage.json";
+/// Database file of games stored in SQLite, inside the game directory.
+pub const SQLITE_FILE: &str = "game.sqlite";
+
+/// Where a game's save documents live.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, rocket::FromFormField)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// One JSON file per document under `data/game/<game_id>/`
+    #[default]
+    Json,
+    /// Every document in a single embedded SQLite database, for large saves
+    Sqlite,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct StorageConfig {
+    backend: StorageBackend,
+}
+
+/// One write of a batch: the document key and its new contents, or `None` to remove it.
+pub type DocumentWrite = (String, Option<Vec<u8>>);
+
+/// A store of JSON save documents.
+///
+/// Documents are addressed by keys that mirror the JSON directory layout,
+/// such as `fleets/Fleet_Player_1.json` or `settings.json`, so every backend
+/// holds exactly the same documents and games can move between them.
+pub trait Storage: Send + Sync {
+    fn backend(&self) -> StorageBackend;
+
+    /// The raw contents of a document, or `None` if it does not exist.
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Names of the documents directly inside a collection, such as `fleets`, sorted.
+    fn list(&self, collection: &str) -> Result<Vec<String>, String>;
+
+    /// Keys of every document, sorted.
+    fn keys(&self) -> Result<Vec<String>, String>;
+
+    /// Applies a batch of writes and removals all together or not at all.
+    fn apply(&self, writes: Vec<DocumentWrite>) -> Result<(), String>;
+
+    fn load_settings(&self) -> Result<Option<GameSettings>, String> {
+        read_document(self, "settings.json")
+    }
+
+    fn save_settings(&self, settings: &GameSettings) -> Result<(), String> {
+        write_document(self, "settings.json", settings)
+    }
+
+    fn load_player(&self, name: &str) -> Result<Option<Player>, String> {
+        read_document(self, &document_key(&["players", &format!("{}.json", name)]))
+    }
+
+    fn save_player(&self, player: &Player) -> Result<(), String> {
+        write_document(self, &document_key(&["players", &format!("{}.json", player.name)]), player)
+    }
+
+    fn load_fleet(&self, name: &str) -> Result<Option<Fleet>, String> {
+        read_document(self, &document_key(&["fleets", &format!("{}.json", name)]))
+    }
+
+    fn save_fleet(&self, fleet: &Fleet) -> Result<(), String> {
+        write_document(self, &document_key(&["fleets", &format!("{}.json", fleet.name)]), fleet)
+    }
+
+    fn remove_fleet(&self, name: &str) -> Result<(), String> {
+        self.apply(vec![(document_key(&["fleets", &format!("{}.json", name)]), None)])
+    }
+
+    /// Every fleet, sorted by name. Documents that cannot be parsed are skipped.
+    fn list_fleets(&self) -> Result<Vec<Fleet>, String> {
+        read_collection(self, "fleets")
+    }
+
+    /// The whole galaxy as generated, from `GameWorld.json`.
+    fn load_galaxy(&self) -> Result<Option<Vec<StarSystem>>, String> {
+        read_document(self, "GameWorld.json")
+    }
+
+    fn save_galaxy(&self, systems: &[StarSystem]) -> Result<(), String> {
+        write_document(self, "GameWorld.json", &systems)
+    }
+
+    fn load_system(&self, system_id: usize) -> Result<Option<StarSystem>, String> {
+        read_document(self, &document_key(&["star_systems", &format!("system_{}.json", system_id)]))
+    }
+
+    fn save_system(&self, system_id: usize, system: &StarSystem) -> Result<(), String> {
+        write_document(self, &document_key(&["star_systems", &format!("system_{}.json", system_id)]), system)
+    }
+
+    fn load_market(&self, system_id: usize, planet_id: usize) -> Result<Option<Market>, String> {
+        read_document(self, &document_key(&["markets", &format!("market_{}_{}.json", system_id, planet_id)]))
+    }
+
+    fn save_market(&self, system_id: usize, planet_id: usize, market: &Market) -> Result<(), String> {
+        write_document(self, &document_key(&["markets", &format!("market_{}_{}.json", system_id, planet_id)]), market)
+    }
+
+    fn load_faction(&self, name: &str) -> Result<Option<Faction>, String> {
+        read_document(self, &document_key(&["factions", &format!("{}.json", name)]))
+    }
+
+    fn save_faction(&self, faction: &Faction) -> Result<(), String> {
+        write_document(self, &document_key(&["factions", &format!("{}.json", faction.name)]), faction)
+    }
+
+    /// Every faction, sorted by name. Documents that cannot be parsed are skipped.
+    fn list_factions(&self) -> Result<Vec<Faction>, String> {
+        read_collection(self, "factions")
+    }
+}
+
+/// Joins path components into a document key.
+pub fn document_key(components: &[&str]) -> String {
+    components.join("/")
+}
+
+fn collection_of(key: &str) -> &str {
+    key.rsplit_once('/').map_or("", |(collection, _)| collection)
+}
+
+/// Reads and parses a document.
+pub fn read_document<S: Storage + ?Sized, T: DeserializeOwned>(storage: &S, key: &str) -> Result<Option<T>, String> {
+    let Some(contents) = storage.read(key)? else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&contents)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", key, e))
+}
+
+/// Serializes and writes a document.
+pub fn write_document<S: Storage + ?Sized, T: Serialize + ?Sized>(storage: &S, key: &str, data: &T) -> Result<(), String> {
+    let contents = serde_json::to_vec(data).map_err(|e| format!("Failed to serialize {}: {}", key, e))?;
+    storage.apply(vec![(key.to_string(), Some(contents))])
+}
+
+/// Parses every document of a collection, skipping ones that cannot be read.
+fn read_collection<S: Storage + ?Sized, T: DeserializeOwned>(storage: &S, collection: &str) -> Result<Vec<T>, String> {
+    let mut documents = Vec::new();
+    for name in storage.list(collection)? {
+        if let Ok(Some(document)) = read_document(storage, &document_key(&[collection, &name])) {
+            documents.push(document);
+        }
+    }
+    Ok(documents)
+}
+
+/// Saves as one JSON file per document, in the game directory layout the
+/// game has always used. Batches go through the write-ahead journal of
+/// `persistence`, so they survive crashes the same way.
+pub struct JsonDirStorage {
+    root: PathBuf,
+}
+
+impl JsonDirStorage {
+    pub fn new(root: PathBuf) -> Self {
+        JsonDirStorage { root }
+    }
+
+    fn path_of(&self, key: &str) -> PathBuf {
+        key.split('/').fold(self.root.clone(), |path, component| path.join(component))
+    }
+
+    /// Whether a file in the game directory is a save document rather than
+    /// bookkeeping such as temp files, journals or the storage config.
+    fn is_document(&self, key: &str) -> bool {
+        key.ends_with(".json") && key != STORAGE_CONFIG_FILE
+    }
+}
+
+impl Storage for JsonDirStorage {
+    fn backend(&self) -> StorageBackend {
+        StorageBackend::Json
+    }
+
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        match fs::read(self.path_of(key)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", key, e)),
+        }
+    }
+
+    fn list(&self, collection: &str) -> Result<Vec<String>, String> {
+        let entries = match fs::read_dir(self.path_of(collection)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to list {}: {}", collection, e)),
+        };
+        let mut names: Vec<String> = entries.flatten()
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|name| self.is_document(&document_key(&[collection, name])))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, String> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut paths = Vec::new();
+        collect_files(&self.root, &mut paths).map_err(|e| format!("Failed to read game directory: {}", e))?;
+        let mut keys: Vec<String> = paths.iter()
+            .filter_map(|path| path.strip_prefix(&self.root).ok())
+            .map(|relative| relative.iter().map(|component| component.to_string_lossy()).collect::<Vec<_>>().join("/"))
+            .filter(|key| self.is_document(key))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn apply(&self, writes: Vec<DocumentWrite>) -> Result<(), String> {
+        let files = writes.into_iter().map(|(key, contents)| (self.path_of(&key), contents)).collect();
+        apply_journaled(&self.root, files)
+    }
+}
+
+/// Saves every document of a game in one embedded SQLite database, which
+/// keeps large galaxies to a single file and commits batches in one
+/// database transaction.
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Opens the database at `path`, creating it and its schema if needed.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let connection = Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS documents (
+                 key TEXT PRIMARY KEY,
+                 collection TEXT NOT NULL,
+                 body TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS documents_by_collection ON documents (collection, key);",
+        ).map_err(|e| format!("Failed to prepare {}: {}", path.display(), e))?;
+        Ok(SqliteStorage { connection: Mutex::new(connection) })
+    }
+
+    fn connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>, String> {
+        self.connection.lock().map_err(|_| "Failed to lock the game database".to_string())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn backend(&self) -> StorageBackend {
+        StorageBackend::Sqlite
+    }
+
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        self.connection()?
+            .query_row("SELECT body FROM documents WHERE key = ?1", params![key], |row| row.get::<_, String>(0))
+            .optional()
+            .map(|body| body.map(String::into_bytes))
+            .map_err(|e| format!("Failed to read {}: {}", key, e))
+    }
+
+    fn list(&self, collection: &str) -> Result<Vec<String>, String> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare("SELECT key FROM documents WHERE collection = ?1 ORDER BY key")
+            .map_err(|e| e.to_string())?;
+        let keys = statement.query_map(params![collection], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+            .map_err(|e| format!("Failed to list {}: {}", collection, e))?;
+        Ok(keys.into_iter().map(|key| key.rsplit('/').next().unwrap_or_default().to_string()).collect())
+    }
+
+    fn keys(&self) -> Result<Vec<String>, String> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare("SELECT key FROM documents ORDER BY key").map_err(|e| e.to_string())?;
+        statement.query_map([], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect())
+            .map_err(|e| format!("Failed to list documents: {}", e))
+    }
+
+    fn apply(&self, writes: Vec<DocumentWrite>) -> Result<(), String> {
+        if writes.is_empty() {
+            return Ok(());
+        }
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        for (key, contents) in writes {
+            let result = match contents {
+                Some(contents) => {
+                    let body = String::from_utf8(contents).map_err(|e| format!("{} is not valid UTF-8: {}", key, e))?;
+                    transaction.execute(
+                        "INSERT INTO documents (key, collection, body) VALUES (?1, ?2, ?3)
+                         ON CONFLICT (key) DO UPDATE SET body = excluded.body",
+                        params![key, collection_of(&key), body],
+                    )
+                }
+                None => transaction.execute("DELETE FROM documents WHERE key = ?1", params![key]),
+            };
+            result.map_err(|e| format!("Failed to write {}: {}", key, e))?;
+        }
+        // Dropping an uncommitted transaction rolls every write back
+        transaction.commit().map_err(|e| format!("Failed to commit: {}", e))
+    }
+}
+
+lazy_static! {
+    /// Open storage of each game, so SQLite connections are reused between requests.
+    static ref STORAGES: Mutex<HashMap<String, Arc<dyn Storage>>> = Mutex::new(HashMap::new());
+}
+
+/// The backend a game is stored in. Games without a storage config predate
+/// the choice and are JSON directories.
+pub fn storage_backend(game_id: &str) -> StorageBackend {
+    fs::read(game_data_path(game_id, &[STORAGE_CONFIG_FILE])).ok()
+        .and_then(|contents| serde_json::from_slice::<StorageConfig>(&contents).ok())
+        .unwrap_or_default()
+        .backend
+}
+
+fn open_storage(game_id: &str, backend: StorageBackend) -> Result<Arc<dyn Storage>, String> {
+    Ok(match backend {
+        StorageBackend::Json => Arc::new(JsonDirStorage::new(game_data_path(game_id, &[]))),
+        StorageBackend::Sqlite => Arc::new(SqliteStorage::open(&game_data_path(game_id, &[SQLITE_FILE]))?),
+    })
+}
+
+/// The storage holding a game's saves.
+pub fn storage_for(game_id: &str) -> Result<Arc<dyn Storage>, String> {
+    let mut storages = STORAGES.lock().map_err(|_| "Failed to lock storage registry".to_string())?;
+    if let Some(storage) = storages.get(game_id) {
+        return Ok(storage.clone());
+    }
+    let storage = open_storage(game_id, storage_backend(game_id))?;
+    storages.insert(game_id.to_string(), storage.clone());
+    Ok(storage)
+}
+
+/// Closes every open storage, so the next access re-reads each game's config.
+pub fn close_storages() {
+    if let Ok(mut storages) = STORAGES.lock() {
+        storages.clear();
+    }
+}
+
+/// Closes the storage of one game, e.g. before its files are deleted.
+pub fn close_storage(game_id: &str) {
+    if let Ok(mut storages) = STORAGES.lock() {
+        storages.remove(game_id);
+    }
+}
+
+/// Picks the backend of a new game. Call before anything of the game is saved.
+pub fn init_storage(game_id: &str, backend: StorageBackend) -> Result<(), String> {
+    write_json_atomic(&game_data_path(game_id, &[STORAGE_CONFIG_FILE]), &StorageConfig { backend })
+        .map_err(|e| format!("Failed to write storage config: {}", e))?;
+    close_storage(game_id);
+    Ok(())
+}
+
+/// Loads a document of a game.
+pub fn load_document<T: DeserializeOwned>(game_id: &str, path: &[&str]) -> Result<Option<T>, String> {
+    read_document(storage_for(game_id)?.as_ref(), &document_key(path))
+}
+
+/// Saves a document of a game on its own.
+pub fn save_document<T: Serialize + ?Sized>(game_id: &str, path: &[&str], data: &T) -> Result<(), String> {
+    write_document(storage_for(game_id)?.as_ref(), &document_key(path), data)
+}
+
+pub fn remove_document(game_id: &str, path: &[&str]) -> Result<(), String> {
+    storage_for(game_id)?.apply(vec![(document_key(path), None)])
+}
+
+pub fn document_exists(game_id: &str, path: &[&str]) -> bool {
+    storage_for(game_id).and_then(|storage| storage.read(&document_key(path))).is_ok_and(|contents| contents.is_some())
+}
+
+/// Names of the documents in a collection of a game, such as `["fleets"]`.
+pub fn list_documents(game_id: &str, collection: &[&str]) -> Result<Vec<String>, String> {
+    storage_for(game_id)?.list(&document_key(collection))
+}
+
+/// Request body for moving a game to another backend.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StorageMigrationData {
+    pub backend: StorageBackend,
+}
+
+/// What `migrate_storage` moved.
+#[derive(Serialize, Debug, Clone)]
+pub struct StorageMigrationReport {
+    pub game_id: String,
+    pub from: StorageBackend,
+    pub to: StorageBackend,
+    pub documents: usize,
+}
+
+/// Moves every document of a game to another backend.
+///
+/// All documents are copied in one batch and read back before the game is
+/// switched over; only then is the old copy deleted. A failure part way
+/// leaves the game on its old backend, untouched.
+///
+/// # Arguments
+/// * `game_id` - The game to move
+/// * `to` - The backend to move it to
+///
+/// # Returns
+/// A report of the move, or an error if anything could not be copied
+pub fn migrate_storage(game_id: &str, to: StorageBackend) -> Result<StorageMigrationReport, String> {
+    let source = storage_for(game_id)?;
+    let from = source.backend();
+    let mut report = StorageMigrationReport { game_id: game_id.to_string(), from, to, documents: 0 };
+    if from == to {
+        return Ok(report);
+    }
+    if source.read("settings.json")?.is_none() {
+        return Err(format!("No settings found for game {}", game_id));
+    }
+
+    let keys = source.keys()?;
+    let mut documents = Vec::with_capacity(keys.len());
+    for key in &keys {
+        let mut contents = source.read(key)?.ok_or_else(|| format!("{} vanished during migration", key))?;
+        if key == "settings.json" {
+            let mut settings: GameSettings = serde_json::from_slice(&contents)
+                .map_err(|e| format!("Failed to parse settings.json: {}", e))?;
+            settings.storage = to;
+            contents = serde_json::to_vec(&settings).map_err(|e| e.to_string())?;
+        }
+        documents.push((key.clone(), Some(contents)));
+    }
+    if to == StorageBackend::Sqlite {
+        // Leftovers of an earlier, failed migration
+        remove_sqlite_files(game_id);
+    }
+    let target = open_storage(game_id, to)?;
+    target.apply(documents)?;
+    if target.keys()? != keys {
+        return Err("Migrated documents do not match the originals".to_string());
+    }
+    report.documents = keys.len();
+
+    // Switching the config is the commit point; the old copy is only cleaned up after
+    init_storage(game_id, to)?;
+    drop(target);
+    if let Ok(Some(mut saved_game)) = SavedGame::load_game(game_id) {
+        saved_game.settings.storage = to;
+        saved_game.save_game().map_err(|e| format!("Failed to update save entry: {}", e))?;
+    }
+    match from {
+        StorageBackend::Json => source.apply(keys.into_iter().map(|key| (key, None)).collect())?,
+        StorageBackend::Sqlite => {
+            drop(source);
+            remove_sqlite_files(game_id);
+        }
+    }
+    Ok(report)
+}
+
+fn remove_sqlite_files(game_id: &str) {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(game_data_path(game_id, &[&format!("{}{}", SQLITE_FILE, suffix)]));
+    }
+}
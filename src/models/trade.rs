@@ -1,7 +1,8 @@
-use crate::models::resource::{Resource, ResourceType};
+use crate::models::resource::ResourceType;
 use crate::models::player::Player;
 use crate::models::market::Market;
 use crate::models::fleet::Fleet;
+use crate::models::cargo::{CargoDistribution, FillPolicy};
 use crate::models::trader::Trader;
 use crate::constants::PRINT_DEBUG;
 use serde::Deserialize;
@@ -34,9 +35,22 @@ pub struct ResourceTradeData {
     pub distribution_mode: Option<String>, // "first" | "even" | "specific"
     #[serde(default)]
     pub allocations: Option<Vec<ShipAllocation>>, // for mode="specific"
+    /// Fill as much of the order as fits instead of refusing it
+    #[serde(default)]
+    pub allow_partial: bool,
 }
 
-#[derive(Deserialize)]
+impl ResourceTradeData {
+    pub fn distribution(&self) -> Result<CargoDistribution, String> {
+        CargoDistribution::from_request(self.distribution_mode.as_deref(), self.allocations.as_deref())
+    }
+
+    pub fn fill_policy(&self) -> FillPolicy {
+        if self.allow_partial { FillPolicy::Partial } else { FillPolicy::AllOrNothing }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct ShipAllocation {
     pub ship_index: usize,
     pub quantity: u32,
@@ -165,159 +179,51 @@ pub fn trade_with_fleet(
     trade_type: &str,
     player: &mut Player
 ) -> Result<(), String> {
-    // Find the resource in trader's cargo
-    let mut trader_resource = None;
-    let mut trader_ship_index = 0;
-    let mut cargo_index = 0;
-
-    for (ship_idx, ship) in trader_fleet.ships.iter().enumerate() {
-        for (cargo_idx, cargo) in ship.cargo.iter().enumerate() {
-            if cargo.resource_type == resource_type {
-                trader_resource = Some(cargo.clone());
-                trader_ship_index = ship_idx;
-                cargo_index = cargo_idx;
-                break;
+    // The trader's prices are the ones set on its cargo of the resource
+    let resource = trader_fleet.ships.iter()
+        .flat_map(|ship| ship.cargo.iter())
+        .find(|cargo| cargo.resource_type == resource_type)
+        .cloned()
+        .ok_or_else(|| "Resource not found in trader's cargo".to_string())?;
+
+    match trade_type {
+        "buy" => {
+            // Stock without a price is the trader's own, not for sale
+            let price = resource.buy.ok_or_else(|| format!("{} does not sell {}", trader_fleet.name, resource_type))?;
+            let total_cost = price * quantity as f64;
+            if player.credits < total_cost {
+                return Err("Insufficient credits".to_string());
+            }
+            if trader_fleet.cargo_quantity(resource_type) < quantity {
+                return Err("Trader doesn't have enough resources".to_string());
             }
-        }
-        if trader_resource.is_some() {
-            break;
-        }
-    }
-
-    match trader_resource {
-        Some(resource) => {
-            match trade_type {
-                "buy" => {
-                    // Calculate total cost
-                    let total_cost = (resource.buy.unwrap_or(0.0) * quantity as f64);
-                    
-                    // Check if player has enough credits
-                    if player.credits < total_cost {
-                        return Err("Insufficient credits".to_string());
-                    }
-
-                    // Check if trader has enough quantity
-                    if resource.quantity.unwrap_or(0) < quantity {
-                        return Err("Trader doesn't have enough resources".to_string());
-                    }
-
-                    // Update player's credits and cargo
-                    player.credits -= total_cost;
-                    
-                    // Add cargo to player's fleet
-                    let mut found = false;
-                    for ship in &mut player_fleet.ships {
-                        for cargo in &mut ship.cargo {
-                            if cargo.resource_type == resource_type {
-                                cargo.quantity = Some(cargo.quantity.unwrap_or(0) + quantity);
-                                found = true;
-                                break;
-                            }
-                        }
-                        if found {
-                            break;
-                        }
-                    }
-
-                    if !found {
-                        // Add new cargo item if not found
-                        if let Some(ship) = player_fleet.ships.first_mut() {
-                            ship.cargo.push(Resource {
-                                resource_type: resource_type.clone(),
-                                quantity: Some(quantity),
-                                buy: None,
-                                sell: None
-                            });
-                        }
-                    }
-
-                    // Update trader's cargo
-                    if let Some(ship) = trader_fleet.ships.get_mut(trader_ship_index) {
-                        if let Some(cargo) = ship.cargo.get_mut(cargo_index) {
-                            cargo.quantity = Some(cargo.quantity.unwrap_or(0) - quantity);
-                        }
-                    }
-                },
-                "sell" => {
-                    // Find resource in player's fleet
-                    let mut player_resource = None;
-                    let mut player_ship_index = 0;
-                    let mut player_cargo_index = 0;
-
-                    for (ship_idx, ship) in player_fleet.ships.iter().enumerate() {
-                        for (cargo_idx, cargo) in ship.cargo.iter().enumerate() {
-                            if cargo.resource_type == resource_type {
-                                player_resource = Some(cargo.clone());
-                                player_ship_index = ship_idx;
-                                player_cargo_index = cargo_idx;
-                                break;
-                            }
-                        }
-                        if player_resource.is_some() {
-                            break;
-                        }
-                    }
-
-                    match player_resource {
-                        Some(resource) => {
-                            // Check if player has enough quantity
-                            if resource.quantity.unwrap_or(0) < quantity {
-                                return Err("You don't have enough resources".to_string());
-                            }
-
-                            // Calculate total earnings
-                            let total_earnings = (resource.sell.unwrap_or(0.0) * quantity as f64);
-                            
-                            // Update player's credits and cargo
-                            player.credits += total_earnings;
-                            
-                            // Update player's cargo
-                            if let Some(ship) = player_fleet.ships.get_mut(player_ship_index) {
-                                if let Some(cargo) = ship.cargo.get_mut(player_cargo_index) {
-                                    cargo.quantity = Some(cargo.quantity.unwrap_or(0) - quantity);
-                                }
-                            }
-
-                            // Add cargo to trader's fleet
-                            let mut found = false;
-                            for ship in &mut trader_fleet.ships {
-                                for cargo in &mut ship.cargo {
-                                    if cargo.resource_type == resource_type {
-                                        cargo.quantity = Some(cargo.quantity.unwrap_or(0) + quantity);
-                                        found = true;
-                                        break;
-                                    }
-                                }
-                                if found {
-                                    break;
-                                }
-                            }
 
-                            if !found {
-                                // Add new cargo item if not found
-                                if let Some(ship) = trader_fleet.ships.first_mut() {
-                                    ship.cargo.push(Resource {
-                                        resource_type: resource_type.clone(),
-                                        quantity: Some(quantity),
-                                        buy: resource.buy,
-                                        sell: resource.sell
-                                    });
-                                }
-                            }
-                        },
-                        None => {
-                            return Err("You don't have this resource".to_string());
-                        }
-                    }
-                },
-                _ => {
-                    return Err("Invalid trade type".to_string());
+            // Stow first: a full hold refuses the trade before anything changes hands
+            player_fleet.load_cargo(resource_type, quantity, &CargoDistribution::First, FillPolicy::AllOrNothing)?;
+            trader_fleet.unload_cargo(resource_type, quantity, &CargoDistribution::First, FillPolicy::AllOrNothing)?;
+            player.credits -= total_cost;
+        },
+        "sell" => {
+            let price = resource.sell.ok_or_else(|| format!("{} does not buy {}", trader_fleet.name, resource_type))?;
+            if player_fleet.cargo_quantity(resource_type) < quantity {
+                return Err("You don't have enough resources".to_string());
+            }
+            trader_fleet.load_cargo(resource_type, quantity, &CargoDistribution::First, FillPolicy::AllOrNothing)
+                .map_err(|e| format!("{} cannot take the goods: {}", trader_fleet.name, e))?;
+            player_fleet.unload_cargo(resource_type, quantity, &CargoDistribution::First, FillPolicy::AllOrNothing)?;
+
+            // The trader pays its own asking price and keeps its prices on new stacks
+            player.credits += price * quantity as f64;
+            for cargo in trader_fleet.ships.iter_mut().flat_map(|ship| ship.cargo.iter_mut()) {
+                if cargo.resource_type == resource_type {
+                    cargo.buy = resource.buy;
+                    cargo.sell = resource.sell;
                 }
             }
-            Ok(())
         },
-        None => {
-            Err("Resource not found in trader's cargo".to_string())
+        _ => {
+            return Err("Invalid trade type".to_string());
         }
     }
-}
\ No newline at end of file
+    Ok(())
+}
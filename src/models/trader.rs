@@ -17,7 +17,7 @@ struct Quote {
     danger_level: String,
     quote: String,
 }
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum TraderPersonality {
     Friendly,
     Neutral,
@@ -85,8 +85,7 @@ impl Trader {
         "Quote not found".to_string()
     }
 
-    pub fn new(name: String, position: Position, credits: f64) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn new<R: Rng + ?Sized>(name: String, position: Position, credits: f64, rng: &mut R) -> Self {
         Trader {
             name,
             position,
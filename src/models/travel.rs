@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use crate::models::fleet::{DistanceBreakdown, Fleet};
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipStatus};
+
+/// Fuel burned per unit of scaled distance by a ship with a fuel factor of 1.0.
+pub const FUEL_PER_DISTANCE: f64 = 0.02;
+
+/// A move that has been ordered but not yet completed.
+///
+/// The fleet keeps flying until the clock reaches `arrival_tick`, at which
+/// point the fleet transit tick system places it at the destination.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FleetTransit {
+    pub origin: Position,
+    pub destination: Position,
+    pub destination_local: Option<Position>,
+    pub destination_system_id: Option<usize>,
+    pub departure_tick: u64,
+    pub arrival_tick: u64,
+    /// Scaled distance of the whole trip, as reported in `DistanceBreakdown::total_scaled`
+    pub distance: f64,
+    pub fuel_used: u32,
+    /// `Warp` for trips through deep space, `SubLightTravel` for trips inside a system
+    pub status: ShipStatus,
+}
+
+/// Scaled distance a ship covers per tick with the given engine.
+pub fn engine_speed(engine: &ShipEngine) -> f64 {
+    match engine {
+        ShipEngine::Basic => 50.0,
+        ShipEngine::Advanced => 100.0,
+        ShipEngine::Experimental => 200.0,
+    }
+}
+
+/// Relative fuel consumption of a hull size.
+pub fn fuel_factor(size: &ShipSize) -> f64 {
+    match size {
+        ShipSize::Tiny => 0.5,
+        ShipSize::Small => 1.0,
+        ShipSize::Medium => 2.0,
+        ShipSize::Large => 3.0,
+        ShipSize::Huge => 5.0,
+        ShipSize::Planetary => 8.0,
+    }
+}
+
+/// Share of its engine speed a badly damaged ship can still make.
+pub const DAMAGED_SPEED_FACTOR: f64 = 0.5;
+
+/// Scaled distance a ship covers per tick, slowed down if its hull is badly damaged.
+pub fn ship_speed(ship: &Ship) -> f64 {
+    let speed = engine_speed(&ship.engine);
+    if ship.is_badly_damaged() { speed * DAMAGED_SPEED_FACTOR } else { speed }
+}
+
+/// A fleet moves at the speed of its slowest ship.
+///
+/// # Returns
+/// The fleet speed, or `None` if the fleet has no ships
+pub fn fleet_speed(fleet: &Fleet) -> Option<f64> {
+    fleet.ships.iter()
+        .map(ship_speed)
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+/// Number of ticks needed to cover `distance` at `speed`. Any movement takes at least one tick.
+pub fn travel_ticks(distance: f64, speed: f64) -> u64 {
+    if distance <= 0.0 || speed <= 0.0 {
+        return 0;
+    }
+    (distance / speed).ceil().max(1.0) as u64
+}
+
+/// Fuel the whole fleet burns to travel `distance`.
+pub fn fuel_required(fleet: &Fleet, distance: f64) -> u32 {
+    let factor: f64 = fleet.ships.iter().map(|ship| fuel_factor(&ship.size)).sum();
+    (distance * FUEL_PER_DISTANCE * factor).round() as u32
+}
+
+/// Total fuel carried in the cargo holds of a fleet.
+pub fn fleet_fuel(fleet: &Fleet) -> u32 {
+    fleet.ships.iter()
+        .flat_map(|ship| ship.cargo.iter())
+        .filter(|cargo| cargo.resource_type == ResourceType::Fuel)
+        .map(|cargo| cargo.quantity.unwrap_or(0))
+        .sum()
+}
+
+/// Removes `amount` fuel from the fleet's cargo, draining ships in order.
+fn burn_fuel(fleet: &mut Fleet, mut amount: u32) {
+    for cargo in fleet.ships.iter_mut().flat_map(|ship| ship.cargo.iter_mut()) {
+        if amount == 0 {
+            break;
+        }
+        if cargo.resource_type != ResourceType::Fuel {
+            continue;
+        }
+        let available = cargo.quantity.unwrap_or(0);
+        let burned = available.min(amount);
+        cargo.quantity = Some(available - burned);
+        amount -= burned;
+    }
+}
+
+fn set_ship_status(ships: &mut [Ship], status: &ShipStatus) {
+    for ship in ships {
+        ship.status = status.clone();
+    }
+}
+
+/// Turns an instantaneous move into a trip over game time.
+///
+/// `origin` is the fleet before the move and `arrived` the state it would
+/// have at the destination. Fuel is taken from `origin` up front and the
+/// returned fleet stays at its departure point, in transit, until the
+/// arrival tick. Moves with no distance complete immediately.
+///
+/// # Arguments
+/// * `origin` - The fleet before moving
+/// * `arrived` - The fleet as it will be once the move completes
+/// * `breakdown` - Distances of the move
+/// * `now` - The current game tick
+///
+/// # Returns
+/// The fleet to persist, or an error if the fleet cannot make the trip
+pub fn begin_transit(mut origin: Fleet, arrived: Fleet, breakdown: &DistanceBreakdown, now: u64) -> Result<Fleet, String> {
+    if let Some(transit) = &origin.transit {
+        return Err(format!(
+            "Fleet {} is already in transit and arrives at tick {}",
+            origin.name, transit.arrival_tick
+        ));
+    }
+
+    if let Some(ship) = origin.ships.iter().find(|ship| ship.is_refitting(now)) {
+        return Err(format!(
+            "{} of fleet {} is being refitted until tick {}",
+            ship.name, origin.name, ship.refit_until.unwrap_or(now)
+        ));
+    }
+
+    let distance = breakdown.total_scaled;
+    let speed = fleet_speed(&origin)
+        .ok_or_else(|| format!("Fleet {} has no ships to move", origin.name))?;
+    let ticks = travel_ticks(distance, speed);
+    if ticks == 0 {
+        return Ok(arrived);
+    }
+
+    let fuel_needed = fuel_required(&origin, distance);
+    let fuel_available = fleet_fuel(&origin);
+    if fuel_needed > fuel_available {
+        return Err(format!(
+            "Insufficient fuel: the trip needs {} fuel but fleet {} only carries {}",
+            fuel_needed, origin.name, fuel_available
+        ));
+    }
+    burn_fuel(&mut origin, fuel_needed);
+
+    let status = if breakdown.deep_space > 0.0 { ShipStatus::Warp } else { ShipStatus::SubLightTravel };
+    if status == ShipStatus::Warp {
+        // Leaving the system: the fleet is between systems until it arrives
+        origin.current_system_id = None;
+        origin.local_position = None;
+    }
+    set_ship_status(&mut origin.ships, &status);
+
+    origin.transit = Some(FleetTransit {
+        origin: origin.position,
+        destination: arrived.position,
+        destination_local: arrived.local_position,
+        destination_system_id: arrived.current_system_id,
+        departure_tick: now,
+        arrival_tick: now + ticks,
+        distance,
+        fuel_used: fuel_needed,
+        status,
+    });
+    Ok(origin)
+}
+
+/// Advances a fleet in transit to `tick`.
+///
+/// Fleets at warp are moved along the straight line to their destination;
+/// once the arrival tick is reached the fleet is placed at its destination.
+///
+/// # Returns
+/// `true` if the fleet arrived on this tick
+pub fn advance_transit(fleet: &mut Fleet, tick: u64) -> bool {
+    let Some(transit) = fleet.transit.clone() else {
+        return false;
+    };
+
+    if tick >= transit.arrival_tick {
+        fleet.update_position(transit.destination);
+        fleet.local_position = transit.destination_local;
+        fleet.current_system_id = transit.destination_system_id;
+        fleet.last_move_distance = Some(transit.distance);
+        fleet.transit = None;
+        set_ship_status(&mut fleet.ships, &ShipStatus::Stationary);
+        return true;
+    }
+
+    if transit.status == ShipStatus::Warp {
+        let total = (transit.arrival_tick - transit.departure_tick) as f64;
+        let progress = (tick.saturating_sub(transit.departure_tick)) as f64 / total;
+        let lerp = |from: i32, to: i32| from + ((to - from) as f64 * progress).round() as i32;
+        fleet.update_position(Position {
+            x: lerp(transit.origin.x, transit.destination.x),
+            y: lerp(transit.origin.y, transit.destination.y),
+            z: lerp(transit.origin.z, transit.destination.z),
+        });
+    }
+    false
+}
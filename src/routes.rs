@@ -1,39 +1,61 @@
-use crate::models::game_world::get_global_game_world;
-use crate::models::game_state::GAME_STATE;
+use crate::models::game_world::load_saved_star_systems;
+use crate::models::persistence::{Transaction, recover_game};
+use crate::models::archive::GameArchive;
+use crate::models::migration::{CURRENT_SCHEMA_VERSION, MigrationReport, migrate_game};
+use crate::models::repair::{RepairData, RepairEstimate, apply_repairs, quote_fleet_repair};
+use crate::models::cargo::{CargoReceipt, CargoTransfer, CargoTransferData, FillPolicy};
+use crate::models::fleet_management::{self, FleetStanceData, MergeFleetData, RenameFleetData, SplitFleetData, TransferShipsData, validate_fleet_name};
+use crate::models::exploration::{EXPLORER_SCAN_RANGE, FleetSight, KnownMarket, PlayerKnowledge, ScanReport, Visibility, load_system_markets, refresh_knowledge};
+use crate::models::spatial::{SegmentHit, with_galaxy_index};
+use crate::models::route::{Route, RoutePlanData, depart_on_route, plan_route};
+use crate::models::storage::{StorageMigrationData, StorageMigrationReport, init_storage, list_documents, load_document, migrate_storage, storage_for};
+use crate::models::outfitting::{EquipmentMarket, InstallData, OutfitReceipt, UninstallData, install_from_market, uninstall_to_market};
+use crate::models::context::{CurrentGame, GameRegistry, validate_game_id};
+use crate::models::auth::{self, Credentials, PlayerSession, Session, SessionStore};
+use crate::models::events::{self, GameEventKind};
+use crate::models::encounter::{Encounter, EncounterAction, EncounterActionData, EncounterLog, EncounterReport, EncounterStatus, encounter_danger, resolve_encounter, roll_encounters};
+use crate::combat::combat::{CombatOrders, auto_resolve_ship_combat_with_orders};
+use crate::models::salvage::{CaptureData, LootData, SalvageField, SalvageLog, ScrapData, capture_ship, collect_salvage, loot_cargo, scrap_ship};
 use std::fs::File;
-use std::path::Path;
-use rocket::Request;
+use rocket::{Request, Shutdown, State};
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
 use rocket::{get, delete};
 use std::io::Read;
 use crate::models::star_system::StarSystem;
 use rocket::catch;
 use rocket::serde::json::Json;
-use crate::models::fleet::{Fleet, generate_and_save_fleet, list_owner_fleets, save_fleet, MoveFleetResponse, MoveFleetData};
+use crate::models::fleet::{Fleet, generate_and_save_fleet, list_all_fleets, list_owner_fleets, save_fleet, MoveFleetResponse, MoveFleetData};
 use crate::models::resource::{Resource, ResourceType};
 use crate::models::player::Player;
-use rand::{Rng, thread_rng};
-use serde_json;
+use rand::Rng;
+use rand::distributions::Standard;
+use crate::models::rng::{next_game_rng, rng_for};
 use crate::models::position::{Position, random_position};
 use std::fs;
-use crate::encounters::generate_encounter_fleet;
 use rocket::post;
-use serde::Deserialize;
-use crate::models::ship::ship::{Ship, ShipType, ShipSize, ShipEngine};
-use crate::models::market::{Market, ShipMarket, regenerate_system_markets, calculate_ship_price};
+use serde::Serialize;
+use crate::models::market::{Market, MarketQuote, ShipMarket, regenerate_system_markets, calculate_ship_price};
 use crate::models::response::ApiResponse;
-use crate::models::game_state::{load_player, load_star_system, save_trade_state, game_path, ensure_parent_dirs, save_json, load_json, load_fleet};
+use crate::models::game_state::{load_player, load_star_system, load_fleet, data_root, game_data_path};
 use crate::models::trade::{ResourceTradeData, ShipTradeData, ShipTradeInData, trade_with_fleet};
 use crate::models::settings::{GameSettings, SavedGame, load_settings};
 use chrono::Utc;
 use std::collections::HashMap;
-use crate::models::faction::{Faction, save_faction};
+use crate::models::faction::{Faction, FactionStanding, ReputationChange, Standing, save_faction, list_factions, generate_relations, assign_territories, apply_reputation_change, ATTACK_REPUTATION_PENALTY, TRADE_REPUTATION_GAIN, controlling_faction, record_piracy};
 use crate::models::planet::PlanetSpecialization;
 use crate::models::economy::Economy;
 use std::error::Error;
 use strum::IntoEnumIterator;
 use std::sync::{Arc, Mutex};
+use crate::models::clock::{GameClock, GameTime, current_tick, stardate_at};
+use crate::models::travel::begin_transit;
+use crate::models::mission::{Mission, MissionBoard};
+use crate::models::npc_trader::{TraderProfile, is_npc_trader, trade_with_npc_trader, NPC_TRADER_COUNT, NPC_TRADER_OWNER_PREFIX};
+use crate::simulation::tick::{self, AdvanceTimeReport};
 
-use crate::models::planet::{load_planet_market, load_planet_ship_market};
+use crate::models::planet::load_planet_ship_market;
 
 
 #[catch(500)]
@@ -42,9 +64,6 @@ pub fn internal_error(_req: &Request) -> Json<ApiResponse<String>> {
 }
 
 // Possible future features::
-// TODO: 
-// Implement multiplayer support
-// Implement player authentication
 // TODO: Implement game events system
 
 /// Handles the root route (`/`) and renders the index page
@@ -54,8 +73,11 @@ pub fn index() -> Json<ApiResponse<String>> {
 }
 
 #[get("/player/<name>")]
-pub fn get_player(name: &str) -> Json<ApiResponse<Player>> {
+pub fn get_player(game: CurrentGame, session: PlayerSession, name: &str) -> Json<ApiResponse<Player>> {
+    let _game = game.enter();
     let result: Result<Player, String> = (|| {
+        // Players see their own details; admins see everyone's
+        session.authorize(name).or_else(|_| session.require_admin())?;
         let mut player = load_player(name)?;
         // Mirror summed cargo across all owned fleets into player.resources
         let fleets = list_owner_fleets(name).map_err(|e| e.to_string())?;
@@ -74,8 +96,6 @@ pub fn get_player(name: &str) -> Json<ApiResponse<Player>> {
             .map(|rt| Resource { resource_type: rt, buy: None, sell: None, quantity: Some(*totals.get(&rt).unwrap_or(&0)) })
             .collect();
         player.resources = updated;
-        // Best effort save so totals persist; ignore save error to not fail GET
-        let _ = player.save();
         Ok(player)
     })();
 
@@ -85,15 +105,23 @@ pub fn get_player(name: &str) -> Json<ApiResponse<Player>> {
     }
 }
 
-// Returns a serialized JSON string representation of the galaxy map
+// Returns the part of the galaxy map the player has charted
 #[get("/galaxy_map")]
-pub fn get_galaxy_map() -> Json<ApiResponse<Vec<StarSystem>>> {
-    let _settings = load_settings().expect("Failed to load settings");
-    let game_world_path = game_path(&["GameWorld.json"]);
+pub fn get_galaxy_map(game: CurrentGame, session: PlayerSession) -> Json<ApiResponse<Vec<StarSystem>>> {
+    // Charting saves what the player's fleets see now
+    let _game = game.enter_for_writes();
+    let settings = load_settings().expect("Failed to load settings");
+    let player_name = match session.player_name() {
+        Ok(player_name) => player_name,
+        Err(e) => return ApiResponse::error(e),
+    };
 
     // Try to load from GameWorld.json
-    if let Ok(world) = load_json::<Vec<StarSystem>>(&game_world_path) {
-        return ApiResponse::success(world, "Successfully retrieved galaxy map".to_string());
+    if let Ok(world) = crate::models::game_world::load_game_world(&settings.game_id) {
+        return match refresh_knowledge(&settings.game_id, player_name, &world, current_tick()) {
+            Ok(knowledge) => ApiResponse::success(knowledge.filter_galaxy(&world), "Successfully retrieved galaxy map".to_string()),
+            Err(e) => ApiResponse::error(e),
+        };
     }
 
     // If GameWorld.json doesn't exist or can't be loaded, return empty vector
@@ -102,83 +130,71 @@ pub fn get_galaxy_map() -> Json<ApiResponse<Vec<StarSystem>>> {
 
 // Returns a star system with the given id from the galaxy map as a serialized JSON string
 #[get("/star_system/<system_id>")]
-pub fn get_star_system(system_id: usize) -> Json<ApiResponse<StarSystem>> {
+pub fn get_star_system(game: CurrentGame, session: PlayerSession, system_id: usize) -> Json<ApiResponse<StarSystem>> {
+    // Charting saves what the player's fleets see now
+    let _game = game.enter_for_writes();
     let settings = load_settings().expect("Failed to load settings");
-    let system_path = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("star_systems")
-        .join(format!("Star_System_{}.json", system_id));
+    let system_path = ["star_systems", &format!("Star_System_{}.json", system_id)];
 
-    match load_json::<StarSystem>(&system_path) {
-        Ok(system) => ApiResponse::success(system, "Successfully retrieved star system".to_string()),
+    let system = match load_document::<StarSystem>(&settings.game_id, &system_path).and_then(|system| system.ok_or_else(|| "not saved".to_string())) {
+        Ok(system) => Ok(system),
         Err(e) => {
             println!("Error loading star system: {}", e);
             // Try loading from the game world file
-            let game_world_path = game_path(&["GameWorld.json"]);
-            match load_json::<Vec<StarSystem>>(&game_world_path) {
-                Ok(systems) => {
-                    if let Some(sys) = systems.iter().find(|s| s.id == system_id) {
-                        ApiResponse::success(sys.clone(), "Successfully retrieved star system from game world".to_string())
-                    } else {
-                        ApiResponse::error(format!("System ID {} not found in game world", system_id))
-                    }
-                },
-                Err(e) => ApiResponse::error(format!("Failed to load star system: {}", e))
+            match crate::models::game_world::load_game_world(&settings.game_id) {
+                Ok(systems) => systems.into_iter().find(|s| s.id == system_id)
+                    .ok_or_else(|| format!("System ID {} not found in game world", system_id)),
+                Err(e) => Err(format!("Failed to load star system: {}", e))
             }
         }
+    };
+
+    // Players only see what they have charted of the system
+    let result = system.and_then(|system| {
+        let player_name = session.player_name()?;
+        let world = crate::models::game_world::load_game_world(&settings.game_id)
+            .map_err(|e| format!("Failed to load game world: {}", e))?;
+        refresh_knowledge(&settings.game_id, player_name, &world, current_tick())?
+            .filter_system(&system)
+            .ok_or_else(|| format!("System ID {} is not on your charts", system_id))
+    });
+
+    match result {
+        Ok(system) => ApiResponse::success(system, "Successfully retrieved star system".to_string()),
+        Err(e) => ApiResponse::error(e)
     }
 }
 
+/// Lists an owner's fleets; other players' fleets are only listed while the
+/// player's own fleets can see them
 #[get("/fleet/<owner_id>")]
-pub fn get_owner_fleets(owner_id: String) -> Json<ApiResponse<Vec<Fleet>>> {
+pub fn get_owner_fleets(game: CurrentGame, session: PlayerSession, owner_id: String) -> Json<ApiResponse<Vec<Fleet>>> {
+    let _game = game.enter();
     println!("Getting fleets for owner: {}", owner_id);
 
     let result: Result<Vec<Fleet>, String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let fleets_dir = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("fleets");
+        let sight = FleetSight::for_player(session.player_name()?)?;
+        let file_names = list_documents(&settings.game_id, &["fleets"])?;
 
-        println!("Looking for fleets in directory: {}", fleets_dir.display());
         let mut fleets = Vec::new();
-
-        if fleets_dir.exists() {
-            if let Ok(entries) = fs::read_dir(fleets_dir) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        if let Some(file_name) = entry.file_name().to_str() {
-                            println!("Found fleet file: {}", file_name);
-                            // Handle fleet naming scheme
-                            if file_name.starts_with("Fleet_") {
-                                let parts: Vec<&str> = file_name.split('_').collect();
-                                if parts.len() >= 3 {
-                                    let fleet_owner = parts[1];
-                                    if fleet_owner == owner_id {
-                                        let fleet_name = file_name.trim_end_matches(".json");
-                                        println!("Loading fleet: {}", fleet_name);
-                                        match crate::models::fleet::load_fleet(fleet_name) {
-                                            Ok(Some(fleet)) => {
-                                                println!("Successfully loaded fleet: {} with {} ships", fleet.name, fleet.ships.len());
-                                                fleets.push(fleet);
-                                            },
-                                            Ok(None) => println!("Fleet not found: {}", fleet_name),
-                                            Err(e) => println!("Error loading fleet {}: {}", fleet_name, e),
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+        for file_name in &file_names {
+            println!("Found fleet file: {}", file_name);
+            // Handle fleet naming scheme
+            let parts: Vec<&str> = file_name.split('_').collect();
+            if file_name.starts_with("Fleet_") && parts.len() >= 3 && parts[1] == owner_id {
+                let fleet_name = file_name.trim_end_matches(".json");
+                println!("Loading fleet: {}", fleet_name);
+                match crate::models::fleet::load_fleet(fleet_name) {
+                    Ok(Some(fleet)) if sight.can_see(&fleet) => {
+                        println!("Successfully loaded fleet: {} with {} ships", fleet.name, fleet.ships.len());
+                        fleets.push(fleet);
+                    },
+                    Ok(Some(_)) => {},
+                    Ok(None) => println!("Fleet not found: {}", fleet_name),
+                    Err(e) => println!("Error loading fleet {}: {}", fleet_name, e),
                 }
-            } else {
-                println!("Error reading fleets directory");
-                return Err("Failed to read fleets directory".to_string());
             }
-        } else {
-            println!("Fleets directory does not exist");
-            return Err("Fleets directory not found".to_string());
         }
 
         println!("Found {} fleets for owner {}", fleets.len(), owner_id);
@@ -192,38 +208,18 @@ pub fn get_owner_fleets(owner_id: String) -> Json<ApiResponse<Vec<Fleet>>> {
 }
 
 #[get("/fleet/<owner_id>/<fleet_number>")]
-pub fn get_fleet(owner_id: String, fleet_number: usize) -> Json<ApiResponse<Fleet>> {
+pub fn get_fleet(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter();
     println!("Getting fleet {} for owner: {}", fleet_number, owner_id);
 
     let result: Result<Fleet, String> = (|| {
-        // First try to load by owner_id (for regular fleets)
+        let sight = FleetSight::for_player(session.player_name()?)?;
         let fleet_name = format!("Fleet_{}_{}", owner_id, fleet_number);
-        
         println!("Looking for fleet with name: {}", fleet_name);
-        
-        match crate::models::fleet::load_fleet(&fleet_name) {
-            Ok(Some(fleet)) => Ok(fleet),
-            Ok(None) => {
-                // If not found, try to load by fleet type (for special fleets)
-                let special_types = vec!["Pirate", "Trader", "Military", "Mercenary"];
-                for fleet_type in special_types {
-                    let fleet_name = format!("Fleet_{}_{}", fleet_type, fleet_number);
-                    match crate::models::fleet::load_fleet(&fleet_name) {
-                        Ok(Some(fleet)) => return Ok(fleet),
-                        Ok(None) => continue,
-                        Err(e) => {
-                            println!("Error loading special fleet: {}", e);
-                            continue;
-                        }
-                    }
-                }
-                Err("Fleet not found".to_string())
-            }
-            Err(e) => {
-                println!("Error loading fleet: {}", e);
-                Err(e.to_string())
-            }
-        }
+        // Fleets out of sight are reported as missing, like fleets that do not exist
+        crate::models::fleet::load_fleet(&fleet_name)?
+            .filter(|fleet| sight.can_see(fleet))
+            .ok_or_else(|| "Fleet not found".to_string())
     })();
 
     match result {
@@ -232,86 +228,253 @@ pub fn get_fleet(owner_id: String, fleet_number: usize) -> Json<ApiResponse<Flee
     }
 }
 
+/// Returns a planet's market as the player last saw it; fleets in the
+/// system keep the information current
 #[get("/planet/<system_id>/<planet_id>/market")]
-pub fn get_planet_market(system_id: usize, planet_id: usize) -> Json<ApiResponse<Market>> {
-    match load_planet_market(system_id, planet_id) {
+pub fn get_planet_market(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize) -> Json<ApiResponse<KnownMarket>> {
+    // Charting saves what the player's fleets see now
+    let _game = game.enter_for_writes();
+    let result: Result<KnownMarket, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let world = crate::models::game_world::load_game_world(&settings.game_id)
+            .map_err(|e| format!("Failed to load game world: {}", e))?;
+        let now = current_tick();
+        refresh_knowledge(&settings.game_id, player_name, &world, now)?
+            .known_market(system_id, planet_id, now)
+    })();
+
+    match result {
         Ok(market) => ApiResponse::success(market, "Successfully retrieved market".to_string()),
         Err(e) => ApiResponse::error(e)
     }
 }
 
+/// Quotes the total price of buying or selling `quantity` units of a resource,
+/// including the price movement the trade itself causes.
+/// The quote uses the market as the player last saw it.
+#[get("/planet/<system_id>/<planet_id>/market/quote/<resource_type>/<quantity>")]
+pub fn get_market_quote(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, resource_type: ResourceType, quantity: u32) -> Json<ApiResponse<MarketQuote>> {
+    // Charting saves what the player's fleets see now
+    let _game = game.enter_for_writes();
+    let result: Result<MarketQuote, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let world = crate::models::game_world::load_game_world(&settings.game_id)
+            .map_err(|e| format!("Failed to load game world: {}", e))?;
+        let now = current_tick();
+        let known = refresh_knowledge(&settings.game_id, player_name, &world, now)?
+            .known_market(system_id, planet_id, now)?;
+        Ok(known.market.quote(resource_type, quantity))
+    })();
+
+    match result {
+        Ok(quote) => ApiResponse::success(quote, "Successfully quoted trade".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Returns a planet's shipyard, for systems the player has surveyed
 #[get("/planet/<system_id>/<planet_id>/ships")]
-pub fn get_planet_ship_market(system_id: usize, planet_id: usize) -> Json<ApiResponse<ShipMarket>> {
-    match load_planet_ship_market(system_id, planet_id) {
+pub fn get_planet_ship_market(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize) -> Json<ApiResponse<ShipMarket>> {
+    let _game = game.enter();
+    let result = check_surveyed(&session, system_id)
+        .and_then(|_| load_planet_ship_market(system_id, planet_id));
+    match result {
         Ok(market) => ApiResponse::success(market, "Successfully retrieved ship market".to_string()),
         Err(e) => ApiResponse::error(e)
     }
 }
 
+/// Fails unless the session's player has surveyed a system.
+fn check_surveyed(session: &PlayerSession, system_id: usize) -> Result<(), String> {
+    let settings = load_settings().map_err(|e| e.to_string())?;
+    PlayerKnowledge::load(&settings.game_id, session.player_name()?)?.check_surveyed(system_id)
+}
+
+/// Loads one of the player's fleets and checks that it is docked at a planet.
+fn load_fleet_at_planet(player_name: &str, fleet_name: &str, system_id: usize, planet: &crate::models::planet::Planet) -> Result<Fleet, String> {
+    let fleet = crate::models::fleet::load_fleet(fleet_name)?
+        .ok_or_else(|| format!("Fleet {} not found", fleet_name))?;
+    if fleet.owner_id != player_name {
+        return Err(format!("Fleet {} does not belong to {}", fleet_name, player_name));
+    }
+    if fleet.transit.is_some() || fleet.current_system_id != Some(system_id) || fleet.local_position != Some(planet.position) {
+        return Err(format!("Fleet must be at {} to use its services", planet.name));
+    }
+    Ok(fleet)
+}
+
+/// Quotes restoring the hull, armor and shields of a fleet's ships at a planet
+#[get("/planet/<system_id>/<planet_id>/repair?<fleet_name>&<ship_index>")]
+pub fn get_repair_quote(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, fleet_name: String, ship_index: Option<usize>) -> Json<ApiResponse<RepairEstimate>> {
+    let _game = game.enter();
+    let result: Result<RepairEstimate, String> = (|| {
+        let player_name = session.player_name()?;
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
+        let fleet = load_fleet_at_planet(player_name, &fleet_name, system_id, planet)?;
+        quote_fleet_repair(&fleet, planet, ship_index)
+    })();
+
+    match result {
+        Ok(estimate) => ApiResponse::success(estimate, "Successfully quoted repairs".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Repairs a fleet's ships at a planet, charging the player the quoted price
+#[post("/planet/<system_id>/<planet_id>/repair", format = "json", data = "<data>")]
+pub fn repair_at_planet(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<RepairData>) -> Json<ApiResponse<RepairEstimate>> {
+    let _game = game.enter_for_writes();
+    let result: Result<RepairEstimate, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name)?;
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
+        let mut fleet = load_fleet_at_planet(player_name, &data.fleet_name, system_id, planet)?;
+
+        let estimate = quote_fleet_repair(&fleet, planet, data.ship_index)?;
+        if estimate.ships.is_empty() {
+            return Err("No repairs needed".to_string());
+        }
+        if player.credits < estimate.total_cost {
+            return Err(format!("Insufficient credits: repairs cost {:.0} but you have {:.0}", estimate.total_cost, player.credits));
+        }
+        println!("Repairing {} ships of {} at {} for {} credits", estimate.ships.len(), fleet.name, planet.name, estimate.total_cost);
+        player.credits -= estimate.total_cost;
+        apply_repairs(&mut fleet, &estimate);
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.stage_player(&player)?;
+        transaction.commit()?;
+        Ok(estimate)
+    })();
+
+    match result {
+        Ok(estimate) => {
+            let message = format!("Repaired {} ships for {:.0} credits", estimate.ships.len(), estimate.total_cost);
+            ApiResponse::success(estimate, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Returns a planet's equipment yard, for systems the player has surveyed
+#[get("/planet/<system_id>/<planet_id>/equipment")]
+pub fn get_equipment_market(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize) -> Json<ApiResponse<EquipmentMarket>> {
+    // A yard is stocked, and saved, the first time it is visited
+    let _game = game.enter_for_writes();
+    let result: Result<EquipmentMarket, String> = (|| {
+        check_surveyed(&session, system_id)?;
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
+        EquipmentMarket::load_or_generate(system_id, planet_id, planet)
+    })();
+
+    match result {
+        Ok(market) => ApiResponse::success(market, "Successfully retrieved equipment market".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Buys a component from a planet's yard and fits it to one of the player's ships
+#[post("/planet/<system_id>/<planet_id>/equipment/install", format = "json", data = "<data>")]
+pub fn install_equipment(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<InstallData>) -> Json<ApiResponse<OutfitReceipt>> {
+    let _game = game.enter_for_writes();
+    let result: Result<OutfitReceipt, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name)?;
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
+        let mut fleet = load_fleet_at_planet(player_name, &data.fleet_name, system_id, planet)?;
+        let mut market = EquipmentMarket::load_or_generate(system_id, planet_id, planet)?;
+
+        let ship = fleet.ships.get_mut(data.ship_index)
+            .ok_or_else(|| format!("Invalid ship index: {}", data.ship_index))?;
+        let receipt = install_from_market(&mut market, planet, data.offer_index, ship, player.credits, current_tick())?;
+        println!("Installed {} on {} at {} for {} credits", receipt.equipment, receipt.ship_name, planet.name, -receipt.credits_change);
+        player.credits += receipt.credits_change;
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.stage_player(&player)?;
+        transaction.stage_equipment_market(system_id, planet_id, &market)?;
+        transaction.commit()?;
+        Ok(receipt)
+    })();
+
+    match result {
+        Ok(receipt) => {
+            let message = format!("Installed {} on {}, ready at tick {}", receipt.equipment, receipt.ship_name, receipt.ready_tick);
+            ApiResponse::success(receipt, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Removes a weapon or module from one of the player's ships and sells it to the planet's yard
+#[post("/planet/<system_id>/<planet_id>/equipment/uninstall", format = "json", data = "<data>")]
+pub fn uninstall_equipment(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<UninstallData>) -> Json<ApiResponse<OutfitReceipt>> {
+    let _game = game.enter_for_writes();
+    let result: Result<OutfitReceipt, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name)?;
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
+        let mut fleet = load_fleet_at_planet(player_name, &data.fleet_name, system_id, planet)?;
+        let mut market = EquipmentMarket::load_or_generate(system_id, planet_id, planet)?;
+
+        let ship = fleet.ships.get_mut(data.ship_index)
+            .ok_or_else(|| format!("Invalid ship index: {}", data.ship_index))?;
+        let receipt = uninstall_to_market(&mut market, planet, ship, data.slot, data.index, player.credits, current_tick())?;
+        println!("Removed {} from {} at {} for {} credits", receipt.equipment, receipt.ship_name, planet.name, receipt.credits_change);
+        player.credits += receipt.credits_change;
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.stage_player(&player)?;
+        transaction.stage_equipment_market(system_id, planet_id, &market)?;
+        transaction.commit()?;
+        Ok(receipt)
+    })();
+
+    match result {
+        Ok(receipt) => {
+            let message = format!("Removed {} from {}, ready at tick {}", receipt.equipment, receipt.ship_name, receipt.ready_tick);
+            ApiResponse::success(receipt, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
 #[post("/planet/<system_id>/<planet_id>/buy", format = "json", data = "<data>")]
-pub fn buy_from_planet(system_id: usize, planet_id: usize, data: Json<ResourceTradeData>) -> Json<ApiResponse<String>> {
-    let result: Result<String, String> = (|| {
+pub fn buy_from_planet(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<ResourceTradeData>) -> Json<ApiResponse<CargoReceipt>> {
+    let _game = game.enter_for_writes();
+    let result: Result<CargoReceipt, String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let mut player = load_player(&settings.player_name)?;
-        let mut system = load_star_system(system_id)?;
+        let player_name = session.player_name()?;
+        let mut transaction = Transaction::new(&settings.game_id);
+        let mut player = load_player(player_name)?;
+        let system = load_star_system(system_id)?;
         let mut market = Market::load(system_id, planet_id).map_err(|e| e.to_string())?;
         
-        let planet = system.planets.get_mut(planet_id)
+        let planet = system.planets.get(planet_id)
             .ok_or_else(|| "Planet not found".to_string())?;
 
         // Enforce fleet selection and co-location for trading
         let fleet_name = data.fleet_name.clone().ok_or_else(|| "Select a fleet to trade at this planet".to_string())?;
-        if let Ok(Some(fleet_for_check)) = crate::models::fleet::load_fleet(&fleet_name) {
-            // Validate system id
-            if fleet_for_check.current_system_id != Some(system_id) {
-                return Err("Fleet must be in this system to trade".to_string());
-            }
-            // Validate strict local position
-            let lp = fleet_for_check.local_position.as_ref().ok_or_else(|| "Fleet must be at this planet to trade".to_string())?;
-            if lp.x != planet.position.x || lp.y != planet.position.y || lp.z != planet.position.z {
-                return Err("Fleet must be at this planet to trade".to_string());
-            }
-        } else {
-            return Err("Fleet not found for trading".to_string());
-        }
+        let mut fleet = load_fleet_at_planet(player_name, &fleet_name, system_id, planet)?;
         
-        // 1) Capacity pre-check: if buying into a fleet, ensure enough cargo space
-        if let Ok(Some(fleet)) = crate::models::fleet::load_fleet(&fleet_name) {
-                let mode = data.distribution_mode.clone().unwrap_or_else(|| "first".to_string());
-                let mut capacity_available: u32 = 0;
-                match mode.as_str() {
-                    "specific" => {
-                        if let Some(allocs) = &data.allocations {
-                            for alloc in allocs {
-                                if let Some(ship) = fleet.ships.get(alloc.ship_index) {
-                                    let space = ship.get_cargo_capacity().saturating_sub(ship.get_current_cargo());
-                                    capacity_available = capacity_available.saturating_add(space);
-                                }
-                            }
-                        }
-                    }
-                    "even" => {
-                        for ship in &fleet.ships {
-                            let space = ship.get_cargo_capacity().saturating_sub(ship.get_current_cargo());
-                            capacity_available = capacity_available.saturating_add(space);
-                        }
-                    }
-                    _ => {
-                        if let Some(ship) = fleet.ships.first() {
-                            capacity_available = ship.get_cargo_capacity().saturating_sub(ship.get_current_cargo());
-                        }
-                    }
-                }
-                if capacity_available < data.quantity {
-                    return Err(format!(
-                        "Not enough cargo capacity in selected ship(s). Required {}, available {}",
-                        data.quantity, capacity_available
-                    ));
-                }
-        }
+        // 1) Stow the goods first so a partial fill only pays for what fit
+        let cargo = fleet.load_cargo(data.resource_type, data.quantity, &data.distribution()?, data.fill_policy())?;
 
         // 2) Calculate total cost and update market quantities
-        let total_cost = market.buy_resource(data.resource_type, data.quantity, system_id, planet_id)
+        let total_cost = market.buy_resource(data.resource_type, cargo.moved, system_id, planet_id)
             .map_err(|e| e.to_string())?;
         
         // 3) Check if player has enough credits
@@ -321,158 +484,479 @@ pub fn buy_from_planet(system_id: usize, planet_id: usize, data: Json<ResourceTr
         
         // 4) Update player's credits
         player.credits -= total_cost;
-
-        // 5) Add resources to a concrete fleet's cargo when provided, enforcing capacity
-        if let Ok(Some(mut fleet)) = crate::models::fleet::load_fleet(&fleet_name) {
-                let mode = data.distribution_mode.clone().unwrap_or_else(|| "first".to_string());
-                let mut remaining = data.quantity;
-                match mode.as_str() {
-                    "specific" => {
-                        if let Some(allocs) = &data.allocations {
-                            for alloc in allocs {
-                                if remaining == 0 { break; }
-                                if let Some(ship) = fleet.ships.get_mut(alloc.ship_index) {
-                                    let cap = ship.get_cargo_capacity();
-                                    let used = ship.get_current_cargo();
-                                    let space = cap.saturating_sub(used);
-                                    let desired = alloc.quantity.min(remaining);
-                                    let add_q = desired.min(space);
-                                    let mut found = false;
-                                    for c in &mut ship.cargo { if c.resource_type == data.resource_type { c.quantity = Some(c.quantity.unwrap_or(0) + add_q); found = true; break; } }
-                                    if !found { ship.cargo.push(Resource { resource_type: data.resource_type, buy: None, sell: None, quantity: Some(add_q) }); }
-                                    remaining -= add_q;
-                                }
-                            }
-                        }
-                    },
-                    "even" => {
-                        // Round-robin place items respecting capacity
-                        while remaining > 0 {
-                            let mut progressed = false;
-                            for ship in &mut fleet.ships {
-                                if remaining == 0 { break; }
-                                let cap = ship.get_cargo_capacity();
-                                let used = ship.get_current_cargo();
-                                let space = cap.saturating_sub(used);
-                                if space == 0 { continue; }
-                                let add_q = 1u32.min(remaining).min(space);
-                                let mut found=false; for c in &mut ship.cargo { if c.resource_type==data.resource_type { c.quantity=Some(c.quantity.unwrap_or(0)+add_q); found=true; break; } }
-                                if !found { ship.cargo.push(Resource{resource_type:data.resource_type,buy:None,sell:None,quantity:Some(add_q)}); }
-                                remaining -= add_q; progressed = true;
-                            }
-                            if !progressed { break; }
-                        }
-                    },
-                    _ => {
-                        if let Some(ship) = fleet.ships.first_mut() {
-                            let cap = ship.get_cargo_capacity();
-                            let used = ship.get_current_cargo();
-                            let space = cap.saturating_sub(used);
-                            let add_q = remaining.min(space);
-                            if add_q > 0 {
-                                let mut found = false; for c in &mut ship.cargo { if c.resource_type==data.resource_type { c.quantity=Some(c.quantity.unwrap_or(0)+add_q); found=true; break; } }
-                                if !found { ship.cargo.push(Resource{resource_type:data.resource_type,buy:None,sell:None,quantity:Some(add_q)}); }
-                                remaining -= add_q;
-                            }
-                        }
-                    }
-                }
-                if remaining > 0 { return Err("Not enough cargo capacity to store purchased goods".to_string()); }
-                crate::models::fleet::save_fleet(&fleet).map_err(|e| e.to_string())?;
-        }
         
-        // Save both player and market state
-        player.save().map_err(|e| e.to_string())?;
-        market.save(system_id, planet_id).map_err(|e| e.to_string())?;
+        // Fleet, player and market are saved together or not at all
+        transaction.stage_fleet(&fleet)?;
+        transaction.stage_player(&player)?;
+        transaction.stage_market(system_id, planet_id, &market)?;
+        transaction.commit()?;
+        events::publish_market_prices(system_id, planet_id, &market, data.resource_type);
         
-        Ok("Successfully bought resource".to_string())
+        Ok(cargo)
     })();
 
     match result {
-        Ok(message) => ApiResponse::success(message, "Trade completed successfully".to_string()),
+        Ok(cargo) => {
+            let message = format!("Bought {} of {} {}", cargo.moved, cargo.requested, cargo.resource_type);
+            ApiResponse::success(cargo, message)
+        }
         Err(e) => ApiResponse::error(e)
     }
 }
 
 #[post("/planet/<system_id>/<planet_id>/sell", format = "json", data = "<data>")]
-pub fn sell_to_planet(system_id: usize, planet_id: usize, data: Json<ResourceTradeData>) -> Json<ApiResponse<String>> {
-    let result: Result<String, String> = (|| {
+pub fn sell_to_planet(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<ResourceTradeData>) -> Json<ApiResponse<CargoReceipt>> {
+    let _game = game.enter_for_writes();
+    let result: Result<(CargoReceipt, String), String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let mut player = load_player(&settings.player_name)?;
-        let mut system = load_star_system(system_id)?;
+        let player_name = session.player_name()?;
+        let mut transaction = Transaction::new(&settings.game_id);
+        let mut player = load_player(player_name)?;
+        let system = load_star_system(system_id)?;
         let mut market = Market::load(system_id, planet_id).map_err(|e| e.to_string())?;
         
-        let planet = system.planets.get_mut(planet_id)
+        let planet = system.planets.get(planet_id)
             .ok_or_else(|| "Planet not found".to_string())?;
 
         // Enforce fleet selection and co-location for selling
         let fleet_name = data.fleet_name.clone().ok_or_else(|| "Select a fleet to trade at this planet".to_string())?;
-        if let Ok(Some(fleet_for_check)) = crate::models::fleet::load_fleet(&fleet_name) {
-            if fleet_for_check.current_system_id != Some(system_id) {
-                return Err("Fleet must be in this system to trade".to_string());
-            }
-            let lp = fleet_for_check.local_position.as_ref().ok_or_else(|| "Fleet must be at this planet to trade".to_string())?;
-            if lp.x != planet.position.x || lp.y != planet.position.y || lp.z != planet.position.z {
-                return Err("Fleet must be at this planet to trade".to_string());
-            }
-        } else {
-            return Err("Fleet not found for trading".to_string());
-        }
-        
-        // Determine source of inventory: fleet cargo takes precedence when provided
-        if let Ok(Some(mut fleet)) = crate::models::fleet::load_fleet(&fleet_name) {
-                let mode = data.distribution_mode.clone().unwrap_or_else(|| "first".to_string());
-                let mut remaining = data.quantity;
-                match mode.as_str() {
-                    "specific" => {
-                        if let Some(allocs) = &data.allocations {
-                            for alloc in allocs { if remaining==0 { break; }
-                                if let Some(ship) = fleet.ships.get_mut(alloc.ship_index) {
-                                    let mut need = alloc.quantity.min(remaining);
-                                    for cargo in &mut ship.cargo { if cargo.resource_type==data.resource_type { let have=cargo.quantity.unwrap_or(0); let take=have.min(need); cargo.quantity=Some(have-take); need-=take; break; } }
-                                    remaining -= alloc.quantity.min(alloc.quantity - need);
-                                }
-                            }
-                        }
-                    },
-                    "even" => {
-                        // One pass to take proportionally as evenly as possible
-                        let mut idx = 0usize;
-                        while remaining>0 && !fleet.ships.is_empty() {
-                            if idx>=fleet.ships.len() { idx=0; }
-                            let ship=&mut fleet.ships[idx];
-                            let mut took = false;
-                            for cargo in &mut ship.cargo { if cargo.resource_type==data.resource_type { let have=cargo.quantity.unwrap_or(0); if have>0 { cargo.quantity=Some(have-1); remaining-=1; took=true; } break; } }
-                            if !took { idx+=1; } else { idx+=1; }
-                        }
-                    },
-                    _ => {
-                        for ship in &mut fleet.ships { for cargo in &mut ship.cargo { if cargo.resource_type==data.resource_type { let have=cargo.quantity.unwrap_or(0); if have>=remaining { cargo.quantity=Some(have-remaining); remaining=0; break; } else { cargo.quantity=Some(0); remaining-=have; } } } if remaining==0 { break; } }
-                    }
-                }
-                if remaining > 0 { return Err("Not enough resources in fleet cargo".to_string()); }
-                crate::models::fleet::save_fleet(&fleet).map_err(|e| e.to_string())?;
-            } else {
-                return Err("Fleet not found for selling".to_string());
-            }
-        
+        let mut fleet = load_fleet_at_planet(player_name, &fleet_name, system_id, planet)?;
+        let cargo = fleet.unload_cargo(data.resource_type, data.quantity, &data.distribution()?, data.fill_policy())?;
         
         // Calculate total value and update market quantities
-        let total_value = market.sell_resource(data.resource_type, data.quantity, system_id, planet_id)
+        let total_value = market.sell_resource(data.resource_type, cargo.moved, system_id, planet_id)
             .map_err(|e| e.to_string())?;
         
         // Update player's inventory and credits
         player.credits += total_value;
+
+        // Deliveries count toward transport missions bound for this planet
+        let completed = record_mission_progress(&mut player, &mut transaction, |board, player, factions| {
+            board.record_delivery(player, factions, system_id, planet_id, data.resource_type, cargo.moved)
+        })?;
         
-        // Save both player and market state
-        player.save().map_err(|e| e.to_string())?;
-        market.save(system_id, planet_id).map_err(|e| e.to_string())?;
+        // Fleet, player, market and missions are saved together or not at all
+        transaction.stage_fleet(&fleet)?;
+        transaction.stage_player(&player)?;
+        transaction.stage_market(system_id, planet_id, &market)?;
+        transaction.commit()?;
+        events::publish_market_prices(system_id, planet_id, &market, data.resource_type);
+        events::publish_missions(&completed);
         
-        Ok("Successfully sold resource".to_string())
+        let message = format!("Sold {} of {} {}", cargo.moved, cargo.requested, cargo.resource_type);
+        Ok((cargo, with_completed_missions(message, &completed)))
+    })();
+
+    match result {
+        Ok((cargo, message)) => ApiResponse::success(cargo, message),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Moves cargo between two ships of one of the player's fleets
+#[post("/fleet/<owner_id>/<fleet_number>/cargo/transfer", format = "json", data = "<data>")]
+pub fn transfer_fleet_cargo(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<CargoTransferData>) -> Json<ApiResponse<CargoTransfer>> {
+    let _game = game.enter_for_writes();
+    let result: Result<CargoTransfer, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+
+        let policy = if data.allow_partial { FillPolicy::Partial } else { FillPolicy::AllOrNothing };
+        let transfer = fleet.transfer_cargo(data.from_ship, data.to_ship, data.resource_type, data.quantity, policy)?;
+        println!("Moved {} {} from {} to {} in {}", transfer.moved, transfer.resource_type, transfer.from_ship, transfer.to_ship, fleet.name);
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.commit()?;
+        Ok(transfer)
+    })();
+
+    match result {
+        Ok(transfer) => {
+            let message = format!("Moved {} {} from {} to {}", transfer.moved, transfer.resource_type, transfer.from_ship, transfer.to_ship);
+            ApiResponse::success(transfer, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Loads one of the player's fleets by owner and number.
+fn load_owned_fleet(player_name: &str, owner_id: &str, fleet_number: usize) -> Result<Fleet, String> {
+    if owner_id != player_name {
+        return Err(format!("Fleet does not belong to {}", player_name));
+    }
+    let fleet_name = format!("Fleet_{}_{}", owner_id, fleet_number);
+    crate::models::fleet::load_fleet(&fleet_name)?
+        .ok_or_else(|| format!("Fleet {} not found", fleet_name))
+}
+
+/// Detaches ships from a fleet into a new fleet at the same position
+#[post("/fleet/<owner_id>/<fleet_number>/split", format = "json", data = "<data>")]
+pub fn split_fleet(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<SplitFleetData>) -> Json<ApiResponse<Vec<Fleet>>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Vec<Fleet>, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name)?;
+        let mut source = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+
+        let display_name = data.display_name.as_deref().map(validate_fleet_name).transpose()?;
+        let new_number = crate::models::fleet::get_next_fleet_number(&owner_id).map_err(|e| e.to_string())?;
+        let mut detached = fleet_management::split_fleet(&mut source, &data.ship_indices, new_number)?;
+        detached.display_name = display_name;
+        println!("Split {} ships from {} into {}", detached.ships.len(), source.name, detached.name);
+        player.add_fleet(&source.name);
+        player.add_fleet(&detached.name);
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&source)?;
+        transaction.stage_fleet(&detached)?;
+        transaction.stage_player(&player)?;
+        transaction.commit()?;
+        Ok(vec![source, detached])
+    })();
+
+    match result {
+        Ok(fleets) => {
+            let message = format!("Created {}", fleets[1].name);
+            ApiResponse::success(fleets, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Absorbs another of the player's fleets at the same position
+#[post("/fleet/<owner_id>/<fleet_number>/merge", format = "json", data = "<data>")]
+pub fn merge_fleet(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<MergeFleetData>) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Fleet, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name)?;
+        let mut target = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        let source = crate::models::fleet::load_fleet(&data.fleet_name)?
+            .ok_or_else(|| format!("Fleet {} not found", data.fleet_name))?;
+
+        let source_name = source.name.clone();
+        fleet_management::merge_fleets(&mut target, source)?;
+        println!("Merged {} into {}", source_name, target.name);
+        player.remove_fleet(&source_name);
+        player.add_fleet(&target.name);
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&target)?;
+        transaction.stage_fleet_removal(&source_name);
+        transaction.stage_player(&player)?;
+        transaction.commit()?;
+        Ok(target)
+    })();
+
+    match result {
+        Ok(fleet) => {
+            let message = format!("{} now has {} ships", fleet.name, fleet.ships.len());
+            ApiResponse::success(fleet, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Gives one of the player's fleets a name of their choosing
+#[post("/fleet/<owner_id>/<fleet_number>/rename", format = "json", data = "<data>")]
+pub fn rename_fleet(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<RenameFleetData>) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Fleet, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        fleet.display_name = Some(validate_fleet_name(&data.display_name)?);
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.commit()?;
+        Ok(fleet)
+    })();
+
+    match result {
+        Ok(fleet) => ApiResponse::success(fleet, "Successfully renamed fleet".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Sets the combat stance every ship of one of the player's fleets flies in
+#[post("/fleet/<owner_id>/<fleet_number>/stance", format = "json", data = "<data>")]
+pub fn set_fleet_stance(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<FleetStanceData>) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Fleet, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        crate::combat::combat::set_fleet_stance(&mut fleet, data.stance)?;
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.commit()?;
+        Ok(fleet)
+    })();
+
+    match result {
+        Ok(fleet) => ApiResponse::success(fleet, format!("Fleet now flies {:?}", data.stance)),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Moves ships to another of the player's fleets at the same position
+#[post("/fleet/<owner_id>/<fleet_number>/transfer_ships", format = "json", data = "<data>")]
+pub fn transfer_fleet_ships(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<TransferShipsData>) -> Json<ApiResponse<Vec<Fleet>>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Vec<Fleet>, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut from = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        let mut to = crate::models::fleet::load_fleet(&data.target_fleet)?
+            .ok_or_else(|| format!("Fleet {} not found", data.target_fleet))?;
+
+        fleet_management::transfer_ships(&mut from, &mut to, &data.ship_indices)?;
+        println!("Moved {} ships from {} to {}", data.ship_indices.len(), from.name, to.name);
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&from)?;
+        transaction.stage_fleet(&to)?;
+        transaction.commit()?;
+        Ok(vec![from, to])
+    })();
+
+    match result {
+        Ok(fleets) => ApiResponse::success(fleets, "Successfully transferred ships".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// The systems a player has charted; routes are only planned through these.
+fn charted_systems(game_id: &str, player_name: &str) -> Result<Vec<StarSystem>, String> {
+    let world = crate::models::game_world::load_game_world(game_id)
+        .map_err(|e| format!("Failed to load game world: {}", e))?;
+    Ok(refresh_knowledge(game_id, player_name, &world, current_tick())?.charted_systems(&world))
+}
+
+/// Plans a route for one of the player's fleets without following it
+#[post("/fleet/<owner_id>/<fleet_number>/route/plan", format = "json", data = "<data>")]
+pub fn plan_fleet_route(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<RoutePlanData>) -> Json<ApiResponse<Route>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Route, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        let systems = charted_systems(&settings.game_id, player_name)?;
+        plan_route(&fleet, &systems, data.system_id, data.planet_id, data.objective)
+    })();
+
+    match result {
+        Ok(route) => ApiResponse::success(route, "Route planned".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Plans a route and sets the fleet off on its first leg; later legs follow as time advances
+#[post("/fleet/<owner_id>/<fleet_number>/route", format = "json", data = "<data>")]
+pub fn follow_fleet_route(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<RoutePlanData>) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Fleet, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        if let Some(transit) = &fleet.transit {
+            return Err(format!("Fleet {} is in transit until tick {}", fleet.name, transit.arrival_tick));
+        }
+        check_no_pending_encounters(&fleet.name)?;
+        let systems = charted_systems(&settings.game_id, player_name)?;
+        let route = plan_route(&fleet, &systems, data.system_id, data.planet_id, data.objective)?;
+        println!("Fleet {} follows a {}-leg route to system {}", fleet.name, route.waypoints.len(), data.system_id);
+        fleet.route = Some(route);
+        let departed = depart_on_route(&fleet, current_tick())?;
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&departed)?;
+        transaction.commit()?;
+        events::publish_fleet_moved(&fleet, &departed);
+        Ok(departed)
+    })();
+
+    match result {
+        Ok(fleet) => ApiResponse::success(fleet, "Fleet is following its route".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Resumes a route halted by an encounter or a leg the fleet could not fly
+#[post("/fleet/<owner_id>/<fleet_number>/route/resume")]
+pub fn resume_fleet_route(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Fleet, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        check_no_pending_encounters(&fleet.name)?;
+        let halted_fleet = fleet.clone();
+        let route = fleet.route.as_mut().ok_or_else(|| format!("Fleet {} is not following a route", fleet.name))?;
+        route.halted = None;
+        if route.is_finished() {
+            fleet.route = None;
+        } else if fleet.transit.is_none() {
+            fleet = depart_on_route(&fleet, current_tick())?;
+        }
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.commit()?;
+        events::publish_fleet_moved(&halted_fleet, &fleet);
+        Ok(fleet)
+    })();
+
+    match result {
+        Ok(fleet) => ApiResponse::success(fleet, "Route resumed".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Stops following a route; a leg already under way is still completed
+#[delete("/fleet/<owner_id>/<fleet_number>/route")]
+pub fn cancel_fleet_route(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Fleet, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        if fleet.route.take().is_none() {
+            return Err(format!("Fleet {} is not following a route", fleet.name));
+        }
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.commit()?;
+        Ok(fleet)
+    })();
+
+    match result {
+        Ok(fleet) => ApiResponse::success(fleet, "Route cancelled".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// A system found by a proximity query.
+#[derive(Serialize, Debug)]
+pub struct NearbySystem {
+    pub system_id: usize,
+    pub position: Position,
+    pub distance: f64,
+}
+
+/// A fleet found by a proximity query.
+#[derive(Serialize, Debug)]
+pub struct NearbyFleet {
+    pub fleet_name: String,
+    pub position: Position,
+    pub distance: f64,
+}
+
+/// The charted system closest to a point
+#[get("/galaxy/nearest_system?<x>&<y>&<z>")]
+pub fn get_nearest_system(game: CurrentGame, session: PlayerSession, x: i32, y: i32, z: i32) -> Json<ApiResponse<NearbySystem>> {
+    let _game = game.enter();
+    let result: Result<NearbySystem, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let knowledge = PlayerKnowledge::load(&settings.game_id, player_name)?;
+        let position = Position { x, y, z };
+        with_galaxy_index(&settings.game_id, |index| {
+            index.nearest_system_matching(&position, |id| knowledge.visibility(id) >= Visibility::StarVisible)
+                .and_then(|(system_id, distance)| {
+                    index.system_position(system_id).map(|position| NearbySystem { system_id, position, distance })
+                })
+        })?
+        .ok_or_else(|| "No charted systems".to_string())
+    })();
+
+    match result {
+        Ok(system) => ApiResponse::success(system, "Successfully found nearest system".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Fleets in sight within `radius` of a point, nearest first
+#[get("/galaxy/fleets_near?<x>&<y>&<z>&<radius>")]
+pub fn get_fleets_near(game: CurrentGame, session: PlayerSession, x: i32, y: i32, z: i32, radius: f64) -> Json<ApiResponse<Vec<NearbyFleet>>> {
+    let _game = game.enter();
+    let result: Result<Vec<NearbyFleet>, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let sight = FleetSight::for_player(session.player_name()?)?;
+        let position = Position { x, y, z };
+        let nearby: Vec<NearbyFleet> = with_galaxy_index(&settings.game_id, |index| {
+            index.fleets_within(&position, radius.max(0.0))
+                .into_iter()
+                .filter_map(|(fleet_name, distance)| {
+                    index.fleet_position(&fleet_name).map(|position| NearbyFleet { fleet_name, position, distance })
+                })
+                .collect()
+        })?;
+        Ok(nearby.into_iter()
+            .filter(|nearby| crate::models::fleet::load_fleet(&nearby.fleet_name).ok().flatten().is_some_and(|fleet| sight.can_see(&fleet)))
+            .collect())
     })();
 
     match result {
-        Ok(message) => ApiResponse::success(message, "Trade completed successfully".to_string()),
+        Ok(fleets) => ApiResponse::success(fleets, "Successfully found nearby fleets".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Charted systems passed within `radius` of the straight line between two points, in passing order
+#[get("/galaxy/systems_along?<x1>&<y1>&<z1>&<x2>&<y2>&<z2>&<radius>")]
+#[allow(clippy::too_many_arguments)]
+pub fn get_systems_along(game: CurrentGame, session: PlayerSession, x1: i32, y1: i32, z1: i32, x2: i32, y2: i32, z2: i32, radius: f64) -> Json<ApiResponse<Vec<SegmentHit<usize>>>> {
+    let _game = game.enter();
+    let result: Result<Vec<SegmentHit<usize>>, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let knowledge = PlayerKnowledge::load(&settings.game_id, player_name)?;
+        let (from, to) = (Position { x: x1, y: y1, z: z1 }, Position { x: x2, y: y2, z: z2 });
+        let hits = with_galaxy_index(&settings.game_id, |index| index.systems_along_segment(&from, &to, radius.max(0.0)))?;
+        Ok(hits.into_iter().filter(|hit| knowledge.visibility(hit.key) >= Visibility::StarVisible).collect())
+    })();
+
+    match result {
+        Ok(hits) => ApiResponse::success(hits, "Successfully found systems along the segment".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Has a fleet's Explorer survey the systems around it
+#[post("/fleet/<owner_id>/<fleet_number>/scan")]
+pub fn scan_with_fleet(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize) -> Json<ApiResponse<ScanReport>> {
+    let _game = game.enter_for_writes();
+    let result: Result<ScanReport, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        let world = crate::models::game_world::load_game_world(&settings.game_id)
+            .map_err(|e| format!("Failed to load game world: {}", e))?;
+        let now = current_tick();
+        let mut knowledge = refresh_knowledge(&settings.game_id, player_name, &world, now)?;
+        let markets = world.iter()
+            .filter(|system| fleet.position.distance(&system.position) <= EXPLORER_SCAN_RANGE)
+            .flat_map(load_system_markets)
+            .collect();
+        let report = knowledge.scan(&fleet, &world, &markets, now)?;
+        println!("{} scanned {} systems, {} of them new", fleet.name, report.surveyed.len(), report.discovered.len());
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage(&["knowledge", &PlayerKnowledge::file_name(&knowledge.player_name)], &knowledge)?;
+        transaction.commit()?;
+        Ok(report)
+    })();
+
+    match result {
+        Ok(report) => ApiResponse::success(report, "Scan complete".to_string()),
         Err(e) => ApiResponse::error(e)
     }
 }
@@ -487,8 +971,15 @@ pub fn sell_to_planet(system_id: usize, planet_id: usize, data: Json<ResourceTra
 /// * `Ok(())` if position is valid
 /// * `Err(String)` with descriptive error message if invalid
 fn validate_galaxy_bounds(target_pos: &Position, settings: &GameSettings) -> Result<(), String> {
-    // Allow moves anywhere; movement handlers will interpret direction and
-    // clamp to system exit appropriately. Keep validation minimal.
+    // Targets just past the galaxy's faces are allowed; movement handlers
+    // clamp them to the system exit. Anything farther out than the galaxy's
+    // corners is out of reach.
+    let half = settings.map_width as f64;
+    let reach = (3.0 * half * half).sqrt();
+    if distance(&Position { x: 0, y: 0, z: 0 }, target_pos) > reach {
+        return Err(format!("Target position ({}, {}, {}) is outside galaxy bounds",
+            target_pos.x, target_pos.y, target_pos.z));
+    }
     Ok(())
 }
 
@@ -666,6 +1157,7 @@ fn handle_system_movement(
         breakdown: Some(make_breakdown(1.0 / (load_settings().map_err(|e| e.to_string())?.map_width as f64), local_distance, 0.0, 0.0)),
         local_current_position: Some(Position { x: target_pos.x - system.position.x, y: target_pos.y - system.position.y, z: target_pos.z - system.position.z }),
         local_target_position: Some(Position { x: target_pos.x - system.position.x, y: target_pos.y - system.position.y, z: target_pos.z - system.position.z }),
+        transit: None,
     };
 
     Ok((response, fleet))
@@ -756,6 +1248,7 @@ fn handle_system_exit(
         breakdown: Some(make_breakdown(1.0 / (settings.map_width as f64), in_exit, 0.0, 0.0)),
         local_current_position: None,
         local_target_position: None,
+        transit: None,
     };
 
     Ok((response, fleet))
@@ -790,53 +1283,49 @@ fn handle_deep_space_movement(
     let system_half = settings_for_entry.map_width as i32; // full cube is [-map_width, +map_width]
 
     // Check if target position matches any system's coordinates
-    for (index, system) in game_world.iter().enumerate() {
-        println!("Checking system {} at position ({}, {}, {})", 
-                index, system.position.x, system.position.y, system.position.z);
-        
-        if target_pos.x == system.position.x && 
-           target_pos.y == system.position.y && 
-           target_pos.z == system.position.z {
-            println!("Fleet entering System {} at galaxy coordinates ({}, {}, {})", 
-                    index, target_pos.x, target_pos.y, target_pos.z);
-
-            // Deterministic entry point at the boundary along the line from start to center
-            let entry_point = if let Some((_te, _tx, entryp, _exitp)) = line_cube_intersection(&start_pos, &target_pos, &system.position, system_half) {
-                entryp
-            } else {
-                // If start is already inside, just keep start; otherwise fall back to center
-                if point_in_cube(&start_pos, &system.position, system_half) { start_pos.clone() } else { system.position.clone() }
-            };
-
-            println!("Deterministic entry point: ({}, {}, {})", entry_point.x, entry_point.y, entry_point.z);
+    let system_at_target = with_galaxy_index(&settings_for_entry.game_id, |index| index.system_at(&target_pos))?
+        .and_then(|id| game_world.iter().enumerate().find(|(_, system)| system.id == id));
+    if let Some((index, system)) = system_at_target {
+        println!("Fleet entering System {} at galaxy coordinates ({}, {}, {})", 
+                index, target_pos.x, target_pos.y, target_pos.z);
+
+        // Deterministic entry point at the boundary along the line from start to center
+        let entry_point = if let Some((_te, _tx, entryp, _exitp)) = line_cube_intersection(&start_pos, &target_pos, &system.position, system_half) {
+            entryp
+        } else {
+            // If start is already inside, just keep start; otherwise fall back to center
+            if point_in_cube(&start_pos, &system.position, system_half) { start_pos.clone() } else { system.position.clone() }
+        };
 
-            let deep_distance = distance(&start_pos, &entry_point);
+        println!("Deterministic entry point: ({}, {}, {})", entry_point.x, entry_point.y, entry_point.z);
 
-            fleet.position = entry_point.clone();
-            fleet.current_system_id = Some(system.id);
-            fleet.last_move_distance = Some(deep_distance);
-            
-            for ship in &mut fleet.ships {
-                ship.position = entry_point.clone();
-            }
+        let deep_distance = distance(&start_pos, &entry_point);
 
-            let in_entry = 0.0; // will be updated by subsequent in-system move when applicable
-                let scale = 1.0 / (load_settings().map_err(|e| e.to_string())?.map_width as f64);
-            let response = MoveFleetResponse {
-                status: "transition_entry".to_string(),
-                message: format!("Fleet entered System {} at coordinates ({}, {}, {})", 
-                    index, entry_point.x, entry_point.y, entry_point.z),
-                encounters: vec![],
-                current_position: entry_point,
-                target_position: target_pos,
-                remaining_distance: 0.0,
-                current_system_id: Some(system.id),
-                breakdown: Some(make_breakdown(scale, 0.0, deep_distance, in_entry)),
-                local_current_position: Some(Position { x: 0, y: 0, z: 0 }),
-                local_target_position: Some(Position { x: target_pos.x - system.position.x, y: target_pos.y - system.position.y, z: target_pos.z - system.position.z }),
-            };
-            return Ok((response, fleet));
+        fleet.position = entry_point.clone();
+        fleet.current_system_id = Some(system.id);
+        fleet.last_move_distance = Some(deep_distance);
+        
+        for ship in &mut fleet.ships {
+            ship.position = entry_point.clone();
         }
+
+        let in_entry = 0.0; // will be updated by subsequent in-system move when applicable
+            let scale = 1.0 / (load_settings().map_err(|e| e.to_string())?.map_width as f64);
+        let response = MoveFleetResponse {
+            status: "transition_entry".to_string(),
+            message: format!("Fleet entered System {} at coordinates ({}, {}, {})", 
+                index, entry_point.x, entry_point.y, entry_point.z),
+            encounters: vec![],
+            current_position: entry_point,
+            target_position: target_pos,
+            remaining_distance: 0.0,
+            current_system_id: Some(system.id),
+            breakdown: Some(make_breakdown(scale, 0.0, deep_distance, in_entry)),
+            local_current_position: Some(Position { x: 0, y: 0, z: 0 }),
+            local_target_position: Some(Position { x: target_pos.x - system.position.x, y: target_pos.y - system.position.y, z: target_pos.z - system.position.z }),
+            transit: None,
+        };
+        return Ok((response, fleet));
     }
 
     // No system entry, complete the move in deep space
@@ -860,36 +1349,27 @@ fn handle_deep_space_movement(
         breakdown: Some(make_breakdown(1.0 / (load_settings().map_err(|e| e.to_string())?.map_width as f64), 0.0, deep_distance_full, 0.0)),
         local_current_position: None,
         local_target_position: None,
+        transit: None,
     };
     Ok((response, fleet))
 }
 
+/// Lists the owners of the fleets in the game the player is logged in to
 #[get("/fleet/owners")]
-pub fn get_fleet_owners() -> Json<ApiResponse<Vec<String>>> {
+pub fn get_fleet_owners(game: CurrentGame, session: PlayerSession) -> Json<ApiResponse<Vec<String>>> {
+    let _game = game.enter();
     let result: Result<Vec<String>, String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let fleets_dir = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("fleets");
+        session.authorize_game(&settings.game_id)?;
         let mut owners = std::collections::HashSet::new();
 
-        if fleets_dir.exists() {
-            if let Ok(entries) = fs::read_dir(fleets_dir) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        if let Some(file_name) = entry.file_name().to_str() {
-                            // Handle fleet naming scheme
-                            if file_name.starts_with("Fleet_") {
-                                // Regular fleet (Fleet_owner_number)
-                                if let Some(owner) = file_name.split('_').nth(1) {
-                                    println!("Found fleet owner: {}", owner);
-                                    owners.insert(owner.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
+        for file_name in list_documents(&settings.game_id, &["fleets"])? {
+            // Handle fleet naming scheme (Fleet_owner_number)
+            if file_name.starts_with("Fleet_")
+                && let Some(owner) = file_name.split('_').nth(1)
+            {
+                println!("Found fleet owner: {}", owner);
+                owners.insert(owner.to_string());
             }
         }
 
@@ -904,281 +1384,455 @@ pub fn get_fleet_owners() -> Json<ApiResponse<Vec<String>>> {
     }
 }
 
-#[get("/fleet/<attacker_id>/<attacker_number>/attack/<defender_id>/<defender_number>")]
-pub fn initiate_combat(attacker_id: String, attacker_number: usize, defender_id: String, defender_number: usize) -> Json<String> {
-    println!("Starting combat initiation:");
-    println!("  Attacker: Fleet_{}_{}", attacker_id, attacker_number);
-    println!("  Defender: Fleet_{}_{}", defender_id, defender_number);
+/// Attacks another fleet at the same position. `retreat_after` orders the
+/// attacker to break off after that many rounds.
+#[post("/fleet/<attacker_id>/<attacker_number>/attack/<defender_id>/<defender_number>?<retreat_after>")]
+pub fn initiate_combat(game: CurrentGame, session: PlayerSession, attacker_id: String, attacker_number: usize, defender_id: String, defender_number: usize, retreat_after: Option<u32>) -> Json<String> {
+    let _game = game.enter_for_writes();
+    let result: Result<String, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        // Players only send their own fleets into battle
+        let player_name = session.player_name()?;
+        session.authorize(&attacker_id)?;
 
-    // First try to load attacker fleet
-    println!("Loading attacker fleet...");
-    let attacker_name = format!("Fleet_{}_{}", attacker_id, attacker_number);
-    let mut attacker_result = Err("Attacker fleet not found".to_string());
-    
-    // Try loading regular fleet first
-    match crate::models::fleet::load_fleet(&attacker_name) {
-        Ok(Some(fleet)) => {
-            println!("Found attacker fleet directly: {}", fleet.name);
-            attacker_result = Ok(fleet);
-        }
-        Ok(None) => {
-            println!("Regular fleet not found, trying special types...");
-            // Try special fleet types
-            let special_types = vec!["Pirate", "Trader", "Military", "Mercenary"];
-            for fleet_type in special_types {
-                let fleet_name = format!("Fleet_{}_{}", fleet_type, attacker_number);
-                println!("Trying special fleet: {}", fleet_name);
-                match crate::models::fleet::load_fleet(&fleet_name) {
-                    Ok(Some(fleet)) => {
-                        println!("Found attacker fleet: {}", fleet.name);
-                        attacker_result = Ok(fleet);
-                break; 
-                    }
-                    Ok(None) => continue,
-                    Err(e) => {
-                        println!("Error loading special fleet: {}", e);
-                        continue;
-                    }
-                }
-            }
+        // Encounters save the fleets they bring, so every fleet is loaded by its own name
+        let load_combatant = |name: String, role: &str| match crate::models::fleet::load_fleet(&name) {
+            Ok(Some(fleet)) => Ok(fleet),
+            Ok(None) => Err(format!("{} fleet {} not found", role, name)),
+            Err(e) => Err(format!("Error loading {} fleet: {}", role.to_lowercase(), e)),
+        };
+        let mut attacker = load_combatant(format!("Fleet_{}_{}", attacker_id, attacker_number), "Attacker")?;
+        let mut defender = load_combatant(format!("Fleet_{}_{}", defender_id, defender_number), "Defender")?;
+
+        if attacker.owner_id != player_name {
+            return Err(format!("Fleet {} does not belong to {}", attacker.name, player_name));
+        }
+        if attacker.name == defender.name || attacker.owner_id == defender.owner_id {
+            return Err(format!("Fleet {} cannot attack its own side", attacker.name));
+        }
+        if let Some(transit) = &attacker.transit {
+            return Err(format!("Fleet {} is in transit until tick {}", attacker.name, transit.arrival_tick));
+        }
+        check_no_pending_encounters(&attacker.name)?;
+        if !crate::combat::combat::can_engage_combat(&attacker, &defender) {
+            return Err("Fleets must be at the same position to engage in combat".to_string());
+        }
+
+        events::publish(GameEventKind::CombatStarted {
+            attacker: attacker.name.clone(),
+            attacker_owner: attacker.owner_id.clone(),
+            defender: defender.name.clone(),
+            defender_owner: defender.owner_id.clone(),
+            position: attacker.position,
+            system_id: attacker.current_system_id,
+        });
+        let orders = CombatOrders { attacker_retreat_after: retreat_after, ..CombatOrders::default() };
+        let mut rng = next_game_rng("combat")?;
+        let combat_result = auto_resolve_ship_combat_with_orders(&mut attacker, &mut defender, orders, &mut rng);
+        let salvage = collect_salvage(&combat_result, &mut attacker, &mut defender, current_tick(), &mut rng);
+
+        // Both fleets, any salvage, and the player's standing and missions are saved together
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&attacker)?;
+        transaction.stage_fleet(&defender)?;
+        if let Some(field) = &salvage {
+            let mut log = SalvageLog::load()?;
+            log.record(field.clone(), current_tick());
+            transaction.stage_salvage(&log)?;
+        }
+
+        // Attacking a faction's fleet costs the player standing with that faction
+        let mut player = load_player(player_name)?;
+        let mut reputation_changes = record_faction_dealings(&mut player, &defender.owner_id, ATTACK_REPUTATION_PENALTY)?;
+        // Preying on traders is piracy, punished by whoever polices the system
+        if is_npc_trader(&defender) {
+            let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+            reputation_changes.extend(record_piracy(&mut player, &factions, defender.current_system_id));
+        }
+        // Wiping out a fleet settles any bounty on it
+        let mut completed_missions = Vec::new();
+        if defender.ships.is_empty() {
+            completed_missions = record_mission_progress(&mut player, &mut transaction, |board, player, factions| {
+                board.record_fleet_destroyed(player, factions, &defender.name)
+            })?;
+        }
+        transaction.stage_player(&player)?;
+        transaction.commit()?;
+
+        events::publish(GameEventKind::CombatResolved {
+            attacker: attacker.name.clone(),
+            attacker_owner: attacker.owner_id.clone(),
+            defender: defender.name.clone(),
+            defender_owner: defender.owner_id.clone(),
+            position: attacker.position,
+            system_id: attacker.current_system_id,
+            attacker_victory: combat_result.attacker_victory,
+            attacker_losses: combat_result.attacker_losses.len(),
+            defender_losses: combat_result.defender_losses.len(),
+        });
+        if let Some(field) = &salvage {
+            events::publish_salvage(field);
+        }
+        events::publish_missions(&completed_missions);
+
+        // Format combat result
+        let mut report = String::new();
+        for log in combat_result.combat_log {
+            report.push_str(&format!("{}\n", log));
+        }
+        for change in &reputation_changes {
+            report.push_str(&format!("Reputation with {} changed by {} (now {})\n", change.faction, change.change, change.reputation));
         }
+        for mission in &completed_missions {
+            report.push_str(&format!("Mission completed: {} (+{} credits)\n", mission.title, mission.reward_credits));
+        }
+        if let Some(field) = &salvage {
+            report.push_str(&format!("{}\n", field.summary()));
+        }
+        report.push_str(&format!("\nFinal fleet sizes:\nAttacker: {} ships\nDefender: {} ships",
+            attacker.ships.len(), defender.ships.len()));
+        Ok(report)
+    })();
+
+    match result {
+        Ok(report) => Json(report),
         Err(e) => {
-            println!("Error loading attacker fleet: {}", e);
+            println!("Error resolving combat: {}", e);
+            Json(e)
         }
     }
+}
 
-    // Now try to load defender fleet
-    println!("Loading defender fleet...");
-    let defender_name = format!("Fleet_{}_{}", defender_id, defender_number);
-    let mut defender_result = Err("Defender fleet not found".to_string());
-    
-    // Try loading regular fleet first
-    match crate::models::fleet::load_fleet(&defender_name) {
-        Ok(Some(fleet)) => {
-            println!("Found defender fleet directly: {}", fleet.name);
-            defender_result = Ok(fleet);
-        }
-        Ok(None) => {
-            println!("Regular fleet not found, trying special types...");
-            // Try special fleet types
-            let special_types = vec!["Pirate", "Trader", "Military", "Mercenary"];
-            for fleet_type in special_types {
-                // Try both naming formats
-                let fleet_names = vec![
-                    format!("Fleet_{}_{}", fleet_type, defender_number),
-                    format!("{}_Fleet_{}", fleet_type, defender_number)
-                ];
-                
-                for fleet_name in fleet_names {
-                    println!("Trying special fleet: {}", fleet_name);
-                    match crate::models::fleet::load_fleet(&fleet_name) {
-                        Ok(Some(fleet)) => {
-                            println!("Found defender fleet: {}", fleet.name);
-                            defender_result = Ok(fleet);
-                            break;
-                        }
-                        Ok(None) => continue,
-                        Err(e) => {
-                            println!("Error loading special fleet: {}", e);
-                            continue;
-                        }
-                    }
-                }
-                
-                if defender_result.is_ok() {
-                    break;
-                }
-            }
+/// Returns the encounters of a fleet still waiting for a decision. Fleets
+/// meet others where they stop, when they arrive.
+#[get("/fleet/<owner_id>/<fleet_number>/encounter")]
+pub fn check_for_encounter(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize) -> Json<ApiResponse<Vec<Encounter>>> {
+    let _game = game.enter();
+    let result: Result<Vec<Encounter>, String> = (|| {
+        let player_name = session.player_name()?;
+        let fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        let log = EncounterLog::load()?;
+        Ok(log.pending_for_fleet(&fleet.name).into_iter().cloned().collect())
+    })();
+
+    match result {
+        Ok(encounters) => {
+            let message = format!("{} encounters", encounters.len());
+            ApiResponse::success(encounters, message)
         }
         Err(e) => {
-            println!("Error loading defender fleet: {}", e);
+            println!("Error checking for encounters: {}", e);
+            ApiResponse::error(e)
         }
     }
+}
 
-    match (attacker_result, defender_result) {
-        (Ok(mut attacker), Ok(mut defender)) => {
-            println!("Both fleets loaded successfully:");
-            println!("  Attacker: {} ({} ships)", attacker.name, attacker.ships.len());
-            println!("  Defender: {} ({} ships)", defender.name, defender.ships.len());
-
-            if !crate::combat::combat::can_engage_combat(&attacker, &defender) {
-                println!("Fleets cannot engage in combat - not at same position");
-                return Json("Fleets must be at the same position to engage in combat".to_string());
-            }
+/// Fails while a fleet has encounters waiting for its owner's decision; a
+/// fleet cannot fly off from fleets it has met.
+fn check_no_pending_encounters(fleet_name: &str) -> Result<(), String> {
+    let pending = EncounterLog::load()?.pending_for_fleet(fleet_name).len();
+    if pending > 0 {
+        return Err(format!("Fleet {} has {} encounters pending; resolve them before moving on", fleet_name, pending));
+    }
+    Ok(())
+}
 
-            println!("Starting combat resolution...");
-            let combat_result = crate::combat::combat::auto_resolve_ship_combat(&mut attacker, &mut defender);
+/// Rolls for what a fleet that moved without taking game time meets where
+/// it stopped, staging the encounters and the fleets met with the move.
+fn roll_arrival_encounters(game_id: &str, fleet: &mut Fleet, transaction: &mut Transaction) -> Result<Vec<Encounter>, String> {
+    let Some(distance) = fleet.last_move_distance.take() else {
+        return Ok(Vec::new());
+    };
+    let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+    let faction = fleet.current_system_id.and_then(|system_id| controlling_faction(&factions, system_id));
+    let (system, nearby) = match fleet.current_system_id {
+        Some(system_id) => {
+            let system = crate::models::game_world::load_star_system(game_id, system_id).map_err(|e| e.to_string())?;
+            let nearby: Vec<Fleet> = with_galaxy_index(game_id, |index| index.fleets_in_system(system_id))?
+                .iter()
+                .filter_map(|name| crate::models::fleet::load_fleet(name).ok().flatten())
+                .collect();
+            (system, nearby)
+        }
+        None => (None, Vec::new()),
+    };
+    let danger = encounter_danger(fleet, system.as_ref());
+    let mut rng = next_game_rng("encounter")?;
+    let (encounters, hostiles) = roll_encounters(
+        fleet, distance, danger, faction, &nearby, current_tick(),
+        |name| crate::models::fleet::load_fleet(name).ok().flatten().is_some(),
+        &mut rng,
+    );
+    for hostile in &hostiles {
+        println!("  {} meets {} ({} ships)", fleet.name, hostile.name, hostile.ships.len());
+        transaction.stage_fleet(hostile)?;
+    }
+    if !encounters.is_empty() {
+        let mut log = EncounterLog::load()?;
+        log.encounters.extend(encounters.iter().cloned());
+        transaction.stage_encounters(&log)?;
+    }
+    Ok(encounters)
+}
 
-            // Save the updated fleets
-            println!("Saving updated fleets...");
-            if let Err(e) = crate::models::fleet::save_fleet(&attacker) {
-                println!("Error saving attacker fleet: {}", e);
-                return Json("Error saving attacker fleet".to_string());
-            }
+/// Lists the active player's encounters, optionally only those still pending
+#[get("/encounters?<pending>")]
+pub fn get_encounters(game: CurrentGame, session: PlayerSession, pending: Option<bool>) -> Json<ApiResponse<Vec<Encounter>>> {
+    let _game = game.enter();
+    let result: Result<Vec<Encounter>, String> = (|| {
+        let player_name = session.player_name()?;
+        let log = EncounterLog::load()?;
+        Ok(log.for_player(player_name).into_iter()
+            .filter(|encounter| !pending.unwrap_or(false) || encounter.is_pending())
+            .cloned()
+            .collect())
+    })();
 
-            if let Err(e) = crate::models::fleet::save_fleet(&defender) {
-                println!("Error saving defender fleet: {}", e);
-                return Json("Error saving defender fleet".to_string());
-            }
+    match result {
+        Ok(encounters) => ApiResponse::success(encounters, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
 
-            // Format combat result
-            let mut result = String::new();
-            for log in combat_result.combat_log {
-                result.push_str(&format!("{}\n", log));
-            }
-            result.push_str(&format!("\nFinal fleet sizes:\nAttacker: {} ships\nDefender: {} ships", 
-                attacker.ships.len(), defender.ships.len()));
+/// Fights, flees, hails, pays off or trades with the fleet of a pending encounter
+#[post("/encounters/<encounter_id>/resolve", format = "json", data = "<data>")]
+pub fn resolve_encounter_action(game: CurrentGame, session: PlayerSession, encounter_id: &str, data: Json<EncounterActionData>) -> Json<ApiResponse<EncounterReport>> {
+    let _game = game.enter_for_writes();
+    let result: Result<EncounterReport, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut log = EncounterLog::load()?;
+        let encounter = log.get_mut(encounter_id)?;
+        session.authorize(&encounter.owner_id)?;
+        let mut player = load_player(player_name)?;
+        let mut fleet = crate::models::fleet::load_fleet(&encounter.fleet_name)?
+            .ok_or_else(|| format!("Fleet {} not found", encounter.fleet_name))?;
+        let mut hostile = crate::models::fleet::load_fleet(&encounter.hostile_fleet)?
+            .ok_or_else(|| format!("Fleet {} not found", encounter.hostile_fleet))?;
+        let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+
+        let mut rng = next_game_rng("encounter")?;
+        let (mut lines, combat) = resolve_encounter(encounter, &data, &mut player, &mut fleet, &mut hostile, &factions, &mut rng)?;
+        let encounter = encounter.clone();
+        println!("Encounter {} of {}: {:?}", encounter.id, fleet.name, encounter.status);
+
+        // The other fleet opened fire on a fleeing player; otherwise the player attacked
+        let salvage = combat.as_ref().and_then(|result| if data.action == EncounterAction::Flee {
+            collect_salvage(result, &mut hostile, &mut fleet, current_tick(), &mut rng)
+        } else {
+            collect_salvage(result, &mut fleet, &mut hostile, current_tick(), &mut rng)
+        });
+        if let Some(field) = &salvage {
+            lines.push(field.summary());
+        }
 
-            println!("Combat completed successfully");
-            Json(result)
+        // Both fleets, the player, the encounter, any salvage and any bounty are saved together
+        let mut transaction = Transaction::new(&settings.game_id);
+        if let Some(field) = &salvage {
+            let mut salvage_log = SalvageLog::load()?;
+            salvage_log.record(field.clone(), current_tick());
+            transaction.stage_salvage(&salvage_log)?;
         }
-        (Err(e), _) => {
-            println!("Error loading attacker fleet: {}", e);
-            Json(e)
+        transaction.stage_fleet(&fleet)?;
+        if encounter.hostile_departs(&hostile) {
+            transaction.stage_fleet_removal(&hostile.name);
+        } else {
+            transaction.stage_fleet(&hostile)?;
         }
-        (_, Err(e)) => {
-            println!("Error loading defender fleet: {}", e);
-            Json(e)
+        let mut completed = Vec::new();
+        if hostile.ships.is_empty() {
+            completed = record_mission_progress(&mut player, &mut transaction, |board, player, factions| {
+                board.record_fleet_destroyed(player, factions, &hostile.name)
+            })?;
+        }
+        transaction.stage_player(&player)?;
+        transaction.stage_encounters(&log)?;
+        transaction.commit()?;
+
+        events::publish(GameEventKind::EncounterResolved {
+            encounter_id: encounter.id.clone(),
+            fleet: fleet.name.clone(),
+            owner_id: fleet.owner_id.clone(),
+            hostile_fleet: hostile.name.clone(),
+            status: encounter.status,
+        });
+        if let Some(result) = &combat {
+            // A fleeing player is the defender of the fight the other fleet opened
+            let (fleet_losses, hostile_losses) = if data.action == EncounterAction::Flee {
+                (result.defender_losses.len(), result.attacker_losses.len())
+            } else {
+                (result.attacker_losses.len(), result.defender_losses.len())
+            };
+            events::publish(GameEventKind::CombatResolved {
+                attacker: fleet.name.clone(),
+                attacker_owner: fleet.owner_id.clone(),
+                defender: hostile.name.clone(),
+                defender_owner: hostile.owner_id.clone(),
+                position: fleet.position,
+                system_id: fleet.current_system_id,
+                attacker_victory: encounter.status == EncounterStatus::Won,
+                attacker_losses: fleet_losses,
+                defender_losses: hostile_losses,
+            });
+        }
+        if let Some(field) = &salvage {
+            events::publish_salvage(field);
+        }
+        events::publish_missions(&completed);
+        lines.extend(completed.iter().map(|mission| format!("Mission completed: {} (+{} credits)", mission.title, mission.reward_credits)));
+        Ok(EncounterReport { encounter, log: lines, salvage })
+    })();
+
+    match result {
+        Ok(report) => {
+            let message = report.encounter.outcome.clone().unwrap_or_else(|| "Encounter resolved".to_string());
+            ApiResponse::success(report, message)
         }
+        Err(e) => ApiResponse::error(e)
     }
 }
 
-#[get("/fleet/<owner_id>/<fleet_number>/encounter")]
-pub fn check_for_encounter(owner_id: String, fleet_number: usize) -> Json<Vec<Fleet>> {
-    println!("Checking for encounters for Fleet_{}_{}", owner_id, fleet_number);
-    
-    let fleet_name = format!("Fleet_{}_{}", owner_id, fleet_number);
-    match crate::models::fleet::load_fleet(&fleet_name) {
-        Ok(Some(fleet)) => {
-            let mut encounters = Vec::new();
-            
-            // Check for star system encounters first
-            if let Some(system_id) = fleet.current_system_id {
-                let settings = load_settings().expect("Failed to load settings");
-                if let Ok(Some(system)) = crate::models::game_world::load_star_system(&settings.game_id, system_id) {
-                    // Check if fleet is near any planets
-                    for planet in &system.planets {
-                        let planet_pos = planet.position;
-                        let distance = ((fleet.position.x - planet_pos.x).pow(2) + 
-                                      (fleet.position.y - planet_pos.y).pow(2) + 
-                                      (fleet.position.z - planet_pos.z).pow(2)) as f64;
-                        if distance <= 10.0 {
-                            // Create a fleet representing the planet's defenses
-                            let planet_fleet = Fleet {
-                                name: format!("Planet_{}", planet.name),
-                                owner_id: "Planet".to_string(),
-                                ships: Vec::new(), // Planet encounters don't have ships
-                                position: planet_pos,
-                                local_position: None,
-                                current_system_id: Some(system_id),
-                                last_move_distance: None,
-                            };
-                            encounters.push(planet_fleet);
-                        }
-                    }
-                }
-            }
-            
-            // Generate random encounters based on distance
-            let distance = fleet.last_move_distance.unwrap_or(0.0);
-            let max_encounters = (distance / 10.0).min(3.0) as i32;
-            
-            for _ in 0..max_encounters {
-                if rand::random::<f64>() < 0.3 { // 30% chance for each potential encounter
-                    let encounter_fleet = generate_encounter_fleet(fleet.position.clone());
-                    
-                    // Only add the encounter if it's not the same owner as the player's fleet
-                    if encounter_fleet.owner_id != owner_id {
-                        let fleet = Fleet {
-                            name: encounter_fleet.name,
-                            owner_id: encounter_fleet.owner_id,
-                            ships: encounter_fleet.ships,
-                            position: encounter_fleet.position,
-                            local_position: None,
-                            current_system_id: fleet.current_system_id,
-                            last_move_distance: None,
-                        };
-                        encounters.push(fleet);
-                    }
-                }
-            }
-            
-            // If we have encounters, save the current fleet's last move distance
-            if !encounters.is_empty() {
-                if let Ok(Some(mut current_fleet)) = crate::models::fleet::load_fleet(&fleet_name) {
-                    current_fleet.last_move_distance = Some(distance);
-                    if let Err(e) = crate::models::fleet::save_fleet(&current_fleet) {
-                        println!("Error saving fleet's last move distance: {}", e);
-                    }
-                }
-            }
-            
-            Json(encounters)
-        }
-        Ok(None) => Json(Vec::new()),
-        Err(e) => {
-            println!("Error loading fleet: {}", e);
-            Json(Vec::new())
+/// Lists the salvage fields the active player won and may still loot
+#[get("/salvage")]
+pub fn get_salvage(game: CurrentGame, session: PlayerSession) -> Json<ApiResponse<Vec<SalvageField>>> {
+    let _game = game.enter();
+    let result: Result<Vec<SalvageField>, String> = (|| {
+        let player_name = session.player_name()?;
+        let log = SalvageLog::load()?;
+        Ok(log.for_claimant(player_name, current_tick()).into_iter().cloned().collect())
+    })();
+
+    match result {
+        Ok(fields) => ApiResponse::success(fields, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Hauls floating cargo from a salvage field into one of the player's fleets, as much as fits
+#[post("/salvage/<field_id>/loot", format = "json", data = "<data>")]
+pub fn loot_salvage(game: CurrentGame, session: PlayerSession, field_id: &str, data: Json<LootData>) -> Json<ApiResponse<CargoReceipt>> {
+    let _game = game.enter_for_writes();
+    let result: Result<CargoReceipt, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, player_name, data.fleet_number)?;
+        let mut log = SalvageLog::load()?;
+        let field = log.get_mut(field_id)?;
+        field.check_reach(&fleet, current_tick())?;
+        let receipt = loot_cargo(field, &mut fleet, data.resource_type, data.quantity)?;
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.stage_salvage(&log)?;
+        transaction.commit()?;
+        Ok(receipt)
+    })();
+
+    match result {
+        Ok(receipt) => {
+            let message = format!("Salvaged {} of {} {}", receipt.moved, receipt.requested, receipt.resource_type);
+            ApiResponse::success(receipt, message)
         }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Boards a disabled ship in a salvage field and adds it to one of the player's fleets
+#[post("/salvage/<field_id>/capture", format = "json", data = "<data>")]
+pub fn capture_salvage(game: CurrentGame, session: PlayerSession, field_id: &str, data: Json<CaptureData>) -> Json<ApiResponse<Fleet>> {
+    let _game = game.enter_for_writes();
+    let result: Result<(Fleet, String), String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut fleet = load_owned_fleet(player_name, player_name, data.fleet_number)?;
+        let mut log = SalvageLog::load()?;
+        let field = log.get_mut(field_id)?;
+        field.check_reach(&fleet, current_tick())?;
+        let ship_name = capture_ship(field, &mut fleet, data.ship_index)?;
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&fleet)?;
+        transaction.stage_salvage(&log)?;
+        transaction.commit()?;
+        Ok((fleet, ship_name))
+    })();
+
+    match result {
+        Ok((fleet, ship_name)) => ApiResponse::success(fleet, format!("Captured {}", ship_name)),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Breaks up a wreck or disabled ship in a salvage field for its scrap value
+#[post("/salvage/<field_id>/scrap", format = "json", data = "<data>")]
+pub fn scrap_salvage(game: CurrentGame, session: PlayerSession, field_id: &str, data: Json<ScrapData>) -> Json<ApiResponse<f64>> {
+    let _game = game.enter_for_writes();
+    let result: Result<(f64, String), String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let fleet = load_owned_fleet(player_name, player_name, data.fleet_number)?;
+        let mut player = load_player(player_name)?;
+        let mut log = SalvageLog::load()?;
+        let field = log.get_mut(field_id)?;
+        field.check_reach(&fleet, current_tick())?;
+        let (ship_name, credits) = scrap_ship(field, data.ship_index, data.derelict)?;
+        player.credits += credits;
+
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_player(&player)?;
+        transaction.stage_salvage(&log)?;
+        transaction.commit()?;
+        Ok((credits, ship_name))
+    })();
+
+    match result {
+        Ok((credits, ship_name)) => ApiResponse::success(credits, format!("Scrapped {} for {:.0} credits", ship_name, credits)),
+        Err(e) => ApiResponse::error(e)
     }
 }
 
-#[get("/fleet/<owner_id>/<fleet_number>/trade/<resource_type>/<quantity>/<trade_type>")]
-pub fn trade_with_trader(owner_id: String, fleet_number: usize, resource_type: ResourceType, quantity: u32, trade_type: String) -> Json<String> {
+#[get("/fleet/<owner_id>/<fleet_number>/trade/<resource_type>/<quantity>/<trade_type>?<trader>")]
+pub fn trade_with_trader(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, resource_type: ResourceType, quantity: u32, trade_type: String, trader: Option<String>) -> Json<String> {
+    let _game = game.enter_for_writes();
     println!("Starting trade operation:");
     println!("  Fleet: Fleet_{}_{}", owner_id, fleet_number);
+    println!("  Trader: {}", trader.as_deref().unwrap_or("any in system"));
     println!("  Resource: {}", resource_type);
     println!("  Quantity: {}", quantity);
     println!("  Trade Type: {}", trade_type);
 
     let result: Result<String, String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let mut player = load_player(&settings.player_name)?;
-        let fleet_name = format!("Fleet_{}_{}", owner_id, fleet_number);
-        let trader_fleet_name = format!("Fleet_Trader_{}", fleet_number);
-
-        // Load both fleets
-        match (crate::models::fleet::load_fleet(&fleet_name), crate::models::fleet::load_fleet(&trader_fleet_name)) {
-            (Ok(Some(mut player_fleet)), Ok(Some(mut trader_fleet))) => {
-                match trade_with_fleet(&mut player_fleet, &mut trader_fleet, resource_type, quantity, &trade_type, &mut player) {
-                    Ok(_) => {
-                        // Save all changes
-                        if let Err(e) = crate::models::fleet::save_fleet(&player_fleet) {
-                            println!("Error saving player fleet: {}", e);
-                            return Err("Error saving player fleet".to_string());
-                        }
-
-                        if let Err(e) = crate::models::fleet::save_fleet(&trader_fleet) {
-                            println!("Error saving trader fleet: {}", e);
-                            return Err("Error saving trader fleet".to_string());
-                        }
-
-                        let player_path = Path::new("data")
-                            .join("game")
-                            .join(&settings.game_id)
-                            .join("players")
-                            .join(format!("{}.json", settings.player_name));
-                        if let Err(e) = save_json(&player_path, &player) {
-                            println!("Error saving player: {}", e);
-                            return Err("Error saving player".to_string());
-                        }
-
-                        Ok("Success".to_string())
-                    },
-                    Err(e) => Err(e)
-                }
-            },
-            (Ok(Some(_)), Ok(None)) => Err("Trader fleet not found".to_string()),
-            (Ok(None), _) => Err("Player fleet not found".to_string()),
-            (Err(e), _) => {
-                println!("Error loading player fleet: {}", e);
-                Err("Error loading player fleet".to_string())
-            },
-            (_, Err(e)) => {
-                println!("Error loading trader fleet: {}", e);
-                Err("Error loading trader fleet".to_string())
-            }
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name)?;
+        let mut player_fleet = load_owned_fleet(player_name, &owner_id, fleet_number)?;
+        if player_fleet.transit.is_some() {
+            return Err("Cannot trade while the fleet is in transit".to_string());
+        }
+        let mut trader_fleet = find_trader_fleet(&settings.game_id, &player_fleet, trader.as_deref())?;
+
+        let message = if trader_fleet.trader.is_some() {
+            let change = trade_with_npc_trader(&mut player_fleet, &mut trader_fleet, resource_type, quantity, &trade_type, &mut player)?;
+            format!("Traded {} {} with {} for {:.2} credits", quantity, resource_type, trader_fleet.name, change.abs())
+        } else {
+            trade_with_fleet(&mut player_fleet, &mut trader_fleet, resource_type, quantity, &trade_type, &mut player)?;
+            "Success".to_string()
+        };
+
+        // Trading with a faction's fleet improves the player's standing with it
+        record_faction_dealings(&mut player, &trader_fleet.owner_id, TRADE_REPUTATION_GAIN)?;
+
+        // Both fleets and the player are saved together or not at all
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_fleet(&player_fleet)?;
+        transaction.stage_fleet(&trader_fleet)?;
+        transaction.stage_player(&player)?;
+        if let Err(e) = transaction.commit() {
+            println!("Error saving trade: {}", e);
+            return Err("Error saving trade".to_string());
         }
+
+        Ok(message)
     })();
 
     match result {
@@ -1187,6 +1841,53 @@ pub fn trade_with_trader(owner_id: String, fleet_number: usize, resource_type: R
     }
 }
 
+/// Finds the trader fleet a player's fleet is dealing with.
+///
+/// A named fleet is loaded directly; otherwise the first NPC trader in the
+/// player's system is used. Either way the trader has to be an NPC trader,
+/// in the same system as the player's fleet and not under way.
+fn find_trader_fleet(game_id: &str, player_fleet: &Fleet, trader_name: Option<&str>) -> Result<Fleet, String> {
+    let trader_fleet = match trader_name {
+        Some(name) => crate::models::fleet::load_fleet(name)?
+            .filter(is_npc_trader)
+            .ok_or_else(|| format!("Trader fleet {} not found", name))?,
+        None => list_all_fleets(game_id)
+            .map_err(|e| format!("Failed to load fleets: {}", e))?
+            .into_iter()
+            .find(|fleet| {
+                is_npc_trader(fleet)
+                    && fleet.transit.is_none()
+                    && !fleet.ships.is_empty()
+                    && fleet.current_system_id.is_some()
+                    && fleet.current_system_id == player_fleet.current_system_id
+            })
+            .ok_or_else(|| "No trader fleet in this system".to_string())?,
+    };
+    if trader_fleet.transit.is_some() || trader_fleet.current_system_id != player_fleet.current_system_id {
+        return Err(format!("{} is not in the same system as {}", trader_fleet.name, player_fleet.name));
+    }
+    Ok(trader_fleet)
+}
+
+/// Lists the persistent NPC trader fleets of the current game that the
+/// player's fleets can see.
+#[get("/traders")]
+pub fn get_npc_traders(game: CurrentGame, session: PlayerSession) -> Json<ApiResponse<Vec<Fleet>>> {
+    let _game = game.enter();
+    let result: Result<Vec<Fleet>, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let sight = FleetSight::for_player(session.player_name()?)?;
+        let fleets = list_all_fleets(&settings.game_id)
+            .map_err(|e| format!("Failed to load fleets: {}", e))?;
+        Ok(fleets.into_iter().filter(|fleet| is_npc_trader(fleet) && sight.can_see(fleet)).collect())
+    })();
+
+    match result {
+        Ok(traders) => ApiResponse::success(traders, "Successfully retrieved NPC traders".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
 #[get("/games")]
 pub fn list_games() -> Json<ApiResponse<Vec<SavedGame>>> {
     match SavedGame::list_saved_games() {
@@ -1196,32 +1897,35 @@ pub fn list_games() -> Json<ApiResponse<Vec<SavedGame>>> {
 }
 
 #[post("/games/new", data = "<settings>")]
-pub fn create_new_game(settings: Json<GameSettings>) -> Json<ApiResponse<String>> {
+pub fn create_new_game(registry: &State<GameRegistry>, settings: Json<GameSettings>) -> Json<ApiResponse<String>> {
     println!("Starting create_new_game with settings: {:?}", settings);
     let mut settings = settings.into_inner();
     let game_id = settings.game_id.clone();
     let display_name = settings.display_name.clone();
+
+    // A new game never replaces another; creating its directory claims the id
+    if let Err(e) = validate_game_id(&game_id) {
+        return ApiResponse::error(e);
+    }
+    let game_dir = game_data_path(&game_id, &[]);
+    let claimed = matches!(SavedGame::load_game(&game_id), Ok(None))
+        && game_dir.parent().is_some_and(|games| fs::create_dir_all(games).is_ok())
+        && fs::create_dir(&game_dir).is_ok();
+    if !claimed {
+        return ApiResponse::error(format!("Game {} already exists", game_id));
+    }
     
     // Add required fields
     let now = Utc::now().to_rfc3339();
     settings.created_at = now.clone();
     settings.last_played = now;
+    // New games are always written in the current save layout
+    settings.schema_version = CURRENT_SCHEMA_VERSION;
     
-    println!("Creating game directories for game_id: {}", game_id);
-    // Create necessary directories
-    let game_dir = Path::new("data").join("game").join(&game_id);
-    let markets_dir = game_dir.join("markets");
-    let fleets_dir = game_dir.join("fleets");
-    let players_dir = game_dir.join("players");
-    let factions_dir = game_dir.join("factions");
-    let star_systems_dir = game_dir.join("star_systems");
-
-    // Create all required directories
-    for dir in [&game_dir, &markets_dir, &fleets_dir, &players_dir, &factions_dir, &star_systems_dir] {
-        if let Err(e) = fs::create_dir_all(dir) {
-            println!("Error creating directory {}: {}", dir.display(), e);
-            return ApiResponse::error(format!("Failed to create game directories: {}", e));
-        }
+    println!("Setting up {:?} storage for game_id: {}", settings.storage, game_id);
+    if let Err(e) = init_storage(&game_id, settings.storage) {
+        println!("Error setting up storage: {}", e);
+        return ApiResponse::error(format!("Failed to create game storage: {}", e));
     }
 
     println!("Creating saved game entry");
@@ -1239,25 +1943,22 @@ pub fn create_new_game(settings: Json<GameSettings>) -> Json<ApiResponse<String>
         return ApiResponse::error("Failed to save game".to_string());
     }
 
-    println!("Saving settings to game directory");
-    // Save the settings in the game directory
-    let settings_path = game_dir.join("settings.json");
-    if let Err(e) = save_json(&settings_path, &settings) {
+    println!("Saving settings to game storage");
+    // Save the settings in the game's storage
+    if let Err(e) = storage_for(&game_id).and_then(|storage| storage.save_settings(&settings)) {
         println!("Error saving settings: {}", e);
         return ApiResponse::error("Failed to save settings".to_string());
     }
 
-    // Update game state with current game ID
-    let mut state = match crate::models::game_state::get_game_state() {
-        Ok(state) => state,
-        Err(e) => return ApiResponse::error(format!("Failed to get game state: {}", e)),
+    let context = match registry.get(&game_id) {
+        Ok(context) => context,
+        Err(e) => return ApiResponse::error(format!("Failed to open game: {}", e)),
     };
-    state.current_game_id = Some(game_id.clone());
-    state.credits = settings.starting_credits as f64;
-    if let Err(e) = crate::models::game_state::save_game_state(state) {
-        println!("Error updating game state: {}", e);
-        return ApiResponse::error("Failed to update game state".to_string());
-    }
+    let _game = context.enter_for_writes();
+
+    // Everything generated below draws from a single RNG seeded by the game seed,
+    // so the same seed always produces the same starting universe
+    let mut rng = rng_for(settings.seed, "new_game");
 
     println!("Creating game world");
     // Create the game world with force_regenerate=true to ensure we create a new one
@@ -1272,33 +1973,11 @@ pub fn create_new_game(settings: Json<GameSettings>) -> Json<ApiResponse<String>
         return ApiResponse::error("Failed to create game world".to_string());
     }
 
-    println!("Updating global game world");
-    // Update the global game world
-    if let Ok(mut guard) = crate::GLOBAL_GAME_WORLD.lock() {
-        println!("Acquired GLOBAL_GAME_WORLD lock");
-        *guard = game_world.clone();
-        println!("Updated GLOBAL_GAME_WORLD with {} systems", guard.len());
-    } else {
-        println!("Failed to acquire GLOBAL_GAME_WORLD lock");
-        return ApiResponse::error("Failed to update game world".to_string());
-    }
-
-    // Ensure the game world is properly initialized in memory
-    let game_world = if let Ok(guard) = crate::GLOBAL_GAME_WORLD.lock() {
-        guard.clone()
-    } else {
-        return ApiResponse::error("Failed to access game world".to_string());
-    };
-
     println!("Starting market generation for {} systems", game_world.len());
     // Now that everything is set up, generate markets for all star systems
     for (system_id, _) in game_world.iter().enumerate() {
         println!("Generating markets for system {} at position {:?}", system_id, game_world[system_id].position);
-        // Ensure the game world is loaded in memory
-        if let Ok(mut guard) = crate::GLOBAL_GAME_WORLD.lock() {
-            *guard = game_world.clone();
-        }
-        if let Err(e) = regenerate_system_markets(system_id) {
+        if let Err(e) = regenerate_system_markets(system_id, &mut rng) {
             println!("Error generating markets for system {}: {}", system_id, e);
             return ApiResponse::error(format!("Failed to generate markets for system {}: {}", system_id, e));
         }
@@ -1307,17 +1986,21 @@ pub fn create_new_game(settings: Json<GameSettings>) -> Json<ApiResponse<String>
     println!("Market generation completed for all systems");
 
     println!("Creating and saving factions");
-    // Create and save factions from settings
-    for faction_settings in &settings.factions {
+    // Create factions from settings and divide the galaxy between them
+    let mut factions: Vec<Faction> = settings.factions.iter().map(|faction_settings| {
         println!("Creating faction: {}", faction_settings.name);
-        let faction = Faction::new(
+        let mut faction = Faction::new(
             faction_settings.name.clone(),
             format!("The {} Empire", faction_settings.name) // Generate a basic description
         );
-        if let Err(e) = save_faction(&faction) {
-            println!("Error saving faction {}: {}", faction_settings.name, e);
-            return ApiResponse::error(format!("Failed to save faction {}: {}", faction_settings.name, e));
-        }
+        faction.influence = faction_settings.influence;
+        faction
+    }).collect();
+    generate_relations(&mut factions, &mut rng);
+    assign_territories(&mut factions, &game_world, settings.map_width, &mut rng);
+
+    for (faction, faction_settings) in factions.iter_mut().zip(&settings.factions) {
+        println!("Faction {} controls {} systems", faction.name, faction.territory.len());
 
         // Create faction fleets - number of fleets scales with influence
         let fleet_count = 2 + (faction_settings.influence as usize / 30); // 2-5 fleets based on influence
@@ -1325,25 +2008,50 @@ pub fn create_new_game(settings: Json<GameSettings>) -> Json<ApiResponse<String>
             println!("Generating fleet {} for faction {}", fleet_num + 1, faction_settings.name);
             // Number of ships also scales with influence
             let ship_count = 1 + (faction_settings.influence as usize / 20); // 1-5 ships based on influence
-            if let Ok(fleet) = generate_and_save_fleet(
-                faction_settings.name.clone(),
-                random_position(
+            // Faction fleets patrol their own territory
+            let home_system = if faction.territory.is_empty() {
+                None
+            } else {
+                let system_id = faction.territory[rng.gen_range(0..faction.territory.len())];
+                game_world.iter().find(|system| system.id == system_id)
+            };
+            let position = match home_system {
+                Some(system) => system.position,
+                None => random_position(
                     settings.map_width as i32,
                     settings.map_height as i32,
-                    settings.map_length as i32
+                    settings.map_length as i32,
+                    &mut rng
                 ),
-                ship_count
+            };
+            if let Ok(mut fleet) = generate_and_save_fleet(
+                faction_settings.name.clone(),
+                position,
+                ship_count,
+                &mut rng
             ) {
                 println!("Generated fleet {} for faction {}: {}", fleet_num + 1, faction_settings.name, fleet.name);
+                if let Some(system) = home_system {
+                    fleet.current_system_id = Some(system.id);
+                    fleet.local_position = Some(Position { x: 0, y: 0, z: 0 });
+                    if let Err(e) = save_fleet(&fleet) {
+                        println!("Error placing fleet {} in its home system: {}", fleet.name, e);
+                    }
+                }
+                faction.fleets.push(fleet.name);
             }
         }
+
+        if let Err(e) = save_faction(faction) {
+            println!("Error saving faction {}: {}", faction.name, e);
+            return ApiResponse::error(format!("Failed to save faction {}: {}", faction.name, e));
+        }
     }
 
     println!("Creating special fleets");
     // Create special fleets (pirates, merchants, etc.)
     let special_fleets = vec![
         ("Pirate", 3),    // 3 pirate fleets
-        ("Merchant", 3),  // 3 merchant fleets
         ("Military", 2),  // 2 military fleets
         ("Mercenary", 2), // 2 mercenary fleets
     ];
@@ -1356,168 +2064,236 @@ pub fn create_new_game(settings: Json<GameSettings>) -> Json<ApiResponse<String>
                 random_position(
                     settings.map_width as i32,
                     settings.map_height as i32,
-                    settings.map_length as i32
+                    settings.map_length as i32,
+                    &mut rng
                 ),
-                rand::random::<usize>() % 3 + 1, // 1-3 ships
+                rng.gen_range(1..=3), // 1-3 ships
+                &mut rng
             ) {
                 println!("Generated {} fleet {}: {}", fleet_type, fleet_num + 1, fleet.name);
             }
         }
     }
 
-    println!("Creating player");
-    // Create a new player with starting credits
-    let player = Player::new(&settings.player_name, settings.starting_credits as f64);
-    
-    println!("Saving player");
-    // Save the player
-    let player_path = game_dir.join("players").join(format!("{}.json", settings.player_name));
-    
-    if let Err(e) = save_json(&player_path, &player) {
+    println!("Creating NPC traders");
+    // Merchants are persistent traders that start docked in a random system
+    for trader_num in 0..NPC_TRADER_COUNT {
+        if game_world.is_empty() {
+            break;
+        }
+        let home_system = &game_world[rng.gen_range(0..game_world.len())];
+        match generate_and_save_fleet(
+            format!("{}{}", NPC_TRADER_OWNER_PREFIX, trader_num + 1),
+            home_system.position,
+            rng.gen_range(1..=3), // 1-3 ships
+            &mut rng
+        ) {
+            Ok(mut fleet) => {
+                fleet.current_system_id = Some(home_system.id);
+                fleet.local_position = Some(Position { x: 0, y: 0, z: 0 });
+                fleet.trader = Some(TraderProfile::new(rng.sample(Standard)));
+                if let Err(e) = save_fleet(&fleet) {
+                    println!("Error saving NPC trader {}: {}", fleet.name, e);
+                }
+            }
+            Err(e) => println!("Error generating NPC trader {}: {}", trader_num + 1, e),
+        }
+    }
+
+    println!("Creating player and starting fleet");
+    // The player starts with the game's starting credits and one small freighter
+    let (player, player_fleet) = Player::start(&settings.player_name, &settings, &mut rng);
+    let mut transaction = Transaction::new(&game_id);
+    let staged = transaction.stage_player(&player).and_then(|_| transaction.stage_fleet(&player_fleet));
+    if let Err(e) = staged.and_then(|_| transaction.commit()) {
         println!("Error saving player: {}", e);
         return ApiResponse::error(format!("Failed to save player: {}", e));
     }
 
-    println!("Creating starting fleet");
-    // Create a starting fleet for the player
-    let mut player_fleet = Fleet::new(
-        settings.player_name.clone(),
-        random_position(
-            settings.map_width as i32,
-            settings.map_height as i32,
-            settings.map_length as i32
-        ),
-        1
-    );
-    
-    println!("Creating starting ship");
-    // Create a starting ship for the player
-    let mut starting_ship = Ship::new(
-        ShipType::Freighter,
-        ShipSize::Small,
-        ShipEngine::Basic
-    );
-    starting_ship.position = player_fleet.position.clone();
-    starting_ship.hp = 100; // Ensure full health
-    starting_ship.name = format!("{}'s First Ship", settings.player_name);
-    player_fleet.ships.push(starting_ship);
-    
-    println!("Saving player fleet");
-    let fleet_path = game_dir.join("fleets").join(format!("Fleet_{}_{}.json", settings.player_name, 1));
-
-    if let Err(e) = save_json(&fleet_path, &player_fleet) {
-        println!("Error saving player fleet: {}", e);
-        return ApiResponse::error(format!("Failed to save player fleet: {}", e));
-    }
+    // Only whoever created the game learns the code to register its player
+    let claim_code = match auth::issue_claim(&game_id, &settings.player_name) {
+        Ok(code) => code,
+        Err(e) => return ApiResponse::error(format!("Failed to issue claim code: {}", e)),
+    };
 
     println!("Game creation completed successfully");
-    ApiResponse::success("Game created successfully".to_string(), "Success".to_string())
+    let message = format!("Game created successfully. Register {} with the returned claim code", settings.player_name);
+    ApiResponse::success(claim_code, message)
 }
 
 #[get("/games/<game_id>/load")]
-pub fn load_game(game_id: String) -> Json<ApiResponse<String>> {
+pub fn load_game(registry: &State<GameRegistry>, game_id: String) -> Json<ApiResponse<String>> {
     let result: Result<String, String> = (|| {
         // Load the saved game
         let saved_game = SavedGame::load_game(&game_id)
             .map_err(|e| e.to_string())?
             .ok_or_else(|| "Game not found".to_string())?;
-        
-        // If running a real game and in-memory world is empty, try loading GameWorld.json from disk
-        if saved_game.settings.game_id != "test_game" {
-            if let Ok(mut guard) = crate::GLOBAL_GAME_WORLD.lock() {
-                if guard.is_empty() {
-                    match crate::models::game_world::load_game_world(&saved_game.settings.game_id) {
-                        Ok(world) => {
-                            println!("Loaded {} systems into GLOBAL_GAME_WORLD at runtime", world.len());
-                            *guard = world.clone();
-                        }
-                        Err(e) => println!("Failed to load GameWorld.json at startup: {}", e),
-                    }
-                }
-            }
-        }
-        // Get the in-memory game world (or test stub)
-        let mut game_world = if saved_game.settings.game_id == "test_game" {
-            println!("  Running in test mode, loading test game world...");
-            let system_path = crate::models::game_state::game_data_path(&saved_game.settings.game_id, &["star_systems", "Star_System_0.json"]);
-            match crate::models::game_state::load_json::<StarSystem>(&system_path) {
-                Ok(system) => vec![system],
-                Err(e) => {
-                    println!("  Error loading test system: {}. Using empty world.", e);
-                    Vec::new()
-                }
-            }
-        } else {
-            get_global_game_world()
-        };
-        // If no systems are in memory for a real game, try loading individual star system files
-        if saved_game.settings.game_id != "test_game" && game_world.is_empty() {
-            println!("  In-memory world empty. Attempting to load star_systems from data directory...");
-            let star_dir = game_path(&["star_systems"]);
-            if star_dir.exists() {
-                let mut systems = Vec::new();
-                for entry in std::fs::read_dir(&star_dir).map_err(|e| e.to_string())? {
-                    let entry = entry.map_err(|e| e.to_string())?;
-                    let path = entry.path();
-                    if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                        if let Ok(system) = crate::models::game_state::load_json::<StarSystem>(&path) {
-                            systems.push(system);
-                        }
+
+        // Keep requests out of the game while its saves are repaired
+        let context = registry.get(&saved_game.settings.game_id)?;
+        let _writes = context.lock_writes();
+
+        // Finish or discard any saves a crash interrupted before reading the game
+        let recovery = recover_game(&crate::models::game_state::game_data_path(&saved_game.settings.game_id, &[]))
+            .map_err(|e| format!("Failed to recover game data: {}", e))?;
+        println!("  Save recovery: {}", recovery.summary());
+
+        // Bring saves from older versions up to the current layout
+        let migration = migrate_game(&saved_game.settings.game_id, false)?;
+        println!("  Save migration: {}", migration.summary());
+
+        // Load the galaxy into memory unless this game already has it there
+        if context.world().is_empty() {
+            let game_world = if saved_game.settings.game_id == "test_game" {
+                println!("  Running in test mode, loading test game world...");
+                match load_document::<StarSystem>(&saved_game.settings.game_id, &["star_systems", "Star_System_0.json"])
+                    .and_then(|system| system.ok_or_else(|| "Star_System_0.json not found".to_string()))
+                {
+                    Ok(system) => vec![system],
+                    Err(e) => {
+                        println!("  Error loading test system: {}. Using empty world.", e);
+                        Vec::new()
                     }
                 }
-                if !systems.is_empty() {
-                    println!("  Loaded {} systems from star_systems folder", systems.len());
-                    if let Ok(mut guard) = crate::GLOBAL_GAME_WORLD.lock() {
-                        *guard = systems.clone();
-                    }
-                    game_world = systems;
+            } else {
+                match crate::models::game_world::load_game_world(&saved_game.settings.game_id) {
+                    Ok(world) if !world.is_empty() => world,
+                    // Fall back to the individual star system documents
+                    _ => load_saved_star_systems(&saved_game.settings.game_id)?,
                 }
-            }
+            };
+            println!("  Loaded {} systems into memory", game_world.len());
+            context.set_world(game_world);
         }
 
-        // Update the global game world
-        if let Ok(mut guard) = crate::GLOBAL_GAME_WORLD.lock() {
-            *guard = game_world;
-        } else {
-            return Err("Failed to update game world".to_string());
-        }
-
-        // Load the player data to get the current credits
-        let player_path = Path::new("data")
-            .join("game")
-            .join(&saved_game.settings.game_id)
-            .join("players")
-            .join(format!("{}.json", saved_game.settings.player_name));
-
-        let player_credits = if player_path.exists() {
-            let file = std::fs::File::open(&player_path)
-                .map_err(|e| format!("Failed to open player file: {}", e))?;
-            let player: Player = serde_json::from_reader(file)
-                .map_err(|e| format!("Failed to parse player data: {}", e))?;
-            player.credits
-        } else {
-            saved_game.settings.starting_credits as f64
-        };
+        let mut message = "Game loaded successfully".to_string();
+        if !recovery.is_clean() {
+            message = format!("{}. {}", message, recovery.summary());
+        }
+        if !migration.is_up_to_date() {
+            message = format!("{}. {}", message, migration.summary());
+        }
+        Ok(message)
+    })();
 
-        // Update the game state with the current game ID and credits
-        let mut state = crate::models::game_state::get_game_state()
-            .map_err(|e| format!("Failed to get game state: {}", e))?;
-        state.current_game_id = Some(saved_game.settings.game_id.clone());
-        state.credits = player_credits;
-        crate::models::game_state::save_game_state(state)
-            .map_err(|e| format!("Failed to save game state: {}", e))?;
+    match result {
+        Ok(message) => ApiResponse::success(message, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
 
-        Ok("Game loaded successfully".to_string())
+/// Creates an account in a game and logs it in; the game's creator claims
+/// the player the game was created for with its claim code and becomes its admin
+#[post("/games/<game_id>/register", format = "json", data = "<credentials>")]
+pub fn register_player(registry: &State<GameRegistry>, sessions: &State<SessionStore>, game_id: String, credentials: Json<Credentials>) -> Json<ApiResponse<Session>> {
+    println!("Registering {} in game {}", credentials.name, game_id);
+    let result: Result<Session, String> = (|| {
+        let context = registry.get(&game_id)?;
+        let _game = context.enter_for_writes();
+        let account = auth::register(&game_id, &credentials, &mut next_game_rng("players")?)?;
+        sessions.start(&game_id, &account)
     })();
 
     match result {
-        Ok(message) => ApiResponse::success(message, "Success".to_string()),
+        Ok(session) => {
+            let message = format!("Welcome, {}", session.player_name);
+            ApiResponse::success(session, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Issues a claim code for a player of the game that has no account yet, so
+/// someone the admin hands it to can register as that player
+#[post("/games/<game_id>/players/<name>/claim")]
+pub fn issue_player_claim(registry: &State<GameRegistry>, session: PlayerSession, game_id: String, name: String) -> Json<ApiResponse<String>> {
+    let result: Result<String, String> = (|| {
+        session.require_admin_of(&game_id)?;
+        let context = registry.get(&game_id)?;
+        let _game = context.enter_for_writes();
+        auth::issue_claim(&game_id, &name)
+    })();
+
+    match result {
+        Ok(code) => ApiResponse::success(code, format!("Register {} with the returned claim code", name.trim())),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Logs a player into a game, returning the token to send as `Authorization: Bearer <token>`
+#[post("/games/<game_id>/login", format = "json", data = "<credentials>")]
+pub fn login_player(registry: &State<GameRegistry>, sessions: &State<SessionStore>, game_id: String, credentials: Json<Credentials>) -> Json<ApiResponse<Session>> {
+    let result: Result<Session, String> = (|| {
+        registry.get(&game_id)?;
+        let account = auth::login(&game_id, &credentials)?;
+        sessions.start(&game_id, &account)
+    })();
+
+    match result {
+        Ok(session) => {
+            let message = format!("Logged in as {}", session.player_name);
+            ApiResponse::success(session, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Ends the session of the request's token
+#[post("/logout")]
+pub fn logout_player(sessions: &State<SessionStore>, session: PlayerSession) -> Json<ApiResponse<String>> {
+    match session.session() {
+        Ok(session) => {
+            sessions.end(&session.token);
+            ApiResponse::success(format!("Logged out {}", session.player_name), "Success".to_string())
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Returns the session of the request's token
+#[get("/session")]
+pub fn get_session(session: PlayerSession) -> Json<ApiResponse<Session>> {
+    match session.session() {
+        Ok(session) => ApiResponse::success(session.clone(), "Success".to_string()),
         Err(e) => ApiResponse::error(e)
     }
 }
 
+/// Streams the events of the session's game that its player may see, each
+/// sent under its `type`. A client that falls behind is sent `lagged` with
+/// the number of events it missed, and should reload what it shows.
+#[get("/events")]
+pub fn stream_events(game: CurrentGame, session: PlayerSession, mut shutdown: Shutdown) -> Result<EventStream![], Json<ApiResponse<String>>> {
+    let subscription = game.context().and_then(|context| {
+        session.authorize_game(&context.game_id)?;
+        Ok((context.subscribe(), session.player_name()?.to_string()))
+    });
+    let (mut events, player_name) = subscription.map_err(ApiResponse::error)?;
+    println!("{} subscribed to game events", player_name);
+
+    Ok(EventStream! {
+        loop {
+            let event = select! {
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        yield Event::data(missed.to_string()).event("lagged");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            if event.audience.includes(&player_name) {
+                yield Event::json(&event).event(event.kind.name());
+            }
+        }
+    })
+}
+
 #[get("/settings")]
-pub fn get_settings() -> Json<ApiResponse<GameSettings>> {
+pub fn get_settings(game: CurrentGame) -> Json<ApiResponse<GameSettings>> {
+    let _game = game.enter();
     // First try to load settings from the game directory
     match load_settings() {
         Ok(settings) => ApiResponse::success(settings, "Successfully retrieved settings".to_string()),
@@ -1526,8 +2302,13 @@ pub fn get_settings() -> Json<ApiResponse<GameSettings>> {
 }
 
 #[post("/settings", data = "<settings>")]
-pub fn update_settings(settings: Json<GameSettings>) -> Json<ApiResponse<String>> {
+pub fn update_settings(game: CurrentGame, session: PlayerSession, settings: Json<GameSettings>) -> Json<ApiResponse<String>> {
+    let _game = game.enter_for_writes();
     let settings = settings.into_inner();
+    // Only the admin of a game may change its settings
+    if let Err(e) = session.require_admin_of(&settings.game_id) {
+        return ApiResponse::error(e);
+    }
     
     // Load the specific saved game using the game_id from the settings
     match SavedGame::load_game(&settings.game_id) {
@@ -1543,11 +2324,89 @@ pub fn update_settings(settings: Json<GameSettings>) -> Json<ApiResponse<String>
     }
 }
 
+/// Reports the migrations loading a game would apply, without changing anything
+#[get("/games/<game_id>/migration")]
+pub fn preview_game_migration(session: PlayerSession, game_id: String) -> Json<ApiResponse<MigrationReport>> {
+    match session.require_admin_of(&game_id).and_then(|_| migrate_game(&game_id, true)) {
+        Ok(report) => {
+            let message = report.summary();
+            ApiResponse::success(report, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Moves a game's saves to another storage backend
+#[post("/games/<game_id>/storage", format = "json", data = "<data>")]
+pub fn migrate_game_storage(registry: &State<GameRegistry>, session: PlayerSession, game_id: String, data: Json<StorageMigrationData>) -> Json<ApiResponse<StorageMigrationReport>> {
+    if let Err(e) = session.require_admin_of(&game_id) {
+        return ApiResponse::error(e);
+    }
+    println!("Moving game {} to {:?} storage", game_id, data.backend);
+    let context = match registry.get(&game_id) {
+        Ok(context) => context,
+        Err(e) => return ApiResponse::error(e),
+    };
+    let _writes = context.lock_writes();
+    match migrate_storage(&game_id, data.backend) {
+        Ok(report) => {
+            context.clear_caches();
+            let message = format!("Moved {} documents from {:?} to {:?} storage", report.documents, report.from, report.to);
+            ApiResponse::success(report, message)
+        }
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Packs a saved game into a single archive that can be shared or backed up
+#[get("/games/<game_id>/export")]
+pub fn export_game(session: PlayerSession, game_id: String) -> Json<ApiResponse<GameArchive>> {
+    println!("Exporting game {}", game_id);
+    match session.require_admin_of(&game_id).and_then(|_| crate::models::archive::export_game(&game_id)) {
+        Ok(archive) => {
+            println!("  Exported {} files", archive.files.len());
+            ApiResponse::success(archive, "Game exported successfully".to_string())
+        }
+        Err(e) => {
+            println!("  Export failed: {}", e);
+            ApiResponse::error(e)
+        }
+    }
+}
+
+/// Restores an exported archive as a new game with its own game id; only
+/// an admin of the game the archive was exported from may restore it
+#[post("/games/import", format = "json", data = "<archive>")]
+pub fn import_game(session: PlayerSession, archive: Json<GameArchive>) -> Json<ApiResponse<SavedGame>> {
+    println!("Importing game {} ({} files)", archive.manifest.game_id, archive.files.len());
+    let archive = archive.into_inner();
+    match session.require_admin_of(&archive.manifest.game_id).and_then(|_| crate::models::archive::import_game(archive)) {
+        Ok(saved_game) => {
+            println!("  Imported as game {}", saved_game.game_id);
+            // Accounts are never archived, so the creator's player is claimed afresh
+            match auth::issue_claim(&saved_game.game_id, &saved_game.settings.player_name) {
+                Ok(code) => {
+                    let message = format!("Game imported successfully. Register {} with claim code {}", saved_game.settings.player_name, code);
+                    ApiResponse::success(saved_game, message)
+                }
+                Err(e) => ApiResponse::error(format!("Failed to issue claim code: {}", e)),
+            }
+        }
+        Err(e) => {
+            println!("  Import failed: {}", e);
+            ApiResponse::error(e)
+        }
+    }
+}
+
 #[delete("/games/<game_id>")]
-pub fn delete_game(game_id: String) -> Json<ApiResponse<String>> {
+pub fn delete_game(registry: &State<GameRegistry>, sessions: &State<SessionStore>, session: PlayerSession, game_id: String) -> Json<ApiResponse<String>> {
+    if let Err(e) = session.require_admin_of(&game_id) {
+        return ApiResponse::error(e);
+    }
     // Delete the game directory and all its contents
-    let game_dir = Path::new("data").join("game").join(&game_id);
-    let save_file = Path::new("data").join("saves").join(format!("{}.json", game_id));
+    let game_dir = game_data_path(&game_id, &[]);
+    let save_file = data_root().join("saves").join(format!("{}.json", game_id));
 
     // Remove the save file
     if save_file.exists() {
@@ -1557,6 +2416,8 @@ pub fn delete_game(game_id: String) -> Json<ApiResponse<String>> {
     }
 
     // Remove the game directory and all its contents
+    registry.evict(&game_id);
+    sessions.end_game(&game_id);
     if game_dir.exists() {
         if let Err(e) = fs::remove_dir_all(&game_dir) {
             return ApiResponse::error(format!("Failed to delete game directory: {}", e));
@@ -1567,21 +2428,22 @@ pub fn delete_game(game_id: String) -> Json<ApiResponse<String>> {
 }
 
 #[post("/planet/<system_id>/<planet_id>/buy_ship", format = "json", data = "<data>")]
-pub fn buy_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeData>) -> Json<ApiResponse<String>> {
+pub fn buy_ship(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<ShipTradeData>) -> Json<ApiResponse<String>> {
+    let _game = game.enter_for_writes();
     let result: Result<String, String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let mut player = load_player(&settings.player_name).map_err(|e| e.to_string())?;
-        let market_path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets")
-            .join(format!("ships_{}_{}.json", system_id, planet_id));
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name).map_err(|e| e.to_string())?;
+        let market_file = format!("ships_{}_{}.json", system_id, planet_id);
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
 
         println!("Buy ship request: system={}, planet={}, ship_index={}, fleet_name={:?}", 
                  system_id, planet_id, data.ship_index, data.fleet_name);
         println!("Player credits before purchase: {}", player.credits);
 
-        let mut ship_market: ShipMarket = load_json(&market_path).map_err(|e| e.to_string())?;
+        let mut ship_market: ShipMarket = load_document(&settings.game_id, &["markets", &market_file])?
+            .ok_or_else(|| format!("No ship market at planet {} in system {}", planet_id, system_id))?;
         if data.ship_index >= ship_market.ships.len() {
             return Err(format!("Invalid ship index: {} (market has {} ships)", 
                               data.ship_index, ship_market.ships.len()));
@@ -1593,10 +2455,8 @@ pub fn buy_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeData>) -
                 player.credits -= price;
                 println!("Player credits after purchase: {}", player.credits);
                 
-                let fleet_name = data.fleet_name.clone().unwrap_or_else(|| format!("Fleet_{}_1", settings.player_name));
-                let mut fleet = crate::models::fleet::load_fleet(&fleet_name)
-                    .map_err(|e| e.to_string())?
-                    .ok_or_else(|| "Fleet not found".to_string())?;
+                let fleet_name = data.fleet_name.clone().unwrap_or_else(|| format!("Fleet_{}_1", player_name));
+                let mut fleet = load_fleet_at_planet(player_name, &fleet_name, system_id, planet)?;
                 
                 println!("Adding ship {} to fleet {}", ship.name, fleet.name);
                 fleet.ships.push(ship.clone());
@@ -1604,13 +2464,12 @@ pub fn buy_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeData>) -
                 // Remove the ship from the market
                 ship_market.ships.remove(data.ship_index);
                 
-                // Save all changes
-                save_json(&market_path, &ship_market).map_err(|e| e.to_string())?;
-                if let Err(e) = player.save() {
-                    println!("Error saving player data: {}", e);
-                    return Err("Failed to save player data".to_string());
-                }
-                crate::models::fleet::save_fleet(&fleet).map_err(|e| e.to_string())?;
+                // Save all changes in one transaction
+                let mut transaction = Transaction::new(&settings.game_id);
+                transaction.stage_ship_market(system_id, planet_id, &ship_market)?;
+                transaction.stage_player(&player)?;
+                transaction.stage_fleet(&fleet)?;
+                transaction.commit()?;
                 
                 Ok(format!("Successfully bought ship for {} credits", price))
             } else {
@@ -1628,25 +2487,24 @@ pub fn buy_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeData>) -
 }
 
 #[post("/planet/<system_id>/<planet_id>/sell_ship", format = "json", data = "<data>")]
-pub fn sell_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeData>) -> Json<ApiResponse<String>> {
+pub fn sell_ship(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<ShipTradeData>) -> Json<ApiResponse<String>> {
+    let _game = game.enter_for_writes();
     let result: Result<String, String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let mut player = load_player(&settings.player_name).map_err(|e| e.to_string())?;
-        let market_path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets")
-            .join(format!("ships_{}_{}.json", system_id, planet_id));
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name).map_err(|e| e.to_string())?;
+        let market_file = format!("ships_{}_{}.json", system_id, planet_id);
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
 
         println!("Sell ship request: system={}, planet={}, ship_index={}, fleet_name={:?}", 
                  system_id, planet_id, data.ship_index, data.fleet_name);
         println!("Player credits before sale: {}", player.credits);
 
-        let mut ship_market: ShipMarket = load_json(&market_path).map_err(|e| e.to_string())?;
-        let fleet_name = data.fleet_name.clone().unwrap_or_else(|| format!("Fleet_{}_1", settings.player_name));
-        let mut fleet = crate::models::fleet::load_fleet(&fleet_name)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| format!("Fleet not found: {}", fleet_name))?;
+        let mut ship_market: ShipMarket = load_document(&settings.game_id, &["markets", &market_file])?
+            .ok_or_else(|| format!("No ship market at planet {} in system {}", planet_id, system_id))?;
+        let fleet_name = data.fleet_name.clone().unwrap_or_else(|| format!("Fleet_{}_1", player_name));
+        let mut fleet = load_fleet_at_planet(player_name, &fleet_name, system_id, planet)?;
         
         if data.ship_index >= fleet.ships.len() {
             return Err(format!("Invalid ship index: {} (fleet has {} ships)", 
@@ -1668,13 +2526,12 @@ pub fn sell_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeData>)
         // Remove ship from fleet
         fleet.ships.remove(data.ship_index);
         
-        // Save all changes
-        save_json(&market_path, &ship_market).map_err(|e| e.to_string())?;
-        if let Err(e) = player.save() {
-            println!("Error saving player data: {}", e);
-            return Err("Failed to save player data".to_string());
-        }
-        crate::models::fleet::save_fleet(&fleet).map_err(|e| e.to_string())?;
+        // Save all changes in one transaction
+        let mut transaction = Transaction::new(&settings.game_id);
+        transaction.stage_ship_market(system_id, planet_id, &ship_market)?;
+        transaction.stage_player(&player)?;
+        transaction.stage_fleet(&fleet)?;
+        transaction.commit()?;
         
         Ok(format!("Successfully sold ship for {} credits", price))
     })();
@@ -1686,17 +2543,18 @@ pub fn sell_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeData>)
 }
 
 #[post("/planet/<system_id>/<planet_id>/trade_in_ship", format = "json", data = "<data>")]
-pub fn trade_in_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeInData>) -> Json<ApiResponse<String>> {
+pub fn trade_in_ship(game: CurrentGame, session: PlayerSession, system_id: usize, planet_id: usize, data: Json<ShipTradeInData>) -> Json<ApiResponse<String>> {
+    let _game = game.enter_for_writes();
     let result: Result<String, String> = (|| {
         let settings = load_settings().map_err(|e| e.to_string())?;
-        let mut player = load_player(&settings.player_name).map_err(|e| e.to_string())?;
-        let market_path = Path::new("data")
-            .join("game")
-            .join(&settings.game_id)
-            .join("markets")
-            .join(format!("ships_{}_{}.json", system_id, planet_id));
-
-        let mut ship_market: ShipMarket = load_json(&market_path).map_err(|e| e.to_string())?;
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name).map_err(|e| e.to_string())?;
+        let market_file = format!("ships_{}_{}.json", system_id, planet_id);
+        let system = load_star_system(system_id)?;
+        let planet = system.planets.get(planet_id).ok_or_else(|| "Planet not found".to_string())?;
+
+        let mut ship_market: ShipMarket = load_document(&settings.game_id, &["markets", &market_file])?
+            .ok_or_else(|| format!("No ship market at planet {} in system {}", planet_id, system_id))?;
         if data.ship_index >= ship_market.ships.len() {
             return Err(format!("Invalid ship index: {} (market has {} ships)", 
                               data.ship_index, ship_market.ships.len()));
@@ -1707,10 +2565,8 @@ pub fn trade_in_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeInD
         
         // Check if the ship has a price
         if let Some(price) = new_ship.price {
-            let fleet_name = data.fleet_name.clone().unwrap_or_else(|| format!("Fleet_{}_1", settings.player_name));
-            let mut fleet = crate::models::fleet::load_fleet(&fleet_name)
-                .map_err(|e| e.to_string())?
-                .ok_or_else(|| "Fleet not found".to_string())?;
+            let fleet_name = data.fleet_name.clone().unwrap_or_else(|| format!("Fleet_{}_1", player_name));
+            let mut fleet = load_fleet_at_planet(player_name, &fleet_name, system_id, planet)?;
 
             // Calculate trade-in value if a ship is being traded in
             let mut trade_in_value = 0.0;
@@ -1748,13 +2604,12 @@ pub fn trade_in_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeInD
                 // Remove the ship from the market
                 ship_market.ships.remove(data.ship_index);
                 
-                // Save all changes
-                save_json(&market_path, &ship_market).map_err(|e| e.to_string())?;
-                if let Err(e) = player.save() {
-                    println!("Error saving player data: {}", e);
-                    return Err("Failed to save player data".to_string());
-                }
-                crate::models::fleet::save_fleet(&fleet).map_err(|e| e.to_string())?;
+                // Save all changes in one transaction
+                let mut transaction = Transaction::new(&settings.game_id);
+                transaction.stage_ship_market(system_id, planet_id, &ship_market)?;
+                transaction.stage_player(&player)?;
+                transaction.stage_fleet(&fleet)?;
+                transaction.commit()?;
                 
                 Ok(format!("Successfully traded in ship for {} credits", final_price))
             } else {
@@ -1772,19 +2627,17 @@ pub fn trade_in_ship(system_id: usize, planet_id: usize, data: Json<ShipTradeInD
 }
 
 #[get("/fleets")]
-pub fn get_player_fleets() -> Json<ApiResponse<Vec<Fleet>>> {
-    let settings = match load_settings() {
-        Ok(settings) => settings,
-        Err(e) => {
-            println!("Error loading settings: {}", e);
-            return ApiResponse::error("Failed to load game settings".to_string());
-        }
+pub fn get_player_fleets(game: CurrentGame, session: PlayerSession) -> Json<ApiResponse<Vec<Fleet>>> {
+    let _game = game.enter();
+    let player_name = match session.player_name() {
+        Ok(player_name) => player_name,
+        Err(e) => return ApiResponse::error(e),
     };
 
-    println!("Loading fleets for player: {}", settings.player_name);
+    println!("Loading fleets for player: {}", player_name);
     
     // First load the player to get their fleet list
-    let player = match load_player(&settings.player_name) {
+    let player = match load_player(player_name) {
         Ok(player) => player,
         Err(e) => {
             println!("Error loading player: {}", e);
@@ -1817,8 +2670,10 @@ pub fn get_player_fleets() -> Json<ApiResponse<Vec<Fleet>>> {
 }
 
 #[post("/player/<name>/add_credits", format = "json", data = "<amount>")]
-pub fn add_credits(name: &str, amount: Json<f64>) -> Json<ApiResponse<String>> {
+pub fn add_credits(game: CurrentGame, session: PlayerSession, name: &str, amount: Json<f64>) -> Json<ApiResponse<String>> {
+    let _game = game.enter_for_writes();
     let result: Result<String, String> = (|| {
+        session.require_admin()?;
         let mut player = load_player(name).map_err(|e| e.to_string())?;
         player.credits += *amount;
         if let Err(e) = player.save() {
@@ -1835,8 +2690,10 @@ pub fn add_credits(name: &str, amount: Json<f64>) -> Json<ApiResponse<String>> {
 }
 
 #[post("/player/<name>/remove_credits", format = "json", data = "<amount>")]
-pub fn remove_credits(name: &str, amount: Json<f64>) -> Json<ApiResponse<String>> {
+pub fn remove_credits(game: CurrentGame, session: PlayerSession, name: &str, amount: Json<f64>) -> Json<ApiResponse<String>> {
+    let _game = game.enter_for_writes();
     let result: Result<String, String> = (|| {
+        session.require_admin()?;
         let mut player = load_player(name).map_err(|e| e.to_string())?;
         if player.credits < *amount {
             return Err(format!("Not enough credits: need {} but have {}", *amount, player.credits));
@@ -1856,34 +2713,236 @@ pub fn remove_credits(name: &str, amount: Json<f64>) -> Json<ApiResponse<String>
 }
 
 #[post("/clear-caches")]
-pub fn clear_caches() -> Json<ApiResponse<String>> {
+pub fn clear_caches(game: CurrentGame, session: PlayerSession) -> Json<ApiResponse<String>> {
+    if let Err(e) = session.require_admin() {
+        return ApiResponse::error(e);
+    }
     // Clear all relevant caches
-    crate::models::game_state::SYSTEM_CACHE.remove_all();
-    crate::models::game_state::FLEET_CACHE.remove_all();
-    crate::models::game_state::MARKET_CACHE.remove_all();
+    let _game = game.enter();
+    crate::models::game_state::clear_caches();
     ApiResponse::success("Caches cleared successfully".to_string(), "Success".to_string())
 }
 
-fn save_fleet_model(fleet: &Fleet) -> Result<(), String> {
-    let settings = load_settings().map_err(|e| e.to_string())?;
-    let fleet_path = Path::new("data")
-        .join("game")
-        .join(&settings.game_id)
-        .join("fleets")
-        .join(format!("{}.json", fleet.name));
+/// Applies a reputation change for the player's dealings with the owner of a fleet.
+///
+/// Fleets owned by anyone other than a faction leave reputation untouched.
+fn record_faction_dealings(player: &mut Player, fleet_owner: &str, change: f32) -> Result<Vec<ReputationChange>, String> {
+    let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+    let changes = apply_reputation_change(player, &factions, fleet_owner, change);
+    for change in &changes {
+        println!("  Reputation with {} changed by {} to {}", change.faction, change.change, change.reputation);
+    }
+    Ok(changes)
+}
 
-    if let Some(parent) = fleet_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create fleet directory: {}", e))?;
+/// Lists every faction in the active game
+#[get("/factions")]
+pub fn get_factions(game: CurrentGame) -> Json<ApiResponse<Vec<Faction>>> {
+    let _game = game.enter();
+    match list_factions() {
+        Ok(factions) => ApiResponse::success(factions, "Success".to_string()),
+        Err(e) => ApiResponse::error(format!("Failed to load factions: {}", e))
     }
+}
 
-    let file = std::fs::File::create(fleet_path)
-        .map_err(|e| format!("Failed to create fleet file: {}", e))?;
-    
-    serde_json::to_writer(file, fleet)
-        .map_err(|e| format!("Failed to write fleet data: {}", e))?;
-    
-    Ok(())
+/// Returns a single faction, including its relations and territory
+#[get("/factions/<name>")]
+pub fn get_faction(game: CurrentGame, name: &str) -> Json<ApiResponse<Faction>> {
+    let _game = game.enter();
+    match crate::models::faction::load_faction(name) {
+        Ok(Some(faction)) => ApiResponse::success(faction, "Success".to_string()),
+        Ok(None) => ApiResponse::error(format!("Faction {} not found", name)),
+        Err(e) => ApiResponse::error(format!("Failed to load faction: {}", e))
+    }
+}
+
+/// Returns the star systems controlled by a faction that are on the player's
+/// charts, as the player knows them
+#[get("/factions/<name>/territory")]
+pub fn get_faction_territory(game: CurrentGame, session: PlayerSession, name: &str) -> Json<ApiResponse<Vec<StarSystem>>> {
+    let _game = game.enter();
+    let result: Result<Vec<StarSystem>, String> = (|| {
+        let settings = load_settings().map_err(|e| e.to_string())?;
+        let knowledge = PlayerKnowledge::load(&settings.game_id, session.player_name()?)?;
+        let faction = crate::models::faction::load_faction(name)
+            .map_err(|e| format!("Failed to load faction: {}", e))?
+            .ok_or_else(|| format!("Faction {} not found", name))?;
+        let systems = faction.territory.iter()
+            .map(|system_id| load_star_system(*system_id))
+            .collect::<Result<Vec<StarSystem>, String>>()?;
+        Ok(systems.iter().filter_map(|system| knowledge.filter_system(system)).collect())
+    })();
+
+    match result {
+        Ok(systems) => ApiResponse::success(systems, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Returns the player's reputation and standing with every faction
+#[get("/factions/standing/<player_name>")]
+pub fn get_faction_standing(game: CurrentGame, session: PlayerSession, player_name: &str) -> Json<ApiResponse<Vec<FactionStanding>>> {
+    let _game = game.enter();
+    let result: Result<Vec<FactionStanding>, String> = (|| {
+        session.authorize(player_name)?;
+        let player = load_player(player_name)?;
+        let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+        Ok(factions.into_iter().map(|faction| {
+            let reputation = player.reputation_with(&faction.name);
+            FactionStanding {
+                faction: faction.name,
+                reputation,
+                standing: Standing::from_reputation(reputation),
+            }
+        }).collect())
+    })();
+
+    match result {
+        Ok(standing) => ApiResponse::success(standing, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Applies a mission board update for the player and stages the board in
+/// the caller's transaction.
+///
+/// The player is updated in place with any rewards; staging it is left to the caller.
+fn record_mission_progress<F>(player: &mut Player, transaction: &mut Transaction, update: F) -> Result<Vec<Mission>, String>
+where
+    F: FnOnce(&mut MissionBoard, &mut Player, &[Faction]) -> Vec<Mission>,
+{
+    let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+    let mut board = MissionBoard::load()?;
+    let completed = update(&mut board, player, &factions);
+    transaction.stage_missions(&board)?;
+    for mission in &completed {
+        println!("  Mission completed: {} ({} credits)", mission.title, mission.reward_credits);
+    }
+    Ok(completed)
+}
+
+fn with_completed_missions(message: String, completed: &[Mission]) -> String {
+    completed.iter().fold(message, |message, mission| {
+        format!("{}. Mission completed: {} (+{} credits)", message, mission.title, mission.reward_credits)
+    })
+}
+
+/// Returns the missions offered at a planet. Boards are restocked as game
+/// time passes
+#[get("/missions/planet/<system_id>/<planet_id>")]
+pub fn get_planet_missions(game: CurrentGame, system_id: usize, planet_id: usize) -> Json<ApiResponse<Vec<Mission>>> {
+    let _game = game.enter();
+    let result: Result<Vec<Mission>, String> = (|| {
+        let board = MissionBoard::load()?;
+        let now = current_tick();
+        Ok(board.available_at(system_id, planet_id).into_iter()
+            .filter(|mission| now < mission.deadline_tick)
+            .cloned()
+            .collect())
+    })();
+
+    match result {
+        Ok(missions) => ApiResponse::success(missions, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Returns every mission the player has accepted, including finished ones
+#[get("/missions/player/<player_name>")]
+pub fn get_player_missions(game: CurrentGame, session: PlayerSession, player_name: &str) -> Json<ApiResponse<Vec<Mission>>> {
+    let _game = game.enter();
+    let result: Result<Vec<Mission>, String> = (|| {
+        session.authorize(player_name)?;
+        let board = MissionBoard::load()?;
+        Ok(board.for_player(player_name).into_iter().cloned().collect())
+    })();
+
+    match result {
+        Ok(missions) => ApiResponse::success(missions, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Accepts a mission for the active player
+#[post("/missions/<mission_id>/accept")]
+pub fn accept_mission(game: CurrentGame, session: PlayerSession, mission_id: &str) -> Json<ApiResponse<Mission>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Mission, String> = (|| {
+        let player_name = session.player_name()?;
+        let mut board = MissionBoard::load()?;
+        let mission = board.accept(mission_id, player_name, current_tick())?.clone();
+        board.save()?;
+        events::publish_missions(std::slice::from_ref(&mission));
+        Ok(mission)
+    })();
+
+    match result {
+        Ok(mission) => ApiResponse::success(mission, "Mission accepted".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Abandons one of the active player's missions
+#[post("/missions/<mission_id>/abandon")]
+pub fn abandon_mission(game: CurrentGame, session: PlayerSession, mission_id: &str) -> Json<ApiResponse<Mission>> {
+    let _game = game.enter_for_writes();
+    let result: Result<Mission, String> = (|| {
+        let player_name = session.player_name()?;
+        let mut player = load_player(player_name)?;
+        let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+        let mut board = MissionBoard::load()?;
+        let mission = board.abandon(mission_id, &mut player, &factions)?;
+        board.save()?;
+        crate::models::game_state::save_player(&player)?;
+        events::publish_missions(std::slice::from_ref(&mission));
+        Ok(mission)
+    })();
+
+    match result {
+        Ok(mission) => ApiResponse::success(mission, "Mission abandoned".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Returns the current tick and stardate of the active game
+#[get("/time")]
+pub fn get_game_time(game: CurrentGame) -> Json<ApiResponse<GameTime>> {
+    let _game = game.enter();
+    let result: Result<GameTime, String> = (|| {
+        let game_id = crate::models::game_state::current_game_id()?;
+        let clock = GameClock::load(&game_id)?;
+        Ok(GameTime::from(&clock))
+    })();
+
+    match result {
+        Ok(time) => ApiResponse::success(time, "Success".to_string()),
+        Err(e) => ApiResponse::error(e)
+    }
+}
+
+/// Advances the game clock, ticking every time-dependent system; reserved
+/// for the game's admin
+///
+/// # Arguments
+/// * `ticks` - Number of ticks to advance
+///
+/// # Returns
+/// * JSON response with the new time and a log of what happened
+#[post("/time/advance/<ticks>")]
+pub fn advance_time(game: CurrentGame, session: PlayerSession, ticks: u32) -> Json<ApiResponse<AdvanceTimeReport>> {
+    let _game = game.enter_for_writes();
+    println!("Advancing game time by {} ticks", ticks);
+    let admitted = game.context().and_then(|context| session.require_admin_of(&context.game_id));
+    match admitted.and_then(|_| tick::advance_time(ticks)) {
+        Ok(report) => {
+            println!("Game time advanced to tick {}", report.end_tick);
+            ApiResponse::success(report, format!("Advanced {} ticks", ticks))
+        }
+        Err(e) => {
+            println!("Failed to advance game time: {}", e);
+            ApiResponse::error(e)
+        }
+    }
 }
 
 /// Handles fleet movement requests, managing both system and deep space movement
@@ -1896,7 +2955,8 @@ fn save_fleet_model(fleet: &Fleet) -> Result<(), String> {
 /// # Returns
 /// * JSON response with movement result or error message
 #[post("/fleet/<owner_id>/<fleet_number>/move", format = "json", data = "<data>")]
-pub fn move_fleet(owner_id: String, fleet_number: usize, data: Json<MoveFleetData>) -> Json<ApiResponse<MoveFleetResponse>> {
+pub fn move_fleet(game: CurrentGame, session: PlayerSession, owner_id: String, fleet_number: usize, data: Json<MoveFleetData>) -> Json<ApiResponse<MoveFleetResponse>> {
+    let _game = game.enter_for_writes();
     println!("--- Starting Fleet Movement Operation ---");
     println!("  Fleet: Fleet_{}_{}", owner_id, fleet_number);
     println!("  Target Position: ({}, {}, {})", data.x, data.y, data.z);
@@ -1906,35 +2966,24 @@ pub fn move_fleet(owner_id: String, fleet_number: usize, data: Json<MoveFleetDat
     
     // Wrap the core logic in a block to handle potential errors and save the fleet at the end
     let result: Result<MoveFleetResponse, String> = (|| {
-        let settings = load_settings().map_err(|e| e.to_string())?;
+        // Only the fleet's owner may move it
+        let player_name = session.player_name()?;
+        session.authorize(&owner_id)?;
         // Load game settings
         let settings = load_settings().map_err(|e| e.to_string())?;
         // Build game_world: first try reading individual system files
-        let mut game_world = Vec::new();
-        let star_dir = crate::models::game_state::game_data_path(&settings.game_id, &["star_systems"]);
-        if star_dir.exists() {
-            println!("  Loading star systems from {}", star_dir.display());
-            for entry in std::fs::read_dir(&star_dir).map_err(|e| e.to_string())? {
-                let path = entry.map_err(|e| e.to_string())?.path();
-                if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                    if let Ok(system) = crate::models::game_state::load_json::<StarSystem>(&path) {
-                        game_world.push(system);
-                    }
-                }
-            }
-            // Update global cache
-            if let Ok(mut guard) = crate::GLOBAL_GAME_WORLD.lock() {
-                *guard = game_world.clone();
-            }
+        let mut game_world = load_saved_star_systems(&settings.game_id)?;
+        if !game_world.is_empty() {
+            println!("  Loaded {} saved star systems", game_world.len());
+            game.context()?.set_world(game_world.clone());
         } else if settings.game_id == "test_game" {
             // Test mode stub: load single test system
-            let system_path = crate::models::game_state::game_data_path(&settings.game_id, &["star_systems", "Star_System_0.json"]);
-            if let Ok(system) = crate::models::game_state::load_json::<StarSystem>(&system_path) {
+            if let Ok(Some(system)) = load_document::<StarSystem>(&settings.game_id, &["star_systems", "Star_System_0.json"]) {
                 game_world = vec![system];
             }
         } else {
             // Fallback to in-memory world
-            game_world = get_global_game_world();
+            game_world = game.context()?.world();
         }
         println!("  Game world contains {} systems", game_world.len());
 
@@ -1944,6 +2993,12 @@ pub fn move_fleet(owner_id: String, fleet_number: usize, data: Json<MoveFleetDat
             Err(e) => return Err(format!("Failed to load fleet: {}", e)),
         };
         println!("  Loaded fleet at position ({}, {}, {})", initial_fleet.position.x, initial_fleet.position.y, initial_fleet.position.z);
+        check_no_pending_encounters(&fleet_name)?;
+
+        if let Some(transit) = &initial_fleet.transit {
+            return Err(format!("Fleet is already in transit and arrives at tick {}", transit.arrival_tick));
+        }
+        let origin_fleet = initial_fleet.clone();
 
         let target_pos = Position { x: data.x, y: data.y, z: data.z };
         let start_pos = initial_fleet.position.clone();
@@ -2175,10 +3230,65 @@ pub fn move_fleet(owner_id: String, fleet_number: usize, data: Json<MoveFleetDat
             }
         };
 
+        // Travel takes game time: the fleet departs now and arrives on a later tick
+        let breakdown = response.breakdown.clone().unwrap_or_default();
+        let departed_fleet = origin_fleet.clone();
+        let mut updated_fleet = begin_transit(origin_fleet, updated_fleet, &breakdown, current_tick())?;
+        // A manual move replaces whatever route the fleet was following
+        updated_fleet.route = None;
+        let mut response = response;
+        if let Some(transit) = &updated_fleet.transit {
+            println!("  Fleet departs at tick {} and arrives at tick {} using {} fuel", transit.departure_tick, transit.arrival_tick, transit.fuel_used);
+            response.status = "in_transit".to_string();
+            response.message = format!(
+                "Fleet departed and will arrive at tick {} (stardate {:.1}), using {} fuel",
+                transit.arrival_tick, stardate_at(transit.arrival_tick), transit.fuel_used
+            );
+            response.current_position = updated_fleet.position;
+            response.current_system_id = updated_fleet.current_system_id;
+            response.local_current_position = updated_fleet.local_position;
+            response.remaining_distance = transit.distance;
+            response.transit = Some(transit.clone());
+        }
+
         // Save the final state of the fleet *after* successful movement
+        let mut transaction = Transaction::new(&settings.game_id);
+        // A fleet that is there right away meets whoever is there right away
+        let encounters = if updated_fleet.transit.is_none() {
+            roll_arrival_encounters(&settings.game_id, &mut updated_fleet, &mut transaction)?
+        } else {
+            Vec::new()
+        };
+        transaction.stage_fleet(&updated_fleet)?;
+
+        // Arriving in a system right away may complete exploration missions
+        let mut completed = Vec::new();
+        if updated_fleet.transit.is_none() && updated_fleet.owner_id == player_name
+            && let Some(system_id) = updated_fleet.current_system_id
+        {
+            let mut player = load_player(player_name)?;
+            completed = record_mission_progress(&mut player, &mut transaction, |board, player, factions| {
+                board.record_system_visit(player, factions, system_id)
+            })?;
+            if !completed.is_empty() {
+                transaction.stage_player(&player)?;
+                response.message = with_completed_missions(response.message, &completed);
+            }
+        }
+
         println!("Saving final fleet state for {}", updated_fleet.name);
-        save_fleet(&updated_fleet)?;
+        transaction.commit()?;
         println!("Fleet saved successfully.");
+        events::publish_fleet_moved(&departed_fleet, &updated_fleet);
+        events::publish_missions(&completed);
+        if !encounters.is_empty() {
+            response.message = format!("{}. Met {} fleets", response.message, encounters.len());
+            events::publish(GameEventKind::EncounterTriggered {
+                fleet: updated_fleet.name.clone(),
+                owner_id: updated_fleet.owner_id.clone(),
+                encounters: encounters.iter().map(|encounter| encounter.hostile_fleet.clone()).collect(),
+            });
+        }
 
         // Return the response part of the result
         Ok(response)
@@ -0,0 +1 @@
+pub mod tick;
\ No newline at end of file
@@ -0,0 +1,772 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use rand::Rng;
+use rand::distributions::Standard;
+use serde::Serialize;
+use crate::models::clock::{GameClock, ScheduledEvent, ScheduledEventKind, load_scheduled_events, stardate_at};
+use crate::models::fleet::{Fleet, list_all_fleets};
+use crate::models::game_state::{clear_caches, current_game_id};
+use crate::models::persistence::Transaction;
+use crate::models::storage::{document_exists, storage_for};
+use crate::models::game_world::load_game_world;
+use crate::models::market::{Market, list_saved_markets};
+use crate::models::mission::{Mission, MissionBoard, MissionKind, MissionStatus, settle_mission};
+use crate::models::encounter::{EncounterLog, encounter_danger, roll_encounters};
+use crate::models::faction::{controlling_faction, list_factions};
+use crate::models::events::{self, GameEventKind};
+use crate::models::exploration::PlayerKnowledge;
+use crate::models::game_state::load_player;
+use crate::models::player::Player;
+use crate::models::rng::{GameRng, next_game_rng};
+use crate::models::star_system::StarSystem;
+use crate::models::npc_trader::{
+    TradeRoute, TraderProfile, deep_space_trip, is_npc_trader, load_cargo, nearest_systems,
+    plan_trade_route, player_prices, unload_cargo,
+};
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::route::advance_route;
+use crate::models::travel::{advance_transit, begin_transit, fleet_fuel, fuel_required};
+
+/// Upper bound on ticks processed by a single `advance_time` call.
+pub const MAX_ADVANCE_TICKS: u32 = 1000;
+
+/// Chance per tick that an NPC trader with nothing worth buying moves on to another system.
+pub const NPC_TRADER_MOVE_CHANCE: f64 = 0.1;
+
+/// Chance per tick that word spreads of a shortage or glut at one of the markets.
+pub const MARKET_SHOCK_CHANCE: f64 = 0.05;
+/// Ticks between the word of a market shock and the shock itself.
+pub const MARKET_SHOCK_WARNING: std::ops::RangeInclusive<u64> = 3..=12;
+/// Units of stock a market shock adds or takes away.
+pub const MARKET_SHOCK_STOCK: std::ops::RangeInclusive<i32> = 100..=400;
+
+/// The slice of the world a tick operates on.
+///
+/// Everything is loaded once before the first tick and written back once
+/// after the last, so advancing many ticks costs a single round of file IO.
+pub struct TickContext {
+    pub game_id: String,
+    pub tick: u64,
+    pub fleets: Vec<Fleet>,
+    pub markets: BTreeMap<(usize, usize), Market>,
+    pub systems: Vec<StarSystem>,
+    pub events: Vec<ScheduledEvent>,
+    pub missions: MissionBoard,
+    /// Every encounter of the game; fleets that stop meet others here
+    pub encounters: EncounterLog,
+    /// Missions completed or failed during this advance, settled with their
+    /// players once the ticks have run
+    pub settled_missions: Vec<Mission>,
+    /// What each player knows of the galaxy, loaded the first time a tick needs it
+    pub knowledge: BTreeMap<String, PlayerKnowledge>,
+    pub rng: GameRng,
+    pub log: Vec<String>,
+    /// Events for subscribed players, published once the advance is saved
+    pub notices: Vec<GameEventKind>,
+}
+
+/// Summary of an `advance_time` call returned to clients.
+#[derive(Serialize, Debug, Clone)]
+pub struct AdvanceTimeReport {
+    pub start_tick: u64,
+    pub end_tick: u64,
+    pub stardate: f64,
+    pub log: Vec<String>,
+}
+
+/// A time-dependent part of the world.
+///
+/// Every system returned by `registered_systems` runs once per tick, in
+/// registration order, against the same `TickContext`.
+pub trait TickSystem {
+    fn name(&self) -> &'static str;
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String>;
+}
+
+impl TickContext {
+    /// Creates an empty context, mostly useful for driving systems in isolation.
+    pub fn new(game_id: &str, tick: u64, rng: GameRng) -> Self {
+        TickContext {
+            game_id: game_id.to_string(),
+            tick,
+            fleets: Vec::new(),
+            markets: BTreeMap::new(),
+            systems: Vec::new(),
+            events: Vec::new(),
+            missions: MissionBoard::default(),
+            encounters: EncounterLog::default(),
+            settled_missions: Vec::new(),
+            knowledge: BTreeMap::new(),
+            rng,
+            log: Vec::new(),
+            notices: Vec::new(),
+        }
+    }
+
+    /// Loads fleets, saved markets, star systems and pending events of a game.
+    pub fn load(game_id: &str, tick: u64, rng: GameRng) -> Result<Self, String> {
+        let mut ctx = TickContext::new(game_id, tick, rng);
+        ctx.fleets = list_all_fleets(game_id)
+            .map_err(|e| format!("Failed to load fleets: {}", e))?;
+        ctx.markets = list_saved_markets(game_id)
+            .map_err(|e| format!("Failed to load markets: {}", e))?
+            .into_iter()
+            .collect();
+        ctx.systems = load_game_world(game_id)
+            .map_err(|e| format!("Failed to load game world: {}", e))?;
+        ctx.events = load_scheduled_events(game_id)?;
+        ctx.missions = MissionBoard::load()?;
+        ctx.encounters = EncounterLog::load()?;
+        Ok(ctx)
+    }
+
+    /// Stages every fleet, market and the remaining scheduled events for
+    /// writing back to the game's storage, along with the rest of an advance.
+    pub fn stage(&self, transaction: &mut Transaction) -> Result<(), String> {
+        for fleet in &self.fleets {
+            transaction.stage_fleet(fleet)?;
+        }
+        for ((system_id, planet_id), market) in &self.markets {
+            transaction.stage_market(*system_id, *planet_id, market)?;
+        }
+        for knowledge in self.knowledge.values() {
+            transaction.stage(&["knowledge", &PlayerKnowledge::file_name(&knowledge.player_name)], knowledge)?;
+        }
+        transaction.stage_missions(&self.missions)?;
+        transaction.stage_encounters(&self.encounters)?;
+        transaction.stage(&["scheduled_events.json"], &self.events)
+    }
+
+    /// Returns the market of a planet, loading it from disk the first time it
+    /// is needed during this advance.
+    pub fn market_mut(&mut self, system_id: usize, planet_id: usize) -> Result<&mut Market, String> {
+        match self.markets.entry((system_id, planet_id)) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let market = Market::load(system_id, planet_id)
+                    .map_err(|e| format!("Failed to load market {}/{}: {}", system_id, planet_id, e))?;
+                Ok(entry.insert(market))
+            }
+        }
+    }
+
+    /// Queues an event to fire once the clock reaches `due_tick`; it is saved
+    /// with the rest of the advance.
+    pub fn schedule(&mut self, due_tick: u64, kind: ScheduledEventKind) {
+        let event = ScheduledEvent::new(due_tick, kind, &mut self.rng);
+        self.events.push(event);
+    }
+
+    /// Whether `owner_id` is a player of this game rather than an NPC.
+    pub fn is_player(&self, owner_id: &str) -> bool {
+        document_exists(&self.game_id, &["players", &format!("{}.json", owner_id)])
+    }
+
+    /// Returns a player's knowledge, loading it the first time it is needed
+    /// during this advance.
+    pub fn knowledge_mut(&mut self, player_name: &str) -> Result<&mut PlayerKnowledge, String> {
+        match self.knowledge.entry(player_name.to_string()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(PlayerKnowledge::load(&self.game_id, player_name)?)),
+        }
+    }
+}
+
+/// Moves fleets in transit and lands them once their arrival tick is reached.
+pub struct FleetTransit;
+
+impl TickSystem for FleetTransit {
+    fn name(&self) -> &'static str {
+        "fleet_transit"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        for fleet in &mut ctx.fleets {
+            if advance_transit(fleet, ctx.tick) {
+                ctx.log.push(format!("Tick {}: {} arrived at its destination", ctx.tick, fleet.name));
+                ctx.notices.push(GameEventKind::FleetArrived {
+                    fleet: fleet.name.clone(),
+                    owner_id: fleet.owner_id.clone(),
+                    position: fleet.position,
+                    system_id: fleet.current_system_id,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rolls for what players' fleets meet where they stop after a move: the
+/// NPC traders working the system and fleets drawn by the dangers of the
+/// area, likelier where no faction patrols. The encounters wait in the
+/// encounter log for the player's decision.
+pub struct Encounters;
+
+impl TickSystem for Encounters {
+    fn name(&self) -> &'static str {
+        "encounters"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        let arrived: Vec<usize> = (0..ctx.fleets.len())
+            .filter(|&index| {
+                let fleet = &ctx.fleets[index];
+                fleet.transit.is_none() && fleet.last_move_distance.is_some() && !is_npc_trader(fleet) && ctx.is_player(&fleet.owner_id)
+            })
+            .collect();
+        if arrived.is_empty() {
+            return Ok(());
+        }
+        let factions = storage_for(&ctx.game_id)?.list_factions()?;
+
+        for index in arrived {
+            // Each move is rolled for once
+            let Some(distance) = ctx.fleets[index].last_move_distance.take() else {
+                continue;
+            };
+            let fleet = ctx.fleets[index].clone();
+            if !ctx.encounters.pending_for_fleet(&fleet.name).is_empty() {
+                continue;
+            }
+            let system = fleet.current_system_id.and_then(|id| ctx.systems.iter().find(|system| system.id == id));
+            let faction = fleet.current_system_id.and_then(|id| controlling_faction(&factions, id));
+            let danger = encounter_danger(&fleet, system);
+            let fleets = &ctx.fleets;
+            let (encounters, hostiles) = roll_encounters(
+                &fleet, distance, danger, faction, fleets, ctx.tick,
+                |name| fleets.iter().any(|other| other.name == name),
+                &mut ctx.rng,
+            );
+            if encounters.is_empty() {
+                continue;
+            }
+            let met: Vec<String> = encounters.iter().map(|encounter| encounter.hostile_fleet.clone()).collect();
+            ctx.log.push(format!("Tick {}: {} met {}", ctx.tick, fleet.name, met.join(", ")));
+            ctx.notices.push(GameEventKind::EncounterTriggered {
+                fleet: fleet.name.clone(),
+                owner_id: fleet.owner_id.clone(),
+                encounters: met,
+            });
+            ctx.fleets.extend(hostiles);
+            ctx.encounters.encounters.extend(encounters);
+        }
+        Ok(())
+    }
+}
+
+/// Lets players' fleets chart the stars around them and survey the systems
+/// they stop in, keeping the markets they see there up to date.
+pub struct Exploration;
+
+impl TickSystem for Exploration {
+    fn name(&self) -> &'static str {
+        "exploration"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        let mut players: Vec<String> = ctx.fleets.iter()
+            .filter(|fleet| !is_npc_trader(fleet))
+            .map(|fleet| fleet.owner_id.clone())
+            .collect();
+        players.sort();
+        players.dedup();
+        players.retain(|player| ctx.knowledge.contains_key(player) || ctx.is_player(player));
+
+        let resting: Vec<(usize, usize)> = ctx.fleets.iter()
+            .filter(|fleet| fleet.transit.is_none() && players.contains(&fleet.owner_id))
+            .filter_map(|fleet| fleet.current_system_id)
+            .filter_map(|id| ctx.systems.iter().find(|system| system.id == id))
+            .flat_map(|system| (0..system.planets.len()).map(move |planet_id| (system.id, planet_id)))
+            .collect();
+        for (system_id, planet_id) in resting {
+            ctx.market_mut(system_id, planet_id)?;
+        }
+
+        for player in players {
+            ctx.knowledge_mut(&player)?;
+            if let Some(knowledge) = ctx.knowledge.get_mut(&player) {
+                knowledge.observe(&ctx.fleets, &ctx.systems, &ctx.markets, ctx.tick);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sends fleets following a planned route on to their next leg.
+pub struct RouteFollowing;
+
+impl TickSystem for RouteFollowing {
+    fn name(&self) -> &'static str {
+        "route_following"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        for fleet in ctx.fleets.iter_mut().filter(|fleet| fleet.route.is_some()) {
+            let pending = ctx.encounters.pending_for_fleet(&fleet.name);
+            if let Some(line) = advance_route(fleet, ctx.tick, &pending) {
+                ctx.log.push(line);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Recharges the shields of every surviving ship by their regen rate. Armor
+/// stays damaged until it is repaired at a planet.
+pub struct ShipRepair;
+
+impl TickSystem for ShipRepair {
+    fn name(&self) -> &'static str {
+        "ship_repair"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        for ship in ctx.fleets.iter_mut().flat_map(|fleet| fleet.ships.iter_mut()) {
+            if ship.hp > 0 {
+                ship.shields.regenerate();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Moves market stock back toward each planet's target level.
+pub struct MarketRecovery;
+
+impl TickSystem for MarketRecovery {
+    fn name(&self) -> &'static str {
+        "market_recovery"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        for market in ctx.markets.values_mut() {
+            market.advance(1);
+        }
+        Ok(())
+    }
+}
+
+/// Runs the persistent NPC traders.
+///
+/// A docked trader first sells the cargo it came for, then buys the most
+/// profitable cargo its personality is willing to risk and sets off for the
+/// market paying most for it. Every purchase and sale goes through the
+/// planets' markets, so traders move prices just like players do.
+pub struct NpcTraders;
+
+impl TickSystem for NpcTraders {
+    fn name(&self) -> &'static str {
+        "npc_traders"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        if ctx.systems.is_empty() {
+            return Ok(());
+        }
+        for index in 0..ctx.fleets.len() {
+            let fleet = &ctx.fleets[index];
+            if !is_npc_trader(fleet) || fleet.transit.is_some() || fleet.ships.is_empty() {
+                continue;
+            }
+            let mut fleet = fleet.clone();
+            if fleet.trader.is_none() {
+                // Merchant fleets from older saves start trading with a fresh profile
+                fleet.trader = Some(TraderProfile::new(ctx.rng.sample(Standard)));
+            }
+            run_trader(ctx, &mut fleet)?;
+            ctx.fleets[index] = fleet;
+        }
+        Ok(())
+    }
+}
+
+/// Takes one decision for a docked NPC trader.
+fn run_trader(ctx: &mut TickContext, fleet: &mut Fleet) -> Result<(), String> {
+    let Some(system_id) = fleet.current_system_id else {
+        // Stranded in deep space: make for the closest system
+        let nearest = ctx.systems.iter()
+            .min_by(|a, b| fleet.position.distance(&a.position).total_cmp(&fleet.position.distance(&b.position)))
+            .map(|system| system.id);
+        if let Some(destination) = nearest {
+            depart_trader(ctx, fleet, destination, None)?;
+        }
+        return Ok(());
+    };
+    let Some(origin_index) = ctx.systems.iter().position(|system| system.id == system_id) else {
+        return Ok(());
+    };
+    let Some(mut profile) = fleet.trader.clone() else {
+        return Ok(());
+    };
+
+    if let Some(route) = profile.route.clone() {
+        if route.sell_system_id == system_id {
+            sell_route_cargo(ctx, fleet, &mut profile, &route)?;
+            fleet.trader = Some(profile);
+        } else {
+            // Still carrying the cargo, for instance after failing to refuel
+            depart_trader(ctx, fleet, route.sell_system_id, Some(route.sell_planet_id))?;
+        }
+        return Ok(());
+    }
+
+    let style = profile.style();
+    let destination_ids: Vec<usize> = nearest_systems(&ctx.systems[origin_index], &ctx.systems, style.scan_systems)
+        .into_iter()
+        .map(|system| system.id)
+        .collect();
+    let market_keys: Vec<(usize, usize)> = std::iter::once(system_id)
+        .chain(destination_ids.iter().copied())
+        .filter_map(|id| ctx.systems.iter().find(|system| system.id == id))
+        .flat_map(|system| (0..system.planets.len()).map(move |planet_id| (system.id, planet_id)))
+        .collect();
+    for (market_system, market_planet) in market_keys {
+        ctx.market_mut(market_system, market_planet)?;
+    }
+
+    let origin = &ctx.systems[origin_index];
+    let destinations = nearest_systems(origin, &ctx.systems, style.scan_systems);
+    let planned = plan_trade_route(fleet, &profile, origin, &destinations, &ctx.markets);
+
+    let Some(mut route) = planned else {
+        if !destination_ids.is_empty() && ctx.rng.gen_bool(NPC_TRADER_MOVE_CHANCE) {
+            // Nothing worth carrying from here: look for business elsewhere
+            let destination = destination_ids[ctx.rng.gen_range(0..destination_ids.len())];
+            depart_trader(ctx, fleet, destination, None)?;
+        }
+        return Ok(());
+    };
+
+    let tick = ctx.tick;
+    let market = ctx.market_mut(route.buy_system_id, route.buy_planet_id)?;
+    let cost = match market.buy_resource(route.resource_type, route.quantity, route.buy_system_id, route.buy_planet_id) {
+        Ok(cost) => cost,
+        Err(e) => {
+            ctx.log.push(format!("Tick {}: {} could not buy {}: {}", tick, fleet.name, route.resource_type, e));
+            return Ok(());
+        }
+    };
+    let (buy, sell) = player_prices(&profile.personality, cost / route.quantity as f64);
+    load_cargo(fleet, route.resource_type, route.quantity, Some(buy), Some(sell));
+    profile.credits -= cost;
+    route.purchase_cost = cost;
+    ctx.log.push(format!(
+        "Tick {}: {} bought {} {} at {}/{} for {:.0} credits",
+        tick, fleet.name, route.quantity, route.resource_type, route.buy_system_id, route.buy_planet_id, cost
+    ));
+
+    let (destination, planet) = (route.sell_system_id, route.sell_planet_id);
+    profile.route = Some(route);
+    fleet.trader = Some(profile);
+    depart_trader(ctx, fleet, destination, Some(planet))
+}
+
+/// Sells everything a trader carries of its route's resource at the route's destination.
+fn sell_route_cargo(ctx: &mut TickContext, fleet: &mut Fleet, profile: &mut TraderProfile, route: &TradeRoute) -> Result<(), String> {
+    let tick = ctx.tick;
+    let quantity = fleet.cargo_quantity(route.resource_type);
+    if quantity > 0 {
+        let market = ctx.market_mut(route.sell_system_id, route.sell_planet_id)?;
+        match market.sell_resource(route.resource_type, quantity, route.sell_system_id, route.sell_planet_id) {
+            Ok(revenue) => {
+                unload_cargo(fleet, route.resource_type);
+                profile.credits += revenue;
+                profile.total_profit += revenue - route.purchase_cost;
+                ctx.log.push(format!(
+                    "Tick {}: {} sold {} {} at {}/{} for {:.0} credits",
+                    tick, fleet.name, quantity, route.resource_type, route.sell_system_id, route.sell_planet_id, revenue
+                ));
+            }
+            Err(e) => {
+                ctx.log.push(format!("Tick {}: {} could not sell {}: {}", tick, fleet.name, route.resource_type, e));
+            }
+        }
+    }
+    profile.route = None;
+    Ok(())
+}
+
+/// Sends a trader to another system, topping up its fuel from the local
+/// markets first. A trader that cannot make the trip stays docked and tries
+/// again on a later tick.
+fn depart_trader(ctx: &mut TickContext, fleet: &mut Fleet, destination_id: usize, planet_id: Option<usize>) -> Result<(), String> {
+    let Some(destination) = ctx.systems.iter().find(|system| system.id == destination_id) else {
+        return Ok(());
+    };
+    let trip = deep_space_trip(&fleet.position, &destination.position);
+    let mut arrived = fleet.clone();
+    arrived.update_position(destination.position);
+    arrived.current_system_id = Some(destination.id);
+    arrived.local_position = planet_id
+        .and_then(|planet_id| destination.planets.get(planet_id))
+        .map(|planet| planet.position)
+        .or(Some(Position { x: 0, y: 0, z: 0 }));
+    let star_name = destination.star.name.clone();
+
+    let shortfall = fuel_required(fleet, trip.total_scaled).saturating_sub(fleet_fuel(fleet));
+    if shortfall > 0 && let Some(system_id) = fleet.current_system_id {
+        refuel_trader(ctx, fleet, system_id, shortfall)?;
+    }
+
+    if let Ok(departed) = begin_transit(fleet.clone(), arrived, &trip, ctx.tick) {
+        *fleet = departed;
+        ctx.log.push(format!("Tick {}: {} set off for {}", ctx.tick, fleet.name, star_name));
+    }
+    Ok(())
+}
+
+/// Buys `amount` fuel from the first market in a system that can supply it,
+/// or as much as the holds can take.
+fn refuel_trader(ctx: &mut TickContext, fleet: &mut Fleet, system_id: usize, amount: u32) -> Result<(), String> {
+    let amount = amount.min(fleet.free_cargo_capacity());
+    if amount == 0 {
+        return Ok(());
+    }
+    let planet_count = ctx.systems.iter()
+        .find(|system| system.id == system_id)
+        .map_or(0, |system| system.planets.len());
+    let credits = fleet.trader.as_ref().map_or(0.0, |profile| profile.credits);
+    for planet_id in 0..planet_count {
+        let market = ctx.market_mut(system_id, planet_id)?;
+        if !market.quote_buy(ResourceType::Fuel, amount).is_ok_and(|cost| cost <= credits) {
+            continue;
+        }
+        let cost = market.buy_resource(ResourceType::Fuel, amount, system_id, planet_id)?;
+        load_cargo(fleet, ResourceType::Fuel, amount, None, None);
+        if let Some(profile) = fleet.trader.as_mut() {
+            profile.credits -= cost;
+        }
+        return Ok(());
+    }
+    Ok(())
+}
+
+/// Fires every scheduled event whose due tick has been reached.
+pub struct ScheduledEvents;
+
+impl TickSystem for ScheduledEvents {
+    fn name(&self) -> &'static str {
+        "scheduled_events"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        let (due, pending): (Vec<ScheduledEvent>, Vec<ScheduledEvent>) = std::mem::take(&mut ctx.events)
+            .into_iter()
+            .partition(|event| event.due_tick <= ctx.tick);
+        ctx.events = pending;
+
+        for event in due {
+            match event.kind {
+                ScheduledEventKind::MarketShock { system_id, planet_id, resource_type, stock_change } => {
+                    let market = ctx.market_mut(system_id, planet_id)?;
+                    if let Some(resource) = market.resources.iter_mut().find(|r| r.resource_type == resource_type) {
+                        let stock = resource.quantity.unwrap_or(0) as i64 + stock_change as i64;
+                        resource.quantity = Some(stock.max(0) as u32);
+                    }
+                    market.refresh_prices();
+                    ctx.log.push(format!(
+                        "Tick {}: {} stock at {}/{} changed by {}",
+                        ctx.tick, resource_type, system_id, planet_id, stock_change
+                    ));
+                }
+                ScheduledEventKind::Announcement { message } => {
+                    ctx.log.push(format!("Tick {}: {}", ctx.tick, message));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Spreads word of shortages and gluts at the markets players have seen,
+/// scheduling each to hit its market a few ticks later.
+pub struct MarketShocks;
+
+impl TickSystem for MarketShocks {
+    fn name(&self) -> &'static str {
+        "market_shocks"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        if ctx.markets.is_empty() || !ctx.rng.gen_bool(MARKET_SHOCK_CHANCE) {
+            return Ok(());
+        }
+        let (&(system_id, planet_id), market) = ctx.markets.iter()
+            .nth(ctx.rng.gen_range(0..ctx.markets.len()))
+            .expect("index is within the markets");
+        if market.resources.is_empty() {
+            return Ok(());
+        }
+        let resource_type = market.resources[ctx.rng.gen_range(0..market.resources.len())].resource_type;
+        let glut = ctx.rng.gen_bool(0.5);
+        let amount = ctx.rng.gen_range(MARKET_SHOCK_STOCK);
+        let due_tick = ctx.tick + ctx.rng.gen_range(MARKET_SHOCK_WARNING);
+
+        ctx.log.push(format!(
+            "Tick {}: traders expect a {} of {} at {}/{} by tick {}",
+            ctx.tick, if glut { "glut" } else { "shortage" }, resource_type, system_id, planet_id, due_tick
+        ));
+        ctx.schedule(due_tick, ScheduledEventKind::MarketShock {
+            system_id,
+            planet_id,
+            resource_type,
+            stock_change: if glut { amount } else { -amount },
+        });
+        Ok(())
+    }
+}
+
+/// Completes exploration missions whose target a player's fleet has reached,
+/// fails missions past their deadline and posts new ones on the planets'
+/// mission boards.
+pub struct MissionProgress;
+
+impl TickSystem for MissionProgress {
+    fn name(&self) -> &'static str {
+        "missions"
+    }
+
+    fn tick(&self, ctx: &mut TickContext) -> Result<(), String> {
+        let mut explorations: Vec<(String, usize)> = ctx.missions.missions.iter()
+            .filter(|m| m.status == MissionStatus::Active)
+            .filter_map(|m| match (&m.kind, &m.accepted_by) {
+                (MissionKind::Exploration { system_id }, Some(player_name)) => Some((player_name.clone(), *system_id)),
+                _ => None,
+            })
+            .collect();
+        explorations.sort();
+        explorations.dedup();
+
+        for (player_name, system_id) in explorations {
+            let reached = ctx.fleets.iter().any(|fleet| {
+                fleet.owner_id == player_name && fleet.transit.is_none() && fleet.current_system_id == Some(system_id)
+            });
+            if reached {
+                for mission in ctx.missions.complete_exploration(&player_name, system_id) {
+                    ctx.log.push(format!("Tick {}: mission '{}' completed", ctx.tick, mission.title));
+                    ctx.settled_missions.push(mission);
+                }
+            }
+        }
+
+        for mission in ctx.missions.expire(ctx.tick) {
+            ctx.log.push(format!("Tick {}: mission '{}' failed, deadline passed", ctx.tick, mission.title));
+            ctx.settled_missions.push(mission);
+        }
+
+        if ctx.missions.needs_restock(&ctx.systems) {
+            let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+            let pirate_fleets: Vec<String> = ctx.fleets.iter()
+                .filter(|fleet| fleet.owner_id.starts_with("Pirate_") && !fleet.ships.is_empty())
+                .map(|fleet| fleet.name.clone())
+                .collect();
+            ctx.missions.restock(&ctx.systems, &factions, &pirate_fleets, ctx.tick, &mut ctx.rng);
+        }
+        Ok(())
+    }
+}
+
+/// Every system that runs on the game clock, in the order they are ticked.
+///
+/// New time-dependent subsystems register here so they advance in lockstep
+/// with the rest of the world.
+pub fn registered_systems() -> Vec<Box<dyn TickSystem>> {
+    vec![
+        Box::new(FleetTransit),
+        Box::new(Encounters),
+        Box::new(Exploration),
+        Box::new(RouteFollowing),
+        Box::new(NpcTraders),
+        Box::new(ShipRepair),
+        Box::new(MarketRecovery),
+        Box::new(MarketShocks),
+        Box::new(ScheduledEvents),
+        Box::new(MissionProgress),
+    ]
+}
+
+/// Runs `ticks` ticks of the given systems against a context.
+///
+/// # Arguments
+/// * `ctx` - The world state to advance
+/// * `systems` - The systems to run each tick
+/// * `ticks` - Number of ticks to advance
+///
+/// # Returns
+/// A Result indicating success or the name of the failing system with its error
+pub fn run_ticks(ctx: &mut TickContext, systems: &[Box<dyn TickSystem>], ticks: u32) -> Result<(), String> {
+    for _ in 0..ticks {
+        ctx.tick += 1;
+        for system in systems {
+            system.tick(ctx)
+                .map_err(|e| format!("{} failed at tick {}: {}", system.name(), ctx.tick, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Advances the active game's clock by `ticks`, running every registered
+/// system once per tick and persisting the result.
+///
+/// # Arguments
+/// * `ticks` - Number of ticks to advance, at most `MAX_ADVANCE_TICKS`
+///
+/// # Returns
+/// A report of the advance or an error if nothing was changed
+pub fn advance_time(ticks: u32) -> Result<AdvanceTimeReport, String> {
+    if ticks == 0 || ticks > MAX_ADVANCE_TICKS {
+        return Err(format!("Ticks must be between 1 and {}", MAX_ADVANCE_TICKS));
+    }
+    let game_id = current_game_id()?;
+
+    let mut clock = GameClock::load(&game_id)?;
+    let start_tick = clock.tick;
+    let mut ctx = TickContext::load(&game_id, start_tick, next_game_rng("tick")?)?;
+
+    run_ticks(&mut ctx, &registered_systems(), ticks)?;
+
+    // The clock only moves if the world it moved is saved with it
+    let mut transaction = Transaction::new(&game_id);
+    ctx.stage(&mut transaction)?;
+    clock.tick = ctx.tick;
+    transaction.stage(&["clock.json"], &clock)?;
+    settle_missions(&ctx.settled_missions, &mut transaction)?;
+    transaction.commit()?;
+    clear_caches();
+    for notice in ctx.notices {
+        events::publish(notice);
+    }
+    events::publish_missions(&ctx.settled_missions);
+
+    Ok(AdvanceTimeReport {
+        start_tick,
+        end_tick: clock.tick,
+        stardate: stardate_at(clock.tick),
+        log: ctx.log,
+    })
+}
+
+/// Pays out or penalizes the players of missions settled during an advance,
+/// staging each player once however many of their missions were settled.
+fn settle_missions(missions: &[Mission], transaction: &mut Transaction) -> Result<(), String> {
+    if missions.is_empty() {
+        return Ok(());
+    }
+    let factions = list_factions().map_err(|e| format!("Failed to load factions: {}", e))?;
+    let mut players: BTreeMap<String, Player> = BTreeMap::new();
+    for mission in missions {
+        let Some(player_name) = &mission.accepted_by else {
+            continue;
+        };
+        let player = match players.entry(player_name.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(load_player(player_name)?),
+        };
+        settle_mission(player, mission, &factions);
+    }
+    for player in players.values() {
+        transaction.stage_player(player)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::models::economy::Economy;
+use crate::models::fleet::Fleet;
+use crate::models::game_state::game_data_path;
+use crate::models::planet::{Planet, PlanetSpecialization};
+use crate::models::position::Position;
+use crate::models::rng::rng_for;
+use crate::models::ship::ship::Ship;
+use crate::models::star_system::{StarSystem, generate_star_system};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+/// Star systems with one planet each, numbered in order and placed at the
+/// given `(x, y)` coordinates.
+pub fn systems_at(seed: u64, coordinates: &[(i32, i32)]) -> Vec<StarSystem> {
+    let mut rng = rng_for(seed, "systems");
+    let mut names = HashSet::new();
+    coordinates.iter().enumerate().map(|(id, &(x, y))| {
+        let mut system = generate_star_system(1000, 1000, 1000, &mut names, &mut rng);
+        system.id = id;
+        system.position = Position { x, y, z: 0 };
+        system.planets.truncate(1);
+        system
+    }).collect()
+}
+
+pub fn planet(specialization: PlanetSpecialization, economy: Economy) -> Planet {
+    let mut system = generate_star_system(1000, 1000, 1000, &mut HashSet::new(), &mut rng_for(5, "systems"));
+    let mut planet = system.planets.remove(0);
+    planet.specialization = specialization;
+    planet.economy = economy;
+    planet
+}
+
+pub fn fleet_of(owner: &str, ships: Vec<Ship>) -> Fleet {
+    let mut fleet = Fleet::new(owner.to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    fleet.ships = ships;
+    fleet
+}
+
+/// An empty game directory owned by one test.
+pub fn game_dir(game_id: &str) -> PathBuf {
+    let dir = game_data_path(game_id, &[]);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,113 @@
+use crate::models::archive::{ARCHIVE_FORMAT_VERSION, export_game, import_game};
+use crate::models::game_state::game_data_path;
+use crate::models::persistence::write_json_atomic;
+use crate::models::player::Player;
+use crate::models::rng::rng_for;
+use crate::models::settings::{GameSettings, SavedGame, load_settings_for_game};
+use std::fs;
+
+/// Writes a minimal saved game: settings, a player and a save list entry.
+fn saved_game(game_id: &str) -> SavedGame {
+    let _ = fs::remove_dir_all(game_data_path(game_id, &[]));
+    let settings = GameSettings {
+        game_id: game_id.to_string(),
+        display_name: "Archive Test".to_string(),
+        player_name: "Archivist".to_string(),
+        ..GameSettings::default()
+    };
+    let player = Player::new("Archivist", 1234.0, &mut rng_for(1, "player"));
+    write_json_atomic(&game_data_path(game_id, &["settings.json"]), &settings).unwrap();
+    write_json_atomic(&game_data_path(game_id, &["players", "Archivist.json"]), &player).unwrap();
+    write_json_atomic(&game_data_path(game_id, &["markets", "market_0_0.json"]), &serde_json::json!({ "resources": [] })).unwrap();
+
+    let saved_game = SavedGame {
+        game_id: game_id.to_string(),
+        display_name: settings.display_name.clone(),
+        created_at: settings.created_at.clone(),
+        last_played: settings.last_played.clone(),
+        settings,
+    };
+    saved_game.save_game().unwrap();
+    saved_game
+}
+
+fn remove_game(game_id: &str) {
+    let _ = fs::remove_dir_all(game_data_path(game_id, &[]));
+    let _ = fs::remove_file(crate::models::game_state::data_root().join("saves").join(format!("{}.json", game_id)));
+}
+
+#[test]
+fn test_export_and_import_round_trip_under_new_id() {
+    saved_game("test_archive_round_trip");
+    let archive = export_game("test_archive_round_trip").unwrap();
+    assert_eq!(archive.manifest.format_version, ARCHIVE_FORMAT_VERSION);
+    assert_eq!(archive.manifest.files, vec!["markets/market_0_0.json", "players/Archivist.json", "settings.json"]);
+
+    // The archive survives being written out as one file and read back
+    let bundle = serde_json::to_string(&archive).unwrap();
+    let imported = import_game(serde_json::from_str(&bundle).unwrap()).unwrap();
+    assert_ne!(imported.game_id, "test_archive_round_trip");
+    assert_eq!(imported.settings.game_id, imported.game_id);
+    assert_eq!(imported.display_name, "Archive Test");
+
+    let settings = load_settings_for_game(&imported.game_id).unwrap();
+    assert_eq!(settings.game_id, imported.game_id);
+    let player: Player = serde_json::from_str(
+        &fs::read_to_string(game_data_path(&imported.game_id, &["players", "Archivist.json"])).unwrap()
+    ).unwrap();
+    assert_eq!(player.credits, 1234.0);
+    assert!(SavedGame::load_game(&imported.game_id).unwrap().is_some());
+
+    remove_game("test_archive_round_trip");
+    remove_game(&imported.game_id);
+}
+
+#[test]
+fn test_import_rejects_invalid_archives() {
+    saved_game("test_archive_invalid");
+    let archive = export_game("test_archive_invalid").unwrap();
+    remove_game("test_archive_invalid");
+
+    let mut future = archive.clone();
+    future.manifest.format_version = ARCHIVE_FORMAT_VERSION + 1;
+    assert!(import_game(future).unwrap_err().contains("Unsupported archive format version"));
+
+    let mut escaping = archive.clone();
+    escaping.files.insert("../escape.json".to_string(), serde_json::json!({}));
+    escaping.manifest.files = escaping.files.keys().cloned().collect();
+    assert!(import_game(escaping).unwrap_err().contains("Invalid file path"));
+
+    let mut missing_player = archive.clone();
+    missing_player.files.remove("players/Archivist.json");
+    missing_player.manifest.files = missing_player.files.keys().cloned().collect();
+    assert!(import_game(missing_player).unwrap_err().contains("Missing players/Archivist.json"));
+
+    let mut corrupt = archive;
+    corrupt.files.insert("settings.json".to_string(), serde_json::json!({ "game_id": 7 }));
+    assert!(import_game(corrupt).unwrap_err().contains("settings.json is invalid"));
+}
+
+#[test]
+fn test_accounts_never_leave_the_server() {
+    saved_game("test_archive_accounts");
+    write_json_atomic(
+        &game_data_path("test_archive_accounts", &["accounts", "Archivist.json"]),
+        &serde_json::json!({ "name": "Archivist", "password_hash": "secret" }),
+    ).unwrap();
+    write_json_atomic(
+        &game_data_path("test_archive_accounts", &["claims", "Stowaway.json"]),
+        &serde_json::json!({ "name": "Stowaway", "code_hash": "claimed" }),
+    ).unwrap();
+
+    let archive = export_game("test_archive_accounts").unwrap();
+    remove_game("test_archive_accounts");
+    assert!(archive.files.keys().all(|path| !path.starts_with("accounts/") && !path.starts_with("claims/")));
+    assert!(!serde_json::to_string(&archive).unwrap().contains("secret"));
+    assert!(!serde_json::to_string(&archive).unwrap().contains("claimed"));
+
+    // Nor can an archive plant logins in the imported game
+    let mut planted = archive;
+    planted.files.insert("accounts/Archivist.json".to_string(), serde_json::json!({}));
+    planted.manifest.files = planted.files.keys().cloned().collect();
+    assert!(import_game(planted).unwrap_err().contains("Archives may not contain accounts/Archivist.json"));
+}
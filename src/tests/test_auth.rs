@@ -0,0 +1,208 @@
+use crate::models::auth::{Credentials, PlayerSession, Role, SessionStore, issue_claim, load_account, login, register};
+use crate::models::context::{CurrentGame, GameRegistry};
+use crate::models::player::Player;
+use crate::models::rng::rng_for;
+use crate::models::settings::{GameSettings, SavedGame};
+use crate::models::storage::{StorageBackend, init_storage, storage_for};
+use crate::routes::{advance_time, create_new_game, delete_game, export_game, get_faction_standing, get_fleet_owners, get_player_missions, import_game, preview_game_migration};
+use crate::tests::fixtures::game_dir;
+use rocket::State;
+use rocket::serde::json::Json;
+
+fn credentials(name: &str, password: &str) -> Credentials {
+    Credentials { name: name.to_string(), password: password.to_string(), claim_code: None }
+}
+
+fn claiming(name: &str, password: &str, claim_code: &str) -> Credentials {
+    Credentials { claim_code: Some(claim_code.to_string()), ..credentials(name, password) }
+}
+
+/// A game created for `Host`, whose player has no account yet; returns the
+/// code its creator registers `Host` with.
+fn hosted_game(game_id: &str) -> String {
+    game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    let settings = GameSettings { game_id: game_id.to_string(), player_name: "Host".to_string(), ..GameSettings::default() };
+    let storage = storage_for(game_id).unwrap();
+    storage.save_settings(&settings).unwrap();
+    storage.save_player(&Player::new("Host", 500.0, &mut rng_for(1, "player"))).unwrap();
+    issue_claim(game_id, "Host").unwrap()
+}
+
+#[test]
+fn test_creator_claims_the_host_player_as_admin() {
+    let game_id = "test_auth_register";
+    let code = hosted_game(game_id);
+    let mut rng = rng_for(7, "players");
+
+    // Joining first makes no one the admin, and the host player stays the creator's
+    let early = register(game_id, &credentials("Early", "correct-horse"), &mut rng).unwrap();
+    assert_eq!(early.role, Role::Player);
+    assert!(register(game_id, &credentials("Host", "stolen-password"), &mut rng).is_err());
+    assert!(register(game_id, &claiming("Host", "stolen-password", "not-the-code"), &mut rng).is_err());
+    assert!(load_account(game_id, "Host").unwrap().is_none());
+
+    let host = register(game_id, &claiming("Host", "hunter2hunter2", &code), &mut rng).unwrap();
+    assert_eq!(host.role, Role::Admin);
+    assert_ne!(host.password_hash, "hunter2hunter2", "passwords are never stored as given");
+    let storage = storage_for(game_id).unwrap();
+    assert_eq!(storage.load_player("Host").unwrap().unwrap().credits, 500.0, "the host keeps their player");
+
+    // Later players join the same world with a player and fleet of their own
+    let guest = register(game_id, &credentials("Guest", "correct-horse"), &mut rng).unwrap();
+    assert_eq!(guest.role, Role::Player);
+    let player = storage.load_player("Guest").unwrap().unwrap();
+    assert_eq!(player.credits, GameSettings::default().starting_credits);
+    let fleet = storage.load_fleet("Fleet_Guest_1").unwrap().unwrap();
+    assert_eq!(fleet.owner_id, "Guest");
+    assert_eq!(fleet.ships.len(), 1);
+
+    assert!(register(game_id, &credentials("Guest", "another-password"), &mut rng).is_err(), "names are taken once");
+    assert!(register(game_id, &credentials("Short", "pass"), &mut rng).is_err());
+    assert!(register(game_id, &credentials("../Host", "long-enough-password"), &mut rng).is_err());
+    assert!(load_account(game_id, "Short").unwrap().is_none());
+}
+
+#[test]
+fn test_existing_players_are_claimed_only_with_an_issued_code() {
+    let game_id = "test_auth_claims";
+    hosted_game(game_id);
+    let storage = storage_for(game_id).unwrap();
+    storage.save_player(&Player::new("Drifter", 300.0, &mut rng_for(2, "player"))).unwrap();
+    let mut rng = rng_for(7, "players");
+
+    assert!(register(game_id, &credentials("Drifter", "correct-horse"), &mut rng).is_err());
+    assert!(issue_claim(game_id, "Nobody").is_err(), "only existing players are claimed");
+    let first = issue_claim(game_id, "Drifter").unwrap();
+    let code = issue_claim(game_id, "Drifter").unwrap();
+    assert!(register(game_id, &claiming("Drifter", "correct-horse", &first), &mut rng).is_err(), "a new code replaces the old");
+
+    let drifter = register(game_id, &claiming("Drifter", "correct-horse", &code), &mut rng).unwrap();
+    assert_eq!(drifter.role, Role::Player);
+    assert_eq!(storage.load_player("Drifter").unwrap().unwrap().credits, 300.0);
+    assert!(issue_claim(game_id, "Drifter").is_err(), "claimed players have an account");
+}
+
+#[test]
+fn test_login_checks_the_password() {
+    let game_id = "test_auth_login";
+    let code = hosted_game(game_id);
+    let host = register(game_id, &claiming("Host", "hunter2hunter2", &code), &mut rng_for(7, "players")).unwrap();
+    assert!(host.password_hash.starts_with("$argon2"), "passwords are hashed with a salted, slow hash");
+
+    assert_eq!(login(game_id, &credentials("Host", "hunter2hunter2")).unwrap().name, "Host");
+    assert!(login(game_id, &credentials("Host", "hunter3hunter3")).is_err());
+    assert!(login(game_id, &credentials("Nobody", "hunter2hunter2")).is_err());
+    assert_eq!(login(game_id, &credentials(" Host ", "hunter2hunter2")).unwrap().name, "Host");
+    // Names are checked before they are used to find the account's file
+    assert!(login(game_id, &credentials("../test_auth_login/accounts/Host", "hunter2hunter2")).is_err());
+    assert!(login(game_id, &credentials("", "hunter2hunter2")).is_err());
+}
+
+#[test]
+fn test_sessions_authorize_only_their_own_player() {
+    let game_id = "test_auth_sessions";
+    let code = hosted_game(game_id);
+    let mut rng = rng_for(7, "players");
+    let host = register(game_id, &claiming("Host", "hunter2hunter2", &code), &mut rng).unwrap();
+    let guest = register(game_id, &credentials("Guest", "correct-horse"), &mut rng).unwrap();
+
+    let sessions = SessionStore::default();
+    let host_session = sessions.start(game_id, &host).unwrap();
+    let guest_session = sessions.start(game_id, &guest).unwrap();
+    assert_ne!(host_session.token, guest_session.token);
+
+    let guest_session = sessions.get(&guest_session.token).unwrap();
+    assert!(guest_session.authorize("Guest").is_ok());
+    assert!(guest_session.authorize("Host").is_err());
+    assert!(guest_session.require_admin().is_err());
+    assert!(guest_session.authorize_game("another_game").is_err());
+    assert!(host_session.require_admin().is_ok());
+    assert!(host_session.authorize("Guest").is_err(), "admins act for their own player too");
+
+    sessions.end(&guest_session.token);
+    assert!(sessions.get(&guest_session.token).is_none());
+    sessions.end_game(game_id);
+    assert!(sessions.get(&host_session.token).is_none());
+}
+
+#[test]
+fn test_only_the_games_admin_manages_it() {
+    let game_id = "test_auth_admin_routes";
+    let code = hosted_game(game_id);
+    let settings = storage_for(game_id).unwrap().load_settings().unwrap().unwrap();
+    SavedGame {
+        game_id: game_id.to_string(),
+        display_name: settings.display_name.clone(),
+        created_at: settings.created_at.clone(),
+        last_played: settings.last_played.clone(),
+        settings,
+    }.save_game().unwrap();
+    let mut rng = rng_for(7, "players");
+    let host = register(game_id, &claiming("Host", "hunter2hunter2", &code), &mut rng).unwrap();
+    let guest = register(game_id, &credentials("Guest", "correct-horse"), &mut rng).unwrap();
+    let sessions = SessionStore::default();
+    let registry = GameRegistry::default();
+    let session = |account| PlayerSession::new(sessions.start(game_id, account).unwrap());
+    let game = || CurrentGame::new(registry.get(game_id).unwrap());
+
+    for outsider in [PlayerSession::default(), session(&guest)] {
+        assert!(!export_game(outsider.clone(), game_id.to_string()).success);
+        assert!(!preview_game_migration(outsider.clone(), game_id.to_string()).success);
+        assert!(!advance_time(game(), outsider.clone(), 1).success);
+        assert!(!delete_game(State::from(&registry), State::from(&sessions), outsider, game_id.to_string()).success);
+    }
+    assert!(storage_for(game_id).unwrap().load_player("Host").unwrap().is_some(), "the game is still there");
+
+    let admin = session(&host);
+    let archive = export_game(admin.clone(), game_id.to_string()).into_inner().data.unwrap();
+    assert!(!import_game(session(&guest), Json(archive.clone())).success);
+    assert!(import_game(admin.clone(), Json(archive)).success);
+    assert!(delete_game(State::from(&registry), State::from(&sessions), admin, game_id.to_string()).success);
+}
+
+#[test]
+fn test_players_read_only_their_own_standings_and_missions() {
+    let game_id = "test_auth_private_reads";
+    let code = hosted_game(game_id);
+    let mut rng = rng_for(7, "players");
+    let host = register(game_id, &claiming("Host", "hunter2hunter2", &code), &mut rng).unwrap();
+    let guest = register(game_id, &credentials("Guest", "correct-horse"), &mut rng).unwrap();
+    let sessions = SessionStore::default();
+    let registry = GameRegistry::default();
+    let session = |account| PlayerSession::new(sessions.start(game_id, account).unwrap());
+    let game = || CurrentGame::new(registry.get(game_id).unwrap());
+
+    for reader in [PlayerSession::default(), session(&guest)] {
+        assert!(!get_faction_standing(game(), reader.clone(), "Host").success);
+        assert!(!get_player_missions(game(), reader, "Host").success);
+    }
+    assert!(!get_fleet_owners(game(), PlayerSession::default()).success);
+
+    let host = session(&host);
+    assert!(get_faction_standing(game(), host.clone(), "Host").success);
+    assert!(get_player_missions(game(), host.clone(), "Host").success);
+    assert!(get_fleet_owners(game(), host).success);
+
+    let stranger = hosted_game("test_auth_private_reads_other");
+    let outsider = register("test_auth_private_reads_other", &claiming("Host", "hunter2hunter2", &stranger), &mut rng).unwrap();
+    let outsider = PlayerSession::new(sessions.start("test_auth_private_reads_other", &outsider).unwrap());
+    assert!(!get_fleet_owners(game(), outsider).success, "the owners of another game stay private");
+}
+
+#[test]
+fn test_new_games_never_replace_existing_ones() {
+    let game_id = "test_auth_new_game";
+    hosted_game(game_id);
+    let registry = GameRegistry::default();
+    let new_game = |game_id: &str| Json(GameSettings { game_id: game_id.to_string(), ..GameSettings::default() });
+
+    let response = create_new_game(State::from(&registry), new_game(game_id));
+    assert!(!response.success);
+    assert!(response.message.contains("already exists"), "{}", response.message);
+    assert!(storage_for(game_id).unwrap().load_player("Host").unwrap().is_some(), "the game is still there");
+
+    for unsafe_id in ["../test_auth_new_game", "", "a/b", "game.db"] {
+        assert!(!create_new_game(State::from(&registry), new_game(unsafe_id)).success, "{:?} was accepted", unsafe_id);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::models::cargo::{CargoDistribution, FillPolicy};
+use crate::models::fleet::Fleet;
+use crate::models::player::Player;
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::rng::rng_for;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::trade::{ShipAllocation, trade_with_fleet};
+
+/// A fleet of two empty ships holding 100 and 250 units.
+fn fleet() -> Fleet {
+    let mut fleet = Fleet::new("Tester".to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    for size in [ShipSize::Tiny, ShipSize::Small] {
+        let mut ship = Ship::new(ShipType::Freighter, size, ShipEngine::Basic);
+        ship.cargo.clear();
+        fleet.ships.push(ship);
+    }
+    fleet
+}
+
+fn allocation(ship_index: usize, quantity: u32) -> ShipAllocation {
+    ShipAllocation { ship_index, quantity }
+}
+
+#[test]
+fn test_distribution_modes_respect_ship_capacity() {
+    let mut first = fleet();
+    let receipt = first.load_cargo(ResourceType::Metals, 150, &CargoDistribution::First, FillPolicy::AllOrNothing).unwrap();
+    assert_eq!((first.ships[0].get_current_cargo(), first.ships[1].get_current_cargo()), (100, 50));
+    assert!(receipt.is_complete());
+
+    let mut even = fleet();
+    even.load_cargo(ResourceType::Metals, 300, &CargoDistribution::Even, FillPolicy::AllOrNothing).unwrap();
+    assert_eq!((even.ships[0].get_current_cargo(), even.ships[1].get_current_cargo()), (100, 200));
+
+    let mut specific = fleet();
+    let split = CargoDistribution::Specific(vec![allocation(0, 10), allocation(1, 30)]);
+    specific.load_cargo(ResourceType::Metals, 40, &split, FillPolicy::AllOrNothing).unwrap();
+    assert_eq!(specific.ships[1].cargo_quantity(ResourceType::Metals), 30);
+    assert!(specific.load_cargo(ResourceType::Metals, 50, &split, FillPolicy::AllOrNothing).is_err(), "allocations must match the order");
+    assert!(CargoDistribution::from_request(Some("specific"), None).is_err());
+}
+
+#[test]
+fn test_orders_that_do_not_fit_are_refused_or_partially_filled() {
+    let mut fleet = fleet();
+    let crowded = CargoDistribution::Specific(vec![allocation(0, 150), allocation(1, 10)]);
+    let error = fleet.load_cargo(ResourceType::Water, 160, &crowded, FillPolicy::AllOrNothing).unwrap_err();
+    assert!(error.contains("short by 50"), "{}", error);
+    assert_eq!(fleet.free_cargo_capacity(), 350, "a refused order moves nothing");
+
+    let receipt = fleet.load_cargo(ResourceType::Water, 160, &crowded, FillPolicy::Partial).unwrap();
+    assert_eq!((receipt.moved, receipt.overflow), (110, 50));
+    assert_eq!(receipt.ships[0].overflow, 50);
+    assert_eq!(receipt.ships[1].overflow, 0);
+
+    let sold = fleet.unload_cargo(ResourceType::Water, 200, &CargoDistribution::First, FillPolicy::Partial).unwrap();
+    assert_eq!(sold.moved, 110);
+    assert!(fleet.ships.iter().all(|ship| ship.cargo.is_empty()), "emptied stacks are removed");
+    assert!(fleet.unload_cargo(ResourceType::Water, 1, &CargoDistribution::First, FillPolicy::Partial).is_err());
+}
+
+#[test]
+fn test_cargo_moves_between_ships_of_a_fleet() {
+    let mut fleet = fleet();
+    fleet.load_cargo(ResourceType::Food, 200, &CargoDistribution::Specific(vec![allocation(1, 200)]), FillPolicy::AllOrNothing).unwrap();
+
+    assert!(fleet.transfer_cargo(1, 0, ResourceType::Food, 150, FillPolicy::AllOrNothing).is_err());
+    let transfer = fleet.transfer_cargo(1, 0, ResourceType::Food, 150, FillPolicy::Partial).unwrap();
+    assert_eq!((transfer.moved, transfer.overflow), (100, 50));
+    assert_eq!(fleet.ships[0].cargo_quantity(ResourceType::Food), 100);
+    assert_eq!(fleet.ships[1].cargo_quantity(ResourceType::Food), 100);
+    assert!(fleet.transfer_cargo(0, 0, ResourceType::Food, 1, FillPolicy::Partial).is_err());
+    assert!(fleet.transfer_cargo(0, 5, ResourceType::Food, 1, FillPolicy::Partial).is_err());
+}
+
+#[test]
+fn test_trading_with_a_fleet_checks_the_buyers_hold() {
+    let mut trader = fleet();
+    trader.load_cargo(ResourceType::Metals, 300, &CargoDistribution::First, FillPolicy::AllOrNothing).unwrap();
+    for cargo in trader.ships.iter_mut().flat_map(|ship| ship.cargo.iter_mut()) {
+        cargo.buy = Some(1.0);
+        cargo.sell = Some(1.0);
+    }
+    let mut buyer = Fleet::new("Buyer".to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    let mut hold = Ship::new(ShipType::Shuttle, ShipSize::Tiny, ShipEngine::Basic);
+    hold.cargo.clear();
+    buyer.ships.push(hold);
+    let mut player = Player::new("Buyer", 10000.0, &mut rng_for(1, "player"));
+
+    assert!(trade_with_fleet(&mut buyer, &mut trader, ResourceType::Metals, 150, "buy", &mut player).is_err());
+    assert_eq!((player.credits, trader.cargo_quantity(ResourceType::Metals)), (10000.0, 300));
+
+    trade_with_fleet(&mut buyer, &mut trader, ResourceType::Metals, 100, "buy", &mut player).unwrap();
+    assert_eq!(buyer.cargo_quantity(ResourceType::Metals), 100);
+    assert_eq!(trader.cargo_quantity(ResourceType::Metals), 200);
+}
@@ -0,0 +1,99 @@
+use crate::models::clock::{GameClock, ScheduledEvent, ScheduledEventKind};
+use crate::models::economy::Economy;
+use crate::models::fleet::generate_random_fleet;
+use crate::models::market::Market;
+use crate::models::planet::PlanetSpecialization;
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::rng::rng_for;
+use crate::simulation::tick::{run_ticks, registered_systems, MARKET_SHOCK_WARNING, MarketRecovery, MarketShocks, ScheduledEvents, ShipRepair, TickContext, TickSystem};
+
+fn context() -> TickContext {
+    TickContext::new("test_clock", 0, rng_for(5, "tick"))
+}
+
+#[test]
+fn test_stardate_advances_with_ticks() {
+    let clock = GameClock { tick: 25 };
+    assert_eq!(clock.stardate(), 3002.5);
+    assert_eq!(GameClock::default().stardate(), 3000.0);
+}
+
+#[test]
+fn test_ship_repair_recharges_shields_but_not_armor() {
+    let mut ctx = context();
+    let mut fleet = generate_random_fleet("Tester".to_string(), Position { x: 0, y: 0, z: 0 }, 1, 1, &mut rng_for(5, "fleet"));
+    let ship = &mut fleet.ships[0];
+    ship.shields.current = 0;
+    ship.armor.current = 0;
+    ctx.fleets.push(fleet);
+
+    let systems: Vec<Box<dyn TickSystem>> = vec![Box::new(ShipRepair)];
+    run_ticks(&mut ctx, &systems, 1).unwrap();
+    let ship = &ctx.fleets[0].ships[0];
+    assert_eq!(ship.shields.current, ship.shields.regen.min(ship.shields.capacity));
+    assert_eq!(ship.armor.current, 0);
+
+    run_ticks(&mut ctx, &systems, 500).unwrap();
+    let ship = &ctx.fleets[0].ships[0];
+    assert_eq!(ship.shields.current, ship.shields.capacity);
+    // Armor is only restored by paying for repairs at a planet
+    assert_eq!(ship.armor.current, 0);
+    assert_eq!(ctx.tick, 501);
+}
+
+#[test]
+fn test_market_recovery_restocks_drained_market() {
+    let mut ctx = context();
+    let mut market = Market::new(&PlanetSpecialization::Agriculture, &Economy::Stable, &mut rng_for(1, "market"));
+    let stock = market.resources.iter().find(|r| r.resource_type == ResourceType::Food).unwrap().quantity.unwrap();
+    market.buy_resource(ResourceType::Food, stock, 0, 0).unwrap();
+    ctx.markets.insert((0, 0), market);
+
+    let systems: Vec<Box<dyn TickSystem>> = vec![Box::new(MarketRecovery)];
+    run_ticks(&mut ctx, &systems, 10).unwrap();
+
+    let food = ctx.markets[&(0, 0)].resources.iter().find(|r| r.resource_type == ResourceType::Food).unwrap();
+    assert!(food.quantity.unwrap() > 0);
+}
+
+#[test]
+fn test_scheduled_events_fire_once_when_due() {
+    let mut ctx = context();
+    let mut rng = rng_for(7, "events");
+    ctx.events.push(ScheduledEvent::new(3, ScheduledEventKind::Announcement { message: "Festival on Terra".to_string() }, &mut rng));
+    ctx.events.push(ScheduledEvent::new(10, ScheduledEventKind::Announcement { message: "Eclipse".to_string() }, &mut rng));
+
+    run_ticks(&mut ctx, &registered_systems(), 2).unwrap();
+    assert!(ctx.log.is_empty());
+
+    run_ticks(&mut ctx, &registered_systems(), 3).unwrap();
+    assert_eq!(ctx.log, vec!["Tick 3: Festival on Terra".to_string()]);
+    assert_eq!(ctx.events.len(), 1);
+    assert_eq!(ctx.events[0].due_tick, 10);
+}
+
+#[test]
+fn test_market_shocks_are_announced_then_hit_their_market() {
+    let mut ctx = context();
+    ctx.markets.insert((0, 0), Market::new(&PlanetSpecialization::Mining, &Economy::Stable, &mut rng_for(1, "market")));
+
+    let systems: Vec<Box<dyn TickSystem>> = vec![Box::new(MarketShocks), Box::new(ScheduledEvents)];
+    while ctx.events.is_empty() {
+        assert!(ctx.tick < 1000, "no shock was ever rumored");
+        run_ticks(&mut ctx, &systems, 1).unwrap();
+    }
+    let event = ctx.events[0].clone();
+    assert!(MARKET_SHOCK_WARNING.contains(&(event.due_tick - ctx.tick)));
+    let ScheduledEventKind::MarketShock { resource_type, stock_change, .. } = event.kind else {
+        panic!("expected a market shock, got {:?}", event.kind);
+    };
+    assert!(ctx.log.last().unwrap().contains("traders expect"));
+
+    let stock = |ctx: &TickContext| ctx.markets[&(0, 0)].resources.iter().find(|r| r.resource_type == resource_type).unwrap().quantity.unwrap() as i64;
+    let before = stock(&ctx);
+    let until_due = (event.due_tick - ctx.tick) as u32;
+    run_ticks(&mut ctx, &[Box::new(ScheduledEvents) as Box<dyn TickSystem>], until_due).unwrap();
+    assert!(ctx.events.is_empty());
+    assert_eq!(stock(&ctx), (before + stock_change as i64).max(0));
+}
@@ -0,0 +1,259 @@
+use crate::combat::combat::{CombatOrders, apply_damage, auto_resolve_ship_combat_with_orders, auto_resolve_ship_combat_with_rng, set_fleet_stance};
+use crate::models::auth::{PlayerSession, Role, Session};
+use crate::models::context::{CurrentGame, GameRegistry};
+use crate::models::encounter::{EncounterKind, EncounterLog, create_encounter};
+use crate::models::player::Player;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::rng::rng_for;
+use crate::models::settings::GameSettings;
+use crate::models::ship::ship::{CombatState, Ship, ShipType, ShipSize, ShipEngine, ShipStatus};
+use crate::models::ship::weapon::Weapon;
+use crate::models::storage::{StorageBackend, init_storage, storage_for};
+use crate::models::travel::FleetTransit;
+use crate::routes::initiate_combat;
+use crate::tests::fixtures::{fleet_of, game_dir};
+use chrono::{Duration, Utc};
+
+fn armed_ship(name: &str, damage: i32) -> Ship {
+    let mut ship = Ship::new(ShipType::Battleship, ShipSize::Medium, ShipEngine::Basic);
+    ship.name = name.to_string();
+    ship.weapons = vec![Weapon::NeutronBeam { damage }];
+    ship
+}
+
+/// A game where `Raider` and `Victim` each have a fleet of one armed ship,
+/// facing each other at the same spot; only the players named are saved.
+fn battlefield(game_id: &str, players: &[&str]) -> CurrentGame {
+    game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    let storage = storage_for(game_id).unwrap();
+    storage.save_settings(&GameSettings { game_id: game_id.to_string(), ..GameSettings::default() }).unwrap();
+    for player in players {
+        storage.save_player(&Player::new(player, 1000.0, &mut rng_for(1, "player"))).unwrap();
+    }
+    for (owner, damage) in [("Raider", 400), ("Victim", 1)] {
+        let mut fleet = fleet_of(owner, vec![armed_ship(owner, damage)]);
+        fleet.name = format!("Fleet_{}_1", owner);
+        storage.save_fleet(&fleet).unwrap();
+    }
+    CurrentGame::new(GameRegistry::default().get(game_id).unwrap())
+}
+
+fn raider(game_id: &str) -> PlayerSession {
+    PlayerSession::new(Session {
+        token: "test_raider".to_string(),
+        game_id: game_id.to_string(),
+        player_name: "Raider".to_string(),
+        role: Role::Player,
+        expires_at: Utc::now() + Duration::hours(1),
+    })
+}
+
+fn ships_left(game_id: &str, fleet_name: &str) -> usize {
+    storage_for(game_id).unwrap().load_fleet(fleet_name).unwrap().unwrap().ships.len()
+}
+
+#[test]
+fn test_damage_hits_shields_then_armor_then_hull() {
+    let mut ship = armed_ship("Target", 0);
+    let shields = ship.shields.current;
+    let armor = ship.armor.current;
+    let hp = ship.hp;
+
+    // Fully absorbed by shields
+    let (shield_absorbed, armor_absorbed, hull_damage) = apply_damage(&mut ship, shields / 2);
+    assert_eq!((shield_absorbed, armor_absorbed, hull_damage), (shields / 2, 0, 0));
+    assert_eq!(ship.armor.current, armor);
+    assert_eq!(ship.hp, hp);
+
+    // Bleed-through past the shields is split between armor and hull
+    let (shield_absorbed, armor_absorbed, hull_damage) = apply_damage(&mut ship, shields);
+    assert_eq!(shield_absorbed, shields - shields / 2);
+    assert!(armor_absorbed > 0);
+    assert_eq!(shield_absorbed + armor_absorbed + hull_damage, shields);
+    assert_eq!(ship.shields.current, 0);
+    assert_eq!(ship.armor.current, armor - armor_absorbed);
+    assert_eq!(ship.hp, hp - hull_damage);
+}
+
+#[test]
+fn test_ship_only_destroyed_at_zero_hull() {
+    let mut ship = armed_ship("Target", 0);
+    ship.shields.current = 0;
+    ship.armor.current = 0;
+    let hp = ship.hp;
+
+    apply_damage(&mut ship, hp - 1);
+    assert_eq!(ship.hp, 1);
+
+    let (_, _, hull_damage) = apply_damage(&mut ship, 1000);
+    assert_eq!(hull_damage, 1);
+    assert_eq!(ship.hp, 0);
+}
+
+#[test]
+fn test_survivors_keep_damage_and_destroyed_ships_are_removed() {
+    let mut attacker = fleet_of("Attacker", vec![armed_ship("Hammer", 400)]);
+    let mut defender = fleet_of("Defender", vec![armed_ship("Anvil", 1)]);
+
+    let result = auto_resolve_ship_combat_with_rng(&mut attacker, &mut defender, &mut rng_for(1, "combat"));
+
+    assert!(result.attacker_victory);
+    assert!(defender.ships.is_empty());
+    assert_eq!(result.defender_losses.len(), 1);
+    assert_eq!(result.defender_losses[0].hp, 0);
+    assert!(result.attacker_losses.is_empty());
+    assert!(!result.rounds.is_empty());
+
+    let survivor = &attacker.ships[0];
+    assert!(survivor.hp > 0);
+    assert!(result.rounds.iter().flat_map(|r| &r.events).any(|e| e.target_ship == "Hammer"));
+}
+
+#[test]
+fn test_unarmed_fleets_end_combat_without_losses() {
+    let mut attacker = fleet_of("Attacker", vec![armed_ship("Drifter", 0)]);
+    attacker.ships[0].weapons.clear();
+    let mut defender = fleet_of("Defender", vec![armed_ship("Hulk", 0)]);
+    defender.ships[0].weapons.clear();
+
+    let result = auto_resolve_ship_combat_with_rng(&mut attacker, &mut defender, &mut rng_for(1, "combat"));
+
+    assert!(result.rounds.is_empty());
+    assert_eq!(attacker.ships.len(), 1);
+    assert_eq!(defender.ships.len(), 1);
+}
+
+#[test]
+fn test_aggressive_ships_focus_on_the_most_battered_target() {
+    let mut attacker = fleet_of("Attacker", vec![armed_ship("Hunter", 1)]);
+    set_fleet_stance(&mut attacker, CombatState::Aggressive).unwrap();
+    let mut defender = fleet_of("Defender", vec![armed_ship("Fresh", 0), armed_ship("Limping", 0)]);
+    defender.ships[1].hp = 20;
+
+    let result = auto_resolve_ship_combat_with_rng(&mut attacker, &mut defender, &mut rng_for(3, "combat"));
+
+    let shots: Vec<_> = result.rounds.iter().flat_map(|r| &r.events).filter(|e| e.attacker_ship == "Hunter").collect();
+    assert!(!shots.is_empty());
+    assert!(shots.iter().all(|e| e.target_ship == "Limping"));
+}
+
+#[test]
+fn test_evasive_ships_take_less_damage() {
+    let damage_taken = |stance: CombatState| {
+        let mut attacker = fleet_of("Attacker", vec![armed_ship("Gunner", 5)]);
+        let mut defender = fleet_of("Defender", vec![armed_ship("Target", 0)]);
+        defender.ships[0].combat_state = stance;
+        let result = auto_resolve_ship_combat_with_rng(&mut attacker, &mut defender, &mut rng_for(4, "combat"));
+        result.rounds.iter().flat_map(|r| &r.events).map(|e| e.damage).sum::<i32>()
+    };
+
+    assert!(damage_taken(CombatState::Evasive) < damage_taken(CombatState::Default));
+    assert!(damage_taken(CombatState::Aggressive) > damage_taken(CombatState::Default));
+}
+
+#[test]
+fn test_ordered_retreat_ends_the_fight_and_costs_cargo() {
+    let mut attacker = fleet_of("Attacker", vec![armed_ship("Runner", 50)]);
+    attacker.ships[0].cargo = vec![Resource::new(ResourceType::Food, 10)];
+    let mut defender = fleet_of("Defender", vec![armed_ship("Picket", 50)]);
+    set_fleet_stance(&mut defender, CombatState::Passive).unwrap();
+    let orders = CombatOrders { attacker_retreat_after: Some(0), ..CombatOrders::default() };
+
+    let result = auto_resolve_ship_combat_with_orders(&mut attacker, &mut defender, orders, &mut rng_for(5, "combat"));
+
+    // Passive fleets never give chase, so the attacker gets away before a shot is fired
+    assert!(result.attacker_retreated);
+    assert!(!result.attacker_victory);
+    assert!(result.rounds.iter().all(|r| r.events.is_empty()));
+    assert_eq!(result.jettisoned.len(), 1);
+    assert_eq!(result.jettisoned[0].quantity, Some(3));
+    assert_eq!(attacker.ships[0].cargo[0].quantity, Some(7));
+    assert_eq!(defender.ships.len(), 1);
+}
+
+#[test]
+fn test_aggressive_pursuers_fire_on_fleeing_passive_ships() {
+    let mut escapes = 0;
+    for seed in 0..200 {
+        let mut attacker = fleet_of("Attacker", vec![armed_ship("Chaser", 5)]);
+        attacker.ships[0].engine = ShipEngine::Experimental;
+        set_fleet_stance(&mut attacker, CombatState::Aggressive).unwrap();
+        let mut defender = fleet_of("Defender", vec![armed_ship("Prey", 5)]);
+        set_fleet_stance(&mut defender, CombatState::Passive).unwrap();
+
+        let result = auto_resolve_ship_combat_with_rng(&mut attacker, &mut defender, &mut rng_for(seed, "combat"));
+
+        // A passive fleet keeps trying to break off and never fires back
+        assert!(result.rounds.iter().flat_map(|r| &r.events).all(|e| e.attacker_ship == "Chaser"));
+        if result.defender_retreated {
+            escapes += 1;
+            assert!(result.attacker_victory);
+            assert!(!result.rounds.last().unwrap().events.is_empty(), "the chaser fires a parting volley");
+        }
+    }
+    assert!(escapes > 0);
+}
+
+#[test]
+fn test_a_fight_is_saved_only_with_its_consequences() {
+    let game_id = "test_combat_consequences";
+    // Without a player to hold the standing it costs, the fight is not saved
+    let game = battlefield(game_id, &[]);
+    let report = initiate_combat(game, raider(game_id), "Raider".to_string(), 1, "Victim".to_string(), 1, None).into_inner();
+    assert!(report.contains("not found"), "{}", report);
+    assert_eq!(ships_left(game_id, "Fleet_Victim_1"), 1);
+
+    let game = battlefield(game_id, &["Raider"]);
+    let report = initiate_combat(game, raider(game_id), "Raider".to_string(), 1, "Victim".to_string(), 1, None).into_inner();
+    assert!(report.contains("Final fleet sizes"), "{}", report);
+    assert_eq!(ships_left(game_id, "Fleet_Victim_1"), 0);
+}
+
+#[test]
+fn test_fleets_attack_only_other_sides_when_free_to() {
+    let game_id = "test_combat_refusals";
+    let game = battlefield(game_id, &["Raider"]);
+    let attack = |game: &CurrentGame, defender: &str| {
+        initiate_combat(game.clone(), raider(game_id), "Raider".to_string(), 1, defender.to_string(), 1, None).into_inner()
+    };
+    let storage = storage_for(game_id).unwrap();
+
+    // A fleet neither fights itself nor a fleet of its own side
+    assert!(attack(&game, "Raider").contains("its own side"));
+    let mut wingman = fleet_of("Raider", vec![armed_ship("Wingman", 1)]);
+    wingman.name = "Fleet_Raider_2".to_string();
+    storage.save_fleet(&wingman).unwrap();
+    let report = initiate_combat(game.clone(), raider(game_id), "Raider".to_string(), 1, "Raider".to_string(), 2, None).into_inner();
+    assert!(report.contains("its own side"), "{}", report);
+    assert_eq!(ships_left(game_id, "Fleet_Raider_2"), 1);
+
+    // Nor one that is underway
+    let mut attacker = storage.load_fleet("Fleet_Raider_1").unwrap().unwrap();
+    attacker.transit = Some(FleetTransit {
+        origin: attacker.position,
+        destination: attacker.position,
+        destination_local: None,
+        destination_system_id: attacker.current_system_id,
+        departure_tick: 0,
+        arrival_tick: 5,
+        distance: 0.0,
+        fuel_used: 0,
+        status: ShipStatus::Warp,
+    });
+    storage.save_fleet(&attacker).unwrap();
+    assert!(attack(&game, "Victim").contains("in transit"));
+    attacker.transit = None;
+    storage.save_fleet(&attacker).unwrap();
+
+    // Nor one that still has an encounter to answer
+    {
+        let _game = game.enter_for_writes();
+        let (encounter, _) = create_encounter(EncounterKind::Pirate, &attacker, None, 0, |_| false, &mut rng_for(1, "encounter"));
+        let mut log = EncounterLog::load().unwrap();
+        log.encounters.push(encounter);
+        log.save().unwrap();
+    }
+    assert!(attack(&game, "Victim").contains("encounters pending"));
+    assert_eq!(ships_left(game_id, "Fleet_Victim_1"), 1);
+}
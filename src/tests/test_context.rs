@@ -0,0 +1,91 @@
+use crate::models::context::{GameContext, GameRegistry};
+use crate::models::game_state::{current_game_id, game_data_path, game_path, load_player, save_player};
+use crate::models::player::Player;
+use crate::models::rng::rng_for;
+use crate::models::storage::{StorageBackend, init_storage};
+use std::fs;
+use std::thread;
+
+/// A fresh, empty game on disk.
+fn new_game(game_id: &str) {
+    let _ = fs::remove_dir_all(game_data_path(game_id, &[]));
+    init_storage(game_id, StorageBackend::Json).unwrap();
+}
+
+fn player(name: &str, credits: f64) -> Player {
+    Player::new(name, credits, &mut rng_for(1, "player"))
+}
+
+#[test]
+fn test_entered_games_are_scoped_to_the_thread() {
+    assert!(current_game_id().is_err(), "no game is entered by default");
+    let outer = GameContext::new("test_context_outer");
+    let inner = GameContext::new("test_context_inner");
+    {
+        let _outer = outer.enter();
+        {
+            let _inner = inner.enter();
+            assert_eq!(current_game_id().unwrap(), "test_context_inner");
+        }
+        assert_eq!(current_game_id().unwrap(), "test_context_outer");
+        thread::spawn(|| assert!(current_game_id().is_err())).join().unwrap();
+    }
+    assert!(current_game_id().is_err());
+}
+
+#[test]
+fn test_games_are_served_side_by_side() {
+    let registry = GameRegistry::default();
+    let handles: Vec<_> = [("test_context_a", 100.0), ("test_context_b", 200.0)].into_iter().map(|(game_id, credits)| {
+        new_game(game_id);
+        let context = registry.get(game_id).unwrap();
+        thread::spawn(move || {
+            let _game = context.enter();
+            save_player(&player("Pilot", credits)).unwrap();
+            // Cached per game, so each game reads back its own pilot
+            assert_eq!(load_player("Pilot").unwrap().credits, credits);
+            context.clear_caches();
+            assert_eq!(load_player("Pilot").unwrap().credits, credits);
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert!(registry.get("../test_context_a").is_err());
+}
+
+#[test]
+fn test_paths_need_an_entered_game() {
+    assert!(game_path(&["players", "Pilot.json"]).is_err());
+    let context = GameContext::new("test_context_path");
+    let _game = context.enter();
+    assert_eq!(game_path(&["players"]).unwrap(), game_data_path("test_context_path", &["players"]));
+}
+
+#[test]
+fn test_writers_to_a_game_take_turns() {
+    new_game("test_context_writes");
+    let context = GameRegistry::default().get("test_context_writes").unwrap();
+    {
+        let _game = context.enter();
+        save_player(&player("Pilot", 0.0)).unwrap();
+    }
+    let handles: Vec<_> = (0..8).map(|_| {
+        let context = context.clone();
+        thread::spawn(move || {
+            for _ in 0..10 {
+                let _game = context.enter_for_writes();
+                let mut pilot = load_player("Pilot").unwrap();
+                thread::yield_now();
+                pilot.credits += 1.0;
+                save_player(&pilot).unwrap();
+            }
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let _game = context.enter();
+    assert_eq!(load_player("Pilot").unwrap().credits, 80.0);
+}
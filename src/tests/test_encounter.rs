@@ -0,0 +1,97 @@
+use crate::models::encounter::{EncounterAction, EncounterActionData, EncounterKind, EncounterStatus, MAX_ENCOUNTER_CHANCE, create_encounter, encounter_chance, resolve_encounter, roll_encounter_kind};
+use crate::combat::combat::escape_chance;
+use crate::models::faction::{ATTACK_REPUTATION_PENALTY, Faction};
+use crate::models::player::Player;
+use crate::models::rng::rng_for;
+use crate::models::ship::ship::{CombatState, Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::ship::weapon::Weapon;
+use crate::tests::fixtures::fleet_of;
+
+fn ship(engine: ShipEngine, damage: i32) -> Ship {
+    let mut ship = Ship::new(ShipType::Battleship, ShipSize::Medium, engine);
+    ship.weapons = vec![Weapon::NeutronBeam { damage }];
+    ship
+}
+
+fn action(action: EncounterAction) -> EncounterActionData {
+    EncounterActionData { action, resource_type: None, quantity: None, trade_type: None, retreat_after: None }
+}
+
+fn federation() -> Faction {
+    Faction::new("Federation".to_string(), String::new())
+}
+
+#[test]
+fn test_encounters_are_likelier_in_dangerous_unpatrolled_space() {
+    let federation = federation();
+    assert!(encounter_chance(8.0, None) > encounter_chance(1.0, None));
+    assert!(encounter_chance(5.0, Some(&federation)) < encounter_chance(5.0, None));
+    assert_eq!(encounter_chance(1000.0, None), MAX_ENCOUNTER_CHANCE);
+
+    let mut rng = rng_for(5, "encounter");
+    let military = |faction: Option<&Faction>, rng: &mut _| {
+        (0..500).filter(|_| roll_encounter_kind(faction, rng) == EncounterKind::Military).count()
+    };
+    assert!(military(Some(&federation), &mut rng) > military(None, &mut rng), "factions patrol their own territory");
+}
+
+#[test]
+fn test_faster_and_evasive_fleets_get_away() {
+    let slow = fleet_of("Runner", vec![ship(ShipEngine::Basic, 10)]);
+    let fast = fleet_of("Runner", vec![ship(ShipEngine::Experimental, 10)]);
+    let mut pursuers = fleet_of("Pirate", vec![ship(ShipEngine::Advanced, 10)]);
+
+    assert!(escape_chance(&fast, &pursuers) > escape_chance(&slow, &pursuers));
+    let mut evasive = slow.clone();
+    evasive.ships[0].combat_state = CombatState::Evasive;
+    assert!(escape_chance(&evasive, &pursuers) > escape_chance(&slow, &pursuers));
+
+    pursuers.ships[0].combat_state = CombatState::Passive;
+    assert_eq!(escape_chance(&slow, &pursuers), 1.0, "passive fleets do not give chase");
+}
+
+#[test]
+fn test_players_choose_how_an_encounter_ends() {
+    let mut rng = rng_for(9, "encounter");
+    let mut player = Player::new("Tester", 100.0, &mut rng_for(1, "player"));
+    let mut fleet = fleet_of("Tester", vec![ship(ShipEngine::Basic, 1000)]);
+    let (mut encounter, mut pirates) = create_encounter(EncounterKind::Pirate, &fleet, None, 3, |_| false, &mut rng);
+    assert_eq!(encounter.status, EncounterStatus::Pending);
+    assert_eq!(encounter.hostile_fleet, pirates.name);
+    assert_eq!((pirates.position, pirates.owner_id.as_str()), (fleet.position, "Pirate"));
+    assert!(encounter.options.contains(&EncounterAction::PayTribute));
+    assert!(!encounter.options.contains(&EncounterAction::Trade), "pirates do not trade");
+
+    // Aggressive pirates will not talk, and the tribute is more than the player has
+    resolve_encounter(&mut encounter, &action(EncounterAction::Hail), &mut player, &mut fleet, &mut pirates, &[], &mut rng).unwrap();
+    assert!(encounter.is_pending());
+    assert!(!encounter.options.contains(&EncounterAction::Hail));
+    assert!(resolve_encounter(&mut encounter, &action(EncounterAction::Hail), &mut player, &mut fleet, &mut pirates, &[], &mut rng).is_err());
+    assert!(resolve_encounter(&mut encounter, &action(EncounterAction::PayTribute), &mut player, &mut fleet, &mut pirates, &[], &mut rng).is_err());
+    assert_eq!(player.credits, 100.0);
+
+    player.credits = encounter.tribute + 1.0;
+    resolve_encounter(&mut encounter, &action(EncounterAction::PayTribute), &mut player, &mut fleet, &mut pirates, &[], &mut rng).unwrap();
+    assert_eq!(encounter.status, EncounterStatus::TributePaid);
+    assert_eq!(player.credits, 1.0);
+    assert!(encounter.options.is_empty());
+    assert!(encounter.hostile_departs(&pirates));
+    assert!(resolve_encounter(&mut encounter, &action(EncounterAction::Fight), &mut player, &mut fleet, &mut pirates, &[], &mut rng).is_err(), "an encounter ends once");
+}
+
+#[test]
+fn test_fighting_a_patrol_costs_standing_with_its_faction() {
+    let mut rng = rng_for(11, "encounter");
+    let federation = federation();
+    let mut player = Player::new("Tester", 100.0, &mut rng_for(1, "player"));
+    let mut fleet = fleet_of("Tester", (0..6).map(|_| ship(ShipEngine::Basic, 5000)).collect());
+    let (mut encounter, mut patrol) = create_encounter(EncounterKind::Military, &fleet, Some(&federation), 3, |_| false, &mut rng);
+    assert_eq!(encounter.faction.as_deref(), Some("Federation"));
+
+    let (log, combat) = resolve_encounter(&mut encounter, &action(EncounterAction::Fight), &mut player, &mut fleet, &mut patrol, std::slice::from_ref(&federation), &mut rng).unwrap();
+    assert!(combat.is_some());
+    assert_eq!(encounter.status, EncounterStatus::Won);
+    assert!(patrol.ships.is_empty());
+    assert!(log.iter().any(|line| line.starts_with("Reputation with Federation")));
+    assert_eq!(player.reputation_with("Federation"), ATTACK_REPUTATION_PENALTY);
+}
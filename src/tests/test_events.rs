@@ -0,0 +1,144 @@
+use crate::models::context::GameContext;
+use crate::models::events::{self, Audience, GameEventKind};
+use crate::models::fleet::Fleet;
+use crate::models::position::Position;
+use crate::models::resource::ResourceType;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipStatus, ShipType};
+use crate::models::storage::{StorageBackend, init_storage, storage_for};
+use crate::models::travel::FleetTransit;
+use crate::tests::fixtures::{game_dir, systems_at};
+
+/// Alice, Bob and Carol each with a fleet resting in one of three systems.
+fn watched_game(game_id: &str) -> Vec<Fleet> {
+    game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    let storage = storage_for(game_id).unwrap();
+    let systems = systems_at(3, &[(0, 0), (2000, 0), (4000, 0)]);
+    storage.save_galaxy(&systems).unwrap();
+    ["Alice", "Bob", "Carol"].iter().zip(&systems).map(|(owner, system)| {
+        let mut fleet = Fleet::new(owner.to_string(), system.position, 1);
+        fleet.ships.push(Ship::new(ShipType::Fighter, ShipSize::Small, ShipEngine::Basic));
+        fleet.current_system_id = Some(system.id);
+        storage.save_fleet(&fleet).unwrap();
+        fleet
+    }).collect()
+}
+
+fn players(names: &[&str]) -> Audience {
+    Audience::Players(names.iter().map(|name| name.to_string()).collect())
+}
+
+#[test]
+fn test_events_reach_only_players_who_can_see_them() {
+    let game_id = "test_events_audience";
+    let fleets = watched_game(game_id);
+    let mut moved = fleets[0].clone();
+    moved.update_position(fleets[1].position);
+    moved.current_system_id = fleets[1].current_system_id;
+
+    let arrival = GameEventKind::FleetMoved {
+        fleet: moved.name.clone(),
+        owner_id: "Alice".to_string(),
+        from: fleets[0].position,
+        from_system_id: fleets[0].current_system_id,
+        to: moved.position,
+        to_system_id: moved.current_system_id,
+        arrival_tick: None,
+    };
+    assert_eq!(arrival.audience(game_id), players(&["Alice", "Bob"]), "Carol is too far away to see it");
+
+    let departure = GameEventKind::FleetMoved {
+        fleet: moved.name.clone(),
+        owner_id: "Alice".to_string(),
+        from: fleets[0].position,
+        from_system_id: fleets[0].current_system_id,
+        to: moved.position,
+        to_system_id: moved.current_system_id,
+        arrival_tick: Some(9),
+    };
+    assert_eq!(departure.audience(game_id), players(&["Alice"]), "Bob cannot see a fleet still on its way");
+
+    let prices = GameEventKind::MarketPriceChanged { system_id: 2, planet_id: 0, resource_type: ResourceType::Food, buy: Some(12.0), sell: None };
+    assert_eq!(prices.audience(game_id), players(&["Carol"]));
+
+    let encounter = GameEventKind::EncounterTriggered { fleet: fleets[1].name.clone(), owner_id: "Bob".to_string(), encounters: vec!["Fleet_Pirate_1".to_string()] };
+    let audience = encounter.audience(game_id);
+    assert!(audience.includes("Bob") && !audience.includes("Alice"));
+    assert!(Audience::Everyone.includes("Carol"));
+}
+
+#[test]
+fn test_subscribers_receive_events_published_in_their_game() {
+    let game_id = "test_events_publish";
+    let fleets = watched_game(game_id);
+    let context = GameContext::new(game_id);
+    let other = GameContext::new("test_events_other");
+    let mut subscriber = context.subscribe();
+    let mut other_subscriber = other.subscribe();
+
+    // Nothing is entered on this thread yet, so there is no game to publish to
+    events::publish(GameEventKind::EncounterTriggered { fleet: fleets[0].name.clone(), owner_id: "Alice".to_string(), encounters: Vec::new() });
+    assert!(subscriber.try_recv().is_err());
+
+    let _game = context.enter();
+    let mut departed = fleets[2].clone();
+    departed.update_position(fleets[0].position);
+    departed.current_system_id = fleets[0].current_system_id;
+    events::publish_fleet_moved(&fleets[2], &departed);
+    events::publish_fleet_moved(&departed, &departed);
+
+    let moved = subscriber.try_recv().unwrap();
+    assert_eq!(moved.kind.name(), "fleet_moved");
+    assert!(moved.audience.includes("Alice") && moved.audience.includes("Carol") && !moved.audience.includes("Bob"));
+    let json = serde_json::to_value(&moved).unwrap();
+    assert_eq!(json["type"], "fleet_moved");
+    assert_eq!(json["fleet"], departed.name.as_str());
+    assert!(json.get("audience").is_none(), "who an event is for stays on the server");
+
+    assert_eq!(subscriber.try_recv().unwrap().kind.name(), "fleet_arrived");
+    assert!(subscriber.try_recv().is_err(), "a fleet that stays put is not news");
+    assert!(other_subscriber.try_recv().is_err(), "events stay within their game");
+}
+
+#[test]
+fn test_deep_space_events_reach_only_fleets_at_the_spot() {
+    let game_id = "test_events_deep_space";
+    let fleets = watched_game(game_id);
+    let storage = storage_for(game_id).unwrap();
+    let spot = Position { x: 1000, y: 500, z: 0 };
+    let place = |fleet: &Fleet, position: Position| {
+        let mut fleet = fleet.clone();
+        fleet.update_position(position);
+        fleet.current_system_id = None;
+        storage.save_fleet(&fleet).unwrap();
+        fleet
+    };
+    place(&fleets[0], spot);
+    place(&fleets[1], Position { x: 1001, y: 500, z: 0 });
+    let mut passing = place(&fleets[2], spot);
+    passing.transit = Some(FleetTransit {
+        origin: spot,
+        destination: Position { x: 3000, y: 0, z: 0 },
+        destination_local: None,
+        destination_system_id: None,
+        departure_tick: 0,
+        arrival_tick: 40,
+        distance: 2000.0,
+        fuel_used: 0,
+        status: ShipStatus::Warp,
+    });
+    storage.save_fleet(&passing).unwrap();
+
+    let fight = GameEventKind::CombatResolved {
+        attacker: "Fleet_Pirate_1".to_string(),
+        attacker_owner: "Pirate".to_string(),
+        defender: "Fleet_Dave_1".to_string(),
+        defender_owner: "Dave".to_string(),
+        position: spot,
+        system_id: None,
+        attacker_victory: true,
+        attacker_losses: 0,
+        defender_losses: 1,
+    };
+    assert_eq!(fight.audience(game_id), players(&["Alice", "Dave", "Pirate"]), "Bob is nearby but not there, Carol is passing through");
+}
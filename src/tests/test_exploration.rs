@@ -0,0 +1,145 @@
+use crate::models::exploration::{FleetSight, MARKET_INTEL_STALE_TICKS, PlayerKnowledge, Visibility};
+use crate::models::fleet::Fleet;
+use crate::models::market::Market;
+use crate::models::npc_trader::deep_space_trip;
+use crate::models::position::Position;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::rng::rng_for;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::star_system::StarSystem;
+use crate::models::travel::begin_transit;
+use crate::simulation::tick::{Exploration, FleetTransit, TickContext, run_ticks};
+use crate::tests::fixtures::systems_at;
+use std::collections::BTreeMap;
+
+/// A home system, a neighbour in star sight and a far system beyond it.
+fn systems() -> Vec<StarSystem> {
+    systems_at(8, &[(0, 0), (500, 0), (1100, 0)])
+}
+
+/// One market per system, selling metals at a price set by the system id.
+fn markets(systems: &[StarSystem]) -> BTreeMap<(usize, usize), Market> {
+    systems.iter().map(|system| {
+        let price = 10.0 + system.id as f64;
+        let resources = vec![Resource { resource_type: ResourceType::Metals, buy: Some(price), sell: Some(price), quantity: Some(100) }];
+        ((system.id, 0), Market { resources, profiles: Vec::new() })
+    }).collect()
+}
+
+fn fleet(specialization: ShipType) -> Fleet {
+    let mut fleet = Fleet::new("Scout".to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    fleet.current_system_id = Some(0);
+    fleet.ships.push(Ship::new(specialization, ShipSize::Small, ShipEngine::Basic));
+    fleet
+}
+
+#[test]
+fn test_fleets_survey_their_system_and_sight_nearby_stars() {
+    let systems = systems();
+    let mut knowledge = PlayerKnowledge::new("Scout");
+    assert!(knowledge.filter_galaxy(&systems).is_empty(), "everything starts unknown");
+
+    knowledge.observe(&[fleet(ShipType::Fighter)], &systems, &markets(&systems), 3);
+    assert_eq!(knowledge.visibility(0), Visibility::Surveyed);
+    assert_eq!(knowledge.visibility(1), Visibility::StarVisible);
+    assert_eq!(knowledge.visibility(2), Visibility::Unknown);
+
+    let charted = knowledge.filter_galaxy(&systems);
+    assert_eq!(charted.len(), 2);
+    assert_eq!(charted[0].planets.len(), 1);
+    assert_eq!(charted[0].planets[0].market.resources[0].buy, Some(10.0));
+    assert!(charted[1].planets.is_empty(), "a sighted star does not reveal its planets");
+    assert!(knowledge.known_market(1, 0, 3).is_err());
+
+    // Other players' fleets teach this player nothing
+    let mut rival = fleet(ShipType::Fighter);
+    rival.owner_id = "Rival".to_string();
+    rival.update_position(systems[2].position);
+    rival.current_system_id = Some(2);
+    knowledge.observe(&[rival], &systems, &markets(&systems), 4);
+    assert_eq!(knowledge.visibility(2), Visibility::Unknown);
+}
+
+#[test]
+fn test_market_information_goes_stale() {
+    let systems = systems();
+    let mut knowledge = PlayerKnowledge::new("Scout");
+    knowledge.observe(&[fleet(ShipType::Fighter)], &systems, &markets(&systems), 10);
+
+    let fresh = knowledge.known_market(0, 0, 10).unwrap();
+    assert_eq!((fresh.age, fresh.stale), (0, false));
+    let old = knowledge.known_market(0, 0, 11 + MARKET_INTEL_STALE_TICKS).unwrap();
+    assert_eq!(old.observed_tick, 10);
+    assert!(old.stale);
+    assert!(knowledge.known_market(0, 5, 10).is_err());
+}
+
+#[test]
+fn test_explorers_scan_systems_in_range() {
+    let systems = systems();
+    let mut knowledge = PlayerKnowledge::new("Scout");
+    assert!(knowledge.scan(&fleet(ShipType::Fighter), &systems, &markets(&systems), 1).is_err());
+
+    let report = knowledge.scan(&fleet(ShipType::Explorer), &systems, &markets(&systems), 1).unwrap();
+    assert_eq!(report.surveyed, vec![0, 1, 2]);
+    assert_eq!(report.discovered, vec![0, 1, 2]);
+    assert_eq!(knowledge.known_market(2, 0, 1).unwrap().market.resources[0].buy, Some(12.0));
+
+    let again = knowledge.scan(&fleet(ShipType::Explorer), &systems, &markets(&systems), 2).unwrap();
+    assert!(again.discovered.is_empty());
+}
+
+#[test]
+fn test_exploration_tick_surveys_systems_fleets_arrive_in() {
+    let mut ctx = TickContext::new("test_exploration", 0, rng_for(4, "tick"));
+    ctx.systems = systems();
+    ctx.markets = markets(&ctx.systems);
+    ctx.knowledge.insert("Scout".to_string(), PlayerKnowledge::new("Scout"));
+    let mut scout = fleet(ShipType::Fighter);
+    scout.ships[0].cargo = vec![Resource { resource_type: ResourceType::Fuel, quantity: Some(50), buy: None, sell: None }];
+    let mut arrived = scout.clone();
+    arrived.update_position(ctx.systems[1].position);
+    arrived.current_system_id = Some(1);
+    let trip = deep_space_trip(&scout.position, &arrived.position);
+    ctx.fleets = vec![begin_transit(scout, arrived, &trip, 0).unwrap()];
+
+    run_ticks(&mut ctx, &[Box::new(FleetTransit), Box::new(Exploration)], 1).unwrap();
+    assert_eq!(ctx.knowledge["Scout"].visibility(1), Visibility::StarVisible);
+    run_ticks(&mut ctx, &[Box::new(FleetTransit), Box::new(Exploration)], 10).unwrap();
+    let knowledge = &ctx.knowledge["Scout"];
+    assert_eq!(knowledge.visibility(1), Visibility::Surveyed);
+    assert_eq!(knowledge.visibility(2), Visibility::StarVisible);
+    assert_eq!(knowledge.known_market(1, 0, ctx.tick).unwrap().age, 0);
+}
+
+#[test]
+fn test_fleets_are_only_seen_where_the_player_has_eyes() {
+    let systems = systems();
+    let scout = fleet(ShipType::Fighter);
+    let sight = FleetSight::new("Scout", vec![scout.clone()]);
+
+    let mut rival = fleet(ShipType::Fighter);
+    rival.owner_id = "Rival".to_string();
+    rival.name = "Fleet_Rival_1".to_string();
+    assert!(sight.can_see(&rival), "a fleet in the same system is in sight");
+
+    rival.update_position(systems[1].position);
+    rival.current_system_id = Some(1);
+    assert!(!sight.can_see(&rival), "a fleet in another system is not");
+
+    // The player's own fleets are always known, wherever they are
+    let mut far_scout = scout.clone();
+    far_scout.update_position(systems[2].position);
+    far_scout.current_system_id = Some(2);
+    assert!(sight.can_see(&far_scout));
+
+    // A fleet in transit sees nothing
+    let mut travelling = scout;
+    travelling.ships[0].cargo = vec![Resource { resource_type: ResourceType::Fuel, quantity: Some(50), buy: None, sell: None }];
+    let mut arrived = travelling.clone();
+    arrived.update_position(systems[1].position);
+    arrived.current_system_id = Some(1);
+    let trip = deep_space_trip(&travelling.position, &arrived.position);
+    let travelling = begin_transit(travelling, arrived, &trip, 0).unwrap();
+    assert!(!FleetSight::new("Scout", vec![travelling]).can_see(&rival));
+}
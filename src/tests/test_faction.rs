@@ -0,0 +1,76 @@
+use crate::models::faction::{Faction, Standing, apply_reputation_change, assign_territories, controlling_faction, MAX_REPUTATION};
+use crate::models::player::Player;
+use crate::models::rng::rng_for;
+use crate::models::star_system::generate_star_system;
+use std::collections::HashSet;
+
+fn faction(name: &str, influence: u32) -> Faction {
+    let mut faction = Faction::new(name.to_string(), format!("The {} Empire", name));
+    faction.influence = influence;
+    faction
+}
+
+#[test]
+fn test_territories_have_distinct_capitals_and_do_not_overlap() {
+    let mut rng = rng_for(11, "galaxy");
+    let mut names = HashSet::new();
+    let systems: Vec<_> = (0..20).map(|id| {
+        let mut system = generate_star_system(1000, 1000, 1000, &mut names, &mut rng);
+        system.id = id;
+        system
+    }).collect();
+    let mut factions = vec![faction("Federation", 50), faction("Empire", 80), faction("Republic", 0)];
+
+    assign_territories(&mut factions, &systems, 1000, &mut rng_for(11, "factions"));
+
+    let capitals: HashSet<_> = factions.iter().map(|f| f.capital_system_id.unwrap()).collect();
+    assert_eq!(capitals.len(), 3);
+    // Without influence a faction holds nothing beyond its capital
+    assert_eq!(factions[2].territory, vec![factions[2].capital_system_id.unwrap()]);
+
+    let mut claimed = HashSet::new();
+    for faction in &factions {
+        for system_id in &faction.territory {
+            assert!(claimed.insert(*system_id), "system {} claimed twice", system_id);
+            assert_eq!(controlling_faction(&factions, *system_id).unwrap().name, faction.name);
+        }
+    }
+}
+
+#[test]
+fn test_reputation_ripples_to_allies_and_rivals() {
+    let mut federation = faction("Federation", 50);
+    federation.add_relation("Alliance".to_string(), 50.0);
+    federation.add_relation("Empire".to_string(), -50.0);
+    let factions = vec![federation, faction("Alliance", 50), faction("Empire", 50)];
+    let mut player = Player::new("Tester", 1000.0, &mut rng_for(1, "player"));
+
+    let changes = apply_reputation_change(&mut player, &factions, "Federation", -20.0);
+
+    assert_eq!(changes.len(), 3);
+    assert_eq!(player.reputation_with("Federation"), -20.0);
+    assert_eq!(player.reputation_with("Alliance"), -10.0);
+    assert_eq!(player.reputation_with("Empire"), 10.0);
+}
+
+#[test]
+fn test_non_faction_owners_do_not_affect_reputation() {
+    let factions = vec![faction("Federation", 50)];
+    let mut player = Player::new("Tester", 1000.0, &mut rng_for(1, "player"));
+
+    assert!(apply_reputation_change(&mut player, &factions, "Pirate_1", -20.0).is_empty());
+    assert!(player.reputation.is_empty());
+}
+
+#[test]
+fn test_reputation_is_clamped_and_mapped_to_standing() {
+    let mut player = Player::new("Tester", 1000.0, &mut rng_for(1, "player"));
+    for _ in 0..20 {
+        player.adjust_reputation("Empire", -15.0);
+    }
+    assert_eq!(player.reputation_with("Empire"), -MAX_REPUTATION);
+    assert_eq!(Standing::from_reputation(player.reputation_with("Empire")), Standing::Hostile);
+    assert_eq!(Standing::from_reputation(0.0), Standing::Neutral);
+    assert_eq!(Standing::from_reputation(25.0), Standing::Friendly);
+    assert_eq!(Standing::from_reputation(75.0), Standing::Allied);
+}
@@ -0,0 +1,77 @@
+use crate::combat::combat::calculate_fleet_power;
+use crate::models::fleet::Fleet;
+use crate::models::fleet_management::{merge_fleets, split_fleet, transfer_ships, validate_fleet_name};
+use crate::models::player::Player;
+use crate::models::position::Position;
+use crate::models::rng::rng_for;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::travel::fleet_speed;
+
+/// A docked fleet of the given ship types.
+fn fleet(number: usize, types: &[ShipType]) -> Fleet {
+    let mut fleet = Fleet::new("Tester".to_string(), Position { x: 5, y: 5, z: 5 }, number);
+    fleet.current_system_id = Some(2);
+    fleet.local_position = Some(Position { x: 1, y: 0, z: 0 });
+    for (index, specialization) in types.iter().enumerate() {
+        let mut ship = Ship::new(specialization.clone(), ShipSize::Small, ShipEngine::Basic);
+        ship.name = format!("Ship {}", index);
+        fleet.ships.push(ship);
+    }
+    fleet
+}
+
+fn names(fleet: &Fleet) -> Vec<&str> {
+    fleet.ships.iter().map(|ship| ship.name.as_str()).collect()
+}
+
+#[test]
+fn test_split_detaches_ships_into_a_new_fleet_in_place() {
+    let mut source = fleet(1, &[ShipType::Fighter, ShipType::Freighter, ShipType::Explorer]);
+    let detached = split_fleet(&mut source, &[2, 0], 4).unwrap();
+
+    assert_eq!(detached.name, "Fleet_Tester_4");
+    assert_eq!(names(&detached), vec!["Ship 0", "Ship 2"]);
+    assert_eq!(names(&source), vec!["Ship 1"]);
+    assert_eq!((detached.current_system_id, detached.local_position), (source.current_system_id, source.local_position));
+    // The new fleet can move and fight on its own
+    assert!(fleet_speed(&detached).is_some());
+    assert!(calculate_fleet_power(&detached) > 0.0);
+
+    assert!(split_fleet(&mut source, &[0], 5).is_err(), "a fleet keeps at least one ship");
+    assert!(split_fleet(&mut source, &[3], 5).is_err());
+    assert!(split_fleet(&mut source, &[], 5).is_err());
+}
+
+#[test]
+fn test_merge_and_transfer_need_co_located_fleets_of_one_owner() {
+    let mut first = fleet(1, &[ShipType::Fighter, ShipType::Battleship]);
+    let mut second = fleet(2, &[ShipType::Freighter]);
+
+    transfer_ships(&mut first, &mut second, &[1]).unwrap();
+    assert_eq!(names(&first), vec!["Ship 0"]);
+    assert_eq!(second.ships.len(), 2);
+
+    let mut elsewhere = fleet(3, &[ShipType::Shuttle]);
+    elsewhere.local_position = Some(Position { x: 9, y: 0, z: 0 });
+    assert!(merge_fleets(&mut first, elsewhere.clone()).is_err());
+    let mut rival = fleet(4, &[ShipType::Shuttle]);
+    rival.owner_id = "Rival".to_string();
+    assert!(transfer_ships(&mut rival, &mut first, &[0]).is_err());
+
+    merge_fleets(&mut first, second).unwrap();
+    assert_eq!(first.ships.len(), 3);
+    assert!(merge_fleets(&mut first.clone(), first.clone()).is_err());
+}
+
+#[test]
+fn test_players_track_their_fleets_and_names_are_checked() {
+    let mut player = Player::new("Tester", 100.0, &mut rng_for(1, "player"));
+    player.add_fleet("Fleet_Tester_2");
+    player.add_fleet("Fleet_Tester_2");
+    player.remove_fleet("Fleet_Tester_1");
+    assert_eq!(player.fleets, vec!["Fleet_Tester_2"]);
+
+    assert_eq!(validate_fleet_name("  Vanguard ").unwrap(), "Vanguard");
+    assert!(validate_fleet_name("   ").is_err());
+    assert!(validate_fleet_name(&"x".repeat(40)).is_err());
+}
@@ -1,4 +1,6 @@
+use crate::models::encounter::{EncounterKind, EncounterLog, create_encounter};
 use crate::models::fleet::{Fleet, MoveFleetData, MoveFleetResponse, save_fleet};
+use crate::models::player::Player;
 use crate::models::position::Position;
 use crate::models::settings::GameSettings;
 use crate::models::star_system::{StarSystem, generate_star_system_default, generate_star_system};
@@ -7,40 +9,41 @@ use crate::models::ship::weapon::Weapon;
 use crate::models::ship::shield::Shield;
 use crate::models::ship::armor::Armor;
 use crate::models::resource::{ResourceType, Resource};
+use crate::models::travel::advance_transit;
 use crate::routes::move_fleet;
 use rocket::serde::json::Json;
 use std::sync::Once;
 use std::fs;
-use crate::models::game_state::{get_game_state, save_game_state, game_data_path, clear_caches};
-use crate::GLOBAL_GAME_WORLD;
+use crate::models::context::{CurrentGame, GameContext};
+use crate::models::auth::{PlayerSession, Role, Session};
+use chrono::{Duration, Utc};
+use crate::models::game_state::{game_data_path, save_player};
+use crate::models::game_world::save_game_world;
+use std::sync::{Arc, OnceLock};
 use serde_json;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 static INIT: Once = Once::new();
+static CONTEXT: OnceLock<Arc<GameContext>> = OnceLock::new();
 
 // Counter for generating unique fleet IDs in tests
 static TEST_FLEET_COUNTER: AtomicUsize = AtomicUsize::new(1);
 
-fn verify_game_world() {
-    if let Ok(guard) = GLOBAL_GAME_WORLD.lock() {
-        assert!(!guard.is_empty(), "Game world should not be empty");
-        println!("Game world contains {} systems", guard.len());
-        for (i, system) in guard.iter().enumerate() {
-            println!("System {} at position ({}, {}, {})", i, system.position.x, system.position.y, system.position.z);
-        }
-    } else {
-        panic!("Failed to access game world");
+fn verify_game_world(context: &GameContext) {
+    let world = context.world();
+    assert!(!world.is_empty(), "Game world should not be empty");
+    println!("Game world contains {} systems", world.len());
+    for (i, system) in world.iter().enumerate() {
+        println!("System {} at position ({}, {}, {})", i, system.position.x, system.position.y, system.position.z);
     }
 }
 
-// Setup runs once, initializes shared environment *without* fleets
-fn setup() {
+// Setup runs once, initializes shared environment *without* fleets.
+// Every test then works on the shared test game through its context.
+fn setup() -> CurrentGame {
     INIT.call_once(|| {
         println!("Initializing shared test environment (once)...");
         
-        // Clear all caches first
-        clear_caches();
-        
         // Clean up any existing test data
         let test_dir = game_data_path("test_game", &[]);
         if test_dir.exists() {
@@ -67,8 +70,9 @@ fn setup() {
 
         println!("Creating test star system...");
         let mut existing_names = std::collections::HashSet::new();
-        let system_data = generate_star_system(100, 100, 100, &mut existing_names);
+        let system_data = generate_star_system(100, 100, 100, &mut existing_names, &mut rand::thread_rng());
         let system = StarSystem {
+            id: 0,
             star: system_data.star,
             position: Position { x: 0, y: 0, z: 0 },
             planets: system_data.planets,
@@ -77,25 +81,36 @@ fn setup() {
         let system_path = test_dir.join("star_systems").join("Star_System_0.json");
         fs::write(system_path, serde_json::to_string(&system).unwrap()).expect("Failed to save test system");
 
-        println!("Initializing game state...");
-        let mut state = get_game_state().expect("Failed to get game state");
-        state.current_game_id = Some("test_game".to_string());
-        state.credits = 1000.0;
-        save_game_state(state).expect("Failed to save game state");
-
-        println!("Initializing global game world...");
-        if let Ok(mut guard) = GLOBAL_GAME_WORLD.lock() {
-            *guard = vec![system.clone()];
-            println!("Game world initialized with {} systems", guard.len());
-        } else {
-            panic!("Failed to initialize game world");
-        }
+        println!("Initializing game context...");
+        let context = GameContext::new("test_game");
+        context.set_world(vec![system.clone()]);
+
+        let game = CurrentGame::new(context.clone());
+        let _game = game.enter();
+        // Entering a system settles exploration missions for the fleet's owner, who must exist
+        save_player(&Player::new("test_player", 1000.0, &mut rand::thread_rng())).expect("Failed to save test player");
+        // The spatial index is built from the saved galaxy
+        save_game_world("test_game", std::slice::from_ref(&system)).expect("Failed to save test galaxy");
+        let _ = CONTEXT.set(context);
         
         // Removed fleet creation from the shared setup
         println!("Shared test environment base setup complete.");
     });
     // Verify world after setup (can stay outside call_once)
-    verify_game_world();
+    let context = CONTEXT.get().expect("Test game context not initialized").clone();
+    verify_game_world(&context);
+    CurrentGame::new(context)
+}
+
+/// A logged in session for the owner of a test fleet.
+fn pilot(owner_id: &str) -> PlayerSession {
+    PlayerSession::new(Session {
+        token: format!("test_{}", owner_id),
+        game_id: "test_game".to_string(),
+        player_name: owner_id.to_string(),
+        role: Role::Player,
+        expires_at: Utc::now() + Duration::hours(1),
+    })
 }
 
 // This function creates the fleet structure but doesn't save it.
@@ -220,29 +235,39 @@ fn create_and_save_unique_fleet() -> Fleet {
     fleet // Return the created and saved fleet
 }
 
+// Lands a fleet at the end of its transit, as the tick would on its arrival tick.
+fn arrive(fleet_name: &str) -> Fleet {
+    let mut fleet = Fleet::load(fleet_name).expect("Failed to load departed fleet");
+    let arrival_tick = fleet.transit.as_ref().expect("Fleet should be in transit").arrival_tick;
+    assert!(advance_transit(&mut fleet, arrival_tick));
+    save_fleet(&fleet).expect("Failed to save arrived fleet");
+    fleet
+}
+
 #[test]
 fn test_valid_move_within_system() {
-    setup(); // Ensure shared env is ready
+    let game = setup(); // Ensure shared env is ready
+    let _game = game.enter();
     let fleet = create_and_save_unique_fleet(); // Create fleet for this test
     let fleet_number = fleet.name.split('_').last().unwrap().parse::<usize>().unwrap();
 
-    let move_data = MoveFleetData { x: 50, y: 50, z: 50 };
-    let response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(move_data));
+    let move_data = MoveFleetData { x: 50, y: 50, z: 50, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(move_data));
     
     println!("Response: {:?}", response);
     assert!(response.success);
     let data = response.into_inner().data.unwrap();
-    assert_eq!(data.status, "success");
-    assert_eq!(data.current_position.x, 50);
-    assert_eq!(data.current_position.y, 50);
-    assert_eq!(data.current_position.z, 50);
+    assert_eq!(data.status, "in_transit");
     assert_eq!(data.current_system_id, Some(0));
+    let transit = data.transit.unwrap();
+    assert_eq!(transit.destination_system_id, Some(0));
+    assert_eq!(transit.destination_local, Some(Position { x: 50, y: 50, z: 50 }));
 
-    let updated_fleet = Fleet::load(&fleet.name).expect("Failed to load updated fleet");
+    let updated_fleet = arrive(&fleet.name);
+    assert_eq!(updated_fleet.current_system_id, Some(0));
+    assert_eq!(updated_fleet.local_position, Some(Position { x: 50, y: 50, z: 50 }));
     for ship in updated_fleet.ships {
-        assert_eq!(ship.position.x, 50);
-        assert_eq!(ship.position.y, 50);
-        assert_eq!(ship.position.z, 50);
+        assert_eq!(ship.position, updated_fleet.position);
         assert_eq!(ship.status, ShipStatus::Stationary);
         assert_eq!(ship.combat_state, CombatState::NotInCombat);
         assert!(ship.shields.current > 0);
@@ -252,107 +277,106 @@ fn test_valid_move_within_system() {
 
 #[test]
 fn test_system_exit() {
-    setup();
+    let game = setup();
+    let _game = game.enter();
     let fleet = create_and_save_unique_fleet();
     let fleet_number = fleet.name.split('_').last().unwrap().parse::<usize>().unwrap();
 
-    let move_data = MoveFleetData { x: 101, y: 0, z: 0 };
-    let response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(move_data));
+    let move_data = MoveFleetData { x: 101, y: 0, z: 0, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(move_data));
     
     println!("Response: {:?}", response);
     assert!(response.success);
     let data = response.into_inner().data.unwrap();
-    assert_eq!(data.status, "transition_exit");
-    assert_eq!(data.current_system_id, None);
-    assert_eq!(data.current_position.x, 101);
-    assert_eq!(data.current_position.y, 0);
-    assert_eq!(data.current_position.z, 0);
+    assert_eq!(data.status, "in_transit");
+    assert_eq!(data.transit.unwrap().destination_system_id, None);
+    // The fleet leaves the system at its edge, in deep space
+    assert_eq!(data.target_position, Position { x: 100, y: 0, z: 0 });
 
-    let updated_fleet = Fleet::load(&fleet.name).expect("Failed to load updated fleet");
+    let updated_fleet = arrive(&fleet.name);
+    assert_eq!(updated_fleet.current_system_id, None);
     for ship in updated_fleet.ships {
-        assert_eq!(ship.position.x, 101);
-        assert_eq!(ship.position.y, 0);
-        assert_eq!(ship.position.z, 0);
+        assert_eq!(ship.position, Position { x: 100, y: 0, z: 0 });
     }
 }
 
 #[test]
 fn test_system_entry() {
-    setup();
+    let game = setup();
+    let _game = game.enter();
     let fleet = create_and_save_unique_fleet();
     let fleet_number = fleet.name.split('_').last().unwrap().parse::<usize>().unwrap();
 
     // First move fleet to deep space
-    let exit_data = MoveFleetData { x: 100, y: 0, z: 0 };
-    let exit_response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(exit_data));
+    let exit_data = MoveFleetData { x: 101, y: 0, z: 0, space: None, system_id: None, planet_id: None };
+    let exit_response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(exit_data));
     println!("Exit response: {:?}", exit_response);
     assert!(exit_response.success);
+    arrive(&fleet.name);
     
     // Now try to enter the system by moving to its coordinates
-    let entry_data = MoveFleetData { x: 0, y: 0, z: 0 };
-    let entry_response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(entry_data));
+    let entry_data = MoveFleetData { x: 0, y: 0, z: 0, space: None, system_id: None, planet_id: None };
+    let entry_response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(entry_data));
     
     println!("Entry response: {:?}", entry_response);
     assert!(entry_response.success);
     let data = entry_response.into_inner().data.unwrap();
+    // The fleet waits at the system's edge, so it enters without travelling
     assert_eq!(data.status, "transition_entry");
     assert_eq!(data.current_system_id, Some(0));
-    
-    // Verify entry point based on the approach from (100, 0, 0)
-    assert_eq!(data.current_position.x, -100); 
-    assert_eq!(data.current_position.y, 0);
-    assert_eq!(data.current_position.z, 0);
+    assert!(data.transit.is_none());
 
     let updated_fleet = Fleet::load(&fleet.name).expect("Failed to load updated fleet");
+    assert_eq!(updated_fleet.current_system_id, Some(0));
     for ship in updated_fleet.ships {
-        assert_eq!(ship.position.x, data.current_position.x);
-        assert_eq!(ship.position.y, data.current_position.y);
-        assert_eq!(ship.position.z, data.current_position.z);
+        assert_eq!(ship.position, data.current_position);
     }
 }
 
 #[test]
 fn test_deep_space_movement() {
-    setup();
+    let game = setup();
+    let _game = game.enter();
     let fleet = create_and_save_unique_fleet();
     let fleet_number = fleet.name.split('_').last().unwrap().parse::<usize>().unwrap();
 
     // First move fleet to deep space 
-    let exit_data = MoveFleetData { x: 100, y: 0, z: 0 };
-    let exit_response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(exit_data));
+    let exit_data = MoveFleetData { x: 101, y: 0, z: 0, space: None, system_id: None, planet_id: None };
+    let exit_response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(exit_data));
     println!("Exit response: {:?}", exit_response);
     assert!(exit_response.success);
+    arrive(&fleet.name);
     
     // Now move in deep space
-    let move_data = MoveFleetData { x: 50, y: 50, z: 50 };
-    let response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(move_data));
+    let move_data = MoveFleetData { x: 50, y: 50, z: 50, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(move_data));
     
     println!("Response: {:?}", response);
     assert!(response.success);
     let data = response.into_inner().data.unwrap();
-    assert_eq!(data.status, "success");
+    assert_eq!(data.status, "in_transit");
     assert_eq!(data.current_system_id, None);
-    assert_eq!(data.current_position.x, 50);
-    assert_eq!(data.current_position.y, 50);
-    assert_eq!(data.current_position.z, 50);
+    let transit = data.transit.unwrap();
+    assert_eq!(transit.status, ShipStatus::Warp);
+    assert_eq!(transit.destination_system_id, None);
 
-    let updated_fleet = Fleet::load(&fleet.name).expect("Failed to load updated fleet");
+    let updated_fleet = arrive(&fleet.name);
+    assert_eq!(updated_fleet.current_system_id, None);
     for ship in updated_fleet.ships {
-        assert_eq!(ship.position.x, 50);
-        assert_eq!(ship.position.y, 50);
-        assert_eq!(ship.position.z, 50);
+        assert_eq!(ship.position, Position { x: 50, y: 50, z: 50 });
     }
 }
 
 #[test]
 fn test_invalid_move_outside_bounds() {
-    setup();
+    let game = setup();
+    let _game = game.enter();
     let fleet = create_and_save_unique_fleet();
     let fleet_number = fleet.name.split('_').last().unwrap().parse::<usize>().unwrap();
 
     // Test moving outside galaxy bounds
-    let move_data = MoveFleetData { x: 101, y: 101, z: 101 };
-    let response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(move_data));
+    let move_data = MoveFleetData { x: 101, y: 101, z: 101, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(move_data));
     
     assert!(!response.success);
     assert!(response.into_inner().message.contains("outside galaxy bounds"));
@@ -360,39 +384,84 @@ fn test_invalid_move_outside_bounds() {
 
 #[test]
 fn test_move_nonexistent_fleet() {
-    setup(); // Ensure shared env is ready
+    let game = setup(); // Ensure shared env is ready
+    let _game = game.enter();
     // Don't create a fleet for this test
 
     // Test moving a fleet that doesn't exist 
-    let move_data = MoveFleetData { x: 0, y: 0, z: 0 };
-    let response = move_fleet("nonexistent_player".to_string(), 999, Json(move_data));
+    let move_data = MoveFleetData { x: 0, y: 0, z: 0, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot("nonexistent_player"), "nonexistent_player".to_string(), 999, Json(move_data));
     
     assert!(!response.success);
     assert!(response.into_inner().message.contains("Fleet 'Fleet_nonexistent_player_999' not found"));
 }
 
+#[test]
+fn test_move_someone_elses_fleet() {
+    let game = setup();
+    let _game = game.enter();
+    let fleet = create_and_save_unique_fleet();
+    let fleet_number = fleet.name.split('_').last().unwrap().parse::<usize>().unwrap();
+
+    let move_data = MoveFleetData { x: 50, y: 50, z: 50, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot("intruder"), fleet.owner_id.clone(), fleet_number, Json(move_data));
+    assert!(!response.success);
+
+    let move_data = MoveFleetData { x: 50, y: 50, z: 50, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), PlayerSession::default(), fleet.owner_id.clone(), fleet_number, Json(move_data));
+    assert!(response.into_inner().message.contains("Not logged in"));
+
+    let unmoved = crate::models::fleet::load_fleet(&fleet.name).unwrap().unwrap();
+    assert_eq!(unmoved.position, fleet.position);
+    assert!(unmoved.transit.is_none());
+}
+
+#[test]
+fn test_move_with_pending_encounters() {
+    let game = setup();
+    let fleet = {
+        // Other tests' moves write the encounter log too
+        let _game = game.enter_for_writes();
+        let fleet = create_and_save_unique_fleet();
+        let (encounter, _) = create_encounter(EncounterKind::Pirate, &fleet, None, 0, |_| false, &mut rand::thread_rng());
+        let mut log = EncounterLog::load().unwrap();
+        log.encounters.push(encounter);
+        log.save().unwrap();
+        fleet
+    };
+    let fleet_number = fleet.name.rsplit('_').next().unwrap().parse::<usize>().unwrap();
+    let _game = game.enter();
+
+    let move_data = MoveFleetData { x: 50, y: 50, z: 50, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(move_data));
+    assert!(!response.success);
+    assert!(response.into_inner().message.contains("encounters pending"));
+
+    let unmoved = crate::models::fleet::load_fleet(&fleet.name).unwrap().unwrap();
+    assert!(unmoved.transit.is_none());
+}
+
 #[test]
 fn test_move_with_cargo() {
-    setup();
+    let game = setup();
+    let _game = game.enter();
     let fleet = create_and_save_unique_fleet();
     let fleet_number = fleet.name.split('_').last().unwrap().parse::<usize>().unwrap();
 
-    let move_data = MoveFleetData { x: 50, y: 50, z: 50 };
-    let response = move_fleet(fleet.owner_id.clone(), fleet_number, Json(move_data));
+    let move_data = MoveFleetData { x: 50, y: 50, z: 50, space: None, system_id: None, planet_id: None };
+    let response = move_fleet(game.clone(), pilot(&fleet.owner_id), fleet.owner_id.clone(), fleet_number, Json(move_data));
     
     println!("Response: {:?}", response);
     assert!(response.success);
     let data = response.into_inner().data.unwrap();
-    assert_eq!(data.status, "success");
+    assert_eq!(data.status, "in_transit");
     
-    let updated_fleet = Fleet::load(&fleet.name).expect("Failed to load updated fleet");
+    let updated_fleet = arrive(&fleet.name);
+    assert_eq!(updated_fleet.local_position, Some(Position { x: 50, y: 50, z: 50 }));
     for ship in updated_fleet.ships {
-        assert_eq!(ship.position.x, 50);
-        assert_eq!(ship.position.y, 50);
-        assert_eq!(ship.position.z, 50);
         assert!(!ship.cargo.is_empty());
         for resource in ship.cargo {
             assert!(resource.quantity.unwrap_or(0) > 0);
         }
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,137 @@
+use crate::models::economy::Economy;
+use crate::models::market::Market;
+use crate::models::planet::PlanetSpecialization;
+use crate::models::resource::ResourceType;
+use crate::models::rng::rng_for;
+
+fn market(specialization: PlanetSpecialization) -> Market {
+    Market::new(&specialization, &Economy::Stable, &mut rng_for(1, "market"))
+}
+
+fn unit_prices(market: &Market, resource_type: ResourceType) -> (f64, f64, u32) {
+    let resource = market.resources.iter().find(|r| r.resource_type == resource_type).unwrap();
+    (resource.buy.unwrap(), resource.sell.unwrap(), resource.quantity.unwrap())
+}
+
+#[test]
+fn test_buying_drains_stock_and_raises_price() {
+    let mut market = market(PlanetSpecialization::Agriculture);
+    let (buy_before, sell_before, stock_before) = unit_prices(&market, ResourceType::Food);
+
+    market.buy_resource(ResourceType::Food, 20, 0, 0).unwrap();
+    let (buy_after, sell_after, stock_after) = unit_prices(&market, ResourceType::Food);
+
+    assert_eq!(stock_after, stock_before - 20);
+    assert!(buy_after > buy_before);
+    assert!(sell_after > sell_before);
+}
+
+#[test]
+fn test_selling_floods_market_and_lowers_price() {
+    let mut market = market(PlanetSpecialization::Mining);
+    let (buy_before, _, stock_before) = unit_prices(&market, ResourceType::Metals);
+
+    market.sell_resource(ResourceType::Metals, 100, 0, 0).unwrap();
+    let (buy_after, _, stock_after) = unit_prices(&market, ResourceType::Metals);
+
+    assert_eq!(stock_after, stock_before + 100);
+    assert!(buy_after < buy_before);
+}
+
+#[test]
+fn test_large_orders_pay_slippage() {
+    let market = market(PlanetSpecialization::Agriculture);
+    let (buy_unit, sell_unit, stock) = unit_prices(&market, ResourceType::Water);
+    let quantity = stock / 2;
+
+    let buy_total = market.quote_buy(ResourceType::Water, quantity).unwrap();
+    let sell_total = market.quote_sell(ResourceType::Water, quantity).unwrap();
+
+    assert!(buy_total > buy_unit * quantity as f64);
+    assert!(sell_total < sell_unit * quantity as f64);
+    assert!(market.quote_buy(ResourceType::Water, stock + 1).is_err());
+}
+
+#[test]
+fn test_quoting_does_not_change_market() {
+    let market = market(PlanetSpecialization::Technology);
+    let before = serde_json::to_string(&market).unwrap();
+    let quote = market.quote(ResourceType::Electronics, 10);
+
+    assert!(quote.buy_total.is_some() && quote.sell_total.is_some());
+    assert_eq!(serde_json::to_string(&market).unwrap(), before);
+}
+
+#[test]
+fn test_producers_sell_cheaper_than_consumers() {
+    let producer = market(PlanetSpecialization::Technology);
+    let consumer = market(PlanetSpecialization::Research);
+    let producer_profile = producer.profiles.iter().find(|p| p.resource_type == ResourceType::Electronics).unwrap();
+    let consumer_profile = consumer.profiles.iter().find(|p| p.resource_type == ResourceType::Electronics).unwrap();
+
+    assert!(producer_profile.production > 0);
+    assert!(consumer_profile.production < 0);
+    assert!(producer_profile.target_stock > consumer_profile.target_stock);
+}
+
+#[test]
+fn test_stock_reverts_toward_target_over_time() {
+    let mut market = market(PlanetSpecialization::Agriculture);
+    let target = market.profiles.iter().find(|p| p.resource_type == ResourceType::Food).unwrap().target_stock;
+    let (_, _, stock) = unit_prices(&market, ResourceType::Food);
+    market.buy_resource(ResourceType::Food, stock, 0, 0).unwrap();
+
+    market.advance(1);
+    let (_, _, after_one) = unit_prices(&market, ResourceType::Food);
+    assert!(after_one > 0 && after_one < target);
+
+    market.advance(500);
+    let (_, _, settled) = unit_prices(&market, ResourceType::Food);
+    assert_eq!(settled, target);
+}
+
+#[test]
+fn test_bulk_orders_cost_the_sum_of_single_units() {
+    let market = market(PlanetSpecialization::Research);
+    let (_, _, stock) = unit_prices(&market, ResourceType::Narcotics);
+    let quantity = stock.min(59);
+
+    let mut buying = market.clone();
+    let bought: f64 = (0..quantity).map(|_| buying.buy_resource(ResourceType::Narcotics, 1, 0, 0).unwrap()).sum();
+    assert!((market.quote_buy(ResourceType::Narcotics, quantity).unwrap() - bought).abs() < 1e-6);
+
+    let mut selling = market.clone();
+    let sold: f64 = (0..quantity).map(|_| selling.sell_resource(ResourceType::Narcotics, 1, 0, 0).unwrap()).sum();
+    assert!((market.quote_sell(ResourceType::Narcotics, quantity).unwrap() - sold).abs() < 1e-6);
+}
+
+#[test]
+fn test_round_trips_never_profit() {
+    for specialization in [PlanetSpecialization::Agriculture, PlanetSpecialization::Mining, PlanetSpecialization::Research] {
+        let start = market(specialization);
+        for resource in start.resources.clone() {
+            let (Some(_), Some(_), Some(stock)) = (resource.buy, resource.sell, resource.quantity) else {
+                continue;
+            };
+            for quantity in [1, stock / 2, stock] {
+                // Buy unit by unit, then dump everything at once
+                let mut market = start.clone();
+                let mut cost = 0.0;
+                for _ in 0..quantity {
+                    cost += market.buy_resource(resource.resource_type, 1, 0, 0).unwrap();
+                }
+                let proceeds = market.sell_resource(resource.resource_type, quantity, 0, 0).unwrap();
+                assert!(proceeds <= cost, "{:?}: bought {} for {}, sold for {}", resource.resource_type, quantity, cost, proceeds);
+
+                // Sell a lot into the market, then buy it back unit by unit
+                let mut market = start.clone();
+                let proceeds = market.sell_resource(resource.resource_type, quantity, 0, 0).unwrap();
+                let mut cost = 0.0;
+                for _ in 0..quantity {
+                    cost += market.buy_resource(resource.resource_type, 1, 0, 0).unwrap();
+                }
+                assert!(proceeds <= cost, "{:?}: sold {} for {}, bought back for {}", resource.resource_type, quantity, proceeds, cost);
+            }
+        }
+    }
+}
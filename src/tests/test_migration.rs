@@ -0,0 +1,84 @@
+use crate::models::game_state::game_data_path;
+use crate::models::migration::{CURRENT_SCHEMA_VERSION, SaveKind, migrate_game};
+use crate::models::settings::load_settings_for_game;
+use serde_json::{Value, json};
+use std::fs;
+
+fn write(game_id: &str, path: &[&str], value: Value) {
+    let path = game_data_path(game_id, path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, value.to_string()).unwrap();
+}
+
+fn read(game_id: &str, path: &[&str]) -> Value {
+    serde_json::from_str(&fs::read_to_string(game_data_path(game_id, path)).unwrap()).unwrap()
+}
+
+/// A game as it was saved before save files were versioned.
+fn legacy_game(game_id: &str) {
+    let _ = fs::remove_dir_all(game_data_path(game_id, &[]));
+    write(game_id, &["settings.json"], json!({
+        "game_id": game_id, "player_name": "Veteran", "map_width": 100, "map_height": 100,
+        "map_length": 100, "star_count": 1, "starting_credits": 1000.0,
+        "created_at": "2024-01-01T00:00:00Z", "last_played": "2024-01-01T00:00:00Z", "factions": []
+    }));
+    write(game_id, &["players", "Veteran.json"], json!({
+        "name": "Veteran", "resources": [], "credits": 500.0, "fleets": ["Fleet_Veteran_1"]
+    }));
+    write(game_id, &["fleets", "Fleet_Veteran_1.json"], json!({
+        "name": "Fleet_Veteran_1", "owner_id": "Veteran", "ships": [], "position": { "x": 1, "y": 2, "z": 3 }
+    }));
+    write(game_id, &["star_systems", "system_3.json"], json!({ "planets": [] }));
+}
+
+#[test]
+fn test_save_kinds_follow_the_directory_layout() {
+    assert_eq!(SaveKind::of("settings.json"), Some(SaveKind::Settings));
+    assert_eq!(SaveKind::of("fleets/Fleet_A_1.json"), Some(SaveKind::Fleet));
+    assert_eq!(SaveKind::of("star_systems/Star_System_2.json"), Some(SaveKind::StarSystem));
+    assert_eq!(SaveKind::of("markets/market_0_0.json"), None);
+}
+
+#[test]
+fn test_legacy_game_is_migrated_step_by_step() {
+    let game_id = "test_migration_legacy";
+    legacy_game(game_id);
+
+    // A dry run reports every change without touching the files
+    let preview = migrate_game(game_id, true).unwrap();
+    assert_eq!((preview.from_version, preview.to_version), (0, CURRENT_SCHEMA_VERSION));
+    assert_eq!(preview.steps.len(), CURRENT_SCHEMA_VERSION as usize);
+    assert!(preview.steps[0].changes.contains(&"star_systems/system_3.json: added id".to_string()));
+    assert!(preview.steps[1].changes.contains(&"players/Veteran.json: added reputation".to_string()));
+    assert_eq!(preview.files_changed, vec![
+        "fleets/Fleet_Veteran_1.json", "players/Veteran.json", "settings.json", "star_systems/system_3.json",
+    ]);
+    assert!(read(game_id, &["settings.json"]).get("schema_version").is_none());
+
+    let report = migrate_game(game_id, false).unwrap();
+    assert!(!report.dry_run);
+    assert_eq!(report.files_changed, preview.files_changed);
+    assert_eq!(read(game_id, &["star_systems", "system_3.json"])["id"], json!(3));
+    assert_eq!(read(game_id, &["fleets", "Fleet_Veteran_1.json"])["transit"], Value::Null);
+
+    // The seed is fixed once instead of rolled on every load
+    let settings = load_settings_for_game(game_id).unwrap();
+    assert_eq!(settings.schema_version, CURRENT_SCHEMA_VERSION);
+    assert_eq!(load_settings_for_game(game_id).unwrap().seed, settings.seed);
+
+    assert!(migrate_game(game_id, false).unwrap().is_up_to_date());
+    let _ = fs::remove_dir_all(game_data_path(game_id, &[]));
+}
+
+#[test]
+fn test_future_versions_are_refused() {
+    let game_id = "test_migration_future";
+    legacy_game(game_id);
+    let mut settings = read(game_id, &["settings.json"]);
+    settings["schema_version"] = json!(CURRENT_SCHEMA_VERSION + 1);
+    write(game_id, &["settings.json"], settings);
+
+    let error = migrate_game(game_id, true).unwrap_err();
+    assert!(error.contains(&format!("format version {}", CURRENT_SCHEMA_VERSION + 1)));
+    let _ = fs::remove_dir_all(game_data_path(game_id, &[]));
+}
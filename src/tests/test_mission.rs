@@ -0,0 +1,163 @@
+use crate::models::faction::Faction;
+use crate::models::mission::{Mission, MissionBoard, MissionKind, MissionStatus, generate_mission, MAX_ACTIVE_MISSIONS, MISSIONS_PER_PLANET, MISSION_REPUTATION_PENALTY, MISSION_REPUTATION_REWARD};
+use crate::models::player::Player;
+use crate::models::resource::ResourceType;
+use crate::models::rng::rng_for;
+use crate::models::star_system::generate_star_system;
+use std::collections::HashSet;
+
+fn mission(id: &str, kind: MissionKind) -> Mission {
+    Mission {
+        id: id.to_string(),
+        title: format!("Mission {}", id),
+        description: String::new(),
+        kind,
+        origin_system_id: 0,
+        origin_planet_id: 0,
+        reward_credits: 500.0,
+        faction: Some("Federation".to_string()),
+        posted_tick: 0,
+        deadline_tick: 50,
+        status: MissionStatus::Available,
+        accepted_by: None,
+    }
+}
+
+fn transport(id: &str, quantity: u32) -> Mission {
+    mission(id, MissionKind::Transport {
+        resource_type: ResourceType::Food,
+        quantity,
+        destination_system_id: 2,
+        destination_planet_id: 1,
+        delivered: 0,
+    })
+}
+
+fn player() -> Player {
+    Player::new("Tester", 1000.0, &mut rng_for(1, "player"))
+}
+
+fn factions() -> Vec<Faction> {
+    vec![Faction::new("Federation".to_string(), "The Federation".to_string())]
+}
+
+#[test]
+fn test_generated_missions_target_other_systems() {
+    let mut rng = rng_for(3, "galaxy");
+    let mut names = HashSet::new();
+    let systems: Vec<_> = (0..5).map(|id| {
+        let mut system = generate_star_system(1000, 1000, 1000, &mut names, &mut rng);
+        system.id = id;
+        system
+    }).collect();
+    let origin = systems.iter().find(|s| !s.planets.is_empty()).unwrap().id;
+    let pirates = vec!["Fleet_Pirate_1_1".to_string()];
+
+    let mut rng = rng_for(3, "missions");
+    for _ in 0..20 {
+        let Some(mission) = generate_mission(origin, 0, &systems, &[], &pirates, 10, &mut rng) else {
+            continue;
+        };
+        assert_eq!(mission.status, MissionStatus::Available);
+        assert!(mission.deadline_tick > 10);
+        assert!(mission.reward_credits > 0.0);
+        match mission.kind {
+            MissionKind::Transport { destination_system_id, .. } => assert_ne!(destination_system_id, origin),
+            MissionKind::Exploration { system_id } => assert_ne!(system_id, origin),
+            MissionKind::Bounty { target_fleet } => assert_eq!(target_fleet, pirates[0]),
+        }
+    }
+}
+
+#[test]
+fn test_deliveries_accumulate_until_transport_completes() {
+    let mut board = MissionBoard { missions: vec![transport("a", 30)] };
+    let mut player = player();
+    let factions = factions();
+    board.accept("a", "Tester", 0).unwrap();
+
+    assert!(board.record_delivery(&mut player, &factions, 2, 1, ResourceType::Food, 20).is_empty());
+    assert!(board.record_delivery(&mut player, &factions, 2, 0, ResourceType::Food, 20).is_empty());
+    assert!(board.record_delivery(&mut player, &factions, 2, 1, ResourceType::Water, 20).is_empty());
+
+    let completed = board.record_delivery(&mut player, &factions, 2, 1, ResourceType::Food, 15);
+    assert_eq!(completed.len(), 1);
+    assert_eq!(board.missions[0].status, MissionStatus::Completed);
+    assert_eq!(player.credits, 1500.0);
+    assert_eq!(player.reputation_with("Federation"), MISSION_REPUTATION_REWARD);
+}
+
+#[test]
+fn test_bounty_completes_when_target_destroyed() {
+    let mut board = MissionBoard { missions: vec![mission("b", MissionKind::Bounty { target_fleet: "Fleet_Pirate_1_1".to_string() })] };
+    let mut player = player();
+    board.accept("b", "Tester", 0).unwrap();
+
+    assert!(board.record_fleet_destroyed(&mut player, &factions(), "Fleet_Pirate_2_1").is_empty());
+    assert_eq!(board.record_fleet_destroyed(&mut player, &factions(), "Fleet_Pirate_1_1").len(), 1);
+    assert_eq!(player.credits, 1500.0);
+}
+
+#[test]
+fn test_accept_rules_and_active_mission_limit() {
+    let mut board = MissionBoard { missions: (0..=MAX_ACTIVE_MISSIONS).map(|i| transport(&i.to_string(), 10)).collect() };
+
+    assert!(board.accept("0", "Tester", 50).is_err(), "expired missions cannot be accepted");
+    for i in 0..MAX_ACTIVE_MISSIONS {
+        board.accept(&i.to_string(), "Tester", 0).unwrap();
+    }
+    assert!(board.accept("0", "Other", 0).is_err(), "accepted missions are no longer available");
+    assert!(board.accept(&MAX_ACTIVE_MISSIONS.to_string(), "Tester", 0).is_err());
+}
+
+#[test]
+fn test_deadlines_fail_active_missions_and_withdraw_postings() {
+    let mut board = MissionBoard { missions: vec![transport("active", 10), transport("posted", 10)] };
+    board.accept("active", "Tester", 0).unwrap();
+
+    assert!(board.expire(49).is_empty());
+    let failed = board.expire(50);
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].id, "active");
+    assert_eq!(board.missions.len(), 1);
+    assert_eq!(board.missions[0].status, MissionStatus::Failed);
+}
+
+#[test]
+fn test_abandoning_costs_reputation() {
+    let mut board = MissionBoard { missions: vec![transport("a", 10)] };
+    let mut player = player();
+    board.accept("a", "Tester", 0).unwrap();
+
+    board.abandon("a", &mut player, &factions()).unwrap();
+    assert_eq!(board.missions[0].status, MissionStatus::Abandoned);
+    assert_eq!(player.reputation_with("Federation"), MISSION_REPUTATION_PENALTY);
+    assert_eq!(player.credits, 1000.0);
+    assert!(board.abandon("a", &mut player, &factions()).is_err());
+}
+
+#[test]
+fn test_restocking_fills_every_planets_board() {
+    let mut rng = rng_for(3, "galaxy");
+    let mut names = HashSet::new();
+    let systems: Vec<_> = (0..4).map(|id| {
+        let mut system = generate_star_system(1000, 1000, 1000, &mut names, &mut rng);
+        system.id = id;
+        system
+    }).collect();
+    let pirates = vec!["Fleet_Pirate_1_1".to_string()];
+    let mut board = MissionBoard::default();
+
+    let posted = board.restock(&systems, &[], &pirates, 0, &mut rng_for(3, "missions"));
+    assert!(posted > 0);
+    for system in &systems {
+        for planet_id in 0..system.planets.len() {
+            assert_eq!(board.available_at(system.id, planet_id).len(), MISSIONS_PER_PLANET);
+        }
+    }
+
+    // Full boards are left alone until missions are taken or withdrawn
+    assert_eq!(board.restock(&systems, &[], &pirates, 1, &mut rng_for(3, "missions")), 0);
+    board.missions.remove(0);
+    assert_eq!(board.restock(&systems, &[], &pirates, 2, &mut rng_for(3, "missions")), 1);
+}
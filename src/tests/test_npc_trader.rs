@@ -0,0 +1,187 @@
+use crate::models::auth::{PlayerSession, Role, Session};
+use crate::models::cargo::{CargoDistribution, FillPolicy};
+use crate::models::context::{CurrentGame, GameRegistry};
+use crate::models::fleet::Fleet;
+use crate::models::market::{Market, ResourceProfile};
+use crate::models::npc_trader::{TraderProfile, load_cargo, plan_trade_route, trade_with_npc_trader, trading_style};
+use crate::models::player::Player;
+use crate::models::position::Position;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::rng::rng_for;
+use crate::models::settings::GameSettings;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::star_system::StarSystem;
+use crate::models::storage::{StorageBackend, init_storage, storage_for};
+use crate::models::trade::trade_with_fleet;
+use crate::models::trader::TraderPersonality;
+use crate::routes::trade_with_trader;
+use crate::simulation::tick::{FleetTransit, NpcTraders, TickContext, TickSystem, run_ticks};
+use crate::tests::fixtures::{fleet_of, game_dir, systems_at};
+use chrono::{Duration, Utc};
+use std::collections::BTreeMap;
+
+fn market(base_buy: f64, base_sell: f64) -> Market {
+    let mut market = Market {
+        resources: vec![Resource { resource_type: ResourceType::Metals, buy: None, sell: None, quantity: Some(200) }],
+        profiles: vec![ResourceProfile {
+            resource_type: ResourceType::Metals,
+            base_buy: Some(base_buy),
+            base_sell: Some(base_sell),
+            target_stock: 200,
+            production: 0,
+        }],
+    };
+    market.refresh_prices();
+    market
+}
+
+/// Two single-planet systems 100 units apart.
+fn systems() -> Vec<StarSystem> {
+    systems_at(3, &[(0, 0), (100, 0)])
+}
+
+/// Metals are cheap at the origin and wanted at the destination.
+fn markets(destination_sell: f64) -> BTreeMap<(usize, usize), Market> {
+    BTreeMap::from([((0, 0), market(10.0, 8.0)), ((1, 0), market(40.0, destination_sell))])
+}
+
+fn trader_fleet(personality: TraderPersonality) -> Fleet {
+    let mut fleet = Fleet::new("Merchant_1".to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    let mut ship = Ship::new(ShipType::Freighter, ShipSize::Small, ShipEngine::Basic);
+    ship.cargo = vec![Resource { resource_type: ResourceType::Fuel, quantity: Some(50), buy: None, sell: None }];
+    fleet.ships.push(ship);
+    fleet.current_system_id = Some(0);
+    fleet.trader = Some(TraderProfile::new(personality));
+    fleet
+}
+
+fn stock(ctx: &TickContext, key: (usize, usize)) -> u32 {
+    ctx.markets[&key].resources[0].quantity.unwrap()
+}
+
+#[test]
+fn test_trader_plans_route_to_market_paying_more() {
+    let systems = systems();
+    let fleet = trader_fleet(TraderPersonality::Neutral);
+    let profile = fleet.trader.clone().unwrap();
+
+    let route = plan_trade_route(&fleet, &profile, &systems[0], &[&systems[1]], &markets(30.0)).unwrap();
+    assert_eq!(route.resource_type, ResourceType::Metals);
+    assert_eq!((route.buy_system_id, route.sell_system_id), (0, 1));
+    assert!(route.expected_revenue > route.purchase_cost * (1.0 + trading_style(&profile.personality).min_margin));
+
+    // Nobody plans a run to a market that pays less than the purchase price
+    assert!(plan_trade_route(&fleet, &profile, &systems[0], &[&systems[1]], &markets(5.0)).is_none());
+}
+
+#[test]
+fn test_aggressive_traders_stake_more_and_ask_more() {
+    let systems = systems();
+    let mut cautious = trader_fleet(TraderPersonality::Friendly);
+    let mut bold = trader_fleet(TraderPersonality::Aggressive);
+    for fleet in [&mut cautious, &mut bold] {
+        fleet.trader.as_mut().unwrap().credits = 600.0;
+    }
+
+    let plan = |fleet: &Fleet| plan_trade_route(fleet, fleet.trader.as_ref().unwrap(), &systems[0], &[&systems[1]], &markets(30.0)).unwrap();
+    let (cautious_route, bold_route) = (plan(&cautious), plan(&bold));
+    assert!(cautious_route.purchase_cost <= 300.0);
+    assert!(bold_route.quantity > cautious_route.quantity);
+
+    let friendly = trading_style(&TraderPersonality::Friendly);
+    let aggressive = trading_style(&TraderPersonality::Aggressive);
+    assert!(aggressive.player_markup > friendly.player_markup);
+    assert!(aggressive.scan_systems > friendly.scan_systems);
+}
+
+#[test]
+fn test_npc_trader_buys_travels_and_sells_through_markets() {
+    let mut ctx = TickContext::new("test_npc_trader", 0, rng_for(9, "tick"));
+    ctx.systems = systems();
+    ctx.markets = markets(30.0);
+    ctx.fleets = vec![trader_fleet(TraderPersonality::Neutral)];
+    let origin_stock = stock(&ctx, (0, 0));
+    let destination_stock = stock(&ctx, (1, 0));
+
+    ctx.tick = 1;
+    NpcTraders.tick(&mut ctx).unwrap();
+    let trader = &ctx.fleets[0];
+    let route = trader.trader.as_ref().unwrap().route.clone().unwrap();
+    assert!(trader.transit.is_some());
+    assert_eq!(trader.cargo_quantity(ResourceType::Metals), route.quantity);
+    assert_eq!(stock(&ctx, (0, 0)), origin_stock - route.quantity);
+
+    run_ticks(&mut ctx, &[Box::new(FleetTransit), Box::new(NpcTraders)], 2).unwrap();
+    let trader = &ctx.fleets[0];
+    let profile = trader.trader.as_ref().unwrap();
+    assert_eq!(trader.current_system_id, Some(1));
+    assert!(profile.route.is_none());
+    assert_eq!(trader.cargo_quantity(ResourceType::Metals), 0);
+    assert_eq!(stock(&ctx, (1, 0)), destination_stock + route.quantity);
+    assert!(profile.total_profit > 0.0);
+}
+
+#[test]
+fn test_players_trade_at_the_traders_prices() {
+    let mut trader = trader_fleet(TraderPersonality::Aggressive);
+    load_cargo(&mut trader, ResourceType::Metals, 40, Some(13.0), Some(7.0));
+    let mut player_fleet = Fleet::new("Tester".to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    player_fleet.ships.push(Ship::new(ShipType::Freighter, ShipSize::Small, ShipEngine::Basic));
+    let mut player = Player::new("Tester", 1000.0, &mut rng_for(1, "player"));
+
+    let paid = trade_with_npc_trader(&mut player_fleet, &mut trader, ResourceType::Metals, 10, "buy", &mut player).unwrap();
+    assert_eq!(paid, -130.0);
+    assert_eq!(player.credits, 870.0);
+    assert_eq!(trader.trader.as_ref().unwrap().credits, 5130.0);
+    assert_eq!(trader.cargo_quantity(ResourceType::Metals), 30);
+
+    let received = trade_with_npc_trader(&mut player_fleet, &mut trader, ResourceType::Metals, 5, "sell", &mut player).unwrap();
+    assert_eq!(received, 35.0);
+    assert_eq!(player_fleet.cargo_quantity(ResourceType::Metals), 5);
+
+    // Fuel is kept for the trader's own use
+    assert!(trade_with_npc_trader(&mut player_fleet, &mut trader, ResourceType::Fuel, 1, "buy", &mut player).is_err());
+}
+
+#[test]
+fn test_only_npc_traders_are_traded_with_by_name() {
+    let game_id = "test_npc_trader_by_name";
+    game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    let storage = storage_for(game_id).unwrap();
+    storage.save_settings(&GameSettings { game_id: game_id.to_string(), ..GameSettings::default() }).unwrap();
+    for owner in ["Buyer", "Rival"] {
+        storage.save_player(&Player::new(owner, 1000.0, &mut rng_for(1, "player"))).unwrap();
+        let mut fleet = fleet_of(owner, vec![Ship::new(ShipType::Freighter, ShipSize::Small, ShipEngine::Basic)]);
+        fleet.name = format!("Fleet_{}_1", owner);
+        fleet.current_system_id = Some(0);
+        storage.save_fleet(&fleet).unwrap();
+    }
+    // Cargo a player stowed carries no price
+    let mut rival = storage.load_fleet("Fleet_Rival_1").unwrap().unwrap();
+    rival.load_cargo(ResourceType::Metals, 50, &CargoDistribution::First, FillPolicy::AllOrNothing).unwrap();
+    storage.save_fleet(&rival).unwrap();
+
+    let game = CurrentGame::new(GameRegistry::default().get(game_id).unwrap());
+    let buyer = PlayerSession::new(Session {
+        token: "test_buyer".to_string(),
+        game_id: game_id.to_string(),
+        player_name: "Buyer".to_string(),
+        role: Role::Player,
+        expires_at: Utc::now() + Duration::hours(1),
+    });
+    for trader in ["Fleet_Rival_1", "Fleet_Buyer_1"] {
+        let message = trade_with_trader(game.clone(), buyer.clone(), "Buyer".to_string(), 1, ResourceType::Metals, 50, "buy".to_string(), Some(trader.to_string()));
+        assert!(message.contains("not found"), "{}", message.into_inner());
+    }
+    assert_eq!(storage.load_fleet("Fleet_Rival_1").unwrap().unwrap().cargo_quantity(ResourceType::Metals), 50);
+    assert_eq!(storage.load_player("Buyer").unwrap().unwrap().credits, 1000.0);
+
+    // Even an NPC trader does not part with stock it has set no price on
+    let mut player = Player::new("Buyer", 1000.0, &mut rng_for(1, "player"));
+    let mut hold = storage.load_fleet("Fleet_Buyer_1").unwrap().unwrap();
+    rival.owner_id = "Merchant_9".to_string();
+    let error = trade_with_fleet(&mut hold, &mut rival, ResourceType::Metals, 10, "buy", &mut player).unwrap_err();
+    assert!(error.contains("does not sell"), "{}", error);
+    assert_eq!(player.credits, 1000.0);
+}
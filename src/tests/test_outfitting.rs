@@ -0,0 +1,96 @@
+use crate::combat::combat::calculate_combat_power;
+use crate::models::economy::Economy;
+use crate::models::market::calculate_ship_price;
+use crate::models::outfitting::{EquipmentMarket, EquipmentOffer, install, install_from_market, uninstall, uninstall_to_market};
+use crate::models::planet::PlanetSpecialization;
+use crate::models::rng::rng_for;
+use crate::models::ship::equipment::{Equipment, Slot, hardpoints};
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::ship::weapon::Weapon;
+use crate::tests::fixtures::planet;
+
+fn market(equipment: Equipment, price: f64) -> EquipmentMarket {
+    EquipmentMarket { offers: vec![EquipmentOffer { equipment, price, stock: 1 }] }
+}
+
+fn beam() -> Equipment {
+    Equipment::Weapon(Weapon::NeutronBeam { damage: 30 })
+}
+
+#[test]
+fn test_slot_limits_follow_ship_type_and_size() {
+    assert!(hardpoints(&ShipType::Battleship, &ShipSize::Small) > hardpoints(&ShipType::Freighter, &ShipSize::Small));
+    assert!(hardpoints(&ShipType::Fighter, &ShipSize::Large) > hardpoints(&ShipType::Fighter, &ShipSize::Small));
+
+    let mut ship = Ship::new(ShipType::Freighter, ShipSize::Small, ShipEngine::Basic);
+    ship.weapons.clear();
+    for tick in 0..2 {
+        install(&mut ship, beam(), tick * 10).unwrap();
+    }
+    assert!(install(&mut ship, beam(), 100).unwrap_err().contains("no free hardpoint"));
+
+    ship.modules.clear();
+    install(&mut ship, Equipment::ArmorPlating { capacity: 50 }, 100).unwrap();
+    install(&mut ship, Equipment::ArmorPlating { capacity: 50 }, 200).unwrap();
+    assert!(install(&mut ship, Equipment::ShieldGenerator { capacity: 50 }, 300).unwrap_err().contains("no free utility slot"));
+}
+
+#[test]
+fn test_modules_change_defenses_and_value() {
+    let mut ship = Ship::new(ShipType::Battleship, ShipSize::Medium, ShipEngine::Basic);
+    let (shields, armor) = (ship.shields.capacity, ship.armor.capacity);
+    let (power, price) = (calculate_combat_power(&ship), calculate_ship_price(&ship));
+
+    install(&mut ship, Equipment::ShieldGenerator { capacity: 100 }, 0).unwrap();
+    assert!(install(&mut ship, Equipment::ArmorPlating { capacity: 100 }, 1).unwrap_err().contains("in the yard"));
+    install(&mut ship, Equipment::ArmorPlating { capacity: 100 }, 2).unwrap();
+    install(&mut ship, beam(), 4).unwrap();
+    assert_eq!(ship.shields.capacity, shields + 100);
+    assert_eq!(ship.armor.capacity, armor + 100);
+    assert!(calculate_combat_power(&ship) > power);
+    assert!(calculate_ship_price(&ship) > price);
+
+    let removed = uninstall(&mut ship, Slot::Utility, 0, 5).unwrap();
+    assert!(matches!(removed, Equipment::ShieldGenerator { capacity: 100 }));
+    assert_eq!(ship.shields.capacity, shields);
+    assert!(ship.shields.current <= ship.shields.capacity);
+    assert!(uninstall(&mut ship, Slot::Engine, 0, 10).is_err());
+}
+
+#[test]
+fn test_outfitting_charges_credits_and_restocks_the_yard() {
+    let yard = planet(PlanetSpecialization::Technology, Economy::Stable);
+    let mut ship = Ship::new(ShipType::Battleship, ShipSize::Medium, ShipEngine::Basic);
+    let mut equipment_market = market(beam(), 3000.0);
+
+    assert!(install_from_market(&mut equipment_market, &yard, 0, &mut ship, 100.0, 0).unwrap_err().contains("Insufficient credits"));
+    let receipt = install_from_market(&mut equipment_market, &yard, 0, &mut ship, 10000.0, 0).unwrap();
+    assert_eq!(receipt.credits_change, -3300.0);
+    assert_eq!(receipt.ready_tick, 1);
+    assert!(equipment_market.offers.is_empty(), "sold out offers leave the yard");
+
+    let index = ship.weapons.len() - 1;
+    let refund = uninstall_to_market(&mut equipment_market, &yard, &mut ship, Slot::Hardpoint, index, 0.0, 1).unwrap();
+    assert!(refund.credits_change > 0.0 && refund.credits_change < 3000.0);
+    assert_eq!(equipment_market.offers[0].stock, 1);
+
+    // An engine swap sells the old engine back to the yard
+    let mut engines = market(Equipment::Engine(ShipEngine::Advanced), 6000.0);
+    let receipt = install_from_market(&mut engines, &yard, 0, &mut ship, 10000.0, 10).unwrap();
+    assert!(matches!(ship.engine, ShipEngine::Advanced));
+    assert!(receipt.credits_change > -6600.0);
+    assert_eq!(engines.offers[0].equipment.name(), "Basic Engine");
+}
+
+#[test]
+fn test_generated_yards_favour_planet_specialties() {
+    let yard = planet(PlanetSpecialization::Manufacturing, Economy::Stable);
+    let market = EquipmentMarket::generate(&yard, &mut rng_for(9, "equipment"));
+    assert!((4..=7).contains(&market.offers.len()));
+    for offer in &market.offers {
+        assert!(offer.stock >= 1);
+        if let Equipment::ArmorPlating { .. } = offer.equipment {
+            assert!(offer.price < offer.equipment.base_price());
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::models::persistence::{Transaction, recover_game, temp_path, write_atomic, write_json_atomic};
+use crate::tests::fixtures::game_dir;
+use std::fs;
+use std::path::Path;
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_atomic_write_replaces_file_without_leftovers() {
+    let dir = game_dir("test_persistence_write");
+    let path = dir.join("player.json");
+
+    write_json_atomic(&path, &vec![1, 2, 3]).unwrap();
+    write_atomic(&path, b"[4,5]").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[4,5]");
+    assert_eq!(file_names(&dir), vec!["player.json"]);
+}
+
+#[test]
+fn test_transaction_commits_every_staged_file() {
+    let game_id = "test_persistence_commit";
+    let dir = game_dir(game_id);
+    fs::write(dir.join("a.json"), "\"old\"").unwrap();
+
+    let mut transaction = Transaction::new(game_id);
+    transaction.stage(&["a.json"], &"first").unwrap();
+    transaction.stage(&["nested", "b.json"], &42).unwrap();
+    // Staging a file again keeps only the later version
+    transaction.stage(&["a.json"], &"second").unwrap();
+    transaction.commit().unwrap();
+
+    assert_eq!(fs::read_to_string(dir.join("a.json")).unwrap(), "\"second\"");
+    assert_eq!(fs::read_to_string(dir.join("nested").join("b.json")).unwrap(), "42");
+    // Neither temp files nor the journal survive a commit
+    assert_eq!(file_names(&dir), vec!["a.json", "nested"]);
+    assert!(recover_game(&dir).unwrap().is_clean());
+}
+
+#[test]
+fn test_transaction_removes_files_with_its_writes() {
+    let game_id = "test_persistence_removal";
+    let dir = game_dir(game_id);
+    fs::write(dir.join("merged.json"), "{}").unwrap();
+
+    let mut transaction = Transaction::new(game_id);
+    transaction.stage(&["kept.json"], &1).unwrap();
+    transaction.stage_removal(&["merged.json"]);
+    transaction.stage_removal(&["missing.json"]);
+    transaction.commit().unwrap();
+    assert_eq!(file_names(&dir), vec!["kept.json"]);
+
+    // A removal interrupted after the commit point is finished on recovery
+    fs::write(dir.join("merged.json"), "{}").unwrap();
+    let journal = serde_json::json!([{ "target": dir.join("merged.json") }]);
+    fs::write(dir.join("tx_2.journal"), journal.to_string()).unwrap();
+    assert_eq!(recover_game(&dir).unwrap().completed_writes.len(), 1);
+    assert_eq!(file_names(&dir), vec!["kept.json"]);
+}
+
+#[test]
+fn test_recovery_rolls_committed_journal_forward() {
+    let dir = game_dir("test_persistence_journal");
+    let target = dir.join("fleet.json");
+    fs::write(&target, "{\"ships\":1}").unwrap();
+
+    // A crash after the journal was written but before the rename
+    let temp = temp_path(&target);
+    fs::write(&temp, "{\"ships\":2}").unwrap();
+    let journal = serde_json::json!([{ "target": target, "temp": temp }]);
+    fs::write(dir.join("tx_1.journal"), journal.to_string()).unwrap();
+
+    let report = recover_game(&dir).unwrap();
+    assert_eq!(report.completed_writes, vec![target.display().to_string()]);
+    assert!(report.discarded_writes.is_empty());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "{\"ships\":2}");
+    assert_eq!(file_names(&dir), vec!["fleet.json"]);
+}
+
+#[test]
+fn test_recovery_discards_uncommitted_writes_and_reports_torn_files() {
+    let dir = game_dir("test_persistence_torn");
+    let target = dir.join("market.json");
+    fs::write(&target, "{\"resources\":[]}").unwrap();
+    // A crash before the commit point leaves only a temp file
+    fs::write(temp_path(&target), "{\"resources\":[1").unwrap();
+    // A save truncated by a crash outside of the atomic writers
+    fs::write(dir.join("player.json"), "{\"name\":\"Tes").unwrap();
+
+    let report = recover_game(&dir).unwrap();
+    assert!(report.completed_writes.is_empty());
+    assert_eq!(report.discarded_writes.len(), 1);
+    assert_eq!(report.torn_files, vec![dir.join("player.json").display().to_string()]);
+    assert!(report.summary().contains("player.json"));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "{\"resources\":[]}");
+    assert_eq!(file_names(&dir), vec!["market.json", "player.json"]);
+}
@@ -0,0 +1,73 @@
+use crate::combat::combat::calculate_combat_power;
+use crate::models::economy::Economy;
+use crate::models::fleet::Fleet;
+use crate::models::market::calculate_ship_price;
+use crate::models::planet::PlanetSpecialization;
+use crate::models::position::Position;
+use crate::models::repair::{apply_repairs, quote_fleet_repair};
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::travel::{engine_speed, fleet_speed};
+use crate::tests::fixtures::planet;
+
+/// A fleet with one ship that lost half its armor and most of its hull.
+fn damaged_fleet(size: ShipSize) -> Fleet {
+    let mut fleet = Fleet::new("Tester".to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    let mut ship = Ship::new(ShipType::Battleship, size, ShipEngine::Basic);
+    ship.hp = ship.max_hp() / 4;
+    ship.armor.current = ship.armor.capacity / 2;
+    ship.shields.current = 0;
+    fleet.ships.push(ship);
+    fleet.ships.push(Ship::new(ShipType::Fighter, ShipSize::Small, ShipEngine::Advanced));
+    fleet
+}
+
+#[test]
+fn test_repair_prices_follow_planet_and_ship_size() {
+    let fleet = damaged_fleet(ShipSize::Medium);
+    let quote = |specialization, economy| quote_fleet_repair(&fleet, &planet(specialization, economy), None).unwrap();
+
+    let shipyard = quote(PlanetSpecialization::Manufacturing, Economy::Stable);
+    let resort = quote(PlanetSpecialization::Tourism, Economy::Stable);
+    assert_eq!(shipyard.ships.len(), 1, "only the damaged ship needs work");
+    assert!(shipyard.total_cost > 0.0);
+    assert!(shipyard.total_cost < resort.total_cost);
+    assert!(quote(PlanetSpecialization::Manufacturing, Economy::Crashing).total_cost > shipyard.total_cost);
+
+    let large = quote_fleet_repair(&damaged_fleet(ShipSize::Large), &planet(PlanetSpecialization::Manufacturing, Economy::Stable), None).unwrap();
+    assert!(large.total_cost > shipyard.total_cost);
+
+    assert!(quote_fleet_repair(&fleet, &planet(PlanetSpecialization::Manufacturing, Economy::Nonexistent), None).is_err());
+    assert!(quote_fleet_repair(&fleet, &planet(PlanetSpecialization::Manufacturing, Economy::Stable), Some(5)).is_err());
+}
+
+#[test]
+fn test_repairs_restore_hull_armor_and_shields() {
+    let mut fleet = damaged_fleet(ShipSize::Medium);
+    let estimate = quote_fleet_repair(&fleet, &planet(PlanetSpecialization::Manufacturing, Economy::Stable), Some(0)).unwrap();
+    let quote = &estimate.ships[0];
+    assert_eq!(quote.hull_points, fleet.ships[0].max_hp() - fleet.ships[0].hp);
+    assert_eq!(quote.shield_points, fleet.ships[0].shields.capacity);
+
+    apply_repairs(&mut fleet, &estimate);
+    let ship = &fleet.ships[0];
+    assert_eq!(ship.hp, ship.max_hp());
+    assert_eq!(ship.armor.current, ship.armor.capacity);
+    assert!(!ship.needs_repair());
+    assert!(quote_fleet_repair(&fleet, &planet(PlanetSpecialization::Manufacturing, Economy::Stable), None).unwrap().ships.is_empty());
+}
+
+#[test]
+fn test_badly_damaged_ships_are_slower_weaker_and_cheaper() {
+    let mut fleet = damaged_fleet(ShipSize::Medium);
+    let damaged = fleet.ships[0].clone();
+    let mut intact = damaged.clone();
+    intact.hp = intact.max_hp();
+
+    assert!(damaged.is_badly_damaged());
+    assert!(fleet_speed(&fleet).unwrap() < engine_speed(&ShipEngine::Basic));
+    assert!(calculate_combat_power(&damaged) < calculate_combat_power(&intact));
+    assert!(calculate_ship_price(&damaged) < calculate_ship_price(&intact));
+
+    fleet.ships[0] = intact;
+    assert_eq!(fleet_speed(&fleet).unwrap(), engine_speed(&ShipEngine::Basic));
+}
@@ -0,0 +1,86 @@
+use crate::combat::combat::auto_resolve_ship_combat_with_rng;
+use crate::encounters::generate_encounter_fleet;
+use crate::models::fleet::generate_random_fleet;
+use crate::models::position::Position;
+use crate::models::clock::{ScheduledEvent, ScheduledEventKind};
+use crate::models::context::{CurrentGame, GameRegistry};
+use crate::models::rng::{game_uuid, next_game_rng, rng_for};
+use crate::models::settings::GameSettings;
+use crate::models::storage::{StorageBackend, init_storage, storage_for};
+use crate::tests::fixtures::game_dir;
+use rand::Rng;
+use crate::models::star_system::generate_star_system;
+use std::collections::HashSet;
+
+#[test]
+fn test_same_seed_generates_identical_star_systems() {
+    let generate = |seed: u64| {
+        let mut rng = rng_for(seed, "galaxy");
+        let mut existing_names = HashSet::new();
+        let systems: Vec<_> = (0..3)
+            .map(|_| generate_star_system(100, 100, 100, &mut existing_names, &mut rng))
+            .collect();
+        serde_json::to_string(&systems).unwrap()
+    };
+
+    assert_eq!(generate(42), generate(42));
+    assert_ne!(generate(42), generate(43));
+}
+
+#[test]
+fn test_streams_are_independent() {
+    let a = serde_json::to_string(&generate_encounter_fleet(Position { x: 1, y: 2, z: 3 }, &mut rng_for(7, "encounter:0"))).unwrap();
+    let b = serde_json::to_string(&generate_encounter_fleet(Position { x: 1, y: 2, z: 3 }, &mut rng_for(7, "encounter:1"))).unwrap();
+    let a_again = serde_json::to_string(&generate_encounter_fleet(Position { x: 1, y: 2, z: 3 }, &mut rng_for(7, "encounter:0"))).unwrap();
+
+    assert_eq!(a, a_again);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_same_seed_replays_combat() {
+    let fight = |seed: u64| {
+        let mut setup_rng = rng_for(seed, "fleets");
+        let position = Position { x: 0, y: 0, z: 0 };
+        let mut attacker = generate_random_fleet("Attacker".to_string(), position, 3, 1, &mut setup_rng);
+        let mut defender = generate_random_fleet("Defender".to_string(), position, 3, 1, &mut setup_rng);
+        let result = auto_resolve_ship_combat_with_rng(&mut attacker, &mut defender, &mut rng_for(seed, "combat:0"));
+        (
+            result.combat_log,
+            serde_json::to_string(&attacker).unwrap(),
+            serde_json::to_string(&defender).unwrap(),
+        )
+    };
+
+    assert_eq!(fight(1234), fight(1234));
+}
+
+#[test]
+fn test_same_seed_hands_out_the_same_ids() {
+    assert_eq!(game_uuid(&mut rng_for(5, "missions:0")), game_uuid(&mut rng_for(5, "missions:0")));
+    assert_ne!(game_uuid(&mut rng_for(5, "missions:0")), game_uuid(&mut rng_for(5, "missions:1")));
+
+    let event = |seed: u64| ScheduledEvent::new(3, ScheduledEventKind::Announcement { message: "Eclipse".to_string() }, &mut rng_for(seed, "events")).id;
+    assert_eq!(event(9), event(9));
+    assert!(uuid::Uuid::parse_str(&event(9)).is_ok());
+}
+
+#[test]
+fn test_game_streams_fail_rather_than_draw_unseeded() {
+    assert!(next_game_rng("combat").is_err(), "no game is entered");
+
+    let game_id = "test_rng_streams";
+    let dir = game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    storage_for(game_id).unwrap().save_settings(&GameSettings { game_id: game_id.to_string(), seed: 11, ..GameSettings::default() }).unwrap();
+    let game = CurrentGame::new(GameRegistry::default().get(game_id).unwrap());
+    let _game = game.enter();
+
+    let first: u64 = next_game_rng("combat").unwrap().gen_range(0..u64::MAX);
+    let second: u64 = next_game_rng("combat").unwrap().gen_range(0..u64::MAX);
+    assert_eq!(first, rng_for(11, "combat:0").gen_range(0..u64::MAX));
+    assert_eq!(second, rng_for(11, "combat:1").gen_range(0..u64::MAX));
+
+    std::fs::write(dir.join("rng_state.json"), "{ not json").unwrap();
+    assert!(next_game_rng("combat").is_err(), "a lost counter would replay draws");
+}
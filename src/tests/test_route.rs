@@ -0,0 +1,156 @@
+use crate::models::encounter::{EncounterKind, create_encounter};
+use crate::models::fleet::Fleet;
+use crate::models::planet::PlanetDanger;
+use crate::models::player::Player;
+use crate::models::position::Position;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::rng::rng_for;
+use crate::models::route::{RouteObjective, advance_route, depart_on_route, fleet_jump_range, plan_route};
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::star_system::StarSystem;
+use crate::models::storage::{StorageBackend, init_storage, storage_for};
+use crate::simulation::tick::{Encounters, FleetTransit, RouteFollowing, TickContext, run_ticks};
+use crate::tests::fixtures::{game_dir, systems_at};
+
+/// Start (0) and destination (3) are 1400 apart. The straight way leads
+/// through a deadly system (1); a slightly longer detour (2) is safe.
+/// System 4 lies far beyond the reach of basic engines.
+fn systems() -> Vec<StarSystem> {
+    let dangers = [PlanetDanger::VerySafe, PlanetDanger::Deadly, PlanetDanger::VerySafe, PlanetDanger::VerySafe, PlanetDanger::VerySafe];
+    let mut systems = systems_at(5, &[(0, 0), (700, 0), (700, 300), (1400, 0), (3000, 0)]);
+    for (system, danger) in systems.iter_mut().zip(dangers) {
+        for planet in &mut system.planets {
+            planet.danger = danger.clone();
+        }
+    }
+    systems
+}
+
+fn fleet(engines: &[ShipEngine]) -> Fleet {
+    let mut fleet = Fleet::new("Tester".to_string(), Position { x: 0, y: 0, z: 0 }, 1);
+    fleet.current_system_id = Some(0);
+    for engine in engines {
+        let mut ship = Ship::new(ShipType::Freighter, ShipSize::Small, engine.clone());
+        ship.cargo = vec![Resource { resource_type: ResourceType::Fuel, quantity: Some(100), buy: None, sell: None }];
+        fleet.ships.push(ship);
+    }
+    fleet
+}
+
+fn stops(fleet: &Fleet, systems: &[StarSystem], destination: usize, objective: RouteObjective) -> Vec<usize> {
+    plan_route(fleet, systems, destination, None, objective).unwrap()
+        .waypoints.iter().map(|waypoint| waypoint.system_id).collect()
+}
+
+#[test]
+fn test_objectives_pick_different_paths() {
+    let systems = systems();
+    let fleet = fleet(&[ShipEngine::Basic]);
+
+    assert_eq!(stops(&fleet, &systems, 3, RouteObjective::Distance), vec![1, 3]);
+    assert_eq!(stops(&fleet, &systems, 3, RouteObjective::Fuel), vec![1, 3]);
+    assert_eq!(stops(&fleet, &systems, 3, RouteObjective::Danger), vec![2, 3]);
+
+    let route = plan_route(&fleet, &systems, 3, Some(0), RouteObjective::Distance).unwrap();
+    assert_eq!(route.total_distance, 1400.0);
+    assert_eq!(route.total_ticks, 28);
+    assert_eq!(route.total_fuel, 28);
+    assert_eq!(route.waypoints[1].planet_id, Some(0));
+    assert_eq!(route.waypoints[1].local_position, systems[3].planets[0].position);
+    assert!(plan_route(&fleet, &systems, 3, Some(5), RouteObjective::Distance).is_err());
+}
+
+#[test]
+fn test_jump_range_follows_the_weakest_engine() {
+    let systems = systems();
+    let mixed = fleet(&[ShipEngine::Experimental, ShipEngine::Basic]);
+    assert_eq!(fleet_jump_range(&mixed), Some(800.0));
+
+    let error = plan_route(&mixed, &systems, 4, None, RouteObjective::Distance).unwrap_err();
+    assert!(error.contains("out of reach"), "{}", error);
+    let route = plan_route(&fleet(&[ShipEngine::Experimental]), &systems, 4, None, RouteObjective::Distance).unwrap();
+    assert_eq!(route.waypoints.last().unwrap().system_id, 4);
+    assert!(route.waypoints.iter().all(|waypoint| waypoint.distance <= 3000.0));
+}
+
+#[test]
+fn test_fleet_follows_its_route_across_ticks() {
+    let mut ctx = TickContext::new("test_route", 0, rng_for(2, "tick"));
+    ctx.systems = systems();
+    let mut traveller = fleet(&[ShipEngine::Basic]);
+    traveller.route = Some(plan_route(&traveller, &ctx.systems, 3, None, RouteObjective::Danger).unwrap());
+    ctx.fleets = vec![depart_on_route(&traveller, 0).unwrap()];
+    assert_eq!(ctx.fleets[0].transit.as_ref().unwrap().destination_system_id, Some(2));
+
+    let systems = [Box::new(FleetTransit) as _, Box::new(RouteFollowing) as _];
+    for _ in 0..40 {
+        run_ticks(&mut ctx, &systems, 1).unwrap();
+        // Resume after any encounter, as the player would
+        if let Some(route) = ctx.fleets[0].route.as_mut().filter(|route| route.halted.is_some()) {
+            assert!(!route.encounters.is_empty());
+            route.halted = None;
+        }
+    }
+
+    let arrived = &ctx.fleets[0];
+    assert!(arrived.route.is_none(), "the route is dropped once completed");
+    assert_eq!((arrived.current_system_id, arrived.position), (Some(3), Position { x: 1400, y: 0, z: 0 }));
+    assert!(ctx.log.iter().any(|line| line.contains("reached the end of its route")));
+}
+
+#[test]
+fn test_encounters_halt_the_route() {
+    let systems = systems();
+    let mut traveller = fleet(&[ShipEngine::Basic]);
+    traveller.route = Some(plan_route(&traveller, &systems, 3, None, RouteObjective::Distance).unwrap());
+    traveller.update_position(systems[1].position);
+    traveller.current_system_id = Some(1);
+
+    let (encounter, _) = create_encounter(EncounterKind::Pirate, &traveller, None, 5, |_| false, &mut rng_for(1, "encounter"));
+    let message = advance_route(&mut traveller, 5, &[&encounter]).unwrap();
+    assert!(message.contains("halted") && message.contains(&encounter.hostile_fleet), "{}", message);
+    let route = traveller.route.as_ref().unwrap();
+    assert_eq!((route.next_leg, route.encounters.len()), (1, 1));
+    assert_eq!((route.encounters[0].leg, route.encounters[0].system_id), (0, 1));
+    assert_eq!(route.encounters[0].encounter_id, encounter.id);
+
+    assert!(advance_route(&mut traveller, 6, &[]).is_none(), "a halted route waits for its owner");
+    assert!(traveller.transit.is_none());
+}
+
+#[test]
+fn test_fleets_meet_others_where_they_stop() {
+    let game_id = "test_route_encounters";
+    game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    storage_for(game_id).unwrap().save_player(&Player::new("Tester", 1000.0, &mut rng_for(1, "player"))).unwrap();
+
+    let mut ctx = TickContext::new(game_id, 0, rng_for(3, "tick"));
+    ctx.systems = systems();
+    let mut traveller = fleet(&[ShipEngine::Basic]);
+    traveller.update_position(ctx.systems[1].position);
+    traveller.current_system_id = Some(1);
+    traveller.local_position = Some(ctx.systems[1].planets[0].position);
+    traveller.last_move_distance = Some(30.0);
+    ctx.fleets = vec![traveller];
+
+    let systems = [Box::new(Encounters) as _];
+    for _ in 0..20 {
+        if !ctx.encounters.encounters.is_empty() {
+            break;
+        }
+        ctx.fleets[0].last_move_distance = Some(30.0);
+        run_ticks(&mut ctx, &systems, 1).unwrap();
+    }
+
+    assert!(!ctx.encounters.encounters.is_empty(), "a deadly system is rarely quiet");
+    assert!(ctx.fleets[0].last_move_distance.is_none(), "each move is rolled for once");
+    let pending = ctx.encounters.pending_for_fleet(&ctx.fleets[0].name);
+    assert!(pending.iter().all(|encounter| ctx.fleets.iter().any(|fleet| fleet.name == encounter.hostile_fleet)));
+
+    // Nothing more is rolled while the fleet has encounters to resolve
+    let met = ctx.encounters.encounters.len();
+    ctx.fleets[0].last_move_distance = Some(30.0);
+    run_ticks(&mut ctx, &systems, 1).unwrap();
+    assert_eq!(ctx.encounters.encounters.len(), met);
+}
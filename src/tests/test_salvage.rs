@@ -0,0 +1,164 @@
+use crate::combat::combat::CombatResult;
+use crate::models::faction::{Faction, PIRACY_REPUTATION_PENALTY, record_piracy};
+use crate::models::player::Player;
+use crate::models::position::Position;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::rng::rng_for;
+use crate::models::salvage::{SALVAGE_FIELD_LIFETIME, capture_ship, collect_salvage, loot_cargo, scrap_ship, scrap_value};
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::tests::fixtures::fleet_of;
+
+fn freighter(name: &str, cargo: Vec<Resource>) -> Ship {
+    let mut ship = Ship::new(ShipType::Freighter, ShipSize::Medium, ShipEngine::Basic);
+    ship.name = name.to_string();
+    ship.cargo = cargo;
+    ship
+}
+
+fn disabled(mut ship: Ship) -> Ship {
+    ship.armor.current = 0;
+    ship
+}
+
+fn won_by_attacker(defender_losses: Vec<Ship>, jettisoned: Vec<Resource>) -> CombatResult {
+    CombatResult {
+        attacker_losses: Vec::new(),
+        defender_losses,
+        attacker_victory: true,
+        attacker_retreated: false,
+        defender_retreated: false,
+        jettisoned,
+        combat_log: Vec::new(),
+        rounds: Vec::new(),
+    }
+}
+
+#[test]
+fn test_fights_leave_cargo_wrecks_and_disabled_ships_to_the_winner() {
+    let mut attacker = fleet_of("Raider", vec![freighter("Corsair", Vec::new())]);
+    let mut defender = fleet_of("Merchant_1", vec![
+        disabled(freighter("Crippled", vec![Resource::new(ResourceType::Metals, 4)])),
+        freighter("Intact", Vec::new()),
+    ]);
+    let result = won_by_attacker(
+        vec![freighter("Sunk", vec![Resource::new(ResourceType::Food, 10)])],
+        vec![Resource::new(ResourceType::Food, 2), Resource::new(ResourceType::Water, 5)],
+    );
+
+    let field = collect_salvage(&result, &mut attacker, &mut defender, 7, &mut rng_for(1, "salvage")).unwrap();
+
+    assert_eq!(field.claimant, "Raider");
+    assert_eq!((field.position, field.tick), (attacker.position, 7));
+    assert_eq!(field.cargo_quantity(ResourceType::Food), 12);
+    assert_eq!(field.cargo_quantity(ResourceType::Water), 5);
+    assert_eq!(field.wrecks.len(), 1);
+    assert!(field.wrecks[0].cargo.is_empty(), "a wreck's cargo floats free");
+    assert_eq!(field.derelicts.len(), 1);
+    assert_eq!(field.derelicts[0].cargo_quantity(ResourceType::Metals), 4);
+    assert_eq!(defender.ships.len(), 1);
+    assert_eq!(defender.ships[0].name, "Intact");
+    assert!(field.is_expired(7 + SALVAGE_FIELD_LIFETIME));
+}
+
+#[test]
+fn test_nobody_boards_when_the_winner_has_no_ships_left() {
+    let mut attacker = fleet_of("Raider", Vec::new());
+    let mut defender = fleet_of("Merchant_1", vec![disabled(freighter("Crippled", Vec::new()))]);
+    let mut result = won_by_attacker(Vec::new(), Vec::new());
+    result.attacker_victory = false;
+    assert!(collect_salvage(&result, &mut attacker, &mut defender, 0, &mut rng_for(1, "salvage")).is_none());
+
+    let mut result = won_by_attacker(Vec::new(), Vec::new());
+    result.attacker_losses = vec![freighter("Sunk", Vec::new())];
+    let field = collect_salvage(&result, &mut defender, &mut attacker, 0, &mut rng_for(1, "salvage")).unwrap();
+    assert_eq!(field.claimant, "Merchant_1");
+    assert_eq!(defender.ships.len(), 1, "the winner keeps its own disabled ships");
+}
+
+#[test]
+fn test_nobody_boards_the_ships_of_a_fleet_that_got_away() {
+    let mut attacker = fleet_of("Raider", vec![freighter("Corsair", Vec::new())]);
+    let mut defender = fleet_of("Merchant_1", vec![disabled(freighter("Crippled", Vec::new()))]);
+    let mut result = won_by_attacker(vec![freighter("Sunk", Vec::new())], Vec::new());
+    result.defender_retreated = true;
+
+    let field = collect_salvage(&result, &mut attacker, &mut defender, 0, &mut rng_for(1, "salvage")).unwrap();
+    assert_eq!(field.wrecks.len(), 1);
+    assert!(field.derelicts.is_empty());
+    assert_eq!(defender.ships.len(), 1, "the fleet tows its disabled ship away");
+}
+
+#[test]
+fn test_npcs_do_not_board_disabled_ships() {
+    for owner in ["Pirate", "Pirate_1234", "Military", "Merchant_2"] {
+        let mut attacker = fleet_of(owner, vec![freighter("Corsair", Vec::new())]);
+        let mut defender = fleet_of("Victim", vec![disabled(freighter("Crippled", Vec::new()))]);
+        let result = won_by_attacker(vec![freighter("Sunk", Vec::new())], Vec::new());
+
+        let field = collect_salvage(&result, &mut attacker, &mut defender, 0, &mut rng_for(1, "salvage")).unwrap();
+        assert!(field.derelicts.is_empty(), "{} boarded a ship", owner);
+        assert_eq!(defender.ships.len(), 1);
+    }
+}
+
+#[test]
+fn test_looting_is_limited_by_cargo_space() {
+    let mut attacker = fleet_of("Raider", vec![freighter("Corsair", Vec::new())]);
+    let mut defender = fleet_of("Merchant_1", Vec::new());
+    let space = attacker.free_cargo_capacity();
+    let result = won_by_attacker(vec![freighter("Sunk", vec![Resource::new(ResourceType::Food, space + 5)])], Vec::new());
+    let mut field = collect_salvage(&result, &mut attacker, &mut defender, 0, &mut rng_for(1, "salvage")).unwrap();
+    field.check_reach(&attacker, 1).unwrap();
+
+    let receipt = loot_cargo(&mut field, &mut attacker, ResourceType::Food, None).unwrap();
+    assert_eq!(receipt.moved, space);
+    assert_eq!(attacker.cargo_quantity(ResourceType::Food), space);
+    assert_eq!(field.cargo_quantity(ResourceType::Food), 5);
+    assert!(loot_cargo(&mut field, &mut attacker, ResourceType::Food, None).is_err(), "the holds are full");
+    assert!(loot_cargo(&mut field, &mut attacker, ResourceType::Water, None).is_err());
+}
+
+#[test]
+fn test_derelicts_can_be_captured_or_scrapped() {
+    let mut attacker = fleet_of("Raider", vec![freighter("Corsair", Vec::new())]);
+    let mut defender = fleet_of("Merchant_1", vec![
+        disabled(freighter("Prize", vec![Resource::new(ResourceType::Electronics, 6)])),
+        disabled(freighter("Hulk", vec![Resource::new(ResourceType::Fuel, 3)])),
+    ]);
+    let result = won_by_attacker(vec![freighter("Sunk", Vec::new())], Vec::new());
+    let mut field = collect_salvage(&result, &mut attacker, &mut defender, 0, &mut rng_for(1, "salvage")).unwrap();
+
+    let mut stranger = fleet_of("Someone", vec![freighter("Passerby", Vec::new())]);
+    assert!(field.check_reach(&stranger, 0).is_err());
+    stranger.owner_id = "Raider".to_string();
+    stranger.position = Position { x: 9, y: 9, z: 9 };
+    assert!(field.check_reach(&stranger, 0).is_err(), "looters must be at the field");
+
+    assert_eq!(capture_ship(&mut field, &mut attacker, 0).unwrap(), "Prize");
+    let prize = attacker.ships.last().unwrap();
+    assert_eq!(prize.owner, "Raider");
+    assert_eq!(prize.cargo_quantity(ResourceType::Electronics), 6, "captured holds come along");
+    assert!(capture_ship(&mut field, &mut attacker, 5).is_err());
+
+    let hulk_value = scrap_value(&field.derelicts[0]);
+    assert_eq!(scrap_ship(&mut field, 0, true).unwrap(), ("Hulk".to_string(), hulk_value));
+    assert_eq!(field.cargo_quantity(ResourceType::Fuel), 3, "scrapping spills the hold");
+    let (wreck, credits) = scrap_ship(&mut field, 0, false).unwrap();
+    assert_eq!(wreck, "Sunk");
+    assert!(credits > 0.0);
+    assert!(field.wrecks.is_empty() && field.derelicts.is_empty());
+}
+
+#[test]
+fn test_piracy_costs_standing_with_the_system_police() {
+    let mut faction = Faction::new("Federation".to_string(), String::new());
+    faction.territory = vec![3];
+    let factions = vec![faction];
+    let mut player = Player::new("Tester", 1000.0, &mut rng_for(1, "player"));
+
+    assert!(record_piracy(&mut player, &factions, Some(4)).is_empty());
+    assert!(record_piracy(&mut player, &factions, None).is_empty(), "nobody polices deep space");
+    let changes = record_piracy(&mut player, &factions, Some(3));
+    assert_eq!(changes.len(), 1);
+    assert_eq!(player.reputation_with("Federation"), PIRACY_REPUTATION_PENALTY);
+}
@@ -0,0 +1,97 @@
+use crate::models::auth::{PlayerSession, Role, Session};
+use crate::models::context::{CurrentGame, GameRegistry};
+use crate::models::fleet::Fleet;
+use crate::models::market::ShipMarket;
+use crate::models::player::Player;
+use crate::models::rng::rng_for;
+use crate::models::settings::GameSettings;
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipType};
+use crate::models::storage::{StorageBackend, init_storage, save_document, storage_for};
+use crate::models::trade::{ShipTradeData, ShipTradeInData};
+use crate::routes::{buy_ship, sell_ship, trade_in_ship};
+use crate::tests::fixtures::{fleet_of, game_dir, systems_at};
+use chrono::{Duration, Utc};
+use rocket::serde::json::Json;
+
+/// A game with one planet whose yard sells a fighter, and a fleet for
+/// `Buyer` and for `Rival` parked at it.
+fn shipyard(game_id: &str) -> CurrentGame {
+    game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    let storage = storage_for(game_id).unwrap();
+    storage.save_settings(&GameSettings { game_id: game_id.to_string(), ..GameSettings::default() }).unwrap();
+    let system = systems_at(3, &[(0, 0)]).remove(0);
+    let planet_position = system.planets[0].position;
+    storage.save_system(0, &system).unwrap();
+
+    let mut for_sale = Ship::new(ShipType::Fighter, ShipSize::Small, ShipEngine::Basic);
+    for_sale.price = Some(100.0);
+    save_document(game_id, &["markets", "ships_0_0.json"], &ShipMarket { ships: vec![for_sale] }).unwrap();
+
+    for owner in ["Buyer", "Rival"] {
+        storage.save_player(&Player::new(owner, 10_000.0, &mut rng_for(1, "player"))).unwrap();
+        let mut fleet = fleet_of(owner, vec![Ship::new(ShipType::Fighter, ShipSize::Small, ShipEngine::Basic)]);
+        fleet.name = format!("Fleet_{}_1", owner);
+        fleet.position = system.position;
+        fleet.current_system_id = Some(0);
+        fleet.local_position = Some(planet_position);
+        storage.save_fleet(&fleet).unwrap();
+    }
+    CurrentGame::new(GameRegistry::default().get(game_id).unwrap())
+}
+
+fn buyer(game_id: &str) -> PlayerSession {
+    PlayerSession::new(Session {
+        token: "test_buyer".to_string(),
+        game_id: game_id.to_string(),
+        player_name: "Buyer".to_string(),
+        role: Role::Player,
+        expires_at: Utc::now() + Duration::hours(1),
+    })
+}
+
+fn rival_fleet(game_id: &str) -> Fleet {
+    storage_for(game_id).unwrap().load_fleet("Fleet_Rival_1").unwrap().unwrap()
+}
+
+fn buyer_credits(game_id: &str) -> f64 {
+    storage_for(game_id).unwrap().load_player("Buyer").unwrap().unwrap().credits
+}
+
+#[test]
+fn test_ships_are_only_bought_into_your_own_fleet() {
+    let game_id = "test_shipyard_buy";
+    let game = shipyard(game_id);
+
+    let response = buy_ship(game.clone(), buyer(game_id), 0, 0, Json(ShipTradeData { ship_index: 0, fleet_name: Some("Fleet_Rival_1".to_string()) }));
+    assert!(!response.success);
+    assert_eq!(rival_fleet(game_id).ships.len(), 1);
+    assert_eq!(buyer_credits(game_id), 10_000.0);
+
+    let response = buy_ship(game, buyer(game_id), 0, 0, Json(ShipTradeData { ship_index: 0, fleet_name: None }));
+    assert!(response.success, "{}", response.message);
+    assert_eq!(buyer_credits(game_id), 9_900.0);
+}
+
+#[test]
+fn test_ships_are_only_sold_from_your_own_fleet() {
+    let game_id = "test_shipyard_sell";
+    let game = shipyard(game_id);
+
+    let response = sell_ship(game, buyer(game_id), 0, 0, Json(ShipTradeData { ship_index: 0, fleet_name: Some("Fleet_Rival_1".to_string()) }));
+    assert!(!response.success);
+    assert_eq!(rival_fleet(game_id).ships.len(), 1);
+    assert_eq!(buyer_credits(game_id), 10_000.0);
+}
+
+#[test]
+fn test_ships_are_only_traded_in_from_your_own_fleet() {
+    let game_id = "test_shipyard_trade_in";
+    let game = shipyard(game_id);
+
+    let data = ShipTradeInData { ship_index: 0, fleet_name: Some("Fleet_Rival_1".to_string()), trade_in_ship_index: Some(0) };
+    let response = trade_in_ship(game, buyer(game_id), 0, 0, Json(data));
+    assert!(!response.success);
+    assert_eq!(rival_fleet(game_id).ships.len(), 1);
+    assert_eq!(buyer_credits(game_id), 10_000.0);
+}
@@ -0,0 +1,103 @@
+use crate::models::fleet::Fleet;
+use crate::models::position::Position;
+use crate::models::rng::rng_for;
+use crate::models::spatial::{GalaxyIndex, PointGrid, segment_distance};
+use crate::models::star_system::StarSystem;
+use crate::tests::fixtures::systems_at;
+use rand::Rng;
+
+fn random_points(count: usize) -> Vec<Position> {
+    let mut rng = rng_for(11, "points");
+    (0..count).map(|_| Position {
+        x: rng.gen_range(-2000..2000),
+        y: rng.gen_range(-2000..2000),
+        z: rng.gen_range(-2000..2000),
+    }).collect()
+}
+
+fn grid(points: &[Position]) -> PointGrid<usize> {
+    let mut grid = PointGrid::new(250.0);
+    for (key, point) in points.iter().enumerate() {
+        grid.insert(key, *point);
+    }
+    grid
+}
+
+#[test]
+fn test_grid_queries_match_a_linear_scan() {
+    let points = random_points(500);
+    let grid = grid(&points);
+    let probes = random_points(520).split_off(500);
+
+    for probe in &probes {
+        let mut expected: Vec<(usize, f64)> = points.iter().enumerate()
+            .map(|(key, point)| (key, probe.distance(point)))
+            .filter(|(_, distance)| *distance <= 600.0)
+            .collect();
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        assert_eq!(grid.within(probe, 600.0), expected);
+
+        let nearest = points.iter().map(|point| probe.distance(point)).fold(f64::INFINITY, f64::min);
+        assert_eq!(grid.nearest(probe).unwrap().1, nearest);
+    }
+
+    let (from, to) = (probes[0], probes[1]);
+    let mut expected: Vec<usize> = points.iter().enumerate()
+        .filter(|(_, point)| segment_distance(&from, &to, point).1 <= 300.0)
+        .map(|(key, _)| key)
+        .collect();
+    let hits = grid.along_segment(&from, &to, 300.0);
+    assert!(hits.windows(2).all(|pair| pair[0].t <= pair[1].t), "hits come in passing order");
+    let mut found: Vec<usize> = hits.iter().map(|hit| hit.key).collect();
+    found.sort();
+    expected.sort();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn test_grid_moves_and_removes_points() {
+    let mut grid = grid(&[Position { x: 0, y: 0, z: 0 }, Position { x: 1000, y: 0, z: 0 }]);
+    grid.insert(0, Position { x: 990, y: 0, z: 0 });
+    assert_eq!(grid.len(), 2);
+    assert_eq!(grid.within(&Position { x: 1000, y: 0, z: 0 }, 20.0).len(), 2);
+    assert!(grid.within(&Position { x: 0, y: 0, z: 0 }, 20.0).is_empty());
+
+    assert!(grid.remove(&1));
+    assert!(!grid.remove(&1));
+    assert_eq!(grid.nearest(&Position { x: 0, y: 0, z: 0 }).unwrap().0, 0);
+    assert_eq!(grid.nearest_matching(&Position { x: 0, y: 0, z: 0 }, |key| *key != 0), None);
+}
+
+fn systems() -> Vec<StarSystem> {
+    systems_at(3, &[(0, 0), (600, 0), (1200, 0)])
+}
+
+#[test]
+fn test_galaxy_index_tracks_fleets() {
+    let systems = systems();
+    let mut fleet = Fleet::new("Tracker".to_string(), systems[0].position, 1);
+    fleet.current_system_id = Some(0);
+    let mut index = GalaxyIndex::build("test_spatial", &systems, std::slice::from_ref(&fleet));
+
+    assert_eq!((index.system_count(), index.fleet_count()), (3, 1));
+    assert_eq!(index.system_at(&Position { x: 600, y: 0, z: 0 }), Some(1));
+    assert_eq!(index.system_at(&Position { x: 601, y: 0, z: 0 }), None);
+    assert_eq!(index.nearest_system(&Position { x: 800, y: 50, z: 0 }).unwrap().0, 1);
+    assert_eq!(index.nearest_system_matching(&Position { x: 800, y: 50, z: 0 }, |id| id != 1).unwrap().0, 2);
+    let passed: Vec<usize> = index.systems_along_segment(&Position { x: -10, y: 5, z: 0 }, &Position { x: 1300, y: 5, z: 0 }, 10.0)
+        .into_iter().map(|hit| hit.key).collect();
+    assert_eq!(passed, vec![0, 1, 2]);
+    let planet = systems[2].planets[0].position;
+    assert!(index.planets_within(2, &planet, 0.0).contains(&(0, 0.0)));
+    assert_eq!(index.fleets_in_system(0), vec![fleet.name.clone()]);
+
+    fleet.update_position(systems[2].position);
+    fleet.current_system_id = Some(2);
+    index.update_fleet(&fleet);
+    assert!(index.fleets_in_system(0).is_empty());
+    assert_eq!(index.fleets_within(&systems[2].position, 1.0).len(), 1);
+
+    index.remove_fleet(&fleet.name);
+    assert_eq!(index.fleet_count(), 0);
+    assert!(index.fleets_in_system(2).is_empty());
+}
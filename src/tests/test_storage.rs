@@ -0,0 +1,81 @@
+use crate::models::fleet::Fleet;
+use crate::models::position::Position;
+use crate::models::settings::GameSettings;
+use crate::models::storage::{
+    JsonDirStorage, SqliteStorage, Storage, StorageBackend, init_storage, load_document, migrate_storage, storage_backend, storage_for,
+};
+use crate::tests::fixtures::game_dir;
+
+fn check_backend(storage: &dyn Storage) {
+    storage.apply(vec![
+        ("settings.json".to_string(), Some(b"{}".to_vec())),
+        ("fleets/Fleet_A_1.json".to_string(), Some(b"1".to_vec())),
+        ("fleets/Fleet_B_1.json".to_string(), Some(b"2".to_vec())),
+        ("markets/market_0_0.json".to_string(), Some(b"3".to_vec())),
+    ]).unwrap();
+    assert_eq!(storage.read("fleets/Fleet_B_1.json").unwrap(), Some(b"2".to_vec()));
+    assert_eq!(storage.read("fleets/Fleet_C_1.json").unwrap(), None);
+    assert_eq!(storage.list("fleets").unwrap(), vec!["Fleet_A_1.json", "Fleet_B_1.json"]);
+    assert!(storage.list("players").unwrap().is_empty());
+
+    // Writes and removals of one batch land together
+    storage.apply(vec![
+        ("fleets/Fleet_A_1.json".to_string(), None),
+        ("fleets/Fleet_B_1.json".to_string(), Some(b"4".to_vec())),
+    ]).unwrap();
+    assert_eq!(storage.keys().unwrap(), vec!["fleets/Fleet_B_1.json", "markets/market_0_0.json", "settings.json"]);
+    assert_eq!(storage.read("fleets/Fleet_B_1.json").unwrap(), Some(b"4".to_vec()));
+
+    let fleet = Fleet::new("Typed".to_string(), Position { x: 1, y: 2, z: 3 }, 1);
+    storage.remove_fleet("Fleet_B_1").unwrap();
+    storage.save_fleet(&fleet).unwrap();
+    let fleets = storage.list_fleets().unwrap();
+    assert_eq!(fleets.len(), 1);
+    assert_eq!(fleets[0].position, fleet.position);
+    assert!(storage.load_fleet("Fleet_B_1").unwrap().is_none());
+}
+
+#[test]
+fn test_json_directory_storage() {
+    let dir = game_dir("test_storage_json");
+    let storage = JsonDirStorage::new(dir.clone());
+    check_backend(&storage);
+    assert!(dir.join("fleets").join("Fleet_Typed_1.json").exists());
+    assert!(!dir.join("fleets").join("Fleet_B_1.json").exists());
+}
+
+#[test]
+fn test_sqlite_storage() {
+    let dir = game_dir("test_storage_sqlite");
+    let storage = SqliteStorage::open(&dir.join("game.sqlite")).unwrap();
+    check_backend(&storage);
+    assert!(!dir.join("fleets").exists(), "documents stay inside the database");
+}
+
+#[test]
+fn test_games_migrate_between_backends() {
+    let game_id = "test_storage_migrate";
+    let dir = game_dir(game_id);
+    init_storage(game_id, StorageBackend::Json).unwrap();
+    let settings = GameSettings { game_id: game_id.to_string(), ..GameSettings::default() };
+    let storage = storage_for(game_id).unwrap();
+    storage.save_settings(&settings).unwrap();
+    storage.save_fleet(&Fleet::new("Mover".to_string(), Position { x: 0, y: 0, z: 0 }, 1)).unwrap();
+    drop(storage);
+
+    let report = migrate_storage(game_id, StorageBackend::Sqlite).unwrap();
+    assert_eq!((report.from, report.to, report.documents), (StorageBackend::Json, StorageBackend::Sqlite, 2));
+    assert_eq!(storage_backend(game_id), StorageBackend::Sqlite);
+    assert!(!dir.join("fleets").join("Fleet_Mover_1.json").exists(), "the JSON copy is removed");
+    assert!(load_document::<Fleet>(game_id, &["fleets", "Fleet_Mover_1.json"]).unwrap().is_some());
+    let moved: GameSettings = load_document(game_id, &["settings.json"]).unwrap().unwrap();
+    assert_eq!(moved.storage, StorageBackend::Sqlite);
+
+    // Migrating to the current backend changes nothing
+    assert_eq!(migrate_storage(game_id, StorageBackend::Sqlite).unwrap().documents, 0);
+
+    let report = migrate_storage(game_id, StorageBackend::Json).unwrap();
+    assert_eq!(report.documents, 2);
+    assert!(!dir.join("game.sqlite").exists());
+    assert!(dir.join("fleets").join("Fleet_Mover_1.json").exists());
+}
@@ -0,0 +1,79 @@
+use crate::models::fleet::{DistanceBreakdown, Fleet};
+use crate::models::position::Position;
+use crate::models::resource::{Resource, ResourceType};
+use crate::models::ship::ship::{Ship, ShipEngine, ShipSize, ShipStatus, ShipType};
+use crate::models::travel::{advance_transit, begin_transit, fleet_fuel, fleet_speed};
+use crate::tests::fixtures::fleet_of;
+
+fn ship_with_fuel(engine: ShipEngine, fuel: u32) -> Ship {
+    let mut ship = Ship::new(ShipType::Freighter, ShipSize::Small, engine);
+    ship.cargo = vec![Resource { resource_type: ResourceType::Fuel, quantity: Some(fuel), buy: None, sell: None }];
+    ship
+}
+
+fn travelling_fleet(ships: Vec<Ship>) -> Fleet {
+    let mut fleet = fleet_of("Traveller", ships);
+    fleet.current_system_id = Some(0);
+    fleet
+}
+
+fn deep_space_trip(distance: f64) -> DistanceBreakdown {
+    DistanceBreakdown { deep_space: distance, total_raw: distance, scale_factor: 1.0, total_scaled: distance, ..Default::default() }
+}
+
+fn arrived_at(fleet: &Fleet, x: i32) -> Fleet {
+    let mut arrived = fleet.clone();
+    arrived.update_position(Position { x, y: 0, z: 0 });
+    arrived.current_system_id = Some(1);
+    arrived
+}
+
+#[test]
+fn test_slowest_engine_sets_fleet_speed() {
+    let fleet = travelling_fleet(vec![ship_with_fuel(ShipEngine::Experimental, 0), ship_with_fuel(ShipEngine::Basic, 0)]);
+    assert_eq!(fleet_speed(&fleet), Some(50.0));
+
+    let fast = travelling_fleet(vec![ship_with_fuel(ShipEngine::Experimental, 0)]);
+    assert_eq!(fleet_speed(&fast), Some(200.0));
+}
+
+#[test]
+fn test_travel_burns_fuel_and_arrives_on_eta() {
+    let fleet = travelling_fleet(vec![ship_with_fuel(ShipEngine::Basic, 30)]);
+    let arrived = arrived_at(&fleet, 500);
+
+    let mut travelling = begin_transit(fleet, arrived, &deep_space_trip(500.0), 100).unwrap();
+    let transit = travelling.transit.clone().unwrap();
+    assert_eq!(transit.arrival_tick, 110);
+    assert_eq!(transit.fuel_used, 10);
+    assert_eq!(fleet_fuel(&travelling), 20);
+    assert_eq!(travelling.ships[0].status, ShipStatus::Warp);
+    assert_eq!(travelling.current_system_id, None);
+
+    assert!(!advance_transit(&mut travelling, 105));
+    assert_eq!(travelling.position.x, 250);
+
+    assert!(advance_transit(&mut travelling, 110));
+    assert!(travelling.transit.is_none());
+    assert_eq!(travelling.position.x, 500);
+    assert_eq!(travelling.current_system_id, Some(1));
+    assert_eq!(travelling.ships[0].status, ShipStatus::Stationary);
+}
+
+#[test]
+fn test_moves_beyond_fuel_range_are_rejected() {
+    let fleet = travelling_fleet(vec![ship_with_fuel(ShipEngine::Basic, 5)]);
+    let arrived = arrived_at(&fleet, 1000);
+
+    let error = begin_transit(fleet, arrived, &deep_space_trip(1000.0), 0).unwrap_err();
+    assert!(error.contains("Insufficient fuel"));
+}
+
+#[test]
+fn test_cannot_move_while_in_transit() {
+    let fleet = travelling_fleet(vec![ship_with_fuel(ShipEngine::Basic, 30)]);
+    let arrived = arrived_at(&fleet, 100);
+    let travelling = begin_transit(fleet, arrived.clone(), &deep_space_trip(100.0), 0).unwrap();
+
+    assert!(begin_transit(travelling, arrived, &deep_space_trip(100.0), 1).is_err());
+}